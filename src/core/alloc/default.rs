@@ -10,3 +10,20 @@ pub fn memory_stats(_opts: &str) -> Option<String> { None }
 /// Always returns None
 #[must_use]
 pub fn memory_usage() -> Option<String> { None }
+
+/// Always returns false
+#[must_use]
+pub fn is_prof_available() -> bool { false }
+
+/// Always returns Ok(false)
+pub fn is_prof_enabled() -> crate::Result<bool> { Ok(false) }
+
+/// Always fails; heap profiling requires jemalloc
+pub fn prof_enable(_enable: bool) -> crate::Result<bool> {
+	crate::Err!("Heap profiling requires the jemalloc allocator.")
+}
+
+/// Always fails; heap profiling requires jemalloc
+pub fn prof_dump(_path: &std::path::Path) -> crate::Result {
+	crate::Err!("Heap profiling requires the jemalloc allocator.")
+}
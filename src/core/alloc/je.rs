@@ -2,8 +2,9 @@
 
 use std::{
 	cell::OnceCell,
-	ffi::{CStr, c_char, c_void},
+	ffi::{CStr, CString, c_char, c_void},
 	fmt::Debug,
+	path::Path,
 	sync::RwLock,
 };
 
@@ -34,7 +35,7 @@
 );
 
 #[cfg(all(feature = "jemalloc_conf", feature = "jemalloc_prof"))]
-const MALLOC_CONF_PROF: &str = ",prof_active:false";
+const MALLOC_CONF_PROF: &str = ",prof:true,prof_active:false";
 #[cfg(all(feature = "jemalloc_conf", not(feature = "jemalloc_prof")))]
 const MALLOC_CONF_PROF: &str = "";
 
@@ -246,6 +247,28 @@ pub fn is_prof_enabled() -> Result<bool> {
 	get::<u8>(&mallctl!("prof.active")).map(is_nonzero!())
 }
 
+/// Whether jemalloc was built with profiling and started with it enabled, so
+/// that prof_enable() can take effect.
+#[must_use]
+pub fn is_prof_available() -> bool {
+	key("opt.prof")
+		.and_then(|key| get::<u8>(&key))
+		.is_ok_and(is_nonzero!())
+}
+
+/// Write a heap profile of the sampled allocations to the path.
+pub fn prof_dump(path: &Path) -> Result {
+	let path = CString::new(path.as_os_str().as_encoded_bytes())
+		.map_err(|e| err!("Invalid heap profile path: {e}"))?;
+
+	let key = mallctl!("prof.dump");
+	let _lock = CONTROL.write()?;
+
+	// SAFETY: prof.dump takes a pointer to a null-terminated path, which outlives
+	// the call.
+	unsafe { mallctl::raw::write_mib(key.as_slice(), path.as_ptr()) }.map_err(map_err)
+}
+
 pub fn trim<I: Into<Option<usize>> + Copy>(arena: I) -> Result {
 	decay(arena).and_then(|()| purge(arena))
 }
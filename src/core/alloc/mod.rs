@@ -1,10 +1,14 @@
 //! Integration with allocators
 
+pub mod profile;
+
 // jemalloc
 #[cfg(all(not(target_env = "msvc"), feature = "jemalloc"))]
 pub mod je;
 #[cfg(all(not(target_env = "msvc"), feature = "jemalloc"))]
-pub use je::{memory_stats, memory_usage, trim};
+pub use je::{
+	is_prof_available, is_prof_enabled, memory_stats, memory_usage, prof_dump, prof_enable, trim,
+};
 
 #[cfg(all(not(target_env = "msvc"), feature = "hardened_malloc", not(feature = "jemalloc")))]
 pub mod hardened;
@@ -13,7 +17,9 @@
 	feature = "hardened_malloc",
 	not(feature = "jemalloc")
 ))]
-pub use hardened::{memory_stats, memory_usage, trim};
+pub use hardened::{
+	is_prof_available, is_prof_enabled, memory_stats, memory_usage, prof_dump, prof_enable, trim,
+};
 
 #[cfg(any(
 	target_env = "msvc",
@@ -24,4 +30,6 @@
 	target_env = "msvc",
 	all(not(feature = "hardened_malloc"), not(feature = "jemalloc"))
 ))]
-pub use default::{memory_stats, memory_usage, trim};
+pub use default::{
+	is_prof_available, is_prof_enabled, memory_stats, memory_usage, prof_dump, prof_enable, trim,
+};
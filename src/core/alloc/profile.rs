@@ -0,0 +1,365 @@
+//! Heap profiles in the format written by jemalloc's `prof.dump`, summarized
+//! in-process so no external tooling is needed. Frames are symbolized against
+//! the running executable, so only profiles dumped by this process resolve to
+//! function names; frames of other profiles are shown as addresses.
+
+use std::{
+	cmp::Reverse,
+	collections::HashMap,
+	fmt, fs,
+	path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{
+	Err, Result, err,
+	utils::{bytes::pretty, time::now_millis},
+};
+
+/// Extension of the profiles written by dump().
+const EXTENSION: &str = "heap";
+
+/// Frames skipped when attributing an allocation to a site: the allocator, the
+/// profiler and the standard containers which call them.
+const INTERNAL: &[&str] = &[
+	"_rjem_",
+	"je_",
+	"prof_",
+	"imalloc",
+	"__rust_",
+	"__rdl_",
+	"tikv_jemalloc",
+	"<tikv_jemalloc",
+	"alloc::",
+	"<alloc::",
+	"core::",
+	"<core::",
+	"std::",
+	"<std::",
+	"hashbrown::",
+	"<hashbrown::",
+];
+
+/// Live allocations sampled from the heap, by call stack.
+#[derive(Debug, Default)]
+pub struct Profile {
+	/// Average number of bytes allocated between samples.
+	pub sample_period: u64,
+
+	stacks: HashMap<Vec<usize>, Usage>,
+}
+
+/// Live allocations at a stack or site. Negative in a diff where they shrank.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct Usage {
+	pub objects: i64,
+	pub bytes: i64,
+}
+
+/// Allocations attributed to the innermost frame outside of the allocator and
+/// the standard library.
+#[derive(Debug, Serialize)]
+pub struct Site {
+	pub name: String,
+
+	#[serde(flatten)]
+	pub usage: Usage,
+}
+
+/// Write a heap profile into the directory, named by the process and time,
+/// creating the directory private to the user when missing. Returns the name
+/// of the profile. This blocks the thread.
+pub fn dump(dir: &Path) -> Result<String> {
+	let mut builder = fs::DirBuilder::new();
+	#[cfg(unix)]
+	std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+	builder.recursive(true).create(dir)?;
+
+	let name = format!("conduwuit.{}.{}.{EXTENSION}", std::process::id(), now_millis());
+	super::prof_dump(&dir.join(&name))?;
+
+	Ok(name)
+}
+
+/// Names of the heap profiles in the directory, oldest first. This blocks the
+/// thread.
+pub fn list(dir: &Path) -> Result<Vec<String>> {
+	let mut profiles = Vec::new();
+	if !dir.exists() {
+		return Ok(profiles);
+	}
+
+	for entry in fs::read_dir(dir)? {
+		let entry = entry?;
+		let modified = entry.metadata()?.modified()?;
+		let name = entry.file_name().to_string_lossy().into_owned();
+		if is_profile_name(&name) {
+			profiles.push((modified, name));
+		}
+	}
+
+	profiles.sort();
+	Ok(profiles.into_iter().map(|(_, name)| name).collect())
+}
+
+/// Path of the named profile in the directory. Names may not refer outside of
+/// it.
+pub fn path(dir: &Path, name: &str) -> Result<PathBuf> {
+	if !is_profile_name(name) || name.contains(['/', '\\']) || name.starts_with('.') {
+		return Err!(Request(InvalidParam("Invalid heap profile name {name:?}.")));
+	}
+
+	let path = dir.join(name);
+	if !path.is_file() {
+		return Err!(Request(NotFound("No heap profile named {name:?}.")));
+	}
+
+	Ok(path)
+}
+
+/// Total and top sites of the named profile, or of what changed since the
+/// base profile when one is named. This reads and symbolizes the whole
+/// profile, so it blocks the thread.
+pub fn summarize(
+	dir: &Path,
+	name: &str,
+	base: Option<&str>,
+	limit: usize,
+) -> Result<(Usage, Vec<Site>)> {
+	let mut profile = Profile::load(&path(dir, name)?)?;
+	if let Some(base) = base {
+		profile = profile.diff(&Profile::load(&path(dir, base)?)?);
+	}
+
+	Ok((profile.total(), profile.top(limit)))
+}
+
+fn is_profile_name(name: &str) -> bool {
+	Path::new(name)
+		.extension()
+		.is_some_and(|extension| extension == EXTENSION)
+}
+
+impl Profile {
+	pub fn load(path: &Path) -> Result<Self> { Self::parse(&fs::read_to_string(path)?) }
+
+	/// Parse a profile of the `heap_v2` format. Only the totals of each stack
+	/// are kept; per-thread counts are ignored.
+	pub fn parse(text: &str) -> Result<Self> {
+		let mut lines = text.lines();
+		let header = lines.next().unwrap_or_default();
+		let sample_period = header
+			.strip_prefix("heap_v2/")
+			.and_then(|period| period.trim().parse().ok())
+			.ok_or_else(|| err!("Not a jemalloc heap profile: {header:?}"))?;
+
+		let mut stacks = HashMap::<_, Usage>::new();
+		let mut frames = None;
+		for line in lines.map(str::trim) {
+			if line == "MAPPED_LIBRARIES:" {
+				break;
+			}
+
+			if let Some(addrs) = line.strip_prefix('@') {
+				frames = Some(
+					addrs
+						.split_whitespace()
+						.map(parse_addr)
+						.collect::<Result<Vec<_>>>()?,
+				);
+			} else if let Some(counts) = line.strip_prefix("t*:") {
+				// The first totals line precedes any stack and covers the whole heap.
+				if let Some(frames) = frames.take() {
+					let usage = stacks.entry(frames).or_default();
+					*usage = usage.add(parse_usage(counts)?);
+				}
+			}
+		}
+
+		Ok(Self { sample_period, stacks })
+	}
+
+	/// Allocations of this profile less those of an earlier one.
+	#[must_use]
+	pub fn diff(&self, base: &Self) -> Self {
+		let mut stacks = self.stacks.clone();
+		for (frames, usage) in &base.stacks {
+			let entry = stacks.entry(frames.clone()).or_default();
+			*entry = entry.sub(*usage);
+		}
+
+		stacks.retain(|_, usage| *usage != Usage::default());
+		Self {
+			sample_period: self.sample_period,
+			stacks,
+		}
+	}
+
+	#[must_use]
+	pub fn total(&self) -> Usage {
+		self.stacks
+			.values()
+			.fold(Usage::default(), |total, usage| total.add(*usage))
+	}
+
+	/// The allocation sites holding the most bytes, largest first.
+	#[must_use]
+	pub fn top(&self, limit: usize) -> Vec<Site> {
+		let mut symbols = HashMap::new();
+		let mut sites = HashMap::<_, Usage>::new();
+		for (frames, usage) in &self.stacks {
+			let name = site(frames, &mut symbols);
+			let entry = sites.entry(name).or_default();
+			*entry = entry.add(*usage);
+		}
+
+		let mut sites: Vec<_> = sites
+			.into_iter()
+			.map(|(name, usage)| Site { name, usage })
+			.collect();
+
+		sites.sort_by_key(|site| Reverse(site.usage.bytes));
+		sites.truncate(limit);
+		sites
+	}
+}
+
+impl Usage {
+	#[must_use]
+	fn add(self, other: Self) -> Self {
+		Self {
+			objects: self.objects.saturating_add(other.objects),
+			bytes: self.bytes.saturating_add(other.bytes),
+		}
+	}
+
+	#[must_use]
+	fn sub(self, other: Self) -> Self {
+		Self {
+			objects: self.objects.saturating_sub(other.objects),
+			bytes: self.bytes.saturating_sub(other.bytes),
+		}
+	}
+}
+
+impl fmt::Display for Usage {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sign = if self.bytes < 0 { "-" } else { "" };
+		let bytes = usize::try_from(self.bytes.unsigned_abs()).unwrap_or(usize::MAX);
+		write!(f, "{sign}{} in {} objects", pretty(bytes), self.objects)
+	}
+}
+
+impl fmt::Display for Site {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}: {}", self.usage, self.name)
+	}
+}
+
+/// Name of the site of a stack, innermost frame first.
+fn site(frames: &[usize], symbols: &mut HashMap<usize, Vec<String>>) -> String {
+	let names = frames.iter().flat_map(|&addr| {
+		symbols
+			.entry(addr)
+			.or_insert_with(|| symbolize(addr))
+			.clone()
+	});
+
+	let mut first = None;
+	for name in names {
+		if !INTERNAL.iter().any(|prefix| name.starts_with(prefix)) {
+			return name;
+		}
+
+		first.get_or_insert(name);
+	}
+
+	first.unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Names of the functions at the address, innermost inlined function first.
+/// Only builds with the 'jemalloc_prof' feature resolve names.
+fn symbolize(addr: usize) -> Vec<String> {
+	let mut names = Vec::new();
+
+	// Return addresses point after the call; look up the call itself.
+	#[cfg(feature = "jemalloc_prof")]
+	{
+		let ip =
+			std::ptr::with_exposed_provenance_mut::<std::ffi::c_void>(addr.saturating_sub(1));
+		backtrace::resolve(ip, |symbol| {
+			if let Some(name) = symbol.name() {
+				names.push(format!("{name:#}"));
+			}
+		});
+	}
+
+	if names.is_empty() {
+		names.push(format!("{addr:#x}"));
+	}
+
+	names
+}
+
+fn parse_addr(addr: &str) -> Result<usize> {
+	let hex = addr.trim_start_matches("0x");
+	usize::from_str_radix(hex, 16).map_err(Into::into)
+}
+
+/// Parse the `objects: bytes [...]` counts following a `t*:` label.
+fn parse_usage(counts: &str) -> Result<Usage> {
+	let mut counts = counts.split(':').map(str::trim);
+	let objects = counts.next().unwrap_or_default();
+	let bytes = counts
+		.next()
+		.and_then(|bytes| bytes.split_whitespace().next())
+		.unwrap_or_default();
+
+	Ok(Usage {
+		objects: objects.parse()?,
+		bytes: bytes.parse()?,
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{Profile, Usage};
+
+	const BASE: &str = "heap_v2/524288
+  t*: 3: 3072 [0: 0]
+  t0: 3: 3072 [0: 0]
+@ 0x1000 0x2000
+  t*: 2: 2048 [0: 0]
+  t0: 2: 2048 [0: 0]
+@ 0x3000
+  t*: 1: 1024 [0: 0]
+  t0: 1: 1024 [0: 0]
+
+MAPPED_LIBRARIES:
+";
+
+	const NEXT: &str = "heap_v2/524288
+  t*: 5: 6144 [0: 0]
+@ 0x1000 0x2000
+  t*: 5: 6144 [0: 0]
+";
+
+	#[test]
+	fn parse_totals() {
+		let profile = Profile::parse(BASE).unwrap();
+		assert_eq!(profile.sample_period, 524_288);
+		assert_eq!(profile.total(), Usage { objects: 3, bytes: 3072 });
+		assert!(Profile::parse("heap_v1\n").is_err());
+	}
+
+	#[test]
+	fn diff_subtracts_base() {
+		let base = Profile::parse(BASE).unwrap();
+		let next = Profile::parse(NEXT).unwrap();
+		let diff = next.diff(&base);
+		assert_eq!(diff.total(), Usage { objects: 2, bytes: 3072 });
+		assert_eq!(diff.stacks.len(), 2);
+		assert_eq!(diff.stacks[&vec![0x3000]], Usage { objects: -1, bytes: -1024 });
+	}
+}
@@ -35,6 +35,24 @@ pub fn check(config: &Config) -> Result {
 	warn_deprecated(config);
 	warn_unknown_key(config);
 
+	if config.rocksdb_secondary && config.rocksdb_secondary_path.is_none() {
+		return Err!(Config(
+			"rocksdb_secondary_path",
+			"A secondary database needs a directory of its own which no other replica shares."
+		));
+	}
+
+	if let (Some(min), Some(max)) = (config.retention_min_lifetime, config.retention_max_lifetime)
+	{
+		if min > max {
+			return Err!(Config(
+				"retention_min_lifetime",
+				"The shortest retention lifetime ({min}s) is longer than the longest ({max}s) \
+				 set by retention_max_lifetime."
+			));
+		}
+	}
+
 	if config.sentry && config.sentry_endpoint.is_none() {
 		return Err!(Config(
 			"sentry_endpoint",
@@ -180,6 +198,15 @@ pub fn check(config: &Config) -> Result {
 		}
 	}
 
+	for cidr in &config.otlp_trusted_proxies {
+		if let Err(e) = ipaddress::IPAddress::parse(cidr) {
+			return Err!(Config(
+				"otlp_trusted_proxies",
+				"Parsing specified IP CIDR range from string failed: {e}."
+			));
+		}
+	}
+
 	if config.allow_registration
 		&& !config.yes_i_am_very_very_sure_i_want_an_open_registration_server_prone_to_abuse
 		&& config.registration_token.is_none()
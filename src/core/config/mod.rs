@@ -18,7 +18,7 @@
 pub use figment::{Figment, value::Value as FigmentValue};
 use regex::RegexSet;
 use ruma::{
-	OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomVersionId,
+	OwnedRoomId, OwnedRoomOrAliasId, OwnedServerName, OwnedUserId, RoomVersionId,
 	api::client::discovery::discover_support::ContactRole,
 };
 use serde::{Deserialize, de::IgnoredAny};
@@ -150,6 +150,81 @@ pub struct Config {
 	#[serde(default = "default_database_backups_to_keep")]
 	pub database_backups_to_keep: i16,
 
+	/// Key used to encrypt the values of sensitive database maps at rest.
+	/// Keys are 32 random bytes encoded as base64, for example the output of
+	/// `openssl rand -base64 32`. Several keys may be given separated by
+	/// whitespace: the first encrypts new values and the rest are only used to
+	/// decrypt values written under them. This may also be set with the
+	/// CONDUWUIT_DATABASE_ENCRYPTION_KEY environment variable.
+	///
+	/// Once values are encrypted the server cannot start without their key. To
+	/// rotate keys, put a new key first, restart, run
+	/// `!admin server rotate-database-key`, and remove the old key once it
+	/// completes.
+	///
+	/// example: "rjLAeVhy8zqUDWDqcf2ONK2Bi4cFNA2HsUaZ1sG9Y5g="
+	///
+	/// display: sensitive
+	pub database_encryption_key: Option<String>,
+
+	/// Path to a file holding the database encryption keys, in the same
+	/// format as "database_encryption_key". Ignored if that option is set.
+	///
+	/// example: "/etc/conduwuit/.database_key"
+	pub database_encryption_key_file: Option<PathBuf>,
+
+	/// Database maps whose values are encrypted at rest when a database
+	/// encryption key is configured. Only values are encrypted; keys remain in
+	/// plaintext. Access tokens are only kept in keys as digests.
+	///
+	/// default: ["global", "onetimekeyid_onetimekeys", "userdeviceid_token", "userid_password"]
+	#[serde(default = "default_database_encrypted_maps")]
+	pub database_encrypted_maps: Vec<String>,
+
+	/// Database maps whose writes are published for change-data capture.
+	/// Each put or delete on these maps is emitted as an ordered record which
+	/// can be consumed in-process or through "database_cdc_file" and
+	/// "database_cdc_socket". Capture is disabled when this is empty.
+	///
+	/// Values of maps which are encrypted at rest are emitted in plaintext.
+	///
+	/// example: ["pduid_pdu", "roomid_pduleaves"]
+	///
+	/// default: []
+	#[serde(default)]
+	pub database_cdc_maps: Vec<String>,
+
+	/// Number of change-data capture records buffered for each consumer. A
+	/// consumer which falls further behind than this loses records and is
+	/// notified so it can resynchronize.
+	///
+	/// default: 8192
+	#[serde(default = "default_database_cdc_capacity")]
+	pub database_cdc_capacity: usize,
+
+	/// Append change-data capture records to this file as JSON lines. The
+	/// file is only readable by the server's user.
+	///
+	/// example: "/var/lib/conduwuit/changes.jsonl"
+	pub database_cdc_file: Option<PathBuf>,
+
+	/// Serve change-data capture records as JSON lines to clients connecting
+	/// to a UNIX socket at this path. Clients receive records written after
+	/// they connect, each at its own pace. Only clients running as the server's
+	/// user are accepted.
+	///
+	/// example: "/run/conduwuit/changes.sock"
+	pub database_cdc_socket: Option<PathBuf>,
+
+	/// Interval in seconds at which expired records are removed from database
+	/// maps which expire them, such as URL previews, login and OpenID tokens,
+	/// and UIAA sessions. Set to 0 to disable removal; expired records are
+	/// still hidden from lookups.
+	///
+	/// default: 3600
+	#[serde(default = "default_database_expiry_interval")]
+	pub database_expiry_interval: u64,
+
 	/// Text which will be added to the end of the user's displayname upon
 	/// registration with a space before the text. In Conduit, this was the
 	/// lightning bolt emoji.
@@ -650,6 +725,38 @@ pub struct Config {
 	#[serde(default = "default_default_room_version")]
 	pub default_room_version: RoomVersionId,
 
+	/// Delete events from the history of rooms once they are older than the
+	/// room's retention policy allows, as set by its `m.room.retention` state
+	/// event or by `retention_default_max_lifetime`. State events are kept.
+	#[serde(default)]
+	pub enforce_retention: bool,
+
+	/// Lifetime in seconds of events in rooms whose retention policy sets
+	/// none. Unset to keep the events of such rooms forever.
+	///
+	/// example: 7776000
+	pub retention_default_max_lifetime: Option<u64>,
+
+	/// Shortest lifetime in seconds a room's retention policy may set for its
+	/// events. Shorter lifetimes are raised to this. It may not be longer than
+	/// retention_max_lifetime.
+	///
+	/// example: 86400
+	pub retention_min_lifetime: Option<u64>,
+
+	/// Longest lifetime in seconds a room's retention policy may set for its
+	/// events. Longer lifetimes are lowered to this.
+	///
+	/// example: 31536000
+	pub retention_max_lifetime: Option<u64>,
+
+	/// Interval in seconds at which events past their lifetime are deleted. The
+	/// first deletion runs one interval after startup.
+	///
+	/// default: 86400
+	#[serde(default = "default_retention_interval")]
+	pub retention_interval: u64,
+
 	// external structure; separate section
 	#[serde(default)]
 	pub well_known: WellKnownConfig,
@@ -661,6 +768,39 @@ pub struct Config {
 	#[serde(default = "default_jaeger_filter")]
 	pub jaeger_filter: String,
 
+	/// If the 'perf_measurements' compile-time feature is enabled, exports
+	/// tracing spans with OTLP to a collector at this endpoint, e.g.
+	/// "http://localhost:4317" for gRPC or "http://localhost:4318/v1/traces"
+	/// for HTTP. W3C trace-context is propagated into outgoing federation,
+	/// appservice and push gateway requests, and taken from incoming requests
+	/// sent by `otlp_trusted_proxies`, so a request can be followed across
+	/// servers.
+	///
+	/// example: "http://localhost:4317"
+	pub otlp_endpoint: Option<String>,
+
+	/// Transport used to export spans to `otlp_endpoint`: "grpc" or "http".
+	///
+	/// default: "grpc"
+	#[serde(default = "default_otlp_protocol")]
+	pub otlp_protocol: String,
+
+	/// Tracing filter for the spans exported over OTLP.
+	///
+	/// default: "info"
+	#[serde(default = "default_otlp_filter")]
+	pub otlp_filter: String,
+
+	/// IP CIDR ranges of the peers whose trace-context is trusted. An incoming
+	/// request only continues the trace named by its `traceparent` header when
+	/// it comes from one of these, such as a reverse proxy which starts or
+	/// sanitises traces; from anyone else the header is ignored. Requests over
+	/// a UNIX socket come from "0.0.0.0".
+	///
+	/// default: []
+	#[serde(default)]
+	pub otlp_trusted_proxies: Vec<String>,
+
 	/// If the 'perf_measurements' compile-time feature is enabled, enables
 	/// collecting folded stack trace profile of tracing spans using
 	/// tracing_flame. The resulting profile can be visualized with inferno[1],
@@ -679,6 +819,32 @@ pub struct Config {
 	#[serde(default = "default_tracing_flame_output_path")]
 	pub tracing_flame_output_path: String,
 
+	/// Directory which heap profiles are written to and read from by the
+	/// `debug heap` admin commands and the service API. Profiling requires
+	/// the 'jemalloc_prof' compile-time feature. Profiles reveal the contents
+	/// of memory, so the directory is created readable by the server's user
+	/// only. Defaults to "heap_profiles" in the database directory.
+	///
+	/// example: "/var/lib/conduwuit/heap"
+	pub heap_profile_dir: Option<PathBuf>,
+
+	/// Bearer token required by the heap profiling endpoints of the service
+	/// API at `/_conduwuit/heap`. The endpoints are disabled while this is
+	/// unset.
+	///
+	/// example: "Qm4vT8xLp2Zc6RnW1hJs"
+	///
+	/// display: sensitive
+	pub heap_api_token: Option<String>,
+
+	/// Directory of plugins loaded at startup and on reload. Each plugin is a
+	/// dynamic library providing services and routes of its own; see the
+	/// plugins chapter of the development documentation. Requires the
+	/// 'plugins' compile-time feature.
+	///
+	/// example: "/var/lib/conduwuit/plugins"
+	pub plugin_dir: Option<PathBuf>,
+
 	#[cfg(not(doctest))]
 	/// Examples:
 	///
@@ -799,6 +965,33 @@ pub struct Config {
 	#[serde(default)]
 	pub log_thread_ids: bool,
 
+	/// Number of recent log events kept in memory with their level, target,
+	/// fields and spans. They can be queried with the `server logs` admin
+	/// command or tailed from the service API. Set to 0 to disable.
+	///
+	/// default: 4096
+	#[serde(default = "default_log_ring_capacity")]
+	pub log_ring_capacity: usize,
+
+	/// EnvFilter selecting the events kept in the in-memory log buffer. This
+	/// is independent of the `log` filter, so more detail can be kept than is
+	/// written to the console.
+	///
+	/// default: "info"
+	#[serde(default = "default_log_ring")]
+	pub log_ring: String,
+
+	/// Bearer token required by the log endpoints of the service API: tailing
+	/// the in-memory log buffer at `/_conduwuit/logs` as server-sent events,
+	/// and managing per-target level overrides at `/_conduwuit/log/directives`.
+	/// The endpoints are disabled while this is unset.
+	///
+	/// example: "7gU2J3WzR9dQk5yXfV0b"
+	///
+	/// display: sensitive
+	#[serde(alias = "log_tail_token")]
+	pub log_api_token: Option<String>,
+
 	/// OpenID token expiration/TTL in seconds.
 	///
 	/// These are the OpenID tokens that are primarily used for Matrix account
@@ -1119,12 +1312,39 @@ pub struct Config {
 	#[serde(default)]
 	pub rocksdb_repair: bool,
 
+	/// Open the database read-only. Like a read replica the server then
+	/// serves only read endpoints and refuses everything else.
 	#[serde(default)]
 	pub rocksdb_read_only: bool,
 
+	/// Open the database as a secondary (read replica) of another conduwuit
+	/// instance using the same "database_path". The replica periodically
+	/// catches up with the primary, refuses all writes, and serves only read
+	/// endpoints, such as the public room directory, room state, members,
+	/// events, relations, media downloads, profiles and room messages. These
+	/// are served from what the primary has stored: remote media and profiles
+	/// are not fetched and room messages are not backfilled. Put it behind a
+	/// reverse proxy which routes those requests to it to offload read traffic
+	/// from the primary.
 	#[serde(default)]
 	pub rocksdb_secondary: bool,
 
+	/// Directory where a secondary instance keeps its own RocksDB info logs.
+	/// Required when "rocksdb_secondary" is enabled. Every replica needs its
+	/// own directory, which must not be the primary's "database_path" nor
+	/// shared with any other replica.
+	///
+	/// example: "/var/lib/conduwuit-replica"
+	pub rocksdb_secondary_path: Option<PathBuf>,
+
+	/// Interval in milliseconds at which a secondary instance catches up with
+	/// the primary. Lower values reduce replication lag at the cost of more
+	/// frequent reads of the primary's write-ahead log.
+	///
+	/// default: 1000
+	#[serde(default = "default_rocksdb_secondary_catchup_interval")]
+	pub rocksdb_secondary_catchup_interval: u64,
+
 	/// Enables idle CPU priority for compaction thread. This is not enabled by
 	/// default to prevent compaction from falling too far behind on busy
 	/// systems.
@@ -1163,6 +1383,19 @@ pub struct Config {
 	#[serde(default = "default_rocksdb_stats_level")]
 	pub rocksdb_stats_level: u8,
 
+	/// Time in seconds a deprecated database column is retained before it is
+	/// dropped automatically at startup. The grace period begins the first
+	/// time the server starts with a version which marks the column as
+	/// deprecated, allowing a downgrade within that window without data loss.
+	///
+	/// Set to 0 to drop deprecated columns on the first startup.
+	///
+	/// Defaults to 30 days.
+	///
+	/// default: 2592000
+	#[serde(default = "default_rocksdb_deprecated_column_grace_period")]
+	pub rocksdb_deprecated_column_grace_period: u64,
+
 	/// This is a password that can be configured that will let you login to the
 	/// server bot account (currently `@conduit`) for emergency troubleshooting
 	/// purposes such as recovering/recreating your admin room, or inviting
@@ -1400,6 +1633,44 @@ pub struct Config {
 	#[serde(default, with = "serde_regex")]
 	pub forbidden_remote_room_directory_server_names: RegexSet,
 
+	/// List of policy rooms (moderation ban lists) whose `m.policy.rule.user`,
+	/// `m.policy.rule.room` and `m.policy.rule.server` rules with an `m.ban`
+	/// recommendation are enforced. Banned users cannot join or be invited,
+	/// banned servers are denied federation, and banned rooms are banned as
+	/// with `!admin rooms moderation ban-room`. Rule entities may contain the
+	/// `*` and `?` globs.
+	///
+	/// A local user, such as the server user, must be joined to each room for
+	/// its rules to be received.
+	///
+	/// example: ["!policies:example.com"]
+	///
+	/// default: []
+	#[serde(default = "Vec::new")]
+	pub policy_lists: Vec<OwnedRoomId>,
+
+	/// URL of an HTTP spam checker. Events sent by local users and received
+	/// over federation, invites, registrations and media uploads are POSTed to
+	/// it as JSON with a `check` field naming which of these it is; it answers
+	/// with an `action` of `allow`, `deny` (with an optional `errcode` and
+	/// `error` for the client) or `soft_fail`. Checks are allowed when the
+	/// webhook cannot be reached.
+	///
+	/// example: "http://127.0.0.1:8009/check"
+	pub spam_checker_webhook_url: Option<Url>,
+
+	/// Bearer token sent to the spam checker webhook.
+	///
+	/// display: sensitive
+	pub spam_checker_webhook_token: Option<String>,
+
+	/// Seconds to wait for an answer from the spam checker webhook before
+	/// allowing the check.
+	///
+	/// default: 5
+	#[serde(default = "default_spam_checker_webhook_timeout")]
+	pub spam_checker_webhook_timeout: u64,
+
 	/// Vector list of IPv4 and IPv6 CIDR ranges / subnets *in quotes* that you
 	/// do not want conduwuit to send outbound requests to. Defaults to
 	/// RFC1918, unroutable, loopback, multicast, and testnet addresses for
@@ -1744,6 +2015,17 @@ pub struct Config {
 	#[serde(default = "default_db_pool_queue_mult")]
 	pub db_pool_queue_mult: usize,
 
+	/// Relative share of the database frontend-pool given to interactive,
+	/// federation and background requests, in that order, when more than one
+	/// class has requests queued. Interactive requests are those made while
+	/// serving clients; background requests come from maintenance tasks such as
+	/// admin scans, media retention and presence timers. A weight of 0 serves
+	/// that class only when no other class is waiting.
+	///
+	/// default: [8, 4, 1]
+	#[serde(default = "default_db_pool_priority_weights")]
+	pub db_pool_priority_weights: [usize; 3],
+
 	/// Sets the initial value for the concurrency of streams. This value simply
 	/// allows overriding the default in the code. The default is 32, which is
 	/// the same as the default in the code. Note this value is itself
@@ -2019,6 +2301,15 @@ fn get_bind_ports(&self) -> Vec<u16> {
 		}
 	}
 
+	/// Directory of heap profiles, inside the database directory unless
+	/// configured.
+	#[must_use]
+	pub fn get_heap_profile_dir(&self) -> PathBuf {
+		self.heap_profile_dir
+			.clone()
+			.unwrap_or_else(|| self.database_path.join("heap_profiles"))
+	}
+
 	pub fn check(&self) -> Result<(), Error> { check(self) }
 }
 
@@ -2142,6 +2433,10 @@ fn default_jaeger_filter() -> String {
 
 fn default_tracing_flame_output_path() -> String { "./tracing.folded".to_owned() }
 
+fn default_otlp_protocol() -> String { "grpc".to_owned() }
+
+fn default_otlp_filter() -> String { "info".to_owned() }
+
 fn default_trusted_servers() -> Vec<OwnedServerName> {
 	vec![OwnedServerName::try_from("matrix.org").unwrap()]
 }
@@ -2158,6 +2453,10 @@ pub fn default_log() -> String {
 #[must_use]
 pub fn default_log_span_events() -> String { "none".into() }
 
+fn default_log_ring_capacity() -> usize { 4096 }
+
+fn default_log_ring() -> String { "info".to_owned() }
+
 fn default_notification_push_path() -> String { "/_matrix/push/v1/notify".to_owned() }
 
 fn default_openid_token_ttl() -> u64 { 60 * 60 }
@@ -2212,6 +2511,22 @@ fn default_rocksdb_bottommost_compression_level() -> i32 { 32767 }
 
 fn default_rocksdb_stats_level() -> u8 { 1 }
 
+fn default_rocksdb_deprecated_column_grace_period() -> u64 { 60 * 60 * 24 * 30 }
+
+fn default_rocksdb_secondary_catchup_interval() -> u64 { 1000 }
+
+fn default_database_cdc_capacity() -> usize { 8192 }
+
+fn default_database_expiry_interval() -> u64 { 3600 }
+
+fn default_retention_interval() -> u64 { 86400 }
+
+fn default_database_encrypted_maps() -> Vec<String> {
+	["global", "onetimekeyid_onetimekeys", "userdeviceid_token", "userid_password"]
+		.map(ToOwned::to_owned)
+		.into()
+}
+
 // I know, it's a great name
 #[must_use]
 #[inline]
@@ -2247,6 +2562,8 @@ fn default_url_preview_max_spider_size() -> usize {
 
 fn default_new_user_displayname_suffix() -> String { "🏳️‍⚧️".to_owned() }
 
+fn default_spam_checker_webhook_timeout() -> u64 { 5 }
+
 fn default_sentry_endpoint() -> Option<Url> {
 	Url::parse("https://fe2eb4536aa04949e28eff3128d64757@o4506996327251968.ingest.us.sentry.io/4506996334657536").ok()
 }
@@ -2288,6 +2605,8 @@ fn default_db_pool_workers_limit() -> usize { 64 }
 
 fn default_db_pool_queue_mult() -> usize { 4 }
 
+fn default_db_pool_priority_weights() -> [usize; 3] { [8, 4, 1] }
+
 fn default_stream_width_default() -> usize { 32 }
 
 fn default_stream_width_scale() -> f32 { 1.0 }
@@ -20,9 +20,56 @@
 /// generated from the data in FLAGS.
 static FEATURES: OnceLock<Vec<&'static str>> = OnceLock::new();
 
+/// Code generation options which change the ABI of a crate.
+const ABI_OPTIONS: &[&str] = &[
+	"code-model",
+	"panic",
+	"prefer-dynamic",
+	"relocation-model",
+	"target-cpu",
+	"target-feature",
+	"tls-model",
+];
+
 /// List of features enabled for the project.
 pub fn features() -> &'static Vec<&'static str> { FEATURES.get_or_init(init_features) }
 
+/// The flags of a crate which must agree with those of every other crate loaded
+/// into the process: the target, options changing the ABI and configuration
+/// other than features. Sorted, so those of two crates can be compared.
+#[must_use]
+pub fn abi_flags(flags: &[&str]) -> Vec<String> {
+	let mut abi = Vec::new();
+	let mut flags = flags.iter().copied();
+	while let Some(flag) = flags.next() {
+		let (opt, val) = match flag {
+			| "--target" | "--cfg" | "-C" | "-Z" => (flag, flags.next().unwrap_or_default()),
+			| _ => match flag.split_at_checked(2) {
+				| Some((opt @ ("-C" | "-Z"), val)) => (opt, val),
+				| _ => match flag.split_once('=') {
+					| Some((opt @ ("--target" | "--cfg"), val)) => (opt, val),
+					| _ => continue,
+				},
+			},
+		};
+
+		let key = val.split_once('=').map_or(val, |(key, _)| key);
+		let is_abi = match opt {
+			| "--target" => true,
+			| "--cfg" => key != "feature",
+			| _ => ABI_OPTIONS.contains(&key),
+		};
+
+		if is_abi {
+			abi.push(format!("{opt} {val}"));
+		}
+	}
+
+	abi.sort_unstable();
+	abi.dedup();
+	abi
+}
+
 fn init_features() -> Vec<&'static str> {
 	let mut features = Vec::new();
 	FLAGS
@@ -51,3 +98,34 @@ fn append_features(features: &mut Vec<&'static str>, flags: &[&'static str]) {
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::abi_flags;
+
+	#[test]
+	fn abi_flags_selects_abi() {
+		let flags = [
+			"--crate-name",
+			"conduwuit_router",
+			"--cfg",
+			"feature=\"systemd\"",
+			"--cfg",
+			"tokio_unstable",
+			"-C",
+			"opt-level=3",
+			"-C",
+			"panic=abort",
+			"-Ctarget-cpu=native",
+			"--target",
+			"x86_64-unknown-linux-gnu",
+		];
+
+		assert_eq!(abi_flags(&flags), [
+			"--cfg tokio_unstable",
+			"--target x86_64-unknown-linux-gnu",
+			"-C panic=abort",
+			"-C target-cpu=native",
+		]);
+	}
+}
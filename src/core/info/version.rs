@@ -8,7 +8,10 @@
 use std::sync::OnceLock;
 
 static BRANDING: &str = "conduwuit";
-static SEMANTIC: &str = env!("CARGO_PKG_VERSION");
+
+/// The version without any extra information; separately built plugins must
+/// match it exactly.
+pub const SEMANTIC: &str = env!("CARGO_PKG_VERSION");
 
 static VERSION: OnceLock<String> = OnceLock::new();
 static USER_AGENT: OnceLock<String> = OnceLock::new();
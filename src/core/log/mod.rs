@@ -5,12 +5,14 @@
 pub mod console;
 pub mod fmt;
 pub mod fmt_span;
+pub mod propagate;
 mod reload;
+pub mod ring;
 mod suppress;
 
 pub use capture::Capture;
 pub use console::{ConsoleFormat, ConsoleWriter, is_systemd_mode};
-pub use reload::{LogLevelReloadHandles, ReloadHandle};
+pub use reload::{Directive, LogLevelReloadHandles, ReloadHandle};
 pub use suppress::Suppress;
 pub use tracing::Level;
 pub use tracing_core::{Event, Metadata};
@@ -25,6 +27,31 @@ pub struct Log {
 
 	/// Tracing capture state for ephemeral/oneshot uses.
 	pub capture: std::sync::Arc<capture::State>,
+
+	/// Buffer of recent structured events for querying and tailing.
+	pub ring: std::sync::Arc<ring::Ring>,
+}
+
+impl Log {
+	/// Per-target level overrides currently in effect.
+	#[inline]
+	#[must_use]
+	pub fn directives(&self) -> Vec<Directive> { self.reload.directives() }
+
+	/// Override the level of a target on every output until reset or expiry.
+	#[inline]
+	pub fn set_directive(&self, directive: Directive) -> crate::Result {
+		self.reload.set_directive(directive)
+	}
+
+	/// Remove the override for a target. Returns whether there was one.
+	#[inline]
+	pub fn reset_directive(&self, target: &str) -> bool { self.reload.reset_directive(target) }
+
+	/// Effective filter of each output, including overrides, by name.
+	#[inline]
+	#[must_use]
+	pub fn filters(&self) -> std::collections::BTreeMap<String, String> { self.reload.filters() }
 }
 
 // Wraps for logging macros. Use these macros rather than extern tracing:: or
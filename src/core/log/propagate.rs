@@ -0,0 +1,78 @@
+//! W3C trace-context propagation. Outgoing requests carry the context of the
+//! current span so the receiver can continue the trace, and incoming requests
+//! carrying a context become children of it. Without the `perf_measurements`
+//! feature, or when no exporter has installed a propagator, these do nothing.
+
+use http::HeaderMap;
+use tracing::Span;
+
+/// Inject the trace-context of the current span into the headers of an
+/// outgoing request.
+#[inline]
+pub fn inject(headers: &mut HeaderMap) {
+	#[cfg(feature = "perf_measurements")]
+	otel::inject(headers);
+
+	#[cfg(not(feature = "perf_measurements"))]
+	_ = headers;
+}
+
+/// Make the span a child of the trace-context carried by the headers of an
+/// incoming request, if any.
+#[inline]
+pub fn set_parent(span: &Span, headers: &HeaderMap) {
+	#[cfg(feature = "perf_measurements")]
+	otel::set_parent(span, headers);
+
+	#[cfg(not(feature = "perf_measurements"))]
+	_ = (span, headers);
+}
+
+#[cfg(feature = "perf_measurements")]
+mod otel {
+	use http::{HeaderMap, HeaderName, HeaderValue};
+	use opentelemetry::{
+		global,
+		propagation::{Extractor, Injector},
+	};
+	use tracing::Span;
+	use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+	struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+	struct HeaderExtractor<'a>(&'a HeaderMap);
+
+	pub(super) fn inject(headers: &mut HeaderMap) {
+		let context = Span::current().context();
+		global::get_text_map_propagator(|propagator| {
+			propagator.inject_context(&context, &mut HeaderInjector(headers));
+		});
+	}
+
+	pub(super) fn set_parent(span: &Span, headers: &HeaderMap) {
+		let context =
+			global::get_text_map_propagator(|propagator| propagator.extract(&HeaderExtractor(headers)));
+
+		span.set_parent(context);
+	}
+
+	impl Injector for HeaderInjector<'_> {
+		fn set(&mut self, key: &str, value: String) {
+			let name = HeaderName::from_bytes(key.as_bytes());
+			let value = HeaderValue::from_str(&value);
+			if let (Ok(name), Ok(value)) = (name, value) {
+				self.0.insert(name, value);
+			}
+		}
+	}
+
+	impl Extractor for HeaderExtractor<'_> {
+		fn get(&self, key: &str) -> Option<&str> {
+			self.0
+				.get(key)
+				.and_then(|value| value.to_str().ok())
+		}
+
+		fn keys(&self) -> Vec<&str> { self.0.keys().map(HeaderName::as_str).collect() }
+	}
+}
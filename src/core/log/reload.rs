@@ -1,11 +1,13 @@
 use std::{
-	collections::HashMap,
+	collections::{BTreeMap, HashMap},
 	sync::{Arc, Mutex},
 };
 
+use serde::{Deserialize, Serialize};
+use tracing::level_filters::LevelFilter;
 use tracing_subscriber::{EnvFilter, reload};
 
-use crate::{Result, error};
+use crate::{Err, Result, err, error, utils::time::now_millis};
 
 /// We need to store a reload::Handle value, but can't name it's type explicitly
 /// because the S type parameter depends on the subscriber's previous layers. In
@@ -36,27 +38,59 @@ fn reload(&self, new_value: L) -> Result<(), reload::Error> { Self::reload(self,
 #[derive(Clone)]
 pub struct LogLevelReloadHandles {
 	handles: Arc<Mutex<HandleMap>>,
+	directives: Arc<Mutex<Directives>>,
 }
 
-type HandleMap = HashMap<String, Handle>;
+/// Level override for one target, applied on top of the filter of every
+/// handle until it is reset or expires.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Directive {
+	/// Target prefix, usually a module path.
+	pub target: String,
+
+	/// Level name, or `off`.
+	pub level: String,
+
+	/// Milliseconds since the epoch after which the override no longer
+	/// applies.
+	pub expires: Option<u64>,
+}
+
+type HandleMap = HashMap<String, Entry>;
 type Handle = Box<dyn ReloadHandle<EnvFilter> + Send + Sync>;
+type Directives = BTreeMap<String, Directive>;
+
+struct Entry {
+	handle: Handle,
+
+	/// Filter last set for the handle, without the directives.
+	base: String,
+}
 
 impl LogLevelReloadHandles {
 	pub fn add(&self, name: &str, handle: Handle) {
+		let base = handle
+			.current()
+			.as_ref()
+			.map(ToString::to_string)
+			.unwrap_or_default();
+
 		self.handles
 			.lock()
 			.expect("locked")
-			.insert(name.into(), handle);
+			.insert(name.into(), Entry { handle, base });
 	}
 
 	pub fn reload(&self, new_value: &EnvFilter, names: Option<&[&str]>) -> Result<()> {
+		let directives = self.directives.lock().expect("locked");
 		self.handles
 			.lock()
 			.expect("locked")
-			.iter()
+			.iter_mut()
 			.filter(|(name, _)| names.is_some_and(|names| names.contains(&name.as_str())))
-			.for_each(|(_, handle)| {
-				_ = handle.reload(new_value.clone()).or_else(error::else_log);
+			.for_each(|(_, entry)| {
+				entry.base = new_value.to_string();
+				entry.apply(&directives);
 			});
 
 		Ok(())
@@ -68,7 +102,150 @@ pub fn current(&self, name: &str) -> Option<EnvFilter> {
 			.lock()
 			.expect("locked")
 			.get(name)
-			.map(|handle| handle.current())?
+			.map(|entry| entry.handle.current())?
+	}
+
+	/// Current filter of every handle, including the directives, by name.
+	#[must_use]
+	pub fn filters(&self) -> BTreeMap<String, String> {
+		self.handles
+			.lock()
+			.expect("locked")
+			.iter()
+			.filter_map(|(name, entry)| Some((name.clone(), entry.handle.current()?.to_string())))
+			.collect()
+	}
+
+	/// Directives which have not expired, by target.
+	#[must_use]
+	pub fn directives(&self) -> Vec<Directive> {
+		let now = now_millis();
+		self.directives
+			.lock()
+			.expect("locked")
+			.values()
+			.filter(|directive| !directive.is_expired(now))
+			.cloned()
+			.collect()
+	}
+
+	/// Add or replace the directive for its target and apply it to every
+	/// handle.
+	pub fn set_directive(&self, directive: Directive) -> Result {
+		directive.validate()?;
+		let mut directives = self.directives.lock().expect("locked");
+		directives.insert(directive.target.clone(), directive);
+		self.apply(&directives);
+
+		Ok(())
+	}
+
+	/// Remove the directive for the target. Returns whether there was one.
+	pub fn reset_directive(&self, target: &str) -> bool {
+		let mut directives = self.directives.lock().expect("locked");
+		let removed = directives.remove(target).is_some();
+		if removed {
+			self.apply(&directives);
+		}
+
+		removed
+	}
+
+	/// Remove every directive. Returns those which were removed.
+	pub fn reset_directives(&self) -> Vec<Directive> {
+		let mut directives = self.directives.lock().expect("locked");
+		let removed: Vec<_> = std::mem::take(&mut *directives).into_values().collect();
+		if !removed.is_empty() {
+			self.apply(&directives);
+		}
+
+		removed
+	}
+
+	/// Remove the directives which have expired. Returns those which were
+	/// removed.
+	pub fn expire_directives(&self) -> Vec<Directive> {
+		let now = now_millis();
+		let mut directives = self.directives.lock().expect("locked");
+		let expired: Vec<_> = directives
+			.values()
+			.filter(|directive| directive.is_expired(now))
+			.cloned()
+			.collect();
+
+		directives.retain(|_, directive| !directive.is_expired(now));
+
+		if !expired.is_empty() {
+			self.apply(&directives);
+		}
+
+		expired
+	}
+
+	/// Time of the earliest expiry of any directive.
+	#[must_use]
+	pub fn next_expiry(&self) -> Option<u64> {
+		self.directives
+			.lock()
+			.expect("locked")
+			.values()
+			.filter_map(|directive| directive.expires)
+			.min()
+	}
+
+	fn apply(&self, directives: &Directives) {
+		self.handles
+			.lock()
+			.expect("locked")
+			.values()
+			.for_each(|entry| entry.apply(directives));
+	}
+}
+
+impl Entry {
+	fn apply(&self, directives: &Directives) {
+		let now = now_millis();
+		let filter = directives
+			.values()
+			.filter(|directive| !directive.is_expired(now))
+			.map(|directive| format!("{}={}", directive.target, directive.level))
+			.fold(self.base.clone(), |filter, directive| match filter.as_str() {
+				| "" => directive,
+				| _ => format!("{filter},{directive}"),
+			});
+
+		let result: Result = EnvFilter::try_new(filter)
+			.map_err(Into::into)
+			.and_then(|filter| self.handle.reload(filter).map_err(Into::into));
+
+		_ = result.or_else(error::else_log);
+	}
+}
+
+impl Directive {
+	/// Whether the directive no longer applies at the time, in milliseconds
+	/// since the epoch.
+	#[inline]
+	#[must_use]
+	pub fn is_expired(&self, now: u64) -> bool {
+		self.expires.is_some_and(|expires| expires <= now)
+	}
+
+	fn validate(&self) -> Result {
+		let valid_target = !self.target.is_empty()
+			&& !self
+				.target
+				.contains(|c: char| c.is_whitespace() || ",=[]{}".contains(c));
+
+		if !valid_target {
+			return Err!(Request(InvalidParam("Invalid log target {:?}", self.target)));
+		}
+
+		self.level.parse::<LevelFilter>().map_err(|e| {
+			err!(Request(InvalidParam("Invalid log level {:?}: {e}", self.level)))
+		})?;
+
+		Ok(())
 	}
 }
 
@@ -76,6 +253,65 @@ impl Default for LogLevelReloadHandles {
 	fn default() -> Self {
 		Self {
 			handles: Arc::new(HandleMap::new().into()),
+			directives: Arc::new(Directives::new().into()),
 		}
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::{Directive, LogLevelReloadHandles};
+
+	fn directive(target: &str, level: &str, expires: Option<u64>) -> Directive {
+		Directive {
+			target: target.to_owned(),
+			level: level.to_owned(),
+			expires,
+		}
+	}
+
+	#[test]
+	fn set_directive_validates() {
+		let handles = LogLevelReloadHandles::default();
+		assert!(
+			handles
+				.set_directive(directive("conduwuit", "verbose", None))
+				.is_err()
+		);
+		assert!(
+			handles
+				.set_directive(directive("a,b", "info", None))
+				.is_err()
+		);
+		assert!(handles.set_directive(directive("", "info", None)).is_err());
+		assert!(
+			handles
+				.set_directive(directive("conduwuit", "trace", None))
+				.is_ok()
+		);
+		assert_eq!(handles.directives().len(), 1);
+	}
+
+	#[test]
+	fn expire_directives_removes_expired() {
+		let handles = LogLevelReloadHandles::default();
+		handles
+			.set_directive(directive("conduwuit_service", "debug", Some(1)))
+			.unwrap();
+		handles
+			.set_directive(directive(
+				"conduwuit_social_service::sending",
+				"trace",
+				Some(u64::MAX),
+			))
+			.unwrap();
+
+		assert_eq!(handles.directives().len(), 1);
+		assert_eq!(handles.next_expiry(), Some(1));
+
+		let expired = handles.expire_directives();
+		assert_eq!(expired.len(), 1);
+		assert_eq!(expired[0].target, "conduwuit_service");
+		assert_eq!(handles.next_expiry(), Some(u64::MAX));
+	}
+}
@@ -0,0 +1,366 @@
+//! In-memory ring buffer of recent structured log events. Unlike the console
+//! output, records keep their level, target, fields and the fields of each
+//! enclosing span, so they can be queried after the fact or tailed live
+//! without restarting the server with a different `log` filter.
+
+use std::{
+	collections::{BTreeMap, VecDeque},
+	fmt,
+	sync::{Arc, Mutex},
+	time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures::{Stream, StreamExt, future, stream};
+use serde::{Serialize, Serializer};
+use tokio::sync::broadcast;
+use tracing::{
+	Level,
+	field::{Field, Visit},
+	span::{Attributes, Id, Record as SpanRecord},
+};
+use tracing_core::{Event, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan};
+
+use crate::{
+	Result, err,
+	utils::{time, time::now_millis},
+};
+
+/// Number of records a live subscriber may fall behind before it is told it
+/// lagged.
+const TAIL_CAPACITY: usize = 1024;
+
+pub type Fields = BTreeMap<&'static str, String>;
+
+/// Ring buffer state; a member of super::Log.
+pub struct Ring {
+	records: Mutex<VecDeque<Arc<Record>>>,
+	capacity: usize,
+	sender: broadcast::Sender<Arc<Record>>,
+}
+
+/// One captured log event.
+#[derive(Debug, Serialize)]
+pub struct Record {
+	/// Milliseconds since the epoch.
+	pub timestamp: u64,
+
+	#[serde(serialize_with = "serialize_level")]
+	pub level: Level,
+
+	pub target: &'static str,
+
+	pub message: String,
+
+	/// Fields of the event other than the message.
+	pub fields: Fields,
+
+	/// Enclosing spans, outermost first.
+	pub spans: Vec<Span>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Span {
+	pub name: &'static str,
+	pub fields: Fields,
+}
+
+/// Filter over records. Every condition which is set must match.
+#[derive(Clone, Debug, Default)]
+pub struct Query {
+	/// Least severe level to include.
+	pub level: Option<Level>,
+
+	/// Earliest time to include.
+	pub since: Option<SystemTime>,
+
+	/// Prefix of the target, usually a module path.
+	pub target: Option<String>,
+
+	/// Name of an enclosing span.
+	pub span: Option<String>,
+
+	/// Substring of the message or of any field value.
+	pub contains: Option<String>,
+}
+
+/// Tracing layer recording events into the ring.
+pub struct Layer {
+	ring: Arc<Ring>,
+}
+
+/// Fields of a span, kept in its extensions.
+struct SpanFields(Fields);
+
+struct Visitor<'a> {
+	fields: &'a mut Fields,
+}
+
+impl Ring {
+	#[must_use]
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			records: Mutex::new(VecDeque::with_capacity(capacity)),
+			capacity,
+			sender: broadcast::channel(TAIL_CAPACITY).0,
+		}
+	}
+
+	/// Whether records are being captured at all.
+	#[inline]
+	#[must_use]
+	pub fn is_enabled(&self) -> bool { self.capacity > 0 }
+
+	/// The most recent records matching the query, oldest first.
+	#[must_use]
+	pub fn query(&self, query: &Query, limit: usize) -> Vec<Arc<Record>> {
+		let records = self.records.lock().expect("locked");
+		let mut matched: Vec<_> = records
+			.iter()
+			.rev()
+			.filter(|record| query.matches(record))
+			.take(limit)
+			.cloned()
+			.collect();
+
+		matched.reverse();
+		matched
+	}
+
+	/// Stream of records captured from now on which match the query.
+	pub fn subscribe(
+		&self,
+		query: Query,
+	) -> impl Stream<Item = Result<Arc<Record>>> + Send + use<> {
+		stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+			let item = match receiver.recv().await {
+				| Ok(record) => Ok(record),
+				| Err(broadcast::error::RecvError::Lagged(count)) =>
+					Err(err!("Log tail lagged; {count} records were dropped.")),
+				| Err(broadcast::error::RecvError::Closed) => return None,
+			};
+
+			Some((item, receiver))
+		})
+		.filter(move |item| {
+			future::ready(match item {
+				| Ok(record) => query.matches(record),
+				| Err(_) => true,
+			})
+		})
+	}
+
+	fn push(&self, record: Record) {
+		let record = Arc::new(record);
+		let mut records = self.records.lock().expect("locked");
+		if records.len() >= self.capacity {
+			records.pop_front();
+		}
+
+		records.push_back(record.clone());
+		drop(records);
+
+		// Fails only when nobody is tailing.
+		self.sender.send(record).ok();
+	}
+}
+
+impl Record {
+	#[inline]
+	#[must_use]
+	pub fn time(&self) -> SystemTime {
+		UNIX_EPOCH
+			.checked_add(Duration::from_millis(self.timestamp))
+			.unwrap_or(UNIX_EPOCH)
+	}
+}
+
+/// Renders a record on one line, like the console output.
+impl fmt::Display for Record {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let time = time::format(self.time(), "%Y-%m-%dT%H:%M:%S%.3fZ");
+		write!(f, "{time} {:>5} {}:", self.level.as_str(), self.target)?;
+		for span in &self.spans {
+			write!(f, " {}", span.name)?;
+			write_fields(f, "{", &span.fields, "}")?;
+			write!(f, ":")?;
+		}
+
+		write!(f, " {}", self.message)?;
+		write_fields(f, " ", &self.fields, "")
+	}
+}
+
+fn write_fields(
+	f: &mut fmt::Formatter<'_>,
+	open: &str,
+	fields: &Fields,
+	close: &str,
+) -> fmt::Result {
+	if fields.is_empty() {
+		return Ok(());
+	}
+
+	f.write_str(open)?;
+	for (i, (key, val)) in fields.iter().enumerate() {
+		let sep = if i > 0 { " " } else { "" };
+		write!(f, "{sep}{key}={val}")?;
+	}
+
+	f.write_str(close)
+}
+
+impl Query {
+	#[must_use]
+	pub fn matches(&self, record: &Record) -> bool {
+		self.level.is_none_or(|level| record.level <= level)
+			&& self
+				.since
+				.is_none_or(|since| record.timestamp >= millis(since))
+			&& self
+				.target
+				.as_deref()
+				.is_none_or(|target| record.target.starts_with(target))
+			&& self
+				.span
+				.as_deref()
+				.is_none_or(|name| record.spans.iter().any(|span| span.name == name))
+			&& self.contains.as_deref().is_none_or(|needle| {
+				record.message.contains(needle)
+					|| record.fields.values().any(|val| val.contains(needle))
+			})
+	}
+}
+
+impl Layer {
+	#[inline]
+	pub fn new(ring: &Arc<Ring>) -> Self { Self { ring: ring.clone() } }
+}
+
+impl fmt::Debug for Layer {
+	#[inline]
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		formatter.debug_struct("ring::Layer").finish()
+	}
+}
+
+impl<S> tracing_subscriber::Layer<S> for Layer
+where
+	S: Subscriber + for<'a> LookupSpan<'a>,
+{
+	fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+		let Some(span) = ctx.span(id) else {
+			return;
+		};
+
+		let mut fields = Fields::new();
+		attrs.record(&mut Visitor { fields: &mut fields });
+		span.extensions_mut().insert(SpanFields(fields));
+	}
+
+	fn on_record(&self, id: &Id, values: &SpanRecord<'_>, ctx: Context<'_, S>) {
+		let Some(span) = ctx.span(id) else {
+			return;
+		};
+
+		if let Some(SpanFields(fields)) = span.extensions_mut().get_mut::<SpanFields>() {
+			values.record(&mut Visitor { fields });
+		}
+	}
+
+	fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+		let mut fields = Fields::new();
+		event.record(&mut Visitor { fields: &mut fields });
+
+		let spans = ctx
+			.event_scope(event)
+			.into_iter()
+			.flat_map(|scope| scope.from_root())
+			.map(|span| Span {
+				name: span.name(),
+				fields: span
+					.extensions()
+					.get::<SpanFields>()
+					.map(|SpanFields(fields)| fields.clone())
+					.unwrap_or_default(),
+			})
+			.collect();
+
+		let metadata = event.metadata();
+		self.ring.push(Record {
+			timestamp: now_millis(),
+			level: *metadata.level(),
+			target: metadata.target(),
+			message: fields.remove("message").unwrap_or_default(),
+			fields,
+			spans,
+		});
+	}
+}
+
+impl Visit for Visitor<'_> {
+	fn record_debug(&mut self, f: &Field, v: &dyn fmt::Debug) {
+		self.fields.insert(f.name(), format!("{v:?}"));
+	}
+
+	fn record_str(&mut self, f: &Field, v: &str) { self.fields.insert(f.name(), v.to_owned()); }
+}
+
+fn serialize_level<S: Serializer>(level: &Level, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.serialize_str(level.as_str())
+}
+
+fn millis(time: SystemTime) -> u64 {
+	let millis = time
+		.duration_since(UNIX_EPOCH)
+		.unwrap_or_default()
+		.as_millis();
+	u64::try_from(millis).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+	use tracing::Level;
+
+	use super::{Fields, Query, Record, Ring};
+
+	fn record(level: Level, message: &str) -> Record {
+		Record {
+			timestamp: 0,
+			level,
+			target: "conduwuit_service::sending",
+			message: message.to_owned(),
+			fields: Fields::new(),
+			spans: Vec::new(),
+		}
+	}
+
+	#[test]
+	fn ring_evicts_oldest() {
+		let ring = Ring::new(2);
+		for message in ["a", "b", "c"] {
+			ring.push(record(Level::INFO, message));
+		}
+
+		let messages: Vec<_> = ring
+			.query(&Query::default(), usize::MAX)
+			.iter()
+			.map(|record| record.message.clone())
+			.collect();
+
+		assert_eq!(messages, ["b", "c"]);
+	}
+
+	#[test]
+	fn query_matches_level_and_target() {
+		let query = Query {
+			level: Some(Level::WARN),
+			target: Some("conduwuit_service".to_owned()),
+			..Query::default()
+		};
+
+		assert!(query.matches(&record(Level::ERROR, "failed")));
+		assert!(query.matches(&record(Level::WARN, "slow")));
+		assert!(!query.matches(&record(Level::INFO, "sent")));
+	}
+}
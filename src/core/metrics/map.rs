@@ -0,0 +1,100 @@
+use std::{
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+/// Operation counters for a database map (column). Each map registers one
+/// instance with the server's Metrics when it is opened. All counters are
+/// monotonic over the life of the process.
+#[derive(Debug, Default)]
+pub struct MapMetrics {
+	/// Point queries performed, including batch elements.
+	pub gets: AtomicU64,
+
+	/// Point queries answered from cache without dispatch to the pool,
+	/// whether or not a value was found.
+	pub cache_hits: AtomicU64,
+
+	/// Point queries which found no value.
+	pub not_found: AtomicU64,
+
+	/// Point queries and pool requests which failed with an error other than
+	/// the value not being found.
+	pub errors: AtomicU64,
+
+	/// Values written, including batch elements.
+	pub puts: AtomicU64,
+
+	/// Keys deleted.
+	pub deletes: AtomicU64,
+
+	/// Records removed by the expiry sweeper.
+	pub expired: AtomicU64,
+
+	/// Cursor movements made while streaming keys or values.
+	pub iterations: AtomicU64,
+
+	/// Requests dispatched to the database pool.
+	pub pool_requests: AtomicU64,
+
+	/// Cumulative time requests spent queued and executing in the pool.
+	pub pool_latency_ns: AtomicU64,
+
+	/// Longest time a single request spent queued and executing in the pool.
+	pub pool_latency_max_ns: AtomicU64,
+}
+
+impl MapMetrics {
+	#[inline]
+	pub fn add(counter: &AtomicU64, count: usize) {
+		counter.fetch_add(count.try_into().unwrap_or(u64::MAX), Ordering::Relaxed);
+	}
+
+	#[inline]
+	pub fn incr(counter: &AtomicU64) { counter.fetch_add(1, Ordering::Relaxed); }
+
+	/// Record the round-trip of one request through the database pool.
+	#[inline]
+	pub fn record_pool(&self, elapsed: Duration) {
+		let nanos = elapsed.as_nanos().try_into().unwrap_or(u64::MAX);
+		self.pool_requests.fetch_add(1, Ordering::Relaxed);
+		self.pool_latency_ns.fetch_add(nanos, Ordering::Relaxed);
+		self.pool_latency_max_ns.fetch_max(nanos, Ordering::Relaxed);
+	}
+
+	/// Record the round-trip of one request through the database pool and
+	/// whether it failed.
+	#[inline]
+	pub fn record_pool_result<T, E>(&self, result: &Result<T, E>, elapsed: Duration) {
+		self.record_pool(elapsed);
+		if result.is_err() {
+			Self::incr(&self.errors);
+		}
+	}
+
+	/// Mean time a request spent queued and executing in the pool.
+	#[must_use]
+	pub fn pool_latency_avg(&self) -> Duration {
+		let requests = self.pool_requests.load(Ordering::Relaxed);
+		let total = self.pool_latency_ns.load(Ordering::Relaxed);
+		Duration::from_nanos(total.checked_div(requests).unwrap_or(0))
+	}
+
+	#[must_use]
+	pub fn pool_latency_max(&self) -> Duration {
+		Duration::from_nanos(self.pool_latency_max_ns.load(Ordering::Relaxed))
+	}
+
+	/// Ratio of point queries answered from cache.
+	#[must_use]
+	#[allow(clippy::cast_precision_loss, clippy::as_conversions)]
+	pub fn cache_hit_ratio(&self) -> f64 {
+		let gets = self.gets.load(Ordering::Relaxed);
+		let hits = self.cache_hits.load(Ordering::Relaxed);
+		if gets == 0 {
+			return 0.0;
+		}
+
+		hits as f64 / gets as f64
+	}
+}
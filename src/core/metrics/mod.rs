@@ -1,10 +1,17 @@
-use std::sync::atomic::AtomicU32;
+mod map;
+
+use std::{
+	collections::BTreeMap,
+	sync::{Arc, RwLock, atomic::AtomicU32},
+};
 
 use tokio::runtime;
 use tokio_metrics::TaskMonitor;
 #[cfg(tokio_unstable)]
 use tokio_metrics::{RuntimeIntervals, RuntimeMonitor};
 
+pub use self::map::MapMetrics;
+
 pub struct Metrics {
 	_runtime: Option<runtime::Handle>,
 
@@ -22,6 +29,8 @@ pub struct Metrics {
 	pub requests_handle_active: AtomicU32,
 	pub requests_handle_finished: AtomicU32,
 	pub requests_panic: AtomicU32,
+
+	maps: RwLock<BTreeMap<&'static str, Arc<MapMetrics>>>,
 }
 
 impl Metrics {
@@ -49,6 +58,8 @@ pub fn new(runtime: Option<runtime::Handle>) -> Self {
 			requests_handle_active: AtomicU32::new(0),
 			requests_handle_finished: AtomicU32::new(0),
 			requests_panic: AtomicU32::new(0),
+
+			maps: RwLock::default(),
 		}
 	}
 
@@ -62,6 +73,27 @@ pub fn runtime_interval(&self) -> Option<tokio_metrics::RuntimeMetrics> {
 			.expect("next interval")
 	}
 
+	/// Counters for a database map. These are registered on first use and
+	/// shared with any later caller for the same map.
+	pub fn map(&self, name: &'static str) -> Arc<MapMetrics> {
+		self.maps
+			.write()
+			.expect("locked for writing")
+			.entry(name)
+			.or_default()
+			.clone()
+	}
+
+	/// Counters for all registered database maps, ordered by name.
+	pub fn maps(&self) -> Vec<(&'static str, Arc<MapMetrics>)> {
+		self.maps
+			.read()
+			.expect("locked for reading")
+			.iter()
+			.map(|(name, metrics)| (*name, metrics.clone()))
+			.collect()
+	}
+
 	#[inline]
 	pub fn task_root(&self) -> Option<&TaskMonitor> { self.task_monitor.as_ref() }
 
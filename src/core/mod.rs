@@ -30,7 +30,7 @@
 
 rustc_flags_capture! {}
 
-#[cfg(any(not(conduwuit_mods), not(feature = "conduwuit_mods")))]
+#[cfg(not(any(all(conduwuit_mods, feature = "conduwuit_mods"), feature = "plugins")))]
 pub mod mods {
 	#[macro_export]
 	macro_rules! mod_ctor {
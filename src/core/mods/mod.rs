@@ -1,4 +1,4 @@
-#![cfg(all(conduwuit_mods, feature = "conduwuit_mods"))]
+#![cfg(any(all(conduwuit_mods, feature = "conduwuit_mods"), feature = "plugins"))]
 
 pub(crate) use libloading::os::unix::{Library, Symbol};
 
@@ -4,7 +4,7 @@
 };
 
 use super::{Library, Symbol, canary, new, path};
-use crate::{Result, error};
+use crate::{Err, Result, error};
 
 pub struct Module {
 	handle: Option<Library>,
@@ -47,9 +47,10 @@ pub fn get<Prototype>(&self, name: &str) -> Result<Symbol<Prototype>> {
 		// SAFETY: Calls dlsym(3) on unix platforms. This might not have to be unsafe
 		// if wrapped in libloading with_dlerror().
 		let sym = unsafe { handle.get::<Prototype>(cname.as_bytes()) };
-		let sym = sym.expect("symbol found; binding successful");
-
-		Ok(sym)
+		sym.or_else(|e| {
+			let module = self.name()?;
+			Err!("Module {module:?} has no symbol {name:?}: {e}")
+		})
 	}
 
 	pub fn deleted(&self) -> Result<bool> {
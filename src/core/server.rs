@@ -65,7 +65,7 @@ pub fn new(config: Config, runtime: Option<runtime::Handle>, log: Log) -> Self {
 	}
 
 	pub fn reload(&self) -> Result<()> {
-		if cfg!(any(not(conduwuit_mods), not(feature = "conduwuit_mods"))) {
+		if cfg!(not(any(all(conduwuit_mods, feature = "conduwuit_mods"), feature = "plugins"))) {
 			return Err!("Reloading not enabled");
 		}
 
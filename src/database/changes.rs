@@ -0,0 +1,142 @@
+//! Change-data capture. Writes to maps listed in the `database_cdc_maps`
+//! option are published as ordered records to any number of subscribers, so
+//! external consumers can follow the database without polling it.
+//!
+//! Records are numbered from the engine's sequence at startup, so sequence
+//! numbers keep increasing across restarts. Writes to the same key are
+//! published in the order they were made; records of concurrent writes to
+//! different keys may arrive out of sequence order. Records are only held in
+//! memory; a subscriber which falls more than the configured capacity behind
+//! loses records and is told how many, after which it must resynchronize.
+
+use std::{
+	hash::{DefaultHasher, Hash, Hasher},
+	sync::{
+		Arc, Mutex, MutexGuard,
+		atomic::{AtomicU64, Ordering},
+	},
+};
+
+use conduwuit::{Result, err, smallvec::SmallVec};
+use futures::{Stream, stream};
+use tokio::sync::broadcast;
+
+/// Number of locks keys are spread over to order writes to the same key.
+const STRIPES: usize = 64;
+
+pub struct Changes {
+	sender: broadcast::Sender<Arc<Change>>,
+
+	/// Sequence number of the last record published.
+	sequence: AtomicU64,
+
+	/// Held for the duration of a captured write to any key hashed to it, so
+	/// records for a key are published in the order they were written.
+	stripes: [Mutex<()>; STRIPES],
+}
+
+/// A single write to a captured map.
+#[derive(Clone, Debug)]
+pub struct Change {
+	pub sequence: u64,
+	pub map: &'static str,
+	pub op: Op,
+	pub key: Vec<u8>,
+
+	/// Value as written by the server; None for deletions. Values of maps
+	/// encrypted at rest are captured in plaintext.
+	pub val: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Op {
+	Put,
+	Delete,
+}
+
+/// Exclusive right to publish records for the keys of a write in progress.
+pub(crate) struct Capture<'a> {
+	changes: &'a Changes,
+	_stripes: SmallVec<[MutexGuard<'a, ()>; 1]>,
+}
+
+impl Changes {
+	pub(crate) fn new(capacity: usize, sequence: u64) -> Self {
+		Self {
+			sender: broadcast::Sender::new(capacity.max(1)),
+			sequence: AtomicU64::new(sequence),
+			stripes: std::array::from_fn(|_| Mutex::default()),
+		}
+	}
+
+	/// Begin a captured write of the given keys. Other captured writes to any
+	/// of these keys wait until the returned guard is dropped.
+	pub(crate) fn capture<'k, I>(&self, keys: I) -> Capture<'_>
+	where
+		I: IntoIterator<Item = &'k [u8]>,
+	{
+		let mut stripes: SmallVec<[usize; 1]> = keys.into_iter().map(stripe).collect();
+
+		// Taken in a fixed order so batches of overlapping keys cannot deadlock.
+		stripes.sort_unstable();
+		stripes.dedup();
+
+		Capture {
+			changes: self,
+			_stripes: stripes
+				.into_iter()
+				.map(|stripe| self.stripes[stripe].lock().expect("locked"))
+				.collect(),
+		}
+	}
+
+	/// Stream of records published after this call. A subscriber which falls
+	/// behind receives an error counting the records it missed.
+	pub fn subscribe(&self) -> impl Stream<Item = Result<Arc<Change>>> + Send + use<> {
+		stream::unfold(self.sender.subscribe(), |mut receiver| async move {
+			let item = match receiver.recv().await {
+				| Ok(change) => Ok(change),
+				| Err(broadcast::error::RecvError::Lagged(count)) =>
+					Err(err!(Database("Change stream lagged; {count} records were dropped."))),
+				| Err(broadcast::error::RecvError::Closed) => return None,
+			};
+
+			Some((item, receiver))
+		})
+	}
+
+	/// Sequence number of the last record published.
+	#[must_use]
+	pub fn sequence(&self) -> u64 { self.sequence.load(Ordering::Acquire) }
+}
+
+impl Capture<'_> {
+	pub(crate) fn publish(&mut self, map: &'static str, op: Op, key: &[u8], val: Option<&[u8]>) {
+		let sequence = self
+			.changes
+			.sequence
+			.fetch_add(1, Ordering::AcqRel)
+			.saturating_add(1);
+
+		let change = Change {
+			sequence,
+			map,
+			op,
+			key: key.to_vec(),
+			val: val.map(<[u8]>::to_vec),
+		};
+
+		// An error only means there are no subscribers at the moment.
+		self.changes.sender.send(Arc::new(change)).ok();
+	}
+}
+
+/// Index of the lock ordering writes to a key.
+fn stripe(key: &[u8]) -> usize {
+	let mut hasher = DefaultHasher::new();
+	key.hash(&mut hasher);
+
+	usize::from(hasher.finish().to_le_bytes()[0])
+		.overflowing_rem(STRIPES)
+		.0
+}
@@ -0,0 +1,172 @@
+//! Authenticated encryption of values at rest. Maps listed in the
+//! `database_encrypted_maps` option have their values sealed with
+//! ChaCha20-Poly1305 before they are written and opened again when read. Keys
+//! are never encrypted since they must remain ordered and seekable.
+//!
+//! Sealed values are laid out as `MAGIC | key id | nonce | ciphertext | tag`.
+//! The map name is bound as associated data so a value cannot be moved into
+//! another map undetected. Values without the magic prefix were written before
+//! encryption was enabled and are passed through unchanged until they are
+//! rewritten.
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use conduwuit::{Config, Err, Result, err};
+use ring::{
+	aead::{Aad, CHACHA20_POLY1305, LessSafeKey, NONCE_LEN, Nonce, UnboundKey},
+	digest::{SHA256, digest},
+	rand::{SecureRandom, SystemRandom},
+};
+
+/// Prefix identifying a sealed value; the last byte is the format version.
+const MAGIC: &[u8] = b"\xFEenc\x01";
+
+/// Length of the truncated key digest identifying which key sealed a value.
+const KEY_ID_LEN: usize = 4;
+
+const HEADER_LEN: usize = MAGIC.len() + KEY_ID_LEN + NONCE_LEN;
+
+pub struct Cipher {
+	/// Keys in order of preference; the first seals new values and all of
+	/// them open existing ones.
+	keys: Vec<Key>,
+	rng: SystemRandom,
+}
+
+struct Key {
+	id: [u8; KEY_ID_LEN],
+	key: LessSafeKey,
+}
+
+impl Cipher {
+	/// Load the configured keys. Returns None when encryption is not enabled.
+	pub(crate) fn load(config: &Config) -> Result<Option<Self>> {
+		let material =
+			match (&config.database_encryption_key, &config.database_encryption_key_file) {
+				| (Some(key), _) => key.clone(),
+				| (None, Some(path)) => std::fs::read_to_string(path)?,
+				| (None, None) => return Ok(None),
+			};
+
+		Self::new(&material).map(Some)
+	}
+
+	/// Construct from whitespace-separated base64 keys, the first of which is
+	/// the active key.
+	pub(crate) fn new(material: &str) -> Result<Self> {
+		let keys = material
+			.split_whitespace()
+			.map(Key::decode)
+			.collect::<Result<Vec<_>>>()?;
+
+		if keys.is_empty() {
+			return Err!(Config(
+				"database_encryption_key",
+				"Database encryption is configured but no key was provided."
+			));
+		}
+
+		Ok(Self { keys, rng: SystemRandom::new() })
+	}
+
+	/// Seal a value for storage in the named map under the active key.
+	pub(crate) fn encrypt(&self, map: &str, val: &[u8]) -> Result<Vec<u8>> {
+		let active = &self.keys[0];
+		let mut nonce = [0_u8; NONCE_LEN];
+		self.rng
+			.fill(&mut nonce)
+			.map_err(|_| err!(Database("Failed to generate a nonce.")))?;
+
+		let mut out =
+			Vec::with_capacity(HEADER_LEN + val.len() + active.key.algorithm().tag_len());
+		out.extend_from_slice(MAGIC);
+		out.extend_from_slice(&active.id);
+		out.extend_from_slice(&nonce);
+		out.extend_from_slice(val);
+
+		let tag = active
+			.key
+			.seal_in_place_separate_tag(
+				Nonce::assume_unique_for_key(nonce),
+				Aad::from(map.as_bytes()),
+				&mut out[HEADER_LEN..],
+			)
+			.map_err(|_| err!(Database("Failed to encrypt value for {map}.")))?;
+
+		out.extend_from_slice(tag.as_ref());
+
+		Ok(out)
+	}
+
+	/// Open a value read from the named map into `out`. Returns false without
+	/// touching `out` when the value was stored before encryption was enabled.
+	pub(crate) fn decrypt_into(&self, map: &str, val: &[u8], out: &mut Vec<u8>) -> Result<bool> {
+		let Some(sealed) = val.strip_prefix(MAGIC) else {
+			return Ok(false);
+		};
+
+		let Some((id, sealed)) = sealed.split_at_checked(KEY_ID_LEN) else {
+			return Err!(Database("Encrypted value in {map} is truncated."));
+		};
+
+		let Some(key) = self.keys.iter().find(|key| key.id == id) else {
+			return Err!(Database("Value in {map} was encrypted with an unknown key."));
+		};
+
+		let Some((nonce, ciphertext)) = sealed.split_at_checked(NONCE_LEN) else {
+			return Err!(Database("Encrypted value in {map} is truncated."));
+		};
+
+		let nonce = Nonce::try_assume_unique_for_key(nonce)
+			.map_err(|_| err!(Database("Encrypted value in {map} has an invalid nonce.")))?;
+
+		out.clear();
+		out.extend_from_slice(ciphertext);
+		let len = key
+			.key
+			.open_in_place(nonce, Aad::from(map.as_bytes()), out)
+			.map_err(|_| err!(Database("Failed to authenticate encrypted value in {map}.")))?
+			.len();
+
+		out.truncate(len);
+
+		Ok(true)
+	}
+
+	/// Open a value read from the named map. Returns None when the value was
+	/// stored before encryption was enabled.
+	pub(crate) fn decrypt(&self, map: &str, val: &[u8]) -> Result<Option<Vec<u8>>> {
+		let mut out = Vec::new();
+		self.decrypt_into(map, val, &mut out)
+			.map(|sealed| sealed.then_some(out))
+	}
+
+	/// Whether the value is already sealed under the active key.
+	#[must_use]
+	pub(crate) fn is_current(&self, val: &[u8]) -> bool {
+		val.strip_prefix(MAGIC)
+			.and_then(|sealed| sealed.get(..KEY_ID_LEN))
+			.is_some_and(|id| id == self.keys[0].id)
+	}
+}
+
+impl Key {
+	fn decode(encoded: &str) -> Result<Self> {
+		let bytes = STANDARD
+			.decode(encoded)
+			.map_err(|e| err!(Config("database_encryption_key", "Invalid base64 key: {e}")))?;
+
+		let key = UnboundKey::new(&CHACHA20_POLY1305, &bytes).map_err(|_| {
+			err!(Config(
+				"database_encryption_key",
+				"Encryption keys must be {} bytes.",
+				CHACHA20_POLY1305.key_len()
+			))
+		})?;
+
+		let id = digest(&SHA256, &bytes).as_ref()[..KEY_ID_LEN]
+			.try_into()
+			.expect("digest is longer than the key id");
+
+		Ok(Self { id, key: LessSafeKey::new(key) })
+	}
+}
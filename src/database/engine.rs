@@ -8,6 +8,8 @@
 mod memory_usage;
 mod open;
 mod repair;
+mod schema;
+pub(crate) mod snapshot;
 
 use std::{
 	ffi::CStr,
@@ -24,7 +26,7 @@
 };
 
 use crate::{
-	Context,
+	Changes, Cipher, Context,
 	pool::Pool,
 	util::{map_err, result},
 };
@@ -36,6 +38,8 @@ pub struct Engine {
 	pub(super) read_only: bool,
 	pub(super) secondary: bool,
 	pub(crate) checksums: bool,
+	pub(crate) cipher: Option<Arc<Cipher>>,
+	pub(crate) changes: Option<Arc<Changes>>,
 	corks: AtomicU32,
 }
 
@@ -112,6 +116,9 @@ pub(crate) fn property(&self, cf: &impl AsColumnFamilyRef, name: &str) -> Result
 			.and_then(|val| val.map_or_else(|| Err!("Property {name:?} not found."), Ok))
 	}
 
+	#[inline]
+	pub(crate) fn has_cf(&self, name: &str) -> bool { self.db.cf_handle(name).is_some() }
+
 	pub(crate) fn cf(&self, name: &str) -> Arc<BoundColumnFamily<'_>> {
 		self.db
 			.cf_handle(name)
@@ -11,6 +11,8 @@
 pub(crate) struct Descriptor {
 	pub(crate) name: &'static str,
 	pub(crate) dropped: bool,
+	pub(crate) deprecated: bool,
+	pub(crate) renamed_from: &'static [&'static str],
 	pub(crate) cache_disp: CacheDisp,
 	pub(crate) key_size_hint: Option<usize>,
 	pub(crate) val_size_hint: Option<usize>,
@@ -26,6 +28,7 @@ pub(crate) struct Descriptor {
 	pub(crate) merge_width: (i32, i32),
 	pub(crate) limit_size: u64,
 	pub(crate) ttl: u64,
+	pub(crate) expiry: u64,
 	pub(crate) compaction: CompactionStyle,
 	pub(crate) compaction_pri: CompactionPri,
 	pub(crate) compression: CompressionType,
@@ -53,6 +56,8 @@ pub(crate) enum CacheDisp {
 static BASE: Descriptor = Descriptor {
 	name: EMPTY,
 	dropped: false,
+	deprecated: false,
+	renamed_from: &[],
 	cache_disp: CacheDisp::Shared,
 	key_size_hint: None,
 	val_size_hint: None,
@@ -68,6 +73,7 @@ pub(crate) enum CacheDisp {
 	merge_width: (2, 16),
 	limit_size: 0,
 	ttl: 60 * 60 * 24 * 21,
+	expiry: 0,
 	compaction: CompactionStyle::Level,
 	compaction_pri: CompactionPri::MinOverlappingRatio,
 	compression: CompressionType::Zstd,
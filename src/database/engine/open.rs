@@ -4,7 +4,7 @@
 	sync::{Arc, atomic::AtomicU32},
 };
 
-use conduwuit::{Result, debug, implement, info, warn};
+use conduwuit::{Err, Result, debug, implement, info, warn};
 use rocksdb::{ColumnFamilyDescriptor, Options};
 
 use super::{
@@ -14,7 +14,7 @@
 	descriptor::{self, Descriptor},
 	repair::repair,
 };
-use crate::{Context, or_else};
+use crate::{Changes, Cipher, Context, or_else};
 
 #[implement(Engine)]
 #[tracing::instrument(skip_all)]
@@ -29,6 +29,7 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 		&ctx.row_cache.lock().expect("row cache locked"),
 	)?;
 
+	let cipher = Cipher::load(config)?.map(Arc::new);
 	let cfds = Self::configure_cfds(&ctx, &db_opts, desc)?;
 	let num_cfds = cfds.len();
 	debug!("Configured {num_cfds} column descriptors...");
@@ -42,7 +43,14 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 	let db = if config.rocksdb_read_only {
 		Db::open_cf_descriptors_read_only(&db_opts, path, cfds, false)
 	} else if config.rocksdb_secondary {
-		Db::open_cf_descriptors_as_secondary(&db_opts, path, path, cfds)
+		let Some(secondary_path) = &config.rocksdb_secondary_path else {
+			return Err!(Config(
+				"rocksdb_secondary_path",
+				"A secondary database needs a directory of its own."
+			));
+		};
+
+		Db::open_cf_descriptors_as_secondary(&db_opts, path, secondary_path, cfds)
 	} else {
 		Db::open_cf_descriptors(&db_opts, path, cfds)
 	}
@@ -55,15 +63,26 @@ pub(crate) async fn open(ctx: Arc<Context>, desc: &[Descriptor]) -> Result<Arc<S
 		"Opened database."
 	);
 
-	Ok(Arc::new(Self {
+	let read_only = config.rocksdb_read_only || config.rocksdb_secondary;
+	let changes = (!read_only && !config.database_cdc_maps.is_empty()).then(|| {
+		Arc::new(Changes::new(config.database_cdc_capacity, db.latest_sequence_number()))
+	});
+
+	let engine = Arc::new(Self {
 		db,
 		pool: ctx.pool.clone(),
 		ctx: ctx.clone(),
 		read_only: config.rocksdb_read_only,
 		secondary: config.rocksdb_secondary,
 		checksums: config.rocksdb_checksums,
+		cipher,
+		changes,
 		corks: AtomicU32::new(0),
-	}))
+	});
+
+	engine.migrate_schema(desc)?;
+
+	Ok(engine)
 }
 
 #[implement(Engine)]
@@ -78,6 +97,14 @@ fn configure_cfds(
 	let path = &config.database_path;
 	let existing = Self::discover_cfs(path, db_opts);
 
+	// Deprecated columns are never created; they are only opened to be retained
+	// through their grace period if they already exist.
+	let desc: Vec<_> = desc
+		.iter()
+		.filter(|desc| !desc.deprecated || existing.contains(desc.name))
+		.copied()
+		.collect();
+
 	let creating = desc.iter().filter(|desc| !existing.contains(desc.name));
 
 	let missing = existing
@@ -85,16 +112,29 @@ fn configure_cfds(
 		.filter(|&name| name != "default")
 		.filter(|&name| !desc.iter().any(|desc| desc.name == name));
 
+	let renamed = missing.clone().filter(|&name| {
+		desc.iter()
+			.any(|desc| desc.renamed_from.iter().any(|&old| old == name))
+	});
+
 	debug!(
 		existing = existing.len(),
 		described = desc.len(),
 		missing = missing.clone().count(),
+		renamed = renamed.clone().count(),
 		creating = creating.clone().count(),
 		"Discovered database columns"
 	);
 
-	missing.clone().for_each(|name| {
-		debug!("Found unrecognized column {name:?} in existing database.");
+	missing
+		.clone()
+		.filter(|&name| !renamed.clone().any(|renamed| renamed == name))
+		.for_each(|name| {
+			debug!("Found unrecognized column {name:?} in existing database.");
+		});
+
+	renamed.for_each(|name| {
+		debug!("Found column {name:?} under a previous name; data will be migrated.");
 	});
 
 	creating.map(|desc| desc.name).for_each(|name| {
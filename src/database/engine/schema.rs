@@ -0,0 +1,163 @@
+//! Online column schema changes. Columns which are renamed or deprecated in
+//! the descriptor list are reconciled with the database when it is opened:
+//! data is moved out of columns under a previous name, and deprecated columns
+//! are dropped once their grace period has elapsed.
+
+use std::time::Duration;
+
+use conduwuit::{Result, debug, implement, info, utils::time, warn};
+use rocksdb::WriteBatchWithTransaction;
+
+use super::{Engine, descriptor::Descriptor};
+use crate::util::result;
+
+/// Key prefix in the default column recording when a column was first found
+/// to be deprecated.
+const DEPRECATED_PREFIX: &[u8] = b"deprecated\xFF";
+
+/// Key prefix in the default column recording the progress of moving a
+/// renamed column: the last key moved, or empty before the first batch.
+const RENAMING_PREFIX: &[u8] = b"renaming\xFF";
+
+/// Number of records moved per write batch when migrating a renamed column.
+const RENAME_BATCH_SIZE: usize = 4096;
+
+#[implement(Engine)]
+#[tracing::instrument(name = "schema", level = "debug", skip_all)]
+pub(crate) fn migrate_schema(&self, desc: &[Descriptor]) -> Result {
+	if self.is_read_only() {
+		return Ok(());
+	}
+
+	for desc in desc {
+		for &old in desc.renamed_from {
+			self.rename_column(old, desc.name)?;
+		}
+
+		if desc.deprecated {
+			self.expire_column(desc.name)?;
+		}
+	}
+
+	Ok(())
+}
+
+/// Move all records from a column under a previous name into its current
+/// column, then drop the old column. Progress is recorded with each batch so
+/// a move interrupted by a crash resumes where it left off on the next start.
+#[implement(Engine)]
+#[tracing::instrument(level = "info", skip(self))]
+fn rename_column(&self, old: &str, new: &str) -> Result {
+	let marker = [RENAMING_PREFIX, old.as_bytes()].concat();
+	let Some(old_cf) = self.db.cf_handle(old) else {
+		// The old column may have been dropped just before a crash.
+		return result(self.db.delete(&marker));
+	};
+
+	let new_cf = self.cf(new);
+	let resume = result(self.db.get(&marker))?;
+	if resume.is_none() {
+		let mut existing = self.db.raw_iterator_cf(&new_cf);
+		existing.seek_to_first();
+		if existing.valid() {
+			warn!(
+				"Column {old:?} was renamed to {new:?} but both contain data. Leaving {old:?} \
+				 in place; it must be reconciled manually."
+			);
+			return Ok(());
+		}
+
+		result(self.db.put(&marker, b""))?;
+	}
+
+	let mut count: usize = 0;
+	let mut batch = WriteBatchWithTransaction::<false>::default();
+	let mut iter = self.db.raw_iterator_cf(&old_cf);
+	match resume.as_deref() {
+		| Some(last) if !last.is_empty() => {
+			info!("Resuming interrupted rename of column {old:?} to {new:?}.");
+			iter.seek(last);
+		},
+		| _ => iter.seek_to_first(),
+	}
+
+	while let Some((key, val)) = iter.item() {
+		batch.put_cf(&new_cf, key, val);
+		count = count.saturating_add(1);
+		if batch.len() >= RENAME_BATCH_SIZE {
+			batch.put(&marker, key);
+			result(self.db.write(std::mem::take(&mut batch)))?;
+		}
+
+		iter.next();
+	}
+
+	result(iter.status())?;
+	if !batch.is_empty() {
+		result(self.db.write(batch))?;
+	}
+
+	drop(iter);
+	drop(old_cf);
+	drop(new_cf);
+	self.sync()?;
+	result(self.db.drop_cf(old))?;
+	result(self.db.delete(&marker))?;
+
+	info!(records = count, "Renamed column {old:?} to {new:?}.");
+
+	Ok(())
+}
+
+/// Drop a deprecated column once the configured grace period has elapsed
+/// since it was first found deprecated.
+#[implement(Engine)]
+#[tracing::instrument(level = "debug", skip(self))]
+fn expire_column(&self, name: &str) -> Result {
+	let marker = [DEPRECATED_PREFIX, name.as_bytes()].concat();
+	if self.db.cf_handle(name).is_none() {
+		return result(self.db.delete(&marker));
+	}
+
+	let now = time::now_millis();
+	let config = &self.ctx.server.config;
+	let grace = Duration::from_secs(config.rocksdb_deprecated_column_grace_period);
+	let since = result(self.db.get(&marker))?
+		.as_deref()
+		.and_then(|val| val.try_into().ok())
+		.map(u64::from_be_bytes);
+
+	let Some(since) = since else {
+		result(self.db.put(&marker, now.to_be_bytes()))?;
+		if !grace.is_zero() {
+			info!(
+				"Column {name:?} is deprecated and will be dropped in {}.",
+				time::pretty(grace)
+			);
+			return Ok(());
+		}
+
+		return self.drop_deprecated(name, &marker);
+	};
+
+	let elapsed = Duration::from_millis(now.saturating_sub(since));
+	if elapsed < grace {
+		debug!(
+			remaining = %time::pretty(grace.saturating_sub(elapsed)),
+			"Retaining deprecated column {name:?}"
+		);
+		return Ok(());
+	}
+
+	self.drop_deprecated(name, &marker)
+}
+
+#[implement(Engine)]
+fn drop_deprecated(&self, name: &str, marker: &[u8]) -> Result {
+	result(self.db.drop_cf(name))?;
+	result(self.db.delete(marker))?;
+
+	info!("Dropped deprecated column {name:?}.");
+
+	Ok(())
+}
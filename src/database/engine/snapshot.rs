@@ -0,0 +1,64 @@
+use std::{fmt, fmt::Debug, sync::Arc};
+
+use conduwuit::implement;
+use rocksdb::SnapshotWithThreadMode;
+
+use super::{Db, Engine};
+
+/// Point-in-time view of the database. Reads performed through a Snapshot
+/// observe the database as it was when the snapshot was taken, regardless of
+/// any writes committed afterward. Multi-step queries across several maps can
+/// share one snapshot to build a consistent result.
+pub struct Snapshot {
+	inner: SnapshotWithThreadMode<'static, Db>,
+	sequence: u64,
+	db: Arc<Engine>,
+}
+
+/// Take a snapshot of the database at the current sequence number.
+#[implement(Engine)]
+#[tracing::instrument(level = "trace", skip_all, fields(sequence))]
+pub fn snapshot(self: &Arc<Self>) -> Arc<Snapshot> {
+	let inner = self.db.snapshot();
+	let sequence = self.current_sequence();
+
+	#[cfg(debug_assertions)]
+	tracing::Span::current().record("sequence", sequence);
+
+	// SAFETY: The snapshot borrows the database for its lifetime. This is
+	// extended to 'static so the Snapshot can be shared across tasks and sent
+	// to the pool. The database is kept alive by the sibling `db` field, which
+	// is declared after `inner` and therefore dropped after it; the snapshot is
+	// always released before the database can close.
+	let inner = unsafe {
+		std::mem::transmute::<SnapshotWithThreadMode<'_, Db>, SnapshotWithThreadMode<'static, Db>>(
+			inner,
+		)
+	};
+
+	Arc::new(Snapshot { inner, sequence, db: self.clone() })
+}
+
+impl Snapshot {
+	/// Sequence number the snapshot is pinned to.
+	#[inline]
+	#[must_use]
+	pub fn sequence(&self) -> u64 { self.sequence }
+
+	/// Number of sequence numbers committed since the snapshot was taken.
+	#[inline]
+	#[must_use]
+	pub fn behind(&self) -> u64 { self.db.current_sequence().saturating_sub(self.sequence) }
+
+	#[inline]
+	pub(crate) fn inner(&self) -> &SnapshotWithThreadMode<'static, Db> { &self.inner }
+
+	#[inline]
+	pub(crate) fn is_engine(&self, db: &Arc<Engine>) -> bool { Arc::ptr_eq(&self.db, db) }
+}
+
+impl Debug for Snapshot {
+	fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(out, "Snapshot {{sequence: {0}}}", self.sequence)
+	}
+}
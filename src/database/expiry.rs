@@ -0,0 +1,47 @@
+//! Record expiry. Maps whose descriptor sets `expiry` have each value framed
+//! with the time it expires, as `MAGIC | expires at (seconds, big-endian) |
+//! value`. The frame is applied before encryption, so it is protected along
+//! with the value. Point reads, existence checks and iteration all treat
+//! expired records as missing until they are removed by the sweeper.
+//!
+//! Values written before expiry was enabled on a map carry no frame. They are
+//! read as live until the sweeper removes them, one period after it first
+//! finds the map expiring.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Prefix identifying a framed value; the last byte is the format version.
+const MAGIC: &[u8] = b"\xFEttl\x01";
+
+pub(crate) const HEADER_LEN: usize = MAGIC.len() + size_of::<u64>();
+
+/// Frame a value to expire once the period has elapsed from now.
+pub(crate) fn frame(val: &[u8], period: Duration) -> Vec<u8> {
+	let expires = now().saturating_add(period.as_secs());
+	let mut out = Vec::with_capacity(HEADER_LEN.saturating_add(val.len()));
+	out.extend_from_slice(MAGIC);
+	out.extend_from_slice(&expires.to_be_bytes());
+	out.extend_from_slice(val);
+	out
+}
+
+/// Split a framed value into its expiry time and the value. Returns None for
+/// values written before expiry was enabled.
+pub(crate) fn unframe(val: &[u8]) -> Option<(u64, &[u8])> {
+	let framed = val.strip_prefix(MAGIC)?;
+	let (expires, val) = framed.split_first_chunk::<{ size_of::<u64>() }>()?;
+
+	Some((u64::from_be_bytes(*expires), val))
+}
+
+#[inline]
+pub(crate) fn is_expired(expires: u64, now: u64) -> bool { expires <= now }
+
+/// Seconds since the epoch.
+#[inline]
+pub(crate) fn now() -> u64 {
+	SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.expect("positive duration after epoch")
+		.as_secs()
+}
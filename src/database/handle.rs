@@ -7,11 +7,22 @@
 use crate::{Deserialized, Slice, keyval::deserialize_val};
 
 pub struct Handle<'a> {
-	val: DBPinnableSlice<'a>,
+	val: Val<'a>,
+}
+
+/// Values are normally pinned in the database; values decrypted on the way
+/// out of an encrypted map are owned instead.
+enum Val<'a> {
+	Pinned(DBPinnableSlice<'a>),
+	Owned(Box<Slice>),
 }
 
 impl<'a> From<DBPinnableSlice<'a>> for Handle<'a> {
-	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val } }
+	fn from(val: DBPinnableSlice<'a>) -> Self { Self { val: Val::Pinned(val) } }
+}
+
+impl From<Vec<u8>> for Handle<'_> {
+	fn from(val: Vec<u8>) -> Self { Self { val: Val::Owned(val.into_boxed_slice()) } }
 }
 
 impl Debug for Handle<'_> {
@@ -72,10 +83,15 @@ impl Deref for Handle<'_> {
 	type Target = Slice;
 
 	#[inline]
-	fn deref(&self) -> &Self::Target { &self.val }
+	fn deref(&self) -> &Self::Target {
+		match &self.val {
+			| Val::Pinned(val) => val,
+			| Val::Owned(val) => val,
+		}
+	}
 }
 
 impl AsRef<Slice> for Handle<'_> {
 	#[inline]
-	fn as_ref(&self) -> &Slice { &self.val }
+	fn as_ref(&self) -> &Slice { self }
 }
@@ -0,0 +1,287 @@
+//! Queries for looking inside the database, shared by the `query raw` admin
+//! commands and the offline `conduwuit dbtool`. Keys are decoded using the
+//! known layouts of the maps, falling back to splitting on the record
+//! separator for maps without one.
+
+use std::{collections::BTreeMap, ops::Deref, sync::Arc};
+
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use conduwuit::{
+	Result,
+	utils::stream::{IterStream, ReadyExt, TryIgnore},
+};
+use futures::{Stream, StreamExt, TryStreamExt};
+use serde_json::{Map as Object, Value, json};
+
+use crate::{Database, Map, SEP};
+
+/// One component of a map's key.
+#[derive(Clone, Copy, Debug)]
+enum Part {
+	/// Text up to the next separator.
+	Str(&'static str),
+
+	/// Big-endian integer of eight bytes, e.g. a short id or a count.
+	U64(&'static str),
+}
+
+use Part::{Str, U64};
+
+const ROOM_USER: &[Part] = &[Str("room_id"), Str("user_id")];
+const USER_ROOM: &[Part] = &[Str("user_id"), Str("room_id")];
+const USER_DEVICE: &[Part] = &[Str("user_id"), Str("device_id")];
+const USER: &[Part] = &[Str("user_id")];
+const ROOM: &[Part] = &[Str("room_id")];
+const EVENT: &[Part] = &[Str("event_id")];
+const SERVER: &[Part] = &[Str("server_name")];
+const ALIAS: &[Part] = &[Str("alias")];
+
+/// Key layouts of the maps whose keys are known.
+const LAYOUTS: &[(&str, &[Part])] = &[
+	("alias_roomid", ALIAS),
+	("alias_userid", ALIAS),
+	("bannedroomids", ROOM),
+	("disabledroomids", ROOM),
+	("eventid_outlierpdu", EVENT),
+	("eventid_pduid", EVENT),
+	("eventid_shorteventid", EVENT),
+	("global", &[Str("key")]),
+	("pduid_pdu", &[U64("shortroomid"), U64("count")]),
+	("publicroomids", ROOM),
+	("roomid_invitedcount", ROOM),
+	("roomid_joinedcount", ROOM),
+	("roomid_shortroomid", ROOM),
+	("roomid_shortstatehash", ROOM),
+	("roomserverids", &[Str("room_id"), Str("server_name")]),
+	("roomsynctoken_shortstatehash", &[U64("shortroomid"), U64("count")]),
+	("roomuserid_invitecount", ROOM_USER),
+	("roomuserid_joined", ROOM_USER),
+	("roomuserid_knockedcount", ROOM_USER),
+	("roomuserid_lastprivatereadupdate", ROOM_USER),
+	("roomuserid_leftcount", ROOM_USER),
+	("roomuserid_privateread", ROOM_USER),
+	("servername_destination", SERVER),
+	("servername_educount", SERVER),
+	("servername_override", SERVER),
+	("serverroomids", &[Str("server_name"), Str("room_id")]),
+	("shorteventid_authchain", &[U64("shorteventid")]),
+	("shorteventid_eventid", &[U64("shorteventid")]),
+	("shorteventid_shortstatehash", &[U64("shorteventid")]),
+	("shortstatehash_statediff", &[U64("shortstatehash")]),
+	("shortstatekey_statekey", &[U64("shortstatekey")]),
+	("statekey_shortstatekey", &[Str("event_type"), Str("state_key")]),
+	("userdeviceid_metadata", USER_DEVICE),
+	("userdeviceid_token", USER_DEVICE),
+	("userfilterid_filter", &[Str("user_id"), Str("filter_id")]),
+	("userid_avatarurl", USER),
+	("userid_blurhash", USER),
+	("userid_devicelistversion", USER),
+	("userid_displayname", USER),
+	("userid_lastonetimekeyupdate", USER),
+	("userid_masterkeyid", USER),
+	("userid_password", USER),
+	("userid_presenceid", USER),
+	("userid_selfsigningkeyid", USER),
+	("userid_usersigningkeyid", USER),
+	("userroomid_highlightcount", USER_ROOM),
+	("userroomid_invitestate", USER_ROOM),
+	("userroomid_joined", USER_ROOM),
+	("userroomid_knockedstate", USER_ROOM),
+	("userroomid_leftstate", USER_ROOM),
+	("userroomid_notificationcount", USER_ROOM),
+];
+
+/// Which records of a map to list.
+#[derive(Clone, Copy, Debug)]
+pub enum Range<'a> {
+	/// Every record.
+	All,
+
+	/// Records whose keys start with the prefix.
+	Prefix(&'a [u8]),
+
+	/// Records from the key onwards.
+	From(&'a [u8]),
+}
+
+/// The record at the key, decoded.
+pub async fn get(map: &Arc<Map>, key: &[u8]) -> Result<Value> {
+	let val = map.get(key).await?;
+
+	Ok(decode(map.name(), key, &val))
+}
+
+/// Keys of the records in the range, decoded.
+pub fn keys<'a>(
+	map: &'a Arc<Map>,
+	range: Range<'a>,
+) -> impl Stream<Item = Result<Value>> + Send + 'a {
+	let keys = match range {
+		| Range::All => map.raw_keys().boxed(),
+		| Range::Prefix(prefix) => map.raw_keys_prefix(prefix).boxed(),
+		| Range::From(start) => map.raw_keys_from(start).boxed(),
+	};
+
+	keys.map_ok(|key| decode_key(map.name(), key))
+}
+
+/// Records in the range, decoded.
+pub fn iter<'a>(
+	map: &'a Arc<Map>,
+	range: Range<'a>,
+) -> impl Stream<Item = Result<Value>> + Send + 'a {
+	let records = match range {
+		| Range::All => map.raw_stream().boxed(),
+		| Range::Prefix(prefix) => map.raw_stream_prefix(prefix).boxed(),
+		| Range::From(start) => map.raw_stream_from(start).boxed(),
+	};
+
+	records.map_ok(|(key, val)| decode(map.name(), key, val))
+}
+
+/// The named map, or every map when none is given. Unknown names are skipped.
+pub fn maps<'a>(
+	db: &'a Database,
+	map: Option<&'a str>,
+) -> impl Stream<Item = &'a Arc<Map>> + Send + 'a {
+	let default_all_maps = map
+		.is_none()
+		.then(|| db.keys().map(Deref::deref))
+		.into_iter()
+		.flatten();
+
+	map.into_iter()
+		.chain(default_all_maps)
+		.map(|map| db.get(map))
+		.filter_map(Result::ok)
+		.stream()
+}
+
+/// Number of records matching the prefix in the map, or in every map.
+pub async fn count(db: &Database, map: Option<&str>, prefix: &str) -> usize {
+	maps(db, map)
+		.then(|map| map.raw_count_prefix(&prefix))
+		.ready_fold(0_usize, usize::saturating_add)
+		.await
+}
+
+/// Number of keys of each length matching the prefix.
+pub async fn keys_sizes(
+	db: &Database,
+	map: Option<&str>,
+	prefix: &str,
+) -> BTreeMap<usize, usize> {
+	maps(db, map)
+		.map(|map| map.raw_keys_prefix(&prefix))
+		.flatten()
+		.ignore_err()
+		.map(<[u8]>::len)
+		.ready_fold_default(histogram)
+		.await
+}
+
+/// Total length of the keys matching the prefix.
+pub async fn keys_total(db: &Database, map: Option<&str>, prefix: &str) -> usize {
+	maps(db, map)
+		.map(|map| map.raw_keys_prefix(&prefix))
+		.flatten()
+		.ignore_err()
+		.map(<[u8]>::len)
+		.ready_fold_default(usize::saturating_add)
+		.await
+}
+
+/// Number of values of each length in records matching the prefix.
+pub async fn vals_sizes(
+	db: &Database,
+	map: Option<&str>,
+	prefix: &str,
+) -> BTreeMap<usize, usize> {
+	maps(db, map)
+		.map(|map| map.raw_stream_prefix(&prefix))
+		.flatten()
+		.ignore_err()
+		.map(|(_, val)| val.len())
+		.ready_fold_default(histogram)
+		.await
+}
+
+/// Total length of the values in records matching the prefix.
+pub async fn vals_total(db: &Database, map: Option<&str>, prefix: &str) -> usize {
+	maps(db, map)
+		.map(|map| map.raw_stream_prefix(&prefix))
+		.flatten()
+		.ignore_err()
+		.map(|(_, val)| val.len())
+		.ready_fold_default(usize::saturating_add)
+		.await
+}
+
+fn histogram(mut sizes: BTreeMap<usize, usize>, len: usize) -> BTreeMap<usize, usize> {
+	let entry = sizes.entry(len).or_default();
+	*entry = entry.saturating_add(1);
+	sizes
+}
+
+/// Decode a record of the map as a JSON object with `key` and `val` members.
+#[must_use]
+pub fn decode(map: &str, key: &[u8], val: &[u8]) -> Value {
+	json!({
+		"key": decode_key(map, key),
+		"val": decode_val(val),
+	})
+}
+
+/// Decode a key of the map. Keys of maps with a known layout become an object
+/// of their named components; other keys become an array of the components
+/// between separators.
+#[must_use]
+pub fn decode_key(map: &str, key: &[u8]) -> Value {
+	let Some((_, layout)) = LAYOUTS.iter().find(|(name, _)| *name == map) else {
+		return key.split(|&b| b == SEP).map(decode_part).collect();
+	};
+
+	let mut out = Object::new();
+	let mut rest = key;
+	for part in *layout {
+		let (name, value, len) = match *part {
+			| Str(name) => {
+				let len = rest.iter().position(|&b| b == SEP).unwrap_or(rest.len());
+				(name, decode_part(&rest[..len]), len)
+			},
+			| U64(name) => match rest.first_chunk::<8>() {
+				| Some(bytes) => (name, u64::from_be_bytes(*bytes).into(), bytes.len()),
+				| None => break,
+			},
+		};
+
+		out.insert(name.into(), value);
+		rest = &rest[len..];
+		rest = rest.strip_prefix(&[SEP]).unwrap_or(rest);
+	}
+
+	if !rest.is_empty() {
+		out.insert("rest".into(), decode_part(rest));
+	}
+
+	out.into()
+}
+
+/// Decode a value: JSON values are embedded, text becomes a string, and
+/// anything else is base64-encoded.
+#[must_use]
+pub fn decode_val(val: &[u8]) -> Value {
+	if val.is_empty() {
+		return Value::Null;
+	}
+
+	serde_json::from_slice(val).unwrap_or_else(|_| decode_part(val))
+}
+
+fn decode_part(part: &[u8]) -> Value {
+	match (std::str::from_utf8(part), part.first_chunk::<8>()) {
+		| (Ok(text), _) if !text.chars().any(char::is_control) => text.into(),
+		| (_, Some(bytes)) if part.len() == bytes.len() => u64::from_be_bytes(*bytes).into(),
+		| _ => json!({ "base64": STANDARD.encode(part) }),
+	}
+}
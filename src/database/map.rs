@@ -2,6 +2,7 @@
 pub mod compact;
 mod contains;
 mod count;
+mod expire;
 mod get;
 mod get_batch;
 mod insert;
@@ -13,6 +14,7 @@
 mod qry;
 mod qry_batch;
 mod remove;
+mod reseal;
 mod rev_keys;
 mod rev_keys_from;
 mod rev_keys_prefix;
@@ -24,28 +26,39 @@
 mod stream_prefix;
 
 use std::{
+	borrow::Cow,
 	convert::AsRef,
 	ffi::CStr,
 	fmt,
 	fmt::{Debug, Display},
 	future::Future,
 	pin::Pin,
-	sync::Arc,
+	sync::{Arc, RwLock, RwLockReadGuard},
+	time::Duration,
 };
 
-use conduwuit::Result;
+use conduwuit::{Err, Result, err, error, metrics::MapMetrics};
 use rocksdb::{AsColumnFamilyRef, ColumnFamily, ReadOptions, WriteOptions};
 
 pub(crate) use self::options::{
-	cache_iter_options_default, cache_read_options_default, iter_options_default,
+	cache_iter_options_at, cache_iter_options_default, cache_read_options_at,
+	cache_read_options_default, iter_options_at, iter_options_default, read_options_at,
 	read_options_default, write_options_default,
 };
 pub use self::{get_batch::Get, qry_batch::Qry};
-use crate::{Engine, watchers::Watchers};
+use crate::{
+	Changes, Cipher, Engine, Handle, Slice, changes::Capture, engine::descriptor::Descriptor,
+	expiry, watchers::Watchers,
+};
 
 pub struct Map {
 	name: &'static str,
 	watchers: Watchers,
+	metrics: Arc<MapMetrics>,
+	cipher: Option<Arc<Cipher>>,
+	changes: Option<Arc<Changes>>,
+	expiry: Option<Duration>,
+	barrier: RwLock<()>,
 	cf: Arc<ColumnFamily>,
 	db: Arc<Engine>,
 	read_options: ReadOptions,
@@ -54,10 +67,16 @@ pub struct Map {
 }
 
 impl Map {
-	pub(crate) fn open(db: &Arc<Engine>, name: &'static str) -> Result<Arc<Self>> {
+	pub(crate) fn open(db: &Arc<Engine>, desc: &Descriptor) -> Result<Arc<Self>> {
+		let name = desc.name;
 		Ok(Arc::new(Self {
 			name,
 			watchers: Watchers::default(),
+			metrics: db.ctx.server.metrics.map(name),
+			cipher: open::cipher(db, name),
+			changes: open::changes(db, name),
+			expiry: (desc.expiry > 0).then(|| Duration::from_secs(desc.expiry)),
+			barrier: RwLock::default(),
 			cf: open::open(db, name),
 			db: db.clone(),
 			read_options: read_options_default(db),
@@ -88,9 +107,100 @@ pub fn property(&self, name: &str) -> Result<String> { self.db.property(&self.cf
 	#[inline]
 	pub fn name(&self) -> &str { self.name }
 
+	#[inline]
+	pub fn metrics(&self) -> &Arc<MapMetrics> { &self.metrics }
+
+	/// Whether values in this map are encrypted at rest.
+	#[inline]
+	pub fn is_encrypted(&self) -> bool { self.cipher.is_some() }
+
 	#[inline]
 	pub(crate) fn db(&self) -> &Arc<Engine> { &self.db }
 
+	/// Writes to a read-only or secondary database are refused here rather
+	/// than failing in the engine. Requests which write are refused before
+	/// reaching the database on such a server, so a write refused here fails
+	/// like any other database error.
+	#[inline]
+	pub(crate) fn check_writable(&self) -> Result {
+		if self.db.is_read_only() {
+			return Err!(Database(error!("Refused write to {self} on read-only database.")));
+		}
+
+		Ok(())
+	}
+
+	/// Period after which records written to this map expire.
+	#[inline]
+	pub fn expiry(&self) -> Option<Duration> { self.expiry }
+
+	/// Whether writes to this map are published for change-data capture.
+	#[inline]
+	pub fn is_captured(&self) -> bool { self.changes.is_some() }
+
+	/// Held shared by every write to this map. A maintenance pass which
+	/// rewrites records in place holds it exclusively around each batch so no
+	/// write lands between reading a record and rewriting it.
+	#[inline]
+	fn barrier(&self) -> RwLockReadGuard<'_, ()> {
+		self.barrier.read().expect("write barrier not poisoned")
+	}
+
+	/// Begin a write of the given keys which is published for change-data
+	/// capture, if this map is captured.
+	#[inline]
+	fn capture<'k, I>(&self, keys: I) -> Option<Capture<'_>>
+	where
+		I: IntoIterator<Item = &'k [u8]>,
+	{
+		self.changes.as_deref().map(|changes| changes.capture(keys))
+	}
+
+	#[inline]
+	pub(crate) fn cipher(&self) -> Option<&Cipher> { self.cipher.as_deref() }
+
+	/// Prepare a value for writing to this map: frame it with its expiry time
+	/// if records expire, then encrypt it if the map is encrypted.
+	#[inline]
+	fn seal<'a>(&self, val: &'a Slice) -> Cow<'a, Slice> {
+		let val = match self.expiry {
+			| Some(period) => Cow::Owned(expiry::frame(val, period)),
+			| None => Cow::Borrowed(val),
+		};
+
+		let Some(cipher) = self.cipher() else {
+			return val;
+		};
+
+		cipher
+			.encrypt(self.name, &val)
+			.map(Cow::Owned)
+			.expect("database encryption error")
+	}
+
+	/// Recover a value read from this map, reversing seal(). Expired records
+	/// are reported as not found.
+	#[inline]
+	fn unseal<'a>(&self, handle: Handle<'a>) -> Result<Handle<'a>> {
+		let handle = match self.cipher() {
+			| Some(cipher) => cipher
+				.decrypt(self.name, &handle)?
+				.map_or(handle, Handle::from),
+			| None => handle,
+		};
+
+		if self.expiry.is_none() {
+			return Ok(handle);
+		}
+
+		match expiry::unframe(&handle) {
+			| None => Ok(handle),
+			| Some((expires, _)) if expiry::is_expired(expires, expiry::now()) =>
+				Err(err!(Request(NotFound("Not found in database")))),
+			| Some((_, val)) => Ok(Handle::from(val.to_vec())),
+		}
+	}
+
 	#[inline]
 	pub(crate) fn cf(&self) -> impl AsColumnFamilyRef + '_ { &*self.cf }
 }
@@ -0,0 +1,148 @@
+use conduwuit::{Result, debug, implement, metrics::MapMetrics};
+use rocksdb::WriteBatchWithTransaction;
+
+use crate::{
+	changes::Op,
+	expiry,
+	util::{or_else, result},
+};
+
+/// Key prefix in the default column recording when a map was first found to
+/// expire records, from which values written before then expire.
+const SINCE_PREFIX: &[u8] = b"expiry_since\xFF";
+
+/// Number of expired records removed per write batch.
+const BATCH_SIZE: usize = 1024;
+
+/// Remove every expired record from this map. This is a thread-blocking call
+/// which walks the entire map. Each record is checked again while writers are
+/// held off, so one rewritten after it was found expired is kept. Returns the
+/// number of records removed.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self), fields(%self), level = "debug")]
+pub fn expire_blocking(&self) -> Result<usize> {
+	let Some(period) = self.expiry else {
+		return Ok(0);
+	};
+
+	if self.db.is_read_only() {
+		return Ok(0);
+	}
+
+	let now = expiry::now();
+	let unframed_expires = self.expiry_since(now)?.saturating_add(period.as_secs());
+
+	let mut count: usize = 0;
+	let mut expired = Vec::with_capacity(BATCH_SIZE);
+	let mut plain = Vec::new();
+	let mut iter = self.db.db.raw_iterator_cf(&self.cf());
+	iter.seek_to_first();
+	while let Some((key, val)) = iter.item() {
+		if self.is_expired(val, now, unframed_expires, &mut plain)? {
+			expired.push(key.to_vec());
+		}
+
+		if expired.len() >= BATCH_SIZE {
+			count = count.saturating_add(self.expire_batch(&expired, now, unframed_expires)?);
+			expired.clear();
+		}
+
+		iter.next();
+	}
+
+	result(iter.status())?;
+	drop(iter);
+	count = count.saturating_add(self.expire_batch(&expired, now, unframed_expires)?);
+
+	MapMetrics::add(&self.metrics.expired, count);
+	if count > 0 {
+		debug!(records = count, "Expired records in {self}.");
+	}
+
+	Ok(count)
+}
+
+/// Remove each record which is still expired, while holding off all other
+/// writes to this map. Returns the number removed.
+#[implement(super::Map)]
+fn expire_batch(&self, keys: &[Vec<u8>], now: u64, unframed_expires: u64) -> Result<usize> {
+	if keys.is_empty() {
+		return Ok(0);
+	}
+
+	let _barrier = self.barrier.write().expect("write barrier not poisoned");
+
+	let mut plain = Vec::new();
+	let mut expired = Vec::with_capacity(keys.len());
+	for key in keys {
+		let Some(val) = result(self.db.db.get_pinned_cf(&self.cf(), key))? else {
+			continue;
+		};
+
+		if self.is_expired(&val, now, unframed_expires, &mut plain)? {
+			expired.push(key.as_slice());
+		}
+	}
+
+	let mut batch = WriteBatchWithTransaction::<false>::default();
+	for key in &expired {
+		batch.delete_cf(&self.cf(), key);
+	}
+
+	let mut capture = self.capture(expired.iter().copied());
+	result(self.db.db.write_opt(batch, &self.write_options))?;
+	if let Some(capture) = capture.as_mut() {
+		for key in &expired {
+			capture.publish(self.name, Op::Delete, key, None);
+		}
+	}
+
+	MapMetrics::add(&self.metrics.deletes, expired.len());
+	if !self.db.corked() {
+		self.db.flush()?;
+	}
+
+	Ok(expired.len())
+}
+
+/// Whether a stored value has expired. Values without an expiry frame expire
+/// at `unframed_expires`.
+#[implement(super::Map)]
+fn is_expired(
+	&self,
+	val: &[u8],
+	now: u64,
+	unframed_expires: u64,
+	plain: &mut Vec<u8>,
+) -> Result<bool> {
+	let val = match self.cipher() {
+		| Some(cipher) if cipher.decrypt_into(self.name, val, plain)? => plain.as_slice(),
+		| _ => val,
+	};
+
+	let expires = expiry::unframe(val).map_or(unframed_expires, |(expires, _)| expires);
+
+	Ok(expiry::is_expired(expires, now))
+}
+
+/// When this map was first found to expire records, recording now if it has
+/// not been before.
+#[implement(super::Map)]
+fn expiry_since(&self, now: u64) -> Result<u64> {
+	let marker = [SINCE_PREFIX, self.name.as_bytes()].concat();
+	let since = result(self.db.db.get(&marker))?
+		.as_deref()
+		.and_then(|val| val.try_into().ok())
+		.map(u64::from_be_bytes);
+
+	if let Some(since) = since {
+		return Ok(since);
+	}
+
+	self.db
+		.db
+		.put(&marker, now.to_be_bytes())
+		.or_else(or_else)?;
+
+	Ok(now)
+}
@@ -1,12 +1,14 @@
 use std::{convert::AsRef, fmt::Debug, sync::Arc};
 
-use conduwuit::{Err, Result, err, implement, utils::result::MapExpect};
+use conduwuit::{
+	Err, Error, Result, err, implement, metrics::MapMetrics, utils::result::MapExpect,
+};
 use futures::{Future, FutureExt, TryFutureExt, future::ready};
 use rocksdb::{DBPinnableSlice, ReadOptions};
 use tokio::task;
 
 use crate::{
-	Handle,
+	Handle, Snapshot,
 	util::{is_incomplete, map_err, or_else},
 };
 
@@ -25,6 +27,7 @@ pub fn get<K>(
 
 	let cached = self.get_cached(key);
 	if matches!(cached, Err(_) | Ok(Some(_))) {
+		self.count_cached(&cached);
 		return task::consume_budget()
 			.map(move |()| cached.map_expect("data found in cache"))
 			.boxed();
@@ -34,6 +37,47 @@ pub fn get<K>(
 	let cmd = Get {
 		map: self.clone(),
 		key: [key.as_ref().into()].into(),
+		snapshot: None,
+		res: None,
+	};
+
+	self.db
+		.pool
+		.execute_get(cmd)
+		.and_then(|mut res| ready(res.remove(0)))
+		.boxed()
+}
+
+/// Fetch a value from the database as of a snapshot, returning a
+/// reference-handle asynchronously. The key is referenced directly to perform
+/// the query.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, key), fields(%self), level = "trace")]
+pub fn get_at<K>(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	key: &K,
+) -> impl Future<Output = Result<Handle<'_>>> + Send + use<'_, K>
+where
+	K: AsRef<[u8]> + Debug + ?Sized,
+{
+	use crate::pool::Get;
+
+	debug_assert!(snapshot.is_engine(&self.db), "snapshot must be of this database");
+
+	let cached = self.get_cached_at(snapshot, key);
+	if matches!(cached, Err(_) | Ok(Some(_))) {
+		self.count_cached(&cached);
+		return task::consume_budget()
+			.map(move |()| cached.map_expect("data found in cache"))
+			.boxed();
+	}
+
+	debug_assert!(matches!(cached, Ok(None)), "expected status Incomplete");
+	let cmd = Get {
+		map: self.clone(),
+		key: [key.as_ref().into()].into(),
+		snapshot: Some(snapshot.clone()),
 		res: None,
 	};
 
@@ -52,7 +96,9 @@ pub(crate) fn get_cached<K>(&self, key: &K) -> Result<Option<Handle<'_>>>
 	K: AsRef<[u8]> + Debug + ?Sized,
 {
 	let res = self.get_blocking_opts(key, &self.cache_read_options);
-	cached_handle_from(res)
+	cached_handle_from(res)?
+		.map(|handle| self.unseal(handle))
+		.transpose()
 }
 
 /// Fetch a value from the database into cache, returning a reference-handle.
@@ -64,8 +110,47 @@ pub fn get_blocking<K>(&self, key: &K) -> Result<Handle<'_>>
 where
 	K: AsRef<[u8]> + ?Sized,
 {
+	MapMetrics::incr(&self.metrics.gets);
 	let res = self.get_blocking_opts(key, &self.read_options);
-	handle_from(res)
+	handle_from(res).and_then(|handle| self.unseal(handle))
+}
+
+/// Fetch a value from the cache as of a snapshot without I/O.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot, key), name = "cache", level = "trace")]
+pub(crate) fn get_cached_at<K>(&self, snapshot: &Snapshot, key: &K) -> Result<Option<Handle<'_>>>
+where
+	K: AsRef<[u8]> + Debug + ?Sized,
+{
+	let read_options = super::cache_read_options_at(&self.db, snapshot);
+	let res = self.get_blocking_opts(key, &read_options);
+	cached_handle_from(res)?
+		.map(|handle| self.unseal(handle))
+		.transpose()
+}
+
+/// Fetch a value from the database as of a snapshot into cache, returning a
+/// reference-handle. This is a thread-blocking call.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot, key), name = "blocking", level = "trace")]
+pub fn get_blocking_at<K>(&self, snapshot: &Snapshot, key: &K) -> Result<Handle<'_>>
+where
+	K: AsRef<[u8]> + ?Sized,
+{
+	MapMetrics::incr(&self.metrics.gets);
+	let read_options = super::read_options_at(&self.db, snapshot);
+	let res = self.get_blocking_opts(key, &read_options);
+	handle_from(res).and_then(|handle| self.unseal(handle))
+}
+
+/// Count a point query answered without dispatch to the pool. A value known
+/// not to exist is a hit; failures were counted when they occurred.
+#[implement(super::Map)]
+fn count_cached(&self, cached: &Result<Option<Handle<'_>>>) {
+	MapMetrics::incr(&self.metrics.gets);
+	if cached.as_ref().is_ok() || cached.as_ref().is_err_and(Error::is_not_found) {
+		MapMetrics::incr(&self.metrics.cache_hits);
+	}
 }
 
 #[implement(super::Map)]
@@ -77,7 +162,19 @@ fn get_blocking_opts<K>(
 where
 	K: AsRef<[u8]> + ?Sized,
 {
-	self.db.db.get_pinned_cf_opt(&self.cf(), key, read_options)
+	self.db
+		.db
+		.get_pinned_cf_opt(&self.cf(), key, read_options)
+		.inspect(|res| {
+			if res.is_none() {
+				MapMetrics::incr(&self.metrics.not_found);
+			}
+		})
+		.inspect_err(|e| {
+			if !is_incomplete(e) {
+				MapMetrics::incr(&self.metrics.errors);
+			}
+		})
 }
 
 #[inline]
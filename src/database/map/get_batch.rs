@@ -2,6 +2,7 @@
 
 use conduwuit::{
 	Result, implement,
+	metrics::MapMetrics,
 	utils::{
 		IterStream,
 		stream::{WidebandExt, automatic_amplification, automatic_width},
@@ -11,7 +12,7 @@
 use rocksdb::{DBPinnableSlice, ReadOptions};
 
 use super::get::{cached_handle_from, handle_from};
-use crate::Handle;
+use crate::{Handle, Snapshot, util::is_incomplete};
 
 pub trait Get<'a, K, S>
 where
@@ -51,6 +52,7 @@ pub(crate) fn get_batch<'a, S, K>(
 			self.db.pool.execute_get(Get {
 				map: self.clone(),
 				key: chunk.iter().map(AsRef::as_ref).map(Into::into).collect(),
+				snapshot: None,
 				res: None,
 			})
 		})
@@ -70,6 +72,7 @@ pub(crate) fn get_batch_cached<'a, I, K>(
 {
 	self.get_batch_blocking_opts(keys, &self.cache_read_options)
 		.map(cached_handle_from)
+		.map(|res| res?.map(|handle| self.unseal(handle)).transpose())
 }
 
 #[implement(super::Map)]
@@ -82,8 +85,28 @@ pub(crate) fn get_batch_blocking<'a, I, K>(
 	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
 	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
 {
+	MapMetrics::add(&self.metrics.gets, keys.len());
 	self.get_batch_blocking_opts(keys, &self.read_options)
 		.map(handle_from)
+		.map(|res| res.and_then(|handle| self.unseal(handle)))
+}
+
+#[implement(super::Map)]
+#[tracing::instrument(name = "batch_blocking", level = "trace", skip_all)]
+pub(crate) fn get_batch_blocking_at<'a, I, K>(
+	&self,
+	snapshot: &Snapshot,
+	keys: I,
+) -> impl Iterator<Item = Result<Handle<'_>>> + Send + use<'_, I, K>
+where
+	I: Iterator<Item = &'a K> + ExactSizeIterator + Send,
+	K: AsRef<[u8]> + Send + ?Sized + Sync + 'a,
+{
+	MapMetrics::add(&self.metrics.gets, keys.len());
+	let read_options = super::read_options_at(&self.db, snapshot);
+	self.get_batch_blocking_opts(keys, &read_options)
+		.map(handle_from)
+		.map(|res| res.and_then(|handle| self.unseal(handle)))
 }
 
 #[implement(super::Map)]
@@ -104,4 +127,9 @@ fn get_batch_blocking_opts<'a, I, K>(
 		.db
 		.batched_multi_get_cf_opt(&self.cf(), keys, SORTED, read_options)
 		.into_iter()
+		.inspect(|res| match res {
+			| Ok(None) => MapMetrics::incr(&self.metrics.not_found),
+			| Err(e) if !is_incomplete(e) => MapMetrics::incr(&self.metrics.errors),
+			| _ => {},
+		})
 }
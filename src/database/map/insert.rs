@@ -5,11 +5,12 @@
 
 use std::{convert::AsRef, fmt::Debug, io::Write};
 
-use conduwuit::{arrayvec::ArrayVec, implement};
+use conduwuit::{arrayvec::ArrayVec, implement, metrics::MapMetrics};
 use rocksdb::WriteBatchWithTransaction;
 use serde::Serialize;
 
 use crate::{
+	changes::Op,
 	keyval::{KeyBuf, ValBuf},
 	ser,
 	util::or_else,
@@ -197,17 +198,26 @@ pub fn insert<K, V>(&self, key: &K, val: V)
 	K: AsRef<[u8]> + ?Sized,
 	V: AsRef<[u8]>,
 {
+	self.check_writable().expect("database insert error");
+
+	let _barrier = self.barrier();
+	let mut capture = self.capture([key.as_ref()]);
 	let write_options = &self.write_options;
 	self.db
 		.db
-		.put_cf_opt(&self.cf(), key, val, write_options)
+		.put_cf_opt(&self.cf(), key, self.seal(val.as_ref()), write_options)
 		.or_else(or_else)
 		.expect("database insert error");
 
+	if let Some(capture) = capture.as_mut() {
+		capture.publish(self.name, Op::Put, key.as_ref(), Some(val.as_ref()));
+	}
+
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
 	}
 
+	MapMetrics::incr(&self.metrics.puts);
 	self.watchers.wake(key.as_ref());
 }
 
@@ -219,11 +229,21 @@ pub fn insert_batch<'a, I, K, V>(&'a self, iter: I)
 	K: AsRef<[u8]> + Sized + Debug + 'a,
 	V: AsRef<[u8]> + Sized + 'a,
 {
+	self.check_writable().expect("database insert batch error");
+
+	let _barrier = self.barrier();
+	let mut captured = Vec::new();
 	let mut batch = WriteBatchWithTransaction::<false>::default();
 	for (key, val) in iter {
-		batch.put_cf(&self.cf(), key.as_ref(), val.as_ref());
+		batch.put_cf(&self.cf(), key.as_ref(), self.seal(val.as_ref()));
+		if self.changes.is_some() {
+			captured.push((key, val));
+		}
 	}
 
+	MapMetrics::add(&self.metrics.puts, batch.len());
+	let mut capture = self.capture(captured.iter().map(|(key, _)| key.as_ref()));
+
 	let write_options = &self.write_options;
 	self.db
 		.db
@@ -231,6 +251,12 @@ pub fn insert_batch<'a, I, K, V>(&'a self, iter: I)
 		.or_else(or_else)
 		.expect("database insert batch error");
 
+	if let Some(capture) = capture.as_mut() {
+		for (key, val) in &captured {
+			capture.publish(self.name, Op::Put, key.as_ref(), Some(val.as_ref()));
+		}
+	}
+
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
 	}
@@ -6,8 +6,8 @@
 use serde::Deserialize;
 use tokio::task;
 
-use super::stream::is_cached;
-use crate::{keyval, keyval::Key, stream};
+use super::stream::{is_cached, is_cached_at};
+use crate::{Snapshot, keyval, keyval::Key, stream};
 
 #[implement(super::Map)]
 pub fn keys<'a, K>(self: &'a Arc<Self>) -> impl Stream<Item = Result<Key<'_, K>>> + Send
@@ -49,3 +49,51 @@ pub fn raw_keys(self: &Arc<Self>) -> impl Stream<Item = Result<Key<'_>>> + Send
 		.try_flatten()
 		.boxed()
 }
+
+#[implement(super::Map)]
+pub fn keys_at<'a, K>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+) -> impl Stream<Item = Result<Key<'_, K>>> + Send
+where
+	K: Deserialize<'a> + Send,
+{
+	self.raw_keys_at(snapshot)
+		.map(keyval::result_deserialize_key::<K>)
+}
+
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot), fields(%self), level = "trace")]
+pub fn raw_keys_at(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+) -> impl Stream<Item = Result<Key<'_>>> + Send {
+	use crate::pool::Seek;
+
+	let opts = super::iter_options_at(&self.db, snapshot);
+	let state = stream::State::new_at(self, opts, snapshot);
+	if is_cached_at(self, snapshot) {
+		let state = state.init_fwd(None);
+		return task::consume_budget()
+			.map(move |()| stream::Keys::<'_>::from(state))
+			.into_stream()
+			.flatten()
+			.boxed();
+	}
+
+	let seek = Seek {
+		map: self.clone(),
+		dir: Direction::Forward,
+		state: crate::pool::into_send_seek(state),
+		key: None,
+		res: None,
+	};
+
+	self.db
+		.pool
+		.execute_iter(seek)
+		.ok_into::<stream::Keys<'_>>()
+		.into_stream()
+		.try_flatten()
+		.boxed()
+}
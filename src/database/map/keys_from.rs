@@ -5,8 +5,9 @@
 use rocksdb::Direction;
 use serde::{Deserialize, Serialize};
 
-use super::stream_from::is_cached;
+use super::stream_from::{is_cached, is_cached_at};
 use crate::{
+	Snapshot,
 	keyval::{Key, result_deserialize_key, serialize_key},
 	stream,
 };
@@ -81,3 +82,38 @@ pub fn raw_keys_from<P>(
 		.try_flatten()
 		.boxed()
 }
+
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot, from), fields(%self), level = "trace")]
+pub fn raw_keys_from_at<P>(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	from: &P,
+) -> impl Stream<Item = Result<Key<'_>>> + Send + use<'_, P>
+where
+	P: AsRef<[u8]> + ?Sized + Debug,
+{
+	use crate::pool::Seek;
+
+	let opts = super::iter_options_at(&self.db, snapshot);
+	let state = stream::State::new_at(self, opts, snapshot);
+	if is_cached_at(self, snapshot, from) {
+		return stream::Keys::<'_>::from(state.init_fwd(from.as_ref().into())).boxed();
+	}
+
+	let seek = Seek {
+		map: self.clone(),
+		dir: Direction::Forward,
+		key: Some(from.as_ref().into()),
+		state: crate::pool::into_send_seek(state),
+		res: None,
+	};
+
+	self.db
+		.pool
+		.execute_iter(seek)
+		.ok_into::<stream::Keys<'_>>()
+		.into_stream()
+		.try_flatten()
+		.boxed()
+}
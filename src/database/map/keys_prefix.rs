@@ -4,7 +4,10 @@
 use futures::{Stream, StreamExt, TryStreamExt, future};
 use serde::{Deserialize, Serialize};
 
-use crate::keyval::{Key, result_deserialize_key, serialize_key};
+use crate::{
+	Snapshot,
+	keyval::{Key, result_deserialize_key, serialize_key},
+};
 
 #[implement(super::Map)]
 pub fn keys_prefix<'a, K, P>(
@@ -57,3 +60,45 @@ pub fn raw_keys_prefix<'a, P>(
 	self.raw_keys_from(prefix)
 		.try_take_while(|k: &Key<'_>| future::ok(k.starts_with(prefix.as_ref())))
 }
+
+#[implement(super::Map)]
+pub fn keys_prefix_at<'a, K, P>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	prefix: &P,
+) -> impl Stream<Item = Result<Key<'_, K>>> + Send + use<'a, K, P>
+where
+	P: Serialize + ?Sized + Debug,
+	K: Deserialize<'a> + Send,
+{
+	self.keys_prefix_raw_at(snapshot, prefix)
+		.map(result_deserialize_key::<K>)
+}
+
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot), level = "trace")]
+pub fn keys_prefix_raw_at<P>(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	prefix: &P,
+) -> impl Stream<Item = Result<Key<'_>>> + Send + use<'_, P>
+where
+	P: Serialize + ?Sized + Debug,
+{
+	let key = serialize_key(prefix).expect("failed to serialize query key");
+	self.raw_keys_from_at(snapshot, &key)
+		.try_take_while(move |k: &Key<'_>| future::ok(k.starts_with(&key)))
+}
+
+#[implement(super::Map)]
+pub fn raw_keys_prefix_at<'a, P>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	prefix: &'a P,
+) -> impl Stream<Item = Result<Key<'_>>> + Send + 'a
+where
+	P: AsRef<[u8]> + ?Sized + Debug + Sync + 'a,
+{
+	self.raw_keys_from_at(snapshot, prefix)
+		.try_take_while(|k: &Key<'_>| future::ok(k.starts_with(prefix.as_ref())))
+}
@@ -2,7 +2,7 @@
 
 use rocksdb::ColumnFamily;
 
-use crate::Engine;
+use crate::{Changes, Cipher, Engine};
 
 pub(super) fn open(db: &Arc<Engine>, name: &str) -> Arc<ColumnFamily> {
 	let bounded_arc = db.cf(name);
@@ -32,3 +32,19 @@ pub(super) fn open(db: &Arc<Engine>, name: &str) -> Arc<ColumnFamily> {
 	// member along with this handle in `Map`, that is prevented.
 	unsafe { Arc::from_raw(cf_ptr) }
 }
+
+/// The cipher for values in this map when it is configured for encryption.
+pub(super) fn cipher(db: &Arc<Engine>, name: &str) -> Option<Arc<Cipher>> {
+	let config = &db.ctx.server.config;
+	let encrypted = config.database_encrypted_maps.iter().any(|map| map == name);
+
+	db.cipher.clone().filter(|_| encrypted)
+}
+
+/// The change-data capture publisher when writes to this map are captured.
+pub(super) fn changes(db: &Arc<Engine>, name: &str) -> Option<Arc<Changes>> {
+	let config = &db.ctx.server.config;
+	let captured = config.database_cdc_maps.iter().any(|map| map == name);
+
+	db.changes.clone().filter(|_| captured)
+}
@@ -2,7 +2,7 @@
 
 use rocksdb::{ReadOptions, ReadTier, WriteOptions};
 
-use crate::Engine;
+use crate::{Engine, Snapshot};
 
 #[inline]
 pub(crate) fn cache_iter_options_default(db: &Arc<Engine>) -> ReadOptions {
@@ -39,5 +39,33 @@ pub(crate) fn read_options_default(db: &Arc<Engine>) -> ReadOptions {
 	options
 }
 
+#[inline]
+pub(crate) fn cache_iter_options_at(db: &Arc<Engine>, snapshot: &Snapshot) -> ReadOptions {
+	let mut options = cache_iter_options_default(db);
+	options.set_snapshot(snapshot.inner());
+	options
+}
+
+#[inline]
+pub(crate) fn iter_options_at(db: &Arc<Engine>, snapshot: &Snapshot) -> ReadOptions {
+	let mut options = iter_options_default(db);
+	options.set_snapshot(snapshot.inner());
+	options
+}
+
+#[inline]
+pub(crate) fn cache_read_options_at(db: &Arc<Engine>, snapshot: &Snapshot) -> ReadOptions {
+	let mut options = cache_read_options_default(db);
+	options.set_snapshot(snapshot.inner());
+	options
+}
+
+#[inline]
+pub(crate) fn read_options_at(db: &Arc<Engine>, snapshot: &Snapshot) -> ReadOptions {
+	let mut options = read_options_default(db);
+	options.set_snapshot(snapshot.inner());
+	options
+}
+
 #[inline]
 pub(crate) fn write_options_default(_db: &Arc<Engine>) -> WriteOptions { WriteOptions::default() }
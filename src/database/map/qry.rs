@@ -4,7 +4,7 @@
 use futures::Future;
 use serde::Serialize;
 
-use crate::{Handle, keyval::KeyBuf, ser};
+use crate::{Handle, Snapshot, keyval::KeyBuf, ser};
 
 /// Fetch a value from the database into cache, returning a reference-handle
 /// asynchronously. The key is serialized into an allocated buffer to perform
@@ -22,6 +22,24 @@ pub fn qry<K>(
 	self.bqry(key, &mut buf)
 }
 
+/// Fetch a value from the database as of a snapshot, returning a
+/// reference-handle asynchronously. The key is serialized into an allocated
+/// buffer to perform the query.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot), level = "trace")]
+pub fn qry_at<K>(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	key: &K,
+) -> impl Future<Output = Result<Handle<'_>>> + Send + use<'_, K>
+where
+	K: Serialize + ?Sized + Debug,
+{
+	let mut buf = KeyBuf::new();
+	let key = ser::serialize(&mut buf, key).expect("failed to serialize query key");
+	self.get_at(snapshot, key)
+}
+
 /// Fetch a value from the database into cache, returning a reference-handle
 /// asynchronously. The key is serialized into a fixed-sized buffer to perform
 /// the query. The maximum size is supplied as const generic parameter.
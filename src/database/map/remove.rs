@@ -1,9 +1,9 @@
 use std::{convert::AsRef, fmt::Debug, io::Write};
 
-use conduwuit::{arrayvec::ArrayVec, implement};
+use conduwuit::{arrayvec::ArrayVec, implement, metrics::MapMetrics};
 use serde::Serialize;
 
-use crate::{keyval::KeyBuf, ser, util::or_else};
+use crate::{changes::Op, keyval::KeyBuf, ser, util::or_else};
 
 #[implement(super::Map)]
 #[inline]
@@ -42,6 +42,10 @@ pub fn remove<K>(&self, key: &K)
 where
 	K: AsRef<[u8]> + ?Sized + Debug,
 {
+	self.check_writable().expect("database remove error");
+
+	let _barrier = self.barrier();
+	let mut capture = self.capture([key.as_ref()]);
 	let write_options = &self.write_options;
 	self.db
 		.db
@@ -49,6 +53,12 @@ pub fn remove<K>(&self, key: &K)
 		.or_else(or_else)
 		.expect("database remove error");
 
+	if let Some(capture) = capture.as_mut() {
+		capture.publish(self.name, Op::Delete, key.as_ref(), None);
+	}
+
+	MapMetrics::incr(&self.metrics.deletes);
+
 	if !self.db.corked() {
 		self.db.flush().expect("database flush error");
 	}
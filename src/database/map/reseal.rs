@@ -0,0 +1,81 @@
+use conduwuit::{Result, implement, info};
+use rocksdb::WriteBatchWithTransaction;
+
+use crate::util::result;
+
+/// Number of records rewritten per write batch.
+const BATCH_SIZE: usize = 1024;
+
+/// Re-encrypt every value in this map which is not already sealed under the
+/// active key, including values written before encryption was enabled. This
+/// is a thread-blocking call which walks the entire map; run it after adding a
+/// new key so older keys can be retired.
+///
+/// Writers are held off while each batch is rewritten, and a record is only
+/// rewritten if it still holds the value which was read, so writes made during
+/// the run are never overwritten.
+#[implement(super::Map)]
+#[tracing::instrument(skip(self), fields(%self), level = "info")]
+pub fn reseal_blocking(&self) -> Result<usize> {
+	let Some(cipher) = self.cipher() else {
+		return Ok(0);
+	};
+
+	self.check_writable()?;
+
+	let mut count: usize = 0;
+	let mut stale = Vec::with_capacity(BATCH_SIZE);
+	let mut iter = self.db.db.raw_iterator_cf(&self.cf());
+	iter.seek_to_first();
+	while let Some((key, val)) = iter.item() {
+		if !cipher.is_current(val) {
+			stale.push((key.to_vec(), val.to_vec()));
+		}
+
+		if stale.len() >= BATCH_SIZE {
+			count = count.saturating_add(self.reseal_batch(&stale)?);
+			stale.clear();
+		}
+
+		iter.next();
+	}
+
+	result(iter.status())?;
+	drop(iter);
+	count = count.saturating_add(self.reseal_batch(&stale)?);
+
+	self.db.flush()?;
+	info!(records = count, "Re-encrypted values in {self}.");
+
+	Ok(count)
+}
+
+/// Rewrite each record which still holds the value it was read with, while
+/// holding off all other writes to this map. Returns the number rewritten.
+#[implement(super::Map)]
+fn reseal_batch(&self, stale: &[(Vec<u8>, Vec<u8>)]) -> Result<usize> {
+	let Some(cipher) = self.cipher() else {
+		return Ok(0);
+	};
+
+	let _barrier = self.barrier.write().expect("write barrier not poisoned");
+
+	let mut batch = WriteBatchWithTransaction::<false>::default();
+	for (key, read) in stale {
+		let current = result(self.db.db.get_pinned_cf(&self.cf(), key))?;
+		if current.as_deref() != Some(read.as_slice()) {
+			continue;
+		}
+
+		let plain = cipher.decrypt(self.name, read)?;
+		let val = plain.as_deref().unwrap_or(read);
+		batch.put_cf(&self.cf(), key, cipher.encrypt(self.name, val)?);
+	}
+
+	let count = batch.len();
+	if count > 0 {
+		result(self.db.db.write_opt(batch, &self.write_options))?;
+	}
+
+	Ok(count)
+}
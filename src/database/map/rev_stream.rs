@@ -6,7 +6,7 @@
 use serde::Deserialize;
 use tokio::task;
 
-use crate::{keyval, keyval::KeyVal, stream};
+use crate::{Snapshot, keyval, keyval::KeyVal, stream};
 
 /// Iterate key-value entries in the map from the end.
 ///
@@ -71,3 +71,71 @@ pub(super) fn is_cached(map: &Arc<super::Map>) -> bool {
 
 	!state.is_incomplete()
 }
+
+/// Iterate key-value entries in the map from the end as of a snapshot.
+///
+/// - Result is deserialized
+#[implement(super::Map)]
+pub fn rev_stream_at<'a, K, V>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+) -> impl Stream<Item = Result<KeyVal<'_, K, V>>> + Send
+where
+	K: Deserialize<'a> + Send,
+	V: Deserialize<'a> + Send,
+{
+	self.rev_raw_stream_at(snapshot)
+		.map(keyval::result_deserialize::<K, V>)
+}
+
+/// Iterate key-value entries in the map from the end as of a snapshot.
+///
+/// - Result is raw
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot), fields(%self), level = "trace")]
+pub fn rev_raw_stream_at(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+) -> impl Stream<Item = Result<KeyVal<'_>>> + Send {
+	use crate::pool::Seek;
+
+	let opts = super::iter_options_at(&self.db, snapshot);
+	let state = stream::State::new_at(self, opts, snapshot);
+	if is_cached_at(self, snapshot) {
+		let state = state.init_rev(None);
+		return task::consume_budget()
+			.map(move |()| stream::ItemsRev::<'_>::from(state))
+			.into_stream()
+			.flatten()
+			.boxed();
+	}
+
+	let seek = Seek {
+		map: self.clone(),
+		dir: Direction::Reverse,
+		state: crate::pool::into_send_seek(state),
+		key: None,
+		res: None,
+	};
+
+	self.db
+		.pool
+		.execute_iter(seek)
+		.ok_into::<stream::ItemsRev<'_>>()
+		.into_stream()
+		.try_flatten()
+		.boxed()
+}
+
+#[tracing::instrument(
+    name = "cached",
+    level = "trace",
+    skip_all,
+    fields(%map),
+)]
+pub(super) fn is_cached_at(map: &Arc<super::Map>, snapshot: &Snapshot) -> bool {
+	let opts = super::cache_iter_options_at(&map.db, snapshot);
+	let state = stream::State::new(map, opts).init_rev(None);
+
+	!state.is_incomplete()
+}
@@ -4,7 +4,10 @@
 use futures::{Stream, StreamExt, TryStreamExt, future};
 use serde::{Deserialize, Serialize};
 
-use crate::keyval::{KeyVal, result_deserialize, serialize_key};
+use crate::{
+	Snapshot,
+	keyval::{KeyVal, result_deserialize, serialize_key},
+};
 
 /// Iterate key-value entries in the map where the key matches a prefix.
 ///
@@ -75,3 +78,43 @@ pub fn rev_raw_stream_prefix<'a, P>(
 	self.rev_raw_stream_from(prefix)
 		.try_take_while(|(k, _): &KeyVal<'_>| future::ok(k.starts_with(prefix.as_ref())))
 }
+
+/// Iterate key-value entries in the map where the key matches a prefix as of
+/// a snapshot.
+///
+/// - Query is serialized
+/// - Result is deserialized
+#[implement(super::Map)]
+pub fn rev_stream_prefix_at<'a, K, V, P>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	prefix: &P,
+) -> impl Stream<Item = Result<KeyVal<'_, K, V>>> + Send + use<'a, K, V, P>
+where
+	P: Serialize + ?Sized + Debug,
+	K: Deserialize<'a> + Send,
+	V: Deserialize<'a> + Send,
+{
+	let key = serialize_key(prefix).expect("failed to serialize query key");
+	self.rev_raw_stream_from_at(snapshot, &key)
+		.try_take_while(move |(k, _): &KeyVal<'_>| future::ok(k.starts_with(&key)))
+		.map(result_deserialize::<K, V>)
+}
+
+/// Iterate key-value entries in the map where the key matches a prefix as of
+/// a snapshot.
+///
+/// - Query is raw
+/// - Result is raw
+#[implement(super::Map)]
+pub fn rev_raw_stream_prefix_at<'a, P>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	prefix: &'a P,
+) -> impl Stream<Item = Result<KeyVal<'_>>> + Send + 'a
+where
+	P: AsRef<[u8]> + ?Sized + Debug + Sync + 'a,
+{
+	self.rev_raw_stream_from_at(snapshot, prefix)
+		.try_take_while(|(k, _): &KeyVal<'_>| future::ok(k.starts_with(prefix.as_ref())))
+}
@@ -7,6 +7,7 @@
 use tokio::task;
 
 use crate::{
+	Snapshot,
 	keyval::{KeyVal, result_deserialize, serialize_key},
 	stream,
 };
@@ -120,3 +121,85 @@ pub(super) fn is_cached<P>(map: &Arc<super::Map>, from: &P) -> bool
 
 	!state.is_incomplete()
 }
+
+/// Iterate key-value entries in the map starting from lower-bound as of a
+/// snapshot.
+///
+/// - Query is serialized
+/// - Result is deserialized
+#[implement(super::Map)]
+pub fn stream_from_at<'a, K, V, P>(
+	self: &'a Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	from: &P,
+) -> impl Stream<Item = Result<KeyVal<'_, K, V>>> + Send + use<'a, K, V, P>
+where
+	P: Serialize + ?Sized + Debug,
+	K: Deserialize<'a> + Send,
+	V: Deserialize<'a> + Send,
+{
+	let key = serialize_key(from).expect("failed to serialize query key");
+	self.raw_stream_from_at(snapshot, &key)
+		.map(result_deserialize::<K, V>)
+}
+
+/// Iterate key-value entries in the map starting from lower-bound as of a
+/// snapshot.
+///
+/// - Query is raw
+/// - Result is raw
+#[implement(super::Map)]
+#[tracing::instrument(skip(self, snapshot, from), fields(%self), level = "trace")]
+pub fn raw_stream_from_at<P>(
+	self: &Arc<Self>,
+	snapshot: &Arc<Snapshot>,
+	from: &P,
+) -> impl Stream<Item = Result<KeyVal<'_>>> + Send + use<'_, P>
+where
+	P: AsRef<[u8]> + ?Sized + Debug,
+{
+	use crate::pool::Seek;
+
+	let opts = super::iter_options_at(&self.db, snapshot);
+	let state = stream::State::new_at(self, opts, snapshot);
+	if is_cached_at(self, snapshot, from) {
+		let state = state.init_fwd(from.as_ref().into());
+		return task::consume_budget()
+			.map(move |()| stream::Items::<'_>::from(state))
+			.into_stream()
+			.flatten()
+			.boxed();
+	}
+
+	let seek = Seek {
+		map: self.clone(),
+		dir: Direction::Forward,
+		key: Some(from.as_ref().into()),
+		state: crate::pool::into_send_seek(state),
+		res: None,
+	};
+
+	self.db
+		.pool
+		.execute_iter(seek)
+		.ok_into::<stream::Items<'_>>()
+		.into_stream()
+		.try_flatten()
+		.boxed()
+}
+
+#[tracing::instrument(
+    name = "cached",
+    level = "trace",
+    skip(map, snapshot, from),
+    fields(%map),
+)]
+pub(super) fn is_cached_at<P>(map: &Arc<super::Map>, snapshot: &Snapshot, from: &P) -> bool
+where
+	P: AsRef<[u8]> + ?Sized,
+{
+	let opts = super::cache_iter_options_at(&map.db, snapshot);
+	let state = stream::State::new(map, opts).init_fwd(from.as_ref().into());
+
+	!state.is_incomplete()
+}
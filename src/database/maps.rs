@@ -16,10 +16,24 @@ pub(super) fn open(db: &Arc<Engine>) -> Result<Maps> { open_list(db, MAPS) }
 #[tracing::instrument(name = "maps", level = "debug", skip_all)]
 pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	maps.iter()
-		.map(|desc| Ok((desc.name, Map::open(db, desc.name)?)))
+		.filter(|desc| !desc.deprecated || db.has_cf(desc.name))
+		.map(|desc| Ok((desc.name, Map::open(db, desc)?)))
 		.collect()
 }
 
+/// Column descriptors for the database.
+///
+/// Schema changes are declared here rather than by hand-written migrations:
+/// - Renaming a column: change `name` and list the previous name(s) in
+///   `renamed_from`; records are moved into the new column when the database is
+///   opened.
+/// - Removing a column: set `deprecated: true`. The column remains accessible
+///   through `Database::get` until the grace period configured by
+///   `rocksdb_deprecated_column_grace_period` elapses, after which it is
+///   dropped at startup. Remove the descriptor once the column is gone.
+///
+/// Setting `expiry` to a number of seconds makes records in the column expire
+/// that long after they are written; they are removed by a periodic sweep.
 pub(super) static MAPS: &[Descriptor] = &[
 	Descriptor {
 		name: "alias_roomid",
@@ -141,6 +155,10 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "referencedevents",
 		..descriptor::RANDOM
 	},
+	Descriptor {
+		name: "reportid_report",
+		..descriptor::SEQUENTIAL_SMALL
+	},
 	Descriptor {
 		name: "roomid_invitedcount",
 		..descriptor::RANDOM_SMALL
@@ -233,6 +251,7 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	},
 	Descriptor {
 		name: "servername_destination",
+		expiry: 60 * 60 * 24 * 7,
 		..descriptor::RANDOM_SMALL_CACHE
 	},
 	Descriptor {
@@ -241,6 +260,7 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	},
 	Descriptor {
 		name: "servername_override",
+		expiry: 60 * 60 * 24 * 7,
 		..descriptor::RANDOM_SMALL_CACHE
 	},
 	Descriptor {
@@ -328,6 +348,7 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	},
 	Descriptor {
 		name: "url_previews",
+		expiry: 60 * 60 * 24 * 30,
 		..descriptor::RANDOM
 	},
 	Descriptor {
@@ -340,6 +361,7 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	},
 	Descriptor {
 		name: "userdevicesessionid_uiaainfo",
+		expiry: 60 * 60 * 24,
 		..descriptor::RANDOM_SMALL
 	},
 	Descriptor {
@@ -382,10 +404,18 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 		name: "userid_presenceid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_restrictions",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_selfsigningkeyid",
 		..descriptor::RANDOM_SMALL
 	},
+	Descriptor {
+		name: "userid_shadowbanned",
+		..descriptor::RANDOM_SMALL
+	},
 	Descriptor {
 		name: "userid_usersigningkeyid",
 		..descriptor::RANDOM_SMALL
@@ -396,10 +426,12 @@ pub(super) fn open_list(db: &Arc<Engine>, maps: &[Descriptor]) -> Result<Maps> {
 	},
 	Descriptor {
 		name: "openidtoken_expiresatuserid",
+		expiry: 60 * 60 * 24 * 7,
 		..descriptor::RANDOM_SMALL
 	},
 	Descriptor {
 		name: "logintoken_expiresatuserid",
+		expiry: 60 * 60 * 24 * 7,
 		..descriptor::RANDOM_SMALL
 	},
 	Descriptor {
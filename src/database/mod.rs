@@ -9,17 +9,22 @@
 
 #[cfg(test)]
 mod benches;
+mod changes;
+mod cipher;
 mod cork;
 mod de;
 mod deserialized;
 mod engine;
+mod expiry;
 mod handle;
+pub mod inspect;
 pub mod keyval;
 mod map;
 pub mod maps;
 mod pool;
 mod ser;
 mod stream;
+pub mod table;
 #[cfg(test)]
 mod tests;
 pub(crate) mod util;
@@ -28,18 +33,26 @@
 use std::{ops::Index, sync::Arc};
 
 use conduwuit::{Result, Server, err};
+pub use conduwuit_social_macros::Table;
+use futures::Stream;
 
+pub(crate) use self::{
+	changes::Changes,
+	cipher::Cipher,
+	engine::{Engine, context::Context},
+	util::or_else,
+};
 pub use self::{
+	changes::{Change, Op as ChangeOp},
 	de::{Ignore, IgnoreAll},
 	deserialized::Deserialized,
+	engine::snapshot::Snapshot,
 	handle::Handle,
 	keyval::{KeyVal, Slice, serialize_key, serialize_val},
 	map::{Get, Map, Qry, compact},
+	pool::{Priority, Stats as PoolStats},
 	ser::{Cbor, Interfix, Json, SEP, Separator, serialize, serialize_to, serialize_to_vec},
-};
-pub(crate) use self::{
-	engine::{Engine, context::Context},
-	util::or_else,
+	table::{Key as TableKey, Prefix, Table, Typed},
 };
 use crate::maps::{Maps, MapsKey, MapsVal};
 
@@ -76,6 +89,42 @@ pub fn iter(&self) -> impl Iterator<Item = (&MapsKey, &MapsVal)> + Send + '_ {
 	#[inline]
 	pub fn keys(&self) -> impl Iterator<Item = &MapsKey> + Send + '_ { self.maps.keys() }
 
+	/// Take a consistent point-in-time view of all maps. Pass the returned
+	/// snapshot to the `*_at` family of `Map` queries.
+	#[inline]
+	#[must_use]
+	pub fn snapshot(&self) -> Arc<Snapshot> { self.db.snapshot() }
+
+	/// Queue statistics of the frontend-pool for each priority class.
+	#[inline]
+	#[must_use]
+	pub fn pool_stats(&self) -> Vec<PoolStats> { self.db.pool.stats() }
+
+	/// Stream of writes to the maps configured for change-data capture.
+	pub fn changes(&self) -> Result<impl Stream<Item = Result<Arc<Change>>> + Send + use<>> {
+		self.db
+			.changes
+			.as_deref()
+			.map(Changes::subscribe)
+			.ok_or_else(|| err!(Database("Change-data capture is not enabled.")))
+	}
+
+	/// Remove expired records from every map with an expiry. This is a
+	/// thread-blocking call which walks each such map. Returns the number of
+	/// records removed.
+	pub fn expire_blocking(&self) -> Result<usize> {
+		self.maps
+			.values()
+			.filter(|map| map.expiry().is_some())
+			.try_fold(0_usize, |count, map| Ok(count.saturating_add(map.expire_blocking()?)))
+	}
+
+	/// Maps whose values are encrypted at rest.
+	#[inline]
+	pub fn encrypted(&self) -> impl Iterator<Item = &Arc<Map>> + Send + '_ {
+		self.maps.values().filter(|map| map.is_encrypted())
+	}
+
 	#[inline]
 	#[must_use]
 	pub fn is_read_only(&self) -> bool { self.db.is_read_only() }
@@ -1,4 +1,5 @@
 mod configure;
+mod priority;
 
 use std::{
 	mem::take,
@@ -8,6 +9,7 @@
 	},
 	thread,
 	thread::JoinHandle,
+	time::Instant,
 };
 
 use async_channel::{QueueStrategy, Receiver, RecvError, Sender};
@@ -18,25 +20,46 @@
 	trace,
 	utils::sys::compute::{get_affinity, nth_core_available, set_affinity},
 };
-use futures::{TryFutureExt, channel::oneshot};
+use futures::{FutureExt, TryFutureExt, channel::oneshot};
 use oneshot::Sender as ResultSender;
 use rocksdb::Direction;
 
-use self::configure::configure;
-use crate::{Handle, Map, keyval::KeyBuf, stream};
+pub(crate) use self::priority::choose;
+pub use self::priority::{Priority, Stats};
+use self::{configure::configure, priority::Metrics};
+use crate::{Handle, Map, Snapshot, keyval::KeyBuf, stream};
 
 /// Frontend thread-pool. Operating system threads are used to make database
 /// requests which are not cached. These thread-blocking requests are offloaded
 /// from the tokio async workers and executed on this threadpool.
 pub(crate) struct Pool {
 	server: Arc<Server>,
-	queues: Vec<Sender<Cmd>>,
+	queues: Vec<Queue>,
 	workers: Mutex<Vec<JoinHandle<()>>>,
 	topology: Vec<usize>,
+	weights: [i64; Priority::COUNT],
+	metrics: [Metrics; Priority::COUNT],
 	busy: AtomicUsize,
 	queued_max: AtomicUsize,
 }
 
+/// Submission side of a queue group: one channel for each priority class and
+/// a doorbell rung once for every command sent on any of them.
+struct Queue {
+	classes: [Sender<Queued>; Priority::COUNT],
+	doorbell: Sender<()>,
+}
+
+/// Receiving side of a queue group, shared by the workers serving it.
+#[derive(Clone)]
+struct Receivers {
+	classes: [Receiver<Queued>; Priority::COUNT],
+	doorbell: Receiver<()>,
+}
+
+/// A command with the time it was submitted.
+type Queued = (Cmd, Instant);
+
 /// Operations which can be submitted to the pool.
 pub(crate) enum Cmd {
 	Get(Get),
@@ -47,6 +70,7 @@ pub(crate) enum Cmd {
 pub(crate) struct Get {
 	pub(crate) map: Arc<Map>,
 	pub(crate) key: BatchQuery<'static>,
+	pub(crate) snapshot: Option<Arc<Snapshot>>,
 	pub(crate) res: Option<ResultSender<BatchResult<'static>>>,
 }
 
@@ -78,16 +102,44 @@ pub(crate) fn new(server: &Arc<Server>) -> Result<Arc<Self>> {
 
 	let (total_workers, queue_sizes, topology) = configure(server);
 
-	let (senders, receivers): (Vec<_>, Vec<_>) = queue_sizes
+	let (queues, receivers): (Vec<_>, Vec<_>) = queue_sizes
 		.into_iter()
-		.map(|cap| async_channel::bounded_with_queue_strategy(cap, CHAN_SCHED))
+		.map(|cap| {
+			let classes: [_; Priority::COUNT] = std::array::from_fn(|_| {
+				async_channel::bounded_with_queue_strategy(cap, CHAN_SCHED)
+			});
+
+			// Every queued command holds one slot in the doorbell, so it is never
+			// full when a command has been accepted by its class.
+			let doorbell_cap = cap.saturating_mul(Priority::COUNT);
+			let doorbell = async_channel::bounded_with_queue_strategy(doorbell_cap, CHAN_SCHED);
+
+			let queue = Queue {
+				classes: classes.clone().map(|(send, _)| send),
+				doorbell: doorbell.0,
+			};
+
+			let receivers = Receivers {
+				classes: classes.map(|(_, recv)| recv),
+				doorbell: doorbell.1,
+			};
+
+			(queue, receivers)
+		})
 		.unzip();
 
+	let weights = server
+		.config
+		.db_pool_priority_weights
+		.map(|weight| weight.try_into().unwrap_or(i64::MAX));
+
 	let pool = Arc::new(Self {
 		server: server.clone(),
-		queues: senders,
+		queues,
 		workers: Vec::new().into(),
 		topology,
+		weights,
+		metrics: Default::default(),
 		busy: AtomicUsize::default(),
 		queued_max: AtomicUsize::default(),
 	});
@@ -97,16 +149,32 @@ pub(crate) fn new(server: &Arc<Server>) -> Result<Arc<Self>> {
 	Ok(pool)
 }
 
+impl Queue {
+	fn close(&self) {
+		for class in &self.classes {
+			class.close();
+		}
+
+		self.doorbell.close();
+	}
+
+	fn is_empty(&self) -> bool { self.classes.iter().all(Sender::is_empty) }
+
+	fn is_closed(&self) -> bool {
+		self.doorbell.is_closed() && self.classes.iter().all(Sender::is_closed)
+	}
+}
+
 impl Drop for Pool {
 	fn drop(&mut self) {
 		self.close();
 
 		debug_assert!(
-			self.queues.iter().all(Sender::is_empty),
+			self.queues.iter().all(Queue::is_empty),
 			"channel must should not have requests queued on drop"
 		);
 		debug_assert!(
-			self.queues.iter().all(Sender::is_closed),
+			self.queues.iter().all(Queue::is_closed),
 			"channel should be closed on drop"
 		);
 	}
@@ -117,12 +185,16 @@ fn drop(&mut self) {
 pub(crate) fn close(&self) {
 	let workers = take(&mut *self.workers.lock().expect("locked"));
 
-	let senders = self.queues.iter().map(Sender::sender_count).sum::<usize>();
+	let senders = self
+		.queues
+		.iter()
+		.map(|queue| queue.doorbell.sender_count())
+		.sum::<usize>();
 
 	let receivers = self
 		.queues
 		.iter()
-		.map(Sender::receiver_count)
+		.map(|queue| queue.doorbell.receiver_count())
 		.sum::<usize>();
 
 	for queue in &self.queues {
@@ -153,7 +225,7 @@ pub(crate) fn close(&self) {
 }
 
 #[implement(Pool)]
-fn spawn_until(self: &Arc<Self>, recv: &[Receiver<Cmd>], count: usize) -> Result {
+fn spawn_until(self: &Arc<Self>, recv: &[Receivers], count: usize) -> Result {
 	let mut workers = self.workers.lock().expect("locked");
 	while workers.len() < count {
 		self.clone().spawn_one(&mut workers, recv)?;
@@ -169,11 +241,7 @@ fn spawn_until(self: &Arc<Self>, recv: &[Receiver<Cmd>], count: usize) -> Result
 	skip_all,
 	fields(id = %workers.len())
 )]
-fn spawn_one(
-	self: Arc<Self>,
-	workers: &mut Vec<JoinHandle<()>>,
-	recv: &[Receiver<Cmd>],
-) -> Result {
+fn spawn_one(self: Arc<Self>, workers: &mut Vec<JoinHandle<()>>, recv: &[Receivers]) -> Result {
 	debug_assert!(!self.queues.is_empty(), "Must have at least one queue");
 	debug_assert!(!recv.is_empty(), "Must have at least one receiver");
 
@@ -197,12 +265,15 @@ pub(crate) async fn execute_get(self: &Arc<Self>, mut cmd: Get) -> Result<BatchR
 	let (send, recv) = oneshot::channel();
 	_ = cmd.res.insert(send);
 
+	let map = cmd.map.clone();
+	let timer = Instant::now();
 	let queue = self.select_queue();
-	self.execute(queue, Cmd::Get(cmd))
+	self.execute(queue, Priority::current(), Cmd::Get(cmd))
 		.and_then(move |()| {
 			recv.map_ok(into_recv_get)
 				.map_err(|e| err!(error!("recv failed {e:?}")))
 		})
+		.inspect(|res| map.metrics().record_pool_result(res, timer.elapsed()))
 		.await
 }
 
@@ -212,17 +283,37 @@ pub(crate) async fn execute_iter(self: &Arc<Self>, mut cmd: Seek) -> Result<stre
 	let (send, recv) = oneshot::channel();
 	_ = cmd.res.insert(send);
 
+	let map = cmd.map.clone();
+	let timer = Instant::now();
 	let queue = self.select_queue();
-	self.execute(queue, Cmd::Iter(cmd))
+	self.execute(queue, Priority::current(), Cmd::Iter(cmd))
 		.and_then(|()| {
 			recv.map_ok(into_recv_seek)
 				.map_err(|e| err!(error!("recv failed {e:?}")))
 		})
+		.inspect(|res| map.metrics().record_pool_result(res, timer.elapsed()))
 		.await
 }
 
+/// Snapshot of the queue for each priority class.
 #[implement(Pool)]
-fn select_queue(&self) -> &Sender<Cmd> {
+pub(crate) fn stats(&self) -> Vec<Stats> {
+	Priority::ALL
+		.into_iter()
+		.map(|priority| {
+			let queued = self
+				.queues
+				.iter()
+				.map(|queue| queue.classes[priority.index()].len())
+				.sum();
+
+			self.metrics[priority.index()].stats(priority, queued)
+		})
+		.collect()
+}
+
+#[implement(Pool)]
+fn select_queue(&self) -> &Queue {
 	let core_id = get_affinity().next().unwrap_or(0);
 	let chan_id = self.topology[core_id];
 	self.queues.get(chan_id).unwrap_or_else(|| &self.queues[0])
@@ -235,20 +326,32 @@ fn select_queue(&self) -> &Sender<Cmd> {
 	skip(self, cmd),
 	fields(
 		task = ?tokio::task::try_id(),
-		receivers = queue.receiver_count(),
-		queued = queue.len(),
+		%priority,
+		receivers = queue.doorbell.receiver_count(),
+		queued = queue.doorbell.len(),
 		queued_max = self.queued_max.load(Ordering::Relaxed),
 	),
 )]
-async fn execute(&self, queue: &Sender<Cmd>, cmd: Cmd) -> Result {
+async fn execute(&self, queue: &Queue, priority: Priority, cmd: Cmd) -> Result {
+	let class = &queue.classes[priority.index()];
 	if cfg!(debug_assertions) {
-		self.queued_max.fetch_max(queue.len(), Ordering::Relaxed);
+		self.queued_max.fetch_max(class.len(), Ordering::Relaxed);
 	}
 
-	queue
-		.send(cmd)
+	class
+		.send((cmd, Instant::now()))
 		.await
-		.map_err(|e| err!(error!("send failed {e:?}")))
+		.map_err(|e| err!(error!("send failed {e:?}")))?;
+
+	// Rung without waiting so the command cannot be stranded without a signal
+	// if this future is dropped; see the capacity set in new(). The command is
+	// already queued, so a failure here is not the caller's: it is either taken
+	// on a later signal or dropped when the pool closes, failing the receiver.
+	if let Err(e) = queue.doorbell.try_send(()) {
+		error!("doorbell failed {e:?}");
+	}
+
+	Ok(())
 }
 
 #[implement(Pool)]
@@ -260,7 +363,7 @@ async fn execute(&self, queue: &Sender<Cmd>, cmd: Cmd) -> Result {
 		tid = ?thread::current().id(),
 	),
 )]
-fn worker(self: Arc<Self>, id: usize, recv: Receiver<Cmd>) {
+fn worker(self: Arc<Self>, id: usize, recv: Receivers) {
 	self.worker_init(id);
 	self.worker_loop(&recv);
 }
@@ -304,28 +407,57 @@ fn worker_init(&self, id: usize) {
 }
 
 #[implement(Pool)]
-fn worker_loop(self: &Arc<Self>, recv: &Receiver<Cmd>) {
+fn worker_loop(self: &Arc<Self>, recv: &Receivers) {
 	// initial +1 needed prior to entering wait
 	self.busy.fetch_add(1, Ordering::Relaxed);
 
-	while let Ok(cmd) = self.worker_wait(recv) {
+	let mut credit = [0_i64; Priority::COUNT];
+	while let Ok(()) = self.worker_wait(recv) {
+		// Nothing to take; go back to waiting on the doorbell, which fails once
+		// the pool is closed.
+		let Some((priority, (cmd, queued))) = self.worker_select(recv, &mut credit) else {
+			continue;
+		};
+
+		self.metrics[priority.index()].record(queued.elapsed());
 		self.worker_handle(cmd);
 	}
 }
 
+/// Take a command once the doorbell has signaled one is waiting. The class is
+/// chosen by smooth weighted round-robin; see [`choose`]. Returns None when
+/// every class is empty, which only happens while the pool is closing.
+#[implement(Pool)]
+fn worker_select(
+	&self,
+	recv: &Receivers,
+	credit: &mut [i64; Priority::COUNT],
+) -> Option<(Priority, Queued)> {
+	loop {
+		let chosen =
+			choose(&self.weights, credit, |priority| !recv.classes[priority.index()].is_empty())?;
+
+		// Another worker may have taken the last command in the chosen class;
+		// ours is then in another class.
+		if let Ok(queued) = recv.classes[chosen.index()].try_recv() {
+			return Some((chosen, queued));
+		}
+	}
+}
+
 #[implement(Pool)]
 #[tracing::instrument(
 	name = "wait",
 	level = "trace",
 	skip_all,
 	fields(
-		receivers = recv.receiver_count(),
-		queued = recv.len(),
+		receivers = recv.doorbell.receiver_count(),
+		queued = recv.doorbell.len(),
 		busy = self.busy.fetch_sub(1, Ordering::Relaxed) - 1,
 	),
 )]
-fn worker_wait(self: &Arc<Self>, recv: &Receiver<Cmd>) -> Result<Cmd, RecvError> {
-	recv.recv_blocking().debug_inspect(|_| {
+fn worker_wait(self: &Arc<Self>, recv: &Receivers) -> Result<(), RecvError> {
+	recv.doorbell.recv_blocking().debug_inspect(|_| {
 		self.busy.fetch_add(1, Ordering::Relaxed);
 	})
 }
@@ -387,7 +519,10 @@ fn handle_batch(self: &Arc<Self>, mut cmd: Get) {
 
 	let keys = cmd.key.iter();
 
-	let result: SmallVec<_> = cmd.map.get_batch_blocking(keys).collect();
+	let result: SmallVec<_> = match cmd.snapshot.as_deref() {
+		| Some(snapshot) => cmd.map.get_batch_blocking_at(snapshot, keys).collect(),
+		| None => cmd.map.get_batch_blocking(keys).collect(),
+	};
 
 	let chan_result = chan.send(into_send_get(result));
 
@@ -416,7 +551,10 @@ fn handle_get(&self, mut cmd: Get) {
 	// Perform the actual database query. We reuse our database::Map interface but
 	// limited to the blocking calls, rather than creating another surface directly
 	// with rocksdb here.
-	let result = cmd.map.get_blocking(&cmd.key[0]);
+	let result = match cmd.snapshot.as_deref() {
+		| Some(snapshot) => cmd.map.get_blocking_at(snapshot, &cmd.key[0]),
+		| None => cmd.map.get_blocking(&cmd.key[0]),
+	};
 
 	// Send the result back to the submitter.
 	let chan_result = chan.send(into_send_get([result].into()));
@@ -0,0 +1,164 @@
+use std::{
+	cmp::Reverse,
+	fmt,
+	future::Future,
+	sync::atomic::{AtomicU64, Ordering},
+	time::Duration,
+};
+
+use conduwuit::smallvec::SmallVec;
+
+tokio::task_local! {
+	static PRIORITY: Priority;
+}
+
+/// Scheduling class of requests to the database frontend-pool. Requests are
+/// queued separately by class and pool workers favor higher classes according
+/// to the configured weights, so interactive requests are not stalled behind
+/// bulk work.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Priority {
+	/// Requests made while serving clients.
+	#[default]
+	Interactive,
+
+	/// Requests made while serving other servers.
+	Federation,
+
+	/// Requests made by maintenance and other bulk work.
+	Background,
+}
+
+impl Priority {
+	pub const ALL: [Self; Self::COUNT] = [Self::Interactive, Self::Federation, Self::Background];
+	pub(crate) const COUNT: usize = 3;
+
+	/// Run a future with all database requests it makes scheduled at this
+	/// priority. Tasks spawned by the future do not inherit it.
+	pub fn scope<F: Future>(self, fut: F) -> impl Future<Output = F::Output> {
+		PRIORITY.scope(self, fut)
+	}
+
+	/// The priority of the current task; Interactive outside of any scope.
+	#[must_use]
+	pub fn current() -> Self { PRIORITY.try_with(|priority| *priority).unwrap_or_default() }
+
+	#[inline]
+	#[must_use]
+	pub(crate) fn index(self) -> usize {
+		match self {
+			| Self::Interactive => 0,
+			| Self::Federation => 1,
+			| Self::Background => 2,
+		}
+	}
+
+	#[must_use]
+	pub fn name(self) -> &'static str {
+		match self {
+			| Self::Interactive => "interactive",
+			| Self::Federation => "federation",
+			| Self::Background => "background",
+		}
+	}
+}
+
+/// Choose the class a worker serves next among those for which `is_waiting`
+/// holds, by smooth weighted round-robin over `weights`. Classes with zero
+/// weight are only chosen when no weighted class is waiting. Returns None when
+/// no class is waiting.
+pub(crate) fn choose<F>(
+	weights: &[i64; Priority::COUNT],
+	credit: &mut [i64; Priority::COUNT],
+	is_waiting: F,
+) -> Option<Priority>
+where
+	F: Fn(Priority) -> bool,
+{
+	let waiting: SmallVec<[Priority; Priority::COUNT]> = Priority::ALL
+		.into_iter()
+		.filter(|&priority| is_waiting(priority))
+		.collect();
+
+	let weighted = waiting.iter().any(|priority| weights[priority.index()] > 0);
+
+	let eligible: SmallVec<[Priority; Priority::COUNT]> = waiting
+		.into_iter()
+		.filter(|priority| !weighted || weights[priority.index()] > 0)
+		.collect();
+
+	let mut total: i64 = 0;
+	for priority in &eligible {
+		let weight = weights[priority.index()];
+		total = total.saturating_add(weight);
+		credit[priority.index()] = credit[priority.index()].saturating_add(weight);
+	}
+
+	let chosen = eligible
+		.into_iter()
+		.max_by_key(|priority| (credit[priority.index()], Reverse(priority.index())))?;
+
+	let credit = &mut credit[chosen.index()];
+	*credit = credit.saturating_sub(total);
+
+	Some(chosen)
+}
+
+impl fmt::Display for Priority {
+	fn fmt(&self, out: &mut fmt::Formatter<'_>) -> fmt::Result { out.write_str(self.name()) }
+}
+
+/// Queue counters for one priority class.
+#[derive(Debug, Default)]
+pub(crate) struct Metrics {
+	/// Requests dequeued by a worker.
+	requests: AtomicU64,
+
+	/// Cumulative time requests spent waiting in the queue.
+	wait_ns: AtomicU64,
+
+	/// Longest time a single request spent waiting in the queue.
+	wait_max_ns: AtomicU64,
+}
+
+/// Point-in-time view of the queue for one priority class.
+#[derive(Clone, Debug)]
+pub struct Stats {
+	pub priority: Priority,
+
+	/// Requests currently waiting for a worker.
+	pub queued: usize,
+
+	/// Requests dequeued by a worker since startup.
+	pub requests: u64,
+
+	/// Mean time a request waited for a worker.
+	pub wait_avg: Duration,
+
+	/// Longest time a request waited for a worker.
+	pub wait_max: Duration,
+}
+
+impl Metrics {
+	#[inline]
+	pub(crate) fn record(&self, waited: Duration) {
+		let nanos = waited.as_nanos().try_into().unwrap_or(u64::MAX);
+		self.requests.fetch_add(1, Ordering::Relaxed);
+		self.wait_ns.fetch_add(nanos, Ordering::Relaxed);
+		self.wait_max_ns.fetch_max(nanos, Ordering::Relaxed);
+	}
+
+	pub(crate) fn stats(&self, priority: Priority, queued: usize) -> Stats {
+		let requests = self.requests.load(Ordering::Relaxed);
+		let total = self.wait_ns.load(Ordering::Relaxed);
+		let max = self.wait_max_ns.load(Ordering::Relaxed);
+
+		Stats {
+			priority,
+			queued,
+			requests,
+			wait_avg: Duration::from_nanos(total.checked_div(requests).unwrap_or(0)),
+			wait_max: Duration::from_nanos(max),
+		}
+	}
+}
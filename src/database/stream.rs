@@ -5,13 +5,14 @@
 
 use std::sync::Arc;
 
-use conduwuit::{Result, utils::exchange};
+use conduwuit::{Error, Result, err, error, metrics::MapMetrics, utils::exchange};
 use rocksdb::{DBRawIteratorWithThreadMode, ReadOptions};
 
 pub(crate) use self::{items::Items, items_rev::ItemsRev, keys::Keys, keys_rev::KeysRev};
 use crate::{
-	Map, Slice,
+	Cipher, Map, Slice, Snapshot,
 	engine::Db,
+	expiry,
 	keyval::{Key, KeyVal, Val},
 	util::{is_incomplete, map_err},
 };
@@ -20,6 +21,16 @@ pub(crate) struct State<'a> {
 	inner: Inner<'a>,
 	seek: bool,
 	init: bool,
+	metrics: &'a MapMetrics,
+	cipher: Option<&'a Cipher>,
+	expiry: bool,
+	now: u64,
+	name: &'a str,
+	plain: Vec<u8>,
+	sealed: bool,
+	expired: bool,
+	failed: bool,
+	_snapshot: Option<Arc<Snapshot>>,
 }
 
 pub(crate) trait Cursor<'a, T> {
@@ -33,7 +44,7 @@ pub(crate) trait Cursor<'a, T> {
 	fn get(&self) -> Option<Result<T>> {
 		self.fetch()
 			.map(Ok)
-			.or_else(|| self.state().status().map(map_err).map(Err))
+			.or_else(|| self.state().error().map(Err))
 	}
 
 	#[inline]
@@ -53,6 +64,27 @@ pub(super) fn new(map: &'a Arc<Map>, opts: ReadOptions) -> Self {
 			inner: map.db().db.raw_iterator_cf_opt(&map.cf(), opts),
 			init: true,
 			seek: false,
+			metrics: map.metrics(),
+			cipher: map.cipher(),
+			expiry: map.expiry().is_some(),
+			now: expiry::now(),
+			name: map.name(),
+			plain: Vec::new(),
+			sealed: false,
+			expired: false,
+			failed: false,
+			_snapshot: None,
+		}
+	}
+
+	/// Construct a State reading from a snapshot. The options must have been
+	/// configured with the same snapshot; it is held here to outlive the
+	/// iterator.
+	#[inline]
+	pub(super) fn new_at(map: &'a Arc<Map>, opts: ReadOptions, snapshot: &Arc<Snapshot>) -> Self {
+		Self {
+			_snapshot: Some(snapshot.clone()),
+			..Self::new(map, opts)
 		}
 	}
 
@@ -90,7 +122,8 @@ pub(super) fn init_rev(mut self, from: From<'_>) -> Self {
 
 	#[inline]
 	#[cfg_attr(unabridged, tracing::instrument(level = "trace", skip_all))]
-	pub(super) fn seek_fwd(&mut self) {
+	fn seek_fwd(&mut self) {
+		MapMetrics::incr(&self.metrics.iterations);
 		if !exchange(&mut self.init, false) {
 			self.inner.next();
 		} else if !self.seek {
@@ -100,7 +133,8 @@ pub(super) fn seek_fwd(&mut self) {
 
 	#[inline]
 	#[cfg_attr(unabridged, tracing::instrument(level = "trace", skip_all))]
-	pub(super) fn seek_rev(&mut self) {
+	fn seek_rev(&mut self) {
+		MapMetrics::incr(&self.metrics.iterations);
 		if !exchange(&mut self.init, false) {
 			self.inner.prev();
 		} else if !self.seek {
@@ -108,6 +142,85 @@ pub(super) fn seek_rev(&mut self) {
 		}
 	}
 
+	/// Move forward to the next record which has not expired, recovering its
+	/// value unless only keys are wanted. Expired records are hidden from
+	/// iteration as they are from point reads until the sweeper removes them.
+	#[inline]
+	pub(super) fn next_fwd(&mut self, values: bool) {
+		loop {
+			self.seek_fwd();
+			if values || self.expiry {
+				self.unseal();
+			}
+
+			if !self.expired {
+				break;
+			}
+		}
+	}
+
+	/// Move backward to the previous record which has not expired; see
+	/// next_fwd().
+	#[inline]
+	pub(super) fn next_rev(&mut self, values: bool) {
+		loop {
+			self.seek_rev();
+			if values || self.expiry {
+				self.unseal();
+			}
+
+			if !self.expired {
+				break;
+			}
+		}
+	}
+
+	/// Recover the value under the cursor when the map is encrypted or its
+	/// records expire, noting whether it has expired. The result is held in a
+	/// buffer reused at each position, so like the cursor's own data it is
+	/// only valid until the next movement.
+	fn unseal(&mut self) {
+		self.expired = false;
+		if self.cipher.is_none() && !self.expiry {
+			return;
+		}
+
+		self.sealed = false;
+		let Some(val) = self.inner.value() else {
+			return;
+		};
+
+		if let Some(cipher) = self.cipher {
+			match cipher.decrypt_into(self.name, val, &mut self.plain) {
+				| Ok(sealed) => self.sealed = sealed,
+				| Err(e) => {
+					error!(map = %self.name, "{e}");
+					self.failed = true;
+					return;
+				},
+			}
+		}
+
+		if !self.expiry {
+			return;
+		}
+
+		let framed = if self.sealed { self.plain.as_slice() } else { val };
+		let Some((expires, _)) = expiry::unframe(framed) else {
+			return;
+		};
+
+		self.expired = expiry::is_expired(expires, self.now);
+
+		if self.sealed {
+			self.plain.drain(..expiry::HEADER_LEN);
+		} else {
+			self.plain.clear();
+			self.plain.extend_from_slice(&val[expiry::HEADER_LEN..]);
+			self.sealed = true;
+		}
+	}
+
 	pub(super) fn is_incomplete(&self) -> bool {
 		matches!(self.status(), Some(e) if is_incomplete(&e))
 	}
@@ -121,9 +234,30 @@ fn _fetch_val(&self) -> Option<Val<'_>> { self.inner.value() }
 	#[inline]
 	fn fetch(&self) -> Option<KeyVal<'_>> { self.inner.item() }
 
+	/// Fetch the key and value under the cursor, substituting the recovered
+	/// value when there is one. Call unseal() after each movement.
+	#[inline]
+	fn fetch_unsealed(&self) -> Option<KeyVal<'_>> {
+		if self.failed {
+			return None;
+		}
+
+		self.fetch()
+			.map(|(key, val)| (key, if self.sealed { &self.plain } else { val }))
+	}
+
 	#[inline]
 	pub(super) fn status(&self) -> Option<rocksdb::Error> { self.inner.status().err() }
 
+	#[inline]
+	pub(super) fn error(&self) -> Option<Error> {
+		if self.failed {
+			return Some(err!(Database("Failed to decrypt value in {}.", self.name)));
+		}
+
+		self.status().map(map_err)
+	}
+
 	#[inline]
 	pub(super) fn valid(&self) -> bool { self.inner.valid() }
 }
@@ -24,10 +24,10 @@ impl<'a> Cursor<'a, KeyVal<'a>> for ItemsRev<'a> {
 	fn state(&self) -> &State<'a> { &self.state }
 
 	#[inline]
-	fn fetch(&self) -> Option<KeyVal<'a>> { self.state.fetch().map(keyval_longevity) }
+	fn fetch(&self) -> Option<KeyVal<'a>> { self.state.fetch_unsealed().map(keyval_longevity) }
 
 	#[inline]
-	fn seek(&mut self) { self.state.seek_rev(); }
+	fn seek(&mut self) { self.state.next_rev(true); }
 }
 
 impl<'a> Stream for ItemsRev<'a> {
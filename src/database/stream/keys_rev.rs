@@ -27,7 +27,7 @@ fn state(&self) -> &State<'a> { &self.state }
 	fn fetch(&self) -> Option<Key<'a>> { self.state.fetch_key().map(slice_longevity) }
 
 	#[inline]
-	fn seek(&mut self) { self.state.seek_rev(); }
+	fn seek(&mut self) { self.state.next_rev(false); }
 }
 
 impl<'a> Stream for KeysRev<'a> {
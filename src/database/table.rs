@@ -0,0 +1,220 @@
+//! Typed tables. A table declares the key layout and value type of a map once,
+//! usually with `#[derive(Table)]` from the macros crate on a struct whose
+//! fields are the key's components in order. Keys are serialized as tuples of
+//! those fields, so a table can be declared over an existing map without
+//! rewriting it.
+//!
+//! Each layout has an identifier derived from the names and types of its
+//! components. The identifier in use is recorded in the database so a change
+//! to a layout without a migration rewriting the map is caught at startup.
+
+use std::{fmt::Write, marker::PhantomData, sync::Arc};
+
+use conduwuit::{Err, Result, err};
+use futures::{Stream, TryStreamExt, future};
+use serde::{Deserialize, Serialize};
+
+use crate::{Database, Deserialized, Map, de, keyval::KeyBuf};
+
+/// Key prefix in the "global" map recording the layout of each table.
+const LAYOUT_PREFIX: &[u8] = b"table_layout\xFF";
+
+/// Declaration of a map's key layout and value type.
+pub trait Table: Send + Sync + 'static {
+	/// Name of the map holding the table.
+	const MAP: &'static str;
+
+	/// Components of the key, in order.
+	const COLUMNS: &'static [Column];
+
+	/// Identifier of the layout; changes whenever a component or the value
+	/// type is renamed, retyped, added or removed.
+	const LAYOUT: u64;
+
+	/// Type stored in each record; `()` for index tables with empty values.
+	type Value;
+
+	/// Human-readable description of the layout.
+	#[must_use]
+	fn schema() -> String {
+		let mut out = format!("{}: ", Self::MAP);
+		for (i, column) in Self::COLUMNS.iter().enumerate() {
+			let sep = if i > 0 { " | " } else { "" };
+			write!(out, "{sep}{}: {}", column.name, column.ty).expect("formatted");
+		}
+
+		write!(out, " => {}", std::any::type_name::<Self::Value>()).expect("formatted");
+		out
+	}
+}
+
+/// A key of some table.
+pub trait Key {
+	type Table: Table;
+
+	/// Serialize the complete key.
+	fn serialize(&self) -> Result<KeyBuf>;
+}
+
+/// One component of a table's key.
+#[derive(Clone, Copy, Debug)]
+pub struct Column {
+	pub name: &'static str,
+	pub ty: &'static str,
+}
+
+/// Serialized leading components of a table's key, for prefix scans.
+#[derive(Debug)]
+pub struct Prefix<T: Table> {
+	buf: KeyBuf,
+	_table: PhantomData<fn() -> T>,
+}
+
+/// Typed access to the map underlying a table.
+pub struct Typed<T: Table> {
+	map: Arc<Map>,
+	_table: PhantomData<fn() -> T>,
+}
+
+impl<T: Table> Typed<T> {
+	pub fn open(db: &Database) -> Result<Self> {
+		Ok(Self {
+			map: db.get(T::MAP)?.clone(),
+			_table: PhantomData,
+		})
+	}
+
+	/// Fetch and deserialize the value stored at the key.
+	pub async fn get<K>(&self, key: &K) -> Result<T::Value>
+	where
+		K: Key<Table = T>,
+		T::Value: for<'de> Deserialize<'de>,
+	{
+		let key = key.serialize()?;
+		self.map.get(&key).await.deserialized()
+	}
+
+	/// Whether a record exists at the key.
+	pub async fn contains<K>(&self, key: &K) -> Result<bool>
+	where
+		K: Key<Table = T>,
+	{
+		let key = key.serialize()?;
+		Ok(self.map.exists(&key).await.is_ok())
+	}
+
+	/// Store a value at the key.
+	pub fn put<K>(&self, key: &K, val: &T::Value) -> Result
+	where
+		K: Key<Table = T>,
+		T::Value: Serialize,
+	{
+		self.map.raw_put(key.serialize()?, val);
+		Ok(())
+	}
+
+	/// Store an empty record at the key; for index tables.
+	pub fn insert<K>(&self, key: &K) -> Result<KeyBuf>
+	where
+		K: Key<Table = T>,
+		T: Table<Value = ()>,
+	{
+		let key = key.serialize()?;
+		self.map.insert(&key, []);
+		Ok(key)
+	}
+
+	pub fn remove<K>(&self, key: &K) -> Result
+	where
+		K: Key<Table = T>,
+	{
+		self.map.remove(&key.serialize()?);
+		Ok(())
+	}
+
+	/// Iterate the raw keys of records matching a prefix.
+	pub fn keys_prefix<'a>(
+		&'a self,
+		prefix: &'a Prefix<T>,
+	) -> impl Stream<Item = Result<&'a [u8]>> + Send + 'a {
+		self.map.raw_keys_prefix(&prefix.buf)
+	}
+
+	/// Iterate the raw keys and deserialized values of records matching a
+	/// prefix.
+	pub fn stream_prefix<'a>(
+		&'a self,
+		prefix: &'a Prefix<T>,
+	) -> impl Stream<Item = Result<(&'a [u8], T::Value)>> + Send + 'a
+	where
+		T::Value: Deserialize<'a> + Send + 'a,
+	{
+		self.map
+			.raw_stream_prefix(&prefix.buf)
+			.and_then(|(key, val)| future::ready(de::from_slice(val).map(|val| (key, val))))
+	}
+
+	/// Remove every record matching a prefix.
+	pub async fn remove_prefix(&self, prefix: &Prefix<T>) -> Result {
+		self.keys_prefix(prefix)
+			.try_for_each(|key| {
+				self.map.remove(key);
+				future::ok(())
+			})
+			.await
+	}
+
+	#[inline]
+	#[must_use]
+	pub fn map(&self) -> &Arc<Map> { &self.map }
+}
+
+impl<T: Table> Prefix<T> {
+	/// Serialize the leading components of a key. Generated per-table prefix
+	/// constructors should be used rather than calling this directly.
+	pub fn new<P: Serialize>(prefix: P) -> Result<Self> {
+		Ok(Self {
+			buf: crate::serialize_key(prefix)?,
+			_table: PhantomData,
+		})
+	}
+}
+
+impl<T: Table> AsRef<[u8]> for Prefix<T> {
+	fn as_ref(&self) -> &[u8] { &self.buf }
+}
+
+/// Verify the layout of a table matches the one recorded in the database,
+/// recording it if none was. Fails when the layout changed without a
+/// migration calling `set_layout`.
+#[tracing::instrument(skip(db), fields(table = T::MAP), level = "debug")]
+pub async fn check_layout<T: Table>(db: &Database) -> Result {
+	let key = layout_key::<T>();
+	let recorded: Result<u64> = db["global"].get(&key).await.deserialized();
+	match recorded {
+		| Ok(layout) if layout == T::LAYOUT => Ok(()),
+		| Ok(layout) => Err!(Database(
+			"Layout of table {} changed from {layout:#018x} to {:#018x}; a migration must \
+			 rewrite it. Current layout is {}",
+			T::MAP,
+			T::LAYOUT,
+			T::schema(),
+		)),
+		| Err(e) if e.is_not_found() => {
+			if !db.is_read_only() {
+				set_layout::<T>(db);
+			}
+
+			Ok(())
+		},
+		| Err(e) => Err(err!(Database("Failed to read layout of table {}: {e}", T::MAP))),
+	}
+}
+
+/// Record the current layout of a table; called by the migration which
+/// rewrites it.
+pub fn set_layout<T: Table>(db: &Database) {
+	db["global"].insert(&layout_key::<T>(), T::LAYOUT.to_be_bytes());
+}
+
+fn layout_key<T: Table>() -> Vec<u8> { [LAYOUT_PREFIX, T::MAP.as_bytes()].concat() }
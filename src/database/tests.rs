@@ -1,15 +1,26 @@
 #![allow(clippy::needless_borrows_for_generic_args)]
 
-use std::fmt::Debug;
+use std::{
+	fmt::Debug,
+	panic::{self, AssertUnwindSafe},
+	path::PathBuf,
+	sync::Arc,
+};
 
 use conduwuit::{
+	Config, Server,
 	arrayvec::ArrayVec,
+	config::Figment,
+	log::{Log, LogLevelReloadHandles, capture, ring::Ring},
 	ruma::{EventId, RoomId, UserId, serde::Raw},
 };
+use futures::{StreamExt, TryStreamExt};
 use serde::Serialize;
 
 use crate::{
-	Ignore, Interfix, de, ser,
+	Changes, Cipher, Database, Ignore, Interfix, Priority,
+	changes::Op,
+	de, expiry, inspect, ser,
 	ser::{Json, serialize_to_vec},
 };
 
@@ -547,3 +558,546 @@ fn serde_tuple_option_none_none_none() {
 	assert_eq!(None, cc.0);
 	assert_eq!(bb, cc);
 }
+
+const KEY_A: &str = "rjLAeVhy8zqUDWDqcf2ONK2Bi4cFNA2HsUaZ1sG9Y5g=";
+const KEY_B: &str = "8nHp2Bw0s0P5KcD3qT1rXx4mN6yZ9aLfVe7uJhGkQiE=";
+
+#[test]
+fn cipher_roundtrip() {
+	let cipher = Cipher::new(KEY_A).expect("valid key");
+	let sealed = cipher.encrypt("userid_password", b"hunter2").unwrap();
+	assert_ne!(sealed.as_slice(), b"hunter2");
+	assert!(cipher.is_current(&sealed));
+
+	let plain = cipher.decrypt("userid_password", &sealed).unwrap();
+	assert_eq!(plain.as_deref(), Some(b"hunter2".as_slice()));
+}
+
+#[test]
+fn cipher_plaintext_passthrough() {
+	let cipher = Cipher::new(KEY_A).expect("valid key");
+	let plain = cipher.decrypt("userid_password", b"hunter2").unwrap();
+	assert_eq!(plain, None);
+	assert!(!cipher.is_current(b"hunter2"));
+}
+
+#[test]
+fn cipher_binds_map_name() {
+	let cipher = Cipher::new(KEY_A).expect("valid key");
+	let sealed = cipher.encrypt("userid_password", b"hunter2").unwrap();
+	cipher
+		.decrypt("userdeviceid_token", &sealed)
+		.expect_err("value moved to another map must not open");
+}
+
+#[test]
+fn cipher_rotation() {
+	let old = Cipher::new(KEY_A).expect("valid key");
+	let sealed = old.encrypt("global", b"keypair").unwrap();
+
+	let new = Cipher::new(&format!("{KEY_B}\n{KEY_A}")).expect("valid keys");
+	assert!(!new.is_current(&sealed));
+
+	let plain = new.decrypt("global", &sealed).unwrap();
+	assert_eq!(plain.as_deref(), Some(b"keypair".as_slice()));
+
+	let resealed = new.encrypt("global", b"keypair").unwrap();
+	assert!(new.is_current(&resealed));
+	old.decrypt("global", &resealed)
+		.expect_err("value sealed under an unknown key must not open");
+}
+
+#[tokio::test]
+async fn changes_ordered() {
+	let changes = Changes::new(8, 100);
+	let mut stream = std::pin::pin!(changes.subscribe());
+
+	let mut capture = changes.capture([b"key".as_slice()]);
+	capture.publish("global", Op::Put, b"key", Some(b"val"));
+	capture.publish("global", Op::Delete, b"key", None);
+	drop(capture);
+
+	let put = stream.next().await.unwrap().unwrap();
+	assert_eq!(put.sequence, 101);
+	assert_eq!(put.op, Op::Put);
+	assert_eq!(put.val.as_deref(), Some(b"val".as_slice()));
+
+	let delete = stream.next().await.unwrap().unwrap();
+	assert_eq!(delete.sequence, 102);
+	assert_eq!(delete.op, Op::Delete);
+	assert_eq!(delete.val, None);
+	assert_eq!(changes.sequence(), 102);
+}
+
+#[tokio::test]
+async fn changes_lagged() {
+	let changes = Changes::new(1, 0);
+	let mut stream = std::pin::pin!(changes.subscribe());
+
+	let mut capture = changes.capture([b"a".as_slice(), b"b".as_slice()]);
+	capture.publish("global", Op::Put, b"a", Some(b"1"));
+	capture.publish("global", Op::Put, b"b", Some(b"2"));
+	drop(capture);
+
+	stream
+		.next()
+		.await
+		.unwrap()
+		.expect_err("subscriber behind capacity must be told it lagged");
+
+	let change = stream.next().await.unwrap().unwrap();
+	assert_eq!(change.sequence, 2);
+}
+
+#[tokio::test]
+async fn changes_consumers_independent() {
+	let changes = Changes::new(1, 0);
+	let mut fast = std::pin::pin!(changes.subscribe());
+	let mut slow = std::pin::pin!(changes.subscribe());
+
+	for key in [b"a", b"b"] {
+		let mut capture = changes.capture([key.as_slice()]);
+		capture.publish("global", Op::Put, key, None);
+		drop(capture);
+
+		let change = fast.next().await.unwrap().unwrap();
+		assert_eq!(change.key, key, "a consumer keeping up never lags");
+	}
+
+	slow.next()
+		.await
+		.unwrap()
+		.expect_err("only the consumer which fell behind lags");
+}
+
+#[test]
+fn expiry_frame_roundtrip() {
+	use std::time::Duration;
+
+	let framed = expiry::frame(b"preview", Duration::from_secs(60));
+	let (expires, val) = expiry::unframe(&framed).expect("framed value");
+	assert_eq!(val, b"preview");
+	assert!(!expiry::is_expired(expires, expiry::now()));
+	assert!(expiry::is_expired(expires, expires));
+}
+
+#[test]
+fn expiry_unframed_passthrough() {
+	assert_eq!(expiry::unframe(b"preview"), None);
+	assert_eq!(expiry::unframe(b"\xFEttl\x01short"), None);
+}
+
+#[test]
+fn inspect_decode_key_layout() {
+	let key = [&1_u64.to_be_bytes()[..], &7_u64.to_be_bytes()].concat();
+	let decoded = inspect::decode_key("pduid_pdu", &key);
+	assert_eq!(decoded, serde_json::json!({ "shortroomid": 1, "count": 7 }));
+
+	let decoded =
+		inspect::decode_key("userroomid_joined", b"@user:example.com\xFF!room:example.com");
+	assert_eq!(decoded["user_id"], "@user:example.com");
+	assert_eq!(decoded["room_id"], "!room:example.com");
+}
+
+#[test]
+fn inspect_decode_key_unknown() {
+	let decoded =
+		inspect::decode_key("unknown", b"text\xFF\x00\x00\x00\x00\x00\x00\x00\x2A\xFF\x01");
+	assert_eq!(decoded, serde_json::json!(["text", 42, { "base64": "AQ==" }]));
+}
+
+/// Path of a scratch database for one test, emptied beforehand.
+fn test_db_path(name: &str) -> PathBuf {
+	let path = std::env::temp_dir().join(format!("conduwuit-{name}-{}", std::process::id()));
+	_ = std::fs::remove_dir_all(&path);
+	path
+}
+
+/// Server for opening scratch databases, with any extra config on top.
+fn test_server(path: &PathBuf, extra: Figment) -> Arc<Server> {
+	let figment = Figment::new()
+		.merge(("server_name", "example.com"))
+		.merge(("database_path", path))
+		.merge(extra);
+
+	let config = Config::new(&figment).expect("valid test config");
+	let log = Log {
+		reload: LogLevelReloadHandles::default(),
+		capture: Arc::new(capture::State::new()),
+		ring: Arc::new(Ring::new(0)),
+	};
+
+	Arc::new(Server::new(config, None, log))
+}
+
+async fn open_test_db(name: &str) -> Arc<Database> {
+	let server = test_server(&test_db_path(name), Figment::new());
+	Database::open(&server)
+		.await
+		.expect("scratch database opens")
+}
+
+#[tokio::test]
+async fn snapshot_get() {
+	let db = open_test_db("snapshot-get").await;
+	let map = &db["global"];
+
+	map.insert(b"key", b"old");
+	let snapshot = db.snapshot();
+	map.insert(b"key", b"new");
+	map.insert(b"other", b"new");
+
+	let old = map.get_at(&snapshot, b"key").await.unwrap();
+	assert_eq!(&*old, b"old");
+	assert!(map.get_at(&snapshot, b"other").await.is_err());
+
+	let new = map.get(b"key").await.unwrap();
+	assert_eq!(&*new, b"new");
+	assert_eq!(snapshot.behind(), 2);
+}
+
+#[tokio::test]
+async fn snapshot_streams() {
+	let db = open_test_db("snapshot-streams").await;
+	let map = &db["global"];
+
+	map.insert(b"a1", b"1");
+	map.insert(b"a2", b"2");
+	let snapshot = db.snapshot();
+	map.insert(b"a3", b"3");
+	map.remove(b"a1");
+
+	let keys = |keys: Vec<&[u8]>| keys.into_iter().map(<[u8]>::to_vec).collect::<Vec<_>>();
+
+	let fwd: Vec<_> = map
+		.raw_stream_at(&snapshot)
+		.map_ok(|(k, _)| k.to_vec())
+		.try_collect()
+		.await
+		.unwrap();
+	assert_eq!(fwd, keys(vec![b"a1", b"a2"]));
+
+	let rev: Vec<_> = map
+		.rev_raw_stream_at(&snapshot)
+		.map_ok(|(k, _)| k.to_vec())
+		.try_collect()
+		.await
+		.unwrap();
+	assert_eq!(rev, keys(vec![b"a2", b"a1"]));
+
+	let key_only: Vec<_> = map
+		.raw_keys_at(&snapshot)
+		.map_ok(<[u8]>::to_vec)
+		.try_collect()
+		.await
+		.unwrap();
+	assert_eq!(key_only, keys(vec![b"a1", b"a2"]));
+
+	let rev_from: Vec<_> = map
+		.rev_raw_stream_from_at(&snapshot, b"a9")
+		.map_ok(|(k, _)| k.to_vec())
+		.try_collect()
+		.await
+		.unwrap();
+	assert_eq!(rev_from, keys(vec![b"a2", b"a1"]));
+
+	let live = map.raw_keys().count().await;
+	assert_eq!(live, 2, "live view sees a2 and a3");
+}
+
+/// Every key of a column, straight from the engine.
+fn column_keys(engine: &crate::Engine, name: &str) -> Vec<Vec<u8>> {
+	let mut keys = Vec::new();
+	let mut iter = engine.db.raw_iterator_cf(&engine.cf(name));
+	iter.seek_to_first();
+	while let Some(key) = iter.key() {
+		keys.push(key.to_vec());
+		iter.next();
+	}
+
+	keys
+}
+
+#[tokio::test]
+async fn schema_rename() {
+	use crate::{
+		Context, Engine,
+		engine::descriptor::{self, Descriptor},
+	};
+
+	let old = Descriptor {
+		name: "test_old",
+		..descriptor::RANDOM_SMALL
+	};
+	let renamed = Descriptor {
+		name: "test_new",
+		renamed_from: &["test_old"],
+		..descriptor::RANDOM_SMALL
+	};
+
+	let server = test_server(&test_db_path("schema-rename"), Figment::new());
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[old]).await.unwrap();
+	for i in 0..10_u8 {
+		engine.db.put_cf(&engine.cf("test_old"), [i], [i]).unwrap();
+	}
+
+	drop(engine);
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[renamed]).await.unwrap();
+	assert!(!engine.has_cf("test_old"));
+	assert_eq!(column_keys(&engine, "test_new").len(), 10);
+	assert!(engine.db.get(b"renaming\xFFtest_old").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn schema_rename_resumes() {
+	use crate::{
+		Context, Engine,
+		engine::descriptor::{self, Descriptor},
+	};
+
+	let old = Descriptor {
+		name: "test_old",
+		..descriptor::RANDOM_SMALL
+	};
+	let new = Descriptor {
+		name: "test_new",
+		..descriptor::RANDOM_SMALL
+	};
+	let renamed = Descriptor { renamed_from: &["test_old"], ..new };
+
+	let server = test_server(&test_db_path("schema-rename-resumes"), Figment::new());
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[old, new]).await.unwrap();
+	for i in 0..10_u8 {
+		engine.db.put_cf(&engine.cf("test_old"), [i], [i]).unwrap();
+	}
+
+	// Leave things as a move interrupted after its first batch would.
+	for i in 0..5_u8 {
+		engine.db.put_cf(&engine.cf("test_new"), [i], [i]).unwrap();
+	}
+
+	engine.db.put(b"renaming\xFFtest_old", [4_u8]).unwrap();
+
+	drop(engine);
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[renamed]).await.unwrap();
+	assert!(!engine.has_cf("test_old"));
+	assert_eq!(
+		column_keys(&engine, "test_new"),
+		(0..10_u8).map(|i| vec![i]).collect::<Vec<_>>()
+	);
+	assert!(engine.db.get(b"renaming\xFFtest_old").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn schema_rename_conflict() {
+	use crate::{
+		Context, Engine,
+		engine::descriptor::{self, Descriptor},
+	};
+
+	let old = Descriptor {
+		name: "test_old",
+		..descriptor::RANDOM_SMALL
+	};
+	let new = Descriptor {
+		name: "test_new",
+		..descriptor::RANDOM_SMALL
+	};
+	let renamed = Descriptor { renamed_from: &["test_old"], ..new };
+
+	let server = test_server(&test_db_path("schema-rename-conflict"), Figment::new());
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[old, new]).await.unwrap();
+	engine
+		.db
+		.put_cf(&engine.cf("test_old"), b"a", b"old")
+		.unwrap();
+	engine
+		.db
+		.put_cf(&engine.cf("test_new"), b"b", b"new")
+		.unwrap();
+
+	drop(engine);
+	let ctx = Context::new(&server).unwrap();
+	let engine = Engine::open(ctx, &[renamed]).await.unwrap();
+	assert!(engine.has_cf("test_old"), "both columns have data so neither is touched");
+	assert_eq!(column_keys(&engine, "test_new"), vec![b"b".to_vec()]);
+}
+
+#[tokio::test]
+async fn read_only_refuses_writes() {
+	let path = test_db_path("read-only");
+	let primary = Database::open(&test_server(&path, Figment::new()))
+		.await
+		.unwrap();
+	primary["global"].insert(b"key", b"primary");
+
+	let extra = Figment::new().merge(("rocksdb_read_only", true));
+	let db = Database::open(&test_server(&path, extra)).await.unwrap();
+	assert!(db.is_read_only());
+	assert!(!db.is_secondary());
+
+	let map = &db["global"];
+	assert_eq!(&*map.get(b"key").await.unwrap(), b"primary");
+
+	let error = map.check_writable().unwrap_err();
+	assert!(error.to_string().contains("read-only database"), "{error}");
+
+	let insert = panic::catch_unwind(AssertUnwindSafe(|| map.insert(b"other", b"replica")));
+	assert!(insert.is_err(), "write fails instead of being dropped");
+	assert!(map.get(b"other").await.is_err());
+}
+
+#[tokio::test]
+async fn secondary_follows_primary() {
+	let path = test_db_path("secondary");
+	let primary = Database::open(&test_server(&path, Figment::new()))
+		.await
+		.unwrap();
+	primary["global"].insert(b"key", b"old");
+
+	let extra = Figment::new()
+		.merge(("rocksdb_secondary", true))
+		.merge(("rocksdb_secondary_path", test_db_path("secondary-own")));
+	let db = Database::open(&test_server(&path, extra)).await.unwrap();
+	assert!(db.is_read_only());
+	assert!(db.is_secondary());
+
+	let map = &db["global"];
+	assert_eq!(&*map.get(b"key").await.unwrap(), b"old");
+
+	primary["global"].insert(b"key", b"new");
+	db.db.update().unwrap();
+	assert_eq!(&*map.get(b"key").await.unwrap(), b"new");
+}
+
+#[test]
+fn secondary_requires_path() {
+	let path = test_db_path("secondary-no-path");
+	let figment = Figment::new()
+		.merge(("server_name", "example.com"))
+		.merge(("database_path", &path))
+		.merge(("rocksdb_secondary", true));
+
+	let config = Config::new(&figment).unwrap();
+	let error = config.check().unwrap_err();
+	assert!(error.to_string().contains("rocksdb_secondary_path"), "{error}");
+}
+
+#[test]
+fn pool_choose_weighted() {
+	use Priority::{Background, Federation, Interactive};
+
+	let weights = [4, 2, 1];
+	let mut credit = [0; Priority::COUNT];
+	let picks: Vec<_> = (0..7)
+		.map(|_| crate::pool::choose(&weights, &mut credit, |_| true).unwrap())
+		.collect();
+
+	let count = |class| picks.iter().filter(|&&pick| pick == class).count();
+	assert_eq!((count(Interactive), count(Federation), count(Background)), (4, 2, 1));
+	assert_eq!(credit, [0; Priority::COUNT], "credit settles after a full round");
+	assert_ne!(picks[0..2], [Interactive, Interactive], "classes are interleaved");
+}
+
+#[test]
+fn pool_choose_waiting() {
+	use Priority::{Background, Federation, Interactive};
+
+	let weights = [1, 1, 0];
+	let mut credit = [0; Priority::COUNT];
+	let choose = |credit: &mut [i64; Priority::COUNT], waiting: &[Priority]| {
+		crate::pool::choose(&weights, credit, |class| waiting.contains(&class))
+	};
+
+	assert_eq!(choose(&mut credit, &[]), None);
+	assert_eq!(choose(&mut credit, &[Federation]), Some(Federation));
+	assert_eq!(
+		choose(&mut credit, &[Federation, Background]),
+		Some(Federation),
+		"unweighted class waits while a weighted one is waiting"
+	);
+	assert_eq!(choose(&mut credit, &[Background]), Some(Background));
+	assert_eq!(choose(&mut credit, &[Interactive, Federation]), Some(Interactive));
+}
+
+#[tokio::test]
+async fn pool_serves_every_priority() {
+	use futures::future::join_all;
+
+	let extra = Figment::new()
+		.merge(("db_pool_workers", 2))
+		.merge(("db_pool_queue_mult", 1));
+
+	let server = test_server(&test_db_path("pool-priorities"), extra);
+	let db = Database::open(&server).await.unwrap();
+	let map = &db["global"];
+	for i in 0_usize..64 {
+		map.insert(&i.to_be_bytes(), b"val");
+	}
+
+	// Out of the memtable so reads miss the cache and are sent to the pool.
+	db.db.db.flush_cf(&db.db.cf("global")).unwrap();
+
+	let gets = (0_usize..256).map(|i| {
+		let priority = Priority::ALL[i % Priority::COUNT];
+		priority.scope(map.get(&(i % 64).to_be_bytes()))
+	});
+
+	let results = join_all(gets).await;
+	assert!(results.iter().all(Result::is_ok));
+
+	let stats = db.pool_stats();
+	assert!(stats.iter().any(|stats| stats.requests > 0), "{stats:?}");
+	assert_eq!(stats.iter().map(|stats| stats.queued).sum::<usize>(), 0);
+}
+
+#[tokio::test]
+async fn expiry_hidden_from_every_read() {
+	use std::time::Duration;
+
+	let db = open_test_db("expiry-reads").await;
+	let map = &db["url_previews"];
+	assert!(map.expiry().is_some());
+
+	map.insert(b"live", b"val");
+	let expired = expiry::frame(b"old", Duration::ZERO);
+	db.db
+		.db
+		.put_cf(&db.db.cf("url_previews"), b"expired", expired)
+		.unwrap();
+
+	assert!(map.get(b"expired").await.is_err());
+	assert!(map.exists(b"expired").await.is_err());
+	assert!(map.exists_blocking(b"expired").is_err());
+	assert!(map.exists(b"live").await.is_ok());
+
+	let keys: Vec<_> = map
+		.raw_keys()
+		.map(|key| key.unwrap().to_vec())
+		.collect()
+		.await;
+	assert_eq!(keys, [b"live".to_vec()]);
+
+	let keys: Vec<_> = map
+		.rev_raw_keys()
+		.map(|key| key.unwrap().to_vec())
+		.collect()
+		.await;
+	assert_eq!(keys, [b"live".to_vec()]);
+
+	let items: Vec<_> = map
+		.raw_stream()
+		.map(|item| item.map(|(key, val)| (key.to_vec(), val.to_vec())).unwrap())
+		.collect()
+		.await;
+	assert_eq!(items, [(b"live".to_vec(), b"val".to_vec())]);
+
+	assert_eq!(map.count().await, 1);
+	assert_eq!(map.expire_blocking().unwrap(), 1);
+	assert_eq!(column_keys(&db.db, "url_previews"), [b"live".to_vec()]);
+}
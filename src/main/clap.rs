@@ -2,7 +2,7 @@
 
 use std::path::PathBuf;
 
-use clap::{ArgAction, Parser};
+use clap::{ArgAction, Parser, Subcommand};
 use conduwuit_core::{
 	Err, Result,
 	config::{Figment, FigmentValue},
@@ -121,6 +121,15 @@ pub(crate) struct Args {
 		require_equals(false),
 	)]
 	pub(crate) gc_muzzy: Option<bool>,
+
+	#[command(subcommand)]
+	pub(crate) command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+pub(crate) enum Command {
+	/// Inspect the database offline without starting the server.
+	Dbtool(crate::dbtool::Args),
 }
 
 /// Parse commandline arguments into structured data
@@ -145,6 +154,20 @@ pub(crate) fn update(mut config: Figment, args: &Args) -> Result<Figment> {
 		config = config.join(("admin_console_automatic", true));
 	}
 
+	// The database tool never writes, serves or logs over its output.
+	if let Some(Command::Dbtool(dbtool)) = &args.command {
+		config = config.merge(("rocksdb_read_only", !dbtool.secondary));
+		config = config.merge(("rocksdb_secondary", dbtool.secondary));
+		if dbtool.secondary {
+			// A directory of our own so no running replica's is shared.
+			let dir = format!("conduwuit-dbtool-{}", std::process::id());
+			config = config.merge(("rocksdb_secondary_path", std::env::temp_dir().join(dir)));
+		}
+		config = config.merge(("startup_netburst", false));
+		config = config.merge(("listening", false));
+		config = config.merge(("log", "off"));
+	}
+
 	// Execute commands after any commands listed in configuration file
 	config = config.adjoin(("admin_execute", &args.execute));
 
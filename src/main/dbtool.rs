@@ -0,0 +1,212 @@
+//! Offline database inspection. Runs the `query raw` admin queries from the
+//! shell against a database opened read-only, or as a secondary of a running
+//! server, without starting any services. Results are printed as JSON; queries
+//! over records print one JSON object per line.
+
+use std::{
+	fs,
+	io::{Write, stdout},
+	sync::Arc,
+};
+
+use clap::Subcommand;
+use conduwuit_core::{Result, utils::string::EMPTY};
+use conduwuit_database::{
+	Database,
+	inspect::{self, Range},
+};
+use futures::{StreamExt, TryStreamExt, future};
+use serde_json::{Value, json};
+
+use crate::server::Server;
+
+/// Inspect the database without starting the server, printing JSON.
+#[derive(clap::Args, Debug)]
+pub(crate) struct Args {
+	/// Open the database as a secondary which follows a running server,
+	/// instead of read-only.
+	#[arg(long)]
+	pub(crate) secondary: bool,
+
+	#[command(subcommand)]
+	query: Query,
+}
+
+#[derive(Debug, Subcommand)]
+enum Query {
+	/// - List database maps
+	Maps,
+
+	/// - Raw database record
+	Get {
+		/// Map name
+		map: String,
+
+		/// Key
+		key: String,
+	},
+
+	/// - Raw database keys iteration
+	Keys {
+		/// Map name
+		map: String,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database items iteration
+	Iter {
+		/// Map name
+		map: String,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database keys iteration
+	KeysFrom {
+		/// Map name
+		map: String,
+
+		/// Lower-bound
+		start: String,
+
+		/// Limit
+		#[arg(short, long)]
+		limit: Option<usize>,
+	},
+
+	/// - Raw database items iteration
+	IterFrom {
+		/// Map name
+		map: String,
+
+		/// Lower-bound
+		start: String,
+
+		/// Limit
+		#[arg(short, long)]
+		limit: Option<usize>,
+	},
+
+	/// - Raw database record count
+	Count {
+		/// Map name
+		map: Option<String>,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database keys size breakdown
+	KeysSizes {
+		/// Map name
+		map: Option<String>,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database keys total bytes
+	KeysTotal {
+		/// Map name
+		map: Option<String>,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database values size breakdown
+	ValsSizes {
+		/// Map name
+		map: Option<String>,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+
+	/// - Raw database values total bytes
+	ValsTotal {
+		/// Map name
+		map: Option<String>,
+
+		/// Key prefix
+		prefix: Option<String>,
+	},
+}
+
+pub(crate) async fn run(server: &Arc<Server>, args: &Args) -> Result {
+	let db = Database::open(&server.server).await?;
+	let result = query(&db, &args.query).await;
+	drop(db);
+
+	// The secondary's directory is private to this run; nothing else reads it.
+	let config = &server.server.config;
+	if let Some(path) = config
+		.rocksdb_secondary_path
+		.as_ref()
+		.filter(|_| args.secondary)
+	{
+		fs::remove_dir_all(path)?;
+	}
+
+	result
+}
+
+async fn query(db: &Arc<Database>, query: &Query) -> Result {
+	match query {
+		| Query::Maps => print(&db.keys().copied().collect()),
+		| Query::Get { map, key } => print(&inspect::get(db.get(map)?, key.as_bytes()).await?),
+		| Query::Keys { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			inspect::keys(db.get(map)?, Range::Prefix(prefix.as_bytes()))
+				.try_for_each(|key| future::ready(print(&key)))
+				.await
+		},
+		| Query::Iter { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			inspect::iter(db.get(map)?, Range::Prefix(prefix.as_bytes()))
+				.try_for_each(|record| future::ready(print(&record)))
+				.await
+		},
+		| Query::KeysFrom { map, start, limit } =>
+			inspect::keys(db.get(map)?, Range::From(start.as_bytes()))
+				.take(limit.unwrap_or(usize::MAX))
+				.try_for_each(|key| future::ready(print(&key)))
+				.await,
+		| Query::IterFrom { map, start, limit } =>
+			inspect::iter(db.get(map)?, Range::From(start.as_bytes()))
+				.take(limit.unwrap_or(usize::MAX))
+				.try_for_each(|record| future::ready(print(&record)))
+				.await,
+		| Query::Count { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			print(&inspect::count(db, map.as_deref(), prefix).await.into())
+		},
+		| Query::KeysSizes { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			print(&json!(inspect::keys_sizes(db, map.as_deref(), prefix).await))
+		},
+		| Query::KeysTotal { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			print(&inspect::keys_total(db, map.as_deref(), prefix).await.into())
+		},
+		| Query::ValsSizes { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			print(&json!(inspect::vals_sizes(db, map.as_deref(), prefix).await))
+		},
+		| Query::ValsTotal { map, prefix } => {
+			let prefix = prefix.as_deref().unwrap_or(EMPTY);
+			print(&inspect::vals_total(db, map.as_deref(), prefix).await.into())
+		},
+	}
+}
+
+fn print(value: &Value) -> Result {
+	let mut out = stdout().lock();
+	serde_json::to_writer(&mut out, value)?;
+	writeln!(out)?;
+
+	Ok(())
+}
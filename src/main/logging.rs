@@ -4,7 +4,7 @@
 	Result,
 	config::Config,
 	debug_warn, err,
-	log::{ConsoleFormat, ConsoleWriter, LogLevelReloadHandles, capture, fmt_span},
+	log::{ConsoleFormat, ConsoleWriter, LogLevelReloadHandles, capture, fmt_span, ring},
 	result::UnwrapOrErr,
 };
 use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, layer::SubscriberExt, reload};
@@ -18,7 +18,7 @@
 #[allow(clippy::redundant_clone)]
 pub(crate) fn init(
 	config: &Config,
-) -> Result<(LogLevelReloadHandles, TracingFlameGuard, Arc<capture::State>)> {
+) -> Result<(LogLevelReloadHandles, TracingFlameGuard, Arc<capture::State>, Arc<ring::Ring>)> {
 	let reload_handles = LogLevelReloadHandles::default();
 
 	let console_span_events = fmt_span::from_str(&config.log_span_events).unwrap_or_err();
@@ -39,9 +39,23 @@ pub(crate) fn init(
 	let cap_state = Arc::new(capture::State::new());
 	let cap_layer = capture::Layer::new(&cap_state);
 
+	let ring = Arc::new(ring::Ring::new(config.log_ring_capacity));
+	let ring_layer = if ring.is_enabled() {
+		let ring_filter = EnvFilter::builder()
+			.with_regex(config.log_filter_regex)
+			.parse(&config.log_ring)
+			.map_err(|e| err!(Config("log_ring", "{e}.")))?;
+		let (ring_reload_filter, ring_reload_handle) = reload::Layer::new(ring_filter);
+		reload_handles.add("ring", Box::new(ring_reload_handle));
+		Some(ring::Layer::new(&ring).with_filter(ring_reload_filter))
+	} else {
+		None
+	};
+
 	let subscriber = Registry::default()
 		.with(console_layer.with_filter(console_reload_filter))
-		.with(cap_layer);
+		.with(cap_layer)
+		.with(ring_layer);
 
 	#[cfg(feature = "sentry_telemetry")]
 	let subscriber = {
@@ -87,7 +101,23 @@ pub(crate) fn init(
 			Some(telemetry.with_filter(jaeger_reload_filter))
 		});
 
-		let subscriber = subscriber.with(flame_layer).with(jaeger_layer);
+		let otlp_layer = match config.otlp_endpoint.as_deref() {
+			| Some(endpoint) => {
+				let otlp_filter = EnvFilter::try_new(&config.otlp_filter)
+					.map_err(|e| err!(Config("otlp_filter", "{e}.")))?;
+				let tracer = otlp_tracer(endpoint, &config.otlp_protocol)?;
+				let telemetry = tracing_opentelemetry::layer().with_tracer(tracer);
+				let (otlp_reload_filter, otlp_reload_handle) = reload::Layer::new(otlp_filter);
+				reload_handles.add("otlp", Box::new(otlp_reload_handle));
+				Some(telemetry.with_filter(otlp_reload_filter))
+			},
+			| None => None,
+		};
+
+		let subscriber = subscriber
+			.with(flame_layer)
+			.with(jaeger_layer)
+			.with(otlp_layer);
 		(subscriber, flame_guard)
 	};
 
@@ -95,7 +125,7 @@ pub(crate) fn init(
 	#[cfg_attr(not(feature = "perf_measurements"), allow(clippy::let_unit_value))]
 	let flame_guard = ();
 
-	let ret = (reload_handles, flame_guard, cap_state);
+	let ret = (reload_handles, flame_guard, cap_state, ring);
 
 	// Enable the tokio console. This is slightly kludgy because we're judggling
 	// compile-time and runtime conditions to elide it, each of those changing the
@@ -122,6 +152,45 @@ pub(crate) fn init(
 	Ok(ret)
 }
 
+/// Install the OTLP pipeline and the W3C trace-context propagator, returning
+/// the tracer spans are exported through.
+#[cfg(feature = "perf_measurements")]
+fn otlp_tracer(endpoint: &str, protocol: &str) -> Result<opentelemetry_sdk::trace::Tracer> {
+	use opentelemetry::KeyValue;
+	use opentelemetry_otlp::{SpanExporterBuilder, WithExportConfig};
+	use opentelemetry_sdk::{Resource, propagation::TraceContextPropagator, runtime, trace};
+
+	let exporter: SpanExporterBuilder = match protocol {
+		| "grpc" => opentelemetry_otlp::new_exporter()
+			.tonic()
+			.with_endpoint(endpoint)
+			.into(),
+		| "http" => opentelemetry_otlp::new_exporter()
+			.http()
+			.with_endpoint(endpoint)
+			.into(),
+		| _ => return Err(err!(Config("otlp_protocol", "Must be either \"grpc\" or \"http\"."))),
+	};
+
+	let resource = Resource::new([KeyValue::new("service.name", "conduwuit")]);
+	let tracer = opentelemetry_otlp::new_pipeline()
+		.tracing()
+		.with_exporter(exporter)
+		.with_trace_config(trace::config().with_resource(resource))
+		.install_batch(runtime::Tokio)
+		.map_err(|e| err!(Config("otlp_endpoint", "{e}.")))?;
+
+	opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+	Ok(tracer)
+}
+
+/// Export any spans still buffered before exiting.
+pub(crate) fn flush() {
+	#[cfg(feature = "perf_measurements")]
+	opentelemetry::global::shutdown_tracer_provider();
+}
+
 fn tokio_console_enabled(config: &Config) -> (bool, &'static str) {
 	if !cfg!(all(feature = "tokio_console", tokio_unstable)) {
 		return (false, "");
@@ -1,6 +1,7 @@
 #![type_length_limit = "49152"] //TODO: reduce me
 
 pub(crate) mod clap;
+mod dbtool;
 mod logging;
 mod mods;
 mod restart;
@@ -21,8 +22,17 @@ fn main() -> Result {
 	let runtime = runtime::new(&args)?;
 	let server = Server::new(&args, Some(runtime.handle()))?;
 
+	if let Some(clap::Command::Dbtool(dbtool)) = &args.command {
+		let result = runtime.block_on(dbtool::run(&server, dbtool));
+		runtime::shutdown(&server, runtime);
+		return result;
+	}
+
+	conduwuit_social_admin::register();
 	runtime.spawn(signal::signal(server.clone()));
-	runtime.block_on(async_main(&server))?;
+	let result = runtime.block_on(async_main(&server));
+	logging::flush();
+	result?;
 	runtime::shutdown(&server, runtime);
 
 	#[cfg(unix)]
@@ -35,7 +45,8 @@ fn main() -> Result {
 }
 
 /// Operate the server normally in release-mode static builds. This will start,
-/// run and stop the server within the asynchronous runtime.
+/// run and stop the server within the asynchronous runtime. Reloading restarts
+/// the services, loading the plugins anew.
 #[cfg(any(not(conduwuit_mods), not(feature = "conduwuit_mods")))]
 #[tracing::instrument(
 	name = "main",
@@ -45,64 +56,93 @@ fn main() -> Result {
 async fn async_main(server: &Arc<Server>) -> Result<(), Error> {
 	extern crate conduwuit_router as router;
 
-	match router::start::<(conduwuit_service::Services, conduwuit_social_service::Services)>(
-		&server.server,
-	)
-	.await
-	{
-		| Ok((core_services, social_services)) => {
-			server.core_services.lock().await.insert(core_services);
-			server.social_services.lock().await.insert(social_services);
-		},
-		| Err(error) => {
-			error!("Critical error starting server: {error}");
+	use router::Plugins;
+
+	loop {
+		match router::start::<
+			Plugins<(conduwuit_service::Services, conduwuit_social_service::Services)>,
+		>(&server.server)
+		.await
+		{
+			| Ok(Plugins {
+				host: (core_services, social_services),
+				loaded,
+			}) => {
+				server.core_services.lock().await.insert(core_services);
+				server.social_services.lock().await.insert(social_services);
+				server.plugins.lock().await.insert(loaded);
+			},
+			| Err(error) => {
+				error!("Critical error starting server: {error}");
+				return Err(error);
+			},
+		};
+
+		if let Err(error) = router::run::<
+			Plugins<(
+				conduwuit_service_api::ServiceApiRouter,
+				conduwuit_social_api::SocialApiRouter,
+			)>,
+		>(Plugins {
+			host: (
+				server
+					.core_services
+					.lock()
+					.await
+					.as_ref()
+					.expect("core services initialized")
+					.clone(),
+				server
+					.social_services
+					.lock()
+					.await
+					.as_ref()
+					.expect("social services initialized")
+					.clone(),
+			),
+			loaded: server
+				.plugins
+				.lock()
+				.await
+				.as_ref()
+				.expect("plugins initialized")
+				.clone(),
+		})
+		.await
+		{
+			error!("Critical error running server: {error}");
 			return Err(error);
-		},
-	};
-
-	if let Err(error) = router::run::<(
-		conduwuit_service_api::ServiceApiRouter,
-		conduwuit_social_api::SocialApiRouter,
-	)>((
-		server
-			.core_services
-			.lock()
-			.await
-			.as_ref()
-			.expect("core services initialized")
-			.clone(),
-		server
-			.social_services
-			.lock()
-			.await
-			.as_ref()
-			.expect("social services initialized")
-			.clone(),
-	))
-	.await
-	{
-		error!("Critical error running server: {error}");
-		return Err(error);
-	}
+		}
+
+		if let Err(error) = router::stop(Plugins {
+			host: (
+				server
+					.core_services
+					.lock()
+					.await
+					.take()
+					.expect("core services stopped"),
+				server
+					.social_services
+					.lock()
+					.await
+					.take()
+					.expect("social services stopped"),
+			),
+			loaded: server.plugins.lock().await.take().expect("plugins stopped"),
+		})
+		.await
+		{
+			error!("Critical error stopping server: {error}");
+			return Err(error);
+		}
+
+		if !server.server.reloading.swap(false, Ordering::AcqRel) {
+			break;
+		}
 
-	if let Err(error) = router::stop((
-		server
-			.core_services
-			.lock()
-			.await
-			.take()
-			.expect("core services stopped"),
-		server
-			.social_services
-			.lock()
-			.await
-			.take()
-			.expect("social services stopped"),
-	))
-	.await
-	{
-		error!("Critical error stopping server: {error}");
-		return Err(error);
+		server.server.stopping.store(false, Ordering::Release);
+		debug_info!("Reloading");
 	}
 
 	debug_info!("Exit runtime");
@@ -20,6 +20,8 @@ pub(crate) struct Server {
 
 	pub(crate) social_services: Mutex<Option<Arc<conduwuit_social_service::Services>>>,
 
+	pub(crate) plugins: Mutex<Option<Arc<conduwuit_router::plugin::Loaded>>>,
+
 	_tracing_flame_guard: TracingFlameGuard,
 
 	#[cfg(feature = "sentry_telemetry")]
@@ -48,7 +50,7 @@ pub(crate) fn new(
 			.and_then(|raw| crate::clap::update(raw, args))
 			.and_then(|raw| Config::new(&raw))?;
 
-		let (tracing_reload_handle, tracing_flame_guard, capture) =
+		let (tracing_reload_handle, tracing_flame_guard, capture, ring) =
 			crate::logging::init(&config)?;
 
 		config.check()?;
@@ -75,10 +77,12 @@ pub(crate) fn new(
 			server: Arc::new(conduwuit_core::Server::new(config, runtime.cloned(), Log {
 				reload: tracing_reload_handle,
 				capture,
+				ring,
 			})),
 
 			core_services: None.into(),
 			social_services: None.into(),
+			plugins: None.into(),
 
 			_tracing_flame_guard: tracing_flame_guard,
 
@@ -1,16 +1,17 @@
-use std::{any::Any, sync::Arc, time::Duration};
+use std::{any::Any, net::SocketAddr, sync::Arc, time::Duration};
 
 use crate::{RouterServices, request, router};
 use axum::{
 	Router,
-	extract::{DefaultBodyLimit, MatchedPath},
+	extract::{ConnectInfo, DefaultBodyLimit, MatchedPath},
 };
 use axum_client_ip::SecureClientIpSource;
-use conduwuit::{Result, Server, debug, error};
+use conduwuit::{Result, Server, debug, err, error, log::propagate};
 use http::{
 	HeaderValue, Method, StatusCode,
 	header::{self, HeaderName},
 };
+use ipaddress::IPAddress;
 use service::ServicesTrait;
 use tower::ServiceBuilder;
 use tower_http::{
@@ -47,12 +48,22 @@ pub(crate) fn build<R: RouterServices>(services: R::Services) -> Result<(Router,
 	))]
 	let layers = layers.layer(compression_layer(server));
 
+	let trusted_proxies: Arc<[IPAddress]> = server
+		.config
+		.otlp_trusted_proxies
+		.iter()
+		.map(IPAddress::parse)
+		.collect::<Result<_, String>>()
+		.map_err(|e| err!(Config("otlp_trusted_proxies", e)))?;
+
 	let services_ = services.clone();
 	let layers = layers
 		.layer(SetSensitiveHeadersLayer::new([header::AUTHORIZATION]))
 		.layer(
 			TraceLayer::new_for_http()
-				.make_span_with(tracing_span::<_>)
+				.make_span_with(move |request: &http::Request<_>| {
+					tracing_span(request, &trusted_proxies)
+				})
 				.on_failure(DefaultOnFailure::new().level(Level::ERROR))
 				.on_request(DefaultOnRequest::new().level(Level::TRACE))
 				.on_response(DefaultOnResponse::new().level(Level::DEBUG)),
@@ -94,7 +105,7 @@ pub(crate) fn build<R: RouterServices>(services: R::Services) -> Result<(Router,
 		.layer(body_limit_layer(server))
 		.layer(CatchPanicLayer::custom(move |panic| catch_panic(panic, services_.clone())));
 
-	let (router, guard) = router::build::<R>(services);
+	let (router, guard) = router::build::<R>(services)?;
 	Ok((router.layer(layers), guard))
 }
 
@@ -200,19 +211,38 @@ fn catch_panic<R: ServicesTrait>(
 		.expect("Failed to create response for our panic catcher?")
 }
 
-fn tracing_span<T>(request: &http::Request<T>) -> tracing::Span {
+fn tracing_span<T>(request: &http::Request<T>, trusted_proxies: &[IPAddress]) -> tracing::Span {
 	let path = request
 		.extensions()
 		.get::<MatchedPath>()
 		.map_or_else(|| request_path_str(request), truncated_matched_path);
 
-	tracing::span! {
+	let span = tracing::span! {
 		parent: None,
 		debug::INFO_SPAN_LEVEL,
 		"router",
 		method = %request.method(),
 		%path,
+	};
+
+	if is_trusted_proxy(request, trusted_proxies) {
+		propagate::set_parent(&span, request.headers());
+	}
+
+	span
+}
+
+/// Whether the peer which sent the request may name the trace it continues.
+fn is_trusted_proxy<T>(request: &http::Request<T>, trusted_proxies: &[IPAddress]) -> bool {
+	if trusted_proxies.is_empty() {
+		return false;
 	}
+
+	request
+		.extensions()
+		.get::<ConnectInfo<SocketAddr>>()
+		.and_then(|ConnectInfo(addr)| IPAddress::parse(addr.ip().to_string()).ok())
+		.is_some_and(|ip| trusted_proxies.iter().any(|cidr| cidr.includes(&ip)))
 }
 
 fn request_path_str<T>(request: &http::Request<T>) -> &str {
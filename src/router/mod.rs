@@ -1,6 +1,7 @@
 #![type_length_limit = "32768"] //TODO: reduce me
 
 mod layers;
+pub mod plugin;
 mod request;
 mod router;
 mod run;
@@ -9,6 +10,7 @@
 pub mod state;
 
 extern crate conduwuit_core as conduwuit;
+extern crate conduwuit_database as database;
 extern crate conduwuit_service_core as service;
 
 use std::{panic::AssertUnwindSafe, pin::Pin, sync::Arc};
@@ -17,6 +19,7 @@
 use futures::{Future, FutureExt, TryFutureExt};
 
 pub use axum::routing::Router;
+pub use plugin::Plugins;
 use service::ServicesTrait;
 pub use services::RouterServices;
 pub use state::Guard;
@@ -0,0 +1,434 @@
+//! Plugins: services and routes built apart from the server as dynamic
+//! libraries and loaded from the `plugin_dir` at startup and on reload. A
+//! plugin crate declares its ServicesTrait and RouterServices pair with the
+//! plugin! macro; the server then starts, serves and stops it alongside its
+//! own services.
+
+use std::{
+	any::Any,
+	collections::{BTreeMap, BTreeSet},
+	future::Future,
+	iter,
+	panic::AssertUnwindSafe,
+	pin::Pin,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use axum::Router;
+use conduwuit::{Err, Error, Result, Server, debug_error, err};
+use database::Database;
+use futures::{FutureExt, TryFutureExt, future::try_join_all, try_join};
+use service::{Map, ServicesTrait};
+
+use crate::{RouterServices, services::merge};
+
+/// Name of the symbol under which each plugin exports its Declaration.
+pub const SYMBOL: &str = "conduwuit_plugin";
+
+pub type StartResult = Pin<Box<dyn Future<Output = Result<Box<dyn Plugin>>> + Send>>;
+
+/// Exported by each plugin under SYMBOL; see the plugin! macro. The name and
+/// version are read before anything else and must remain the leading fields.
+#[repr(C)]
+pub struct Declaration {
+	/// Name of the plugin crate.
+	pub name: &'static str,
+
+	/// Version of conduwuit the plugin was built against.
+	pub version: &'static str,
+
+	/// Flags the plugin crate was built with.
+	pub rustc_flags: &'static [&'static str],
+
+	/// Start the services of the plugin.
+	pub start: fn(Arc<Server>) -> StartResult,
+}
+
+/// A started plugin; the object-safe part of its ServicesTrait and
+/// RouterServices pair.
+#[async_trait]
+pub trait Plugin: Send + Sync {
+	fn name(&self) -> String;
+
+	fn service_map(&self) -> Arc<Map>;
+
+	fn build(&self) -> Result<(Router, Box<dyn Any + Send + Sync>)>;
+
+	async fn poll(&self) -> Result<()>;
+
+	async fn stop(&self);
+
+	fn check_refs(self: Box<Self>);
+}
+
+/// The services of a plugin along with the router serving them.
+struct Instance<R: RouterServices> {
+	services: R::Services,
+}
+
+/// Services of the server along with those of the loaded plugins.
+pub struct Plugins<S> {
+	pub host: S,
+	pub loaded: Arc<Loaded>,
+}
+
+/// Plugins loaded into the process. The plugins are dropped before the modules
+/// providing their code are unloaded.
+#[derive(Default)]
+pub struct Loaded {
+	plugins: Vec<Box<dyn Plugin>>,
+
+	#[cfg(feature = "plugins")]
+	modules: Vec<conduwuit::mods::Module>,
+}
+
+/// Declare the plugin of a crate built as a dynamic library: the services it
+/// starts and the router serving them. Invoke once at the crate root in place
+/// of `rustc_flags_capture!`; the crate must depend on `conduwuit_core`.
+#[macro_export]
+macro_rules! plugin {
+	($services:ty, $router:ty) => {
+		::conduwuit_core::rustc_flags_capture! {}
+		::conduwuit_core::mod_ctor! {}
+		::conduwuit_core::mod_dtor! {}
+
+		#[unsafe(export_name = "conduwuit_plugin")]
+		pub static PLUGIN: $crate::plugin::Declaration = $crate::plugin::Declaration {
+			name: env!("CARGO_PKG_NAME"),
+			version: ::conduwuit_core::info::version::SEMANTIC,
+			rustc_flags: &RUSTC_FLAGS,
+			start: $crate::plugin::start::<$services, $router>,
+		};
+	};
+}
+
+/// Start the services S of a plugin served by R. Called by the server through
+/// the Declaration of the plugin.
+pub fn start<S, R>(server: Arc<Server>) -> StartResult
+where
+	S: ServicesTrait<BuildResult = R::Services>,
+	R: RouterServices,
+	R::Guard: 'static,
+{
+	AssertUnwindSafe(S::start(server))
+		.catch_unwind()
+		.map_err(Error::from_panic)
+		.unwrap_or_else(Err)
+		.map_ok(|services| Box::new(Instance::<R> { services }) as Box<dyn Plugin>)
+		.boxed()
+}
+
+#[async_trait]
+impl<R> Plugin for Instance<R>
+where
+	R: RouterServices,
+	R::Guard: 'static,
+{
+	fn name(&self) -> String { self.services.name() }
+
+	fn service_map(&self) -> Arc<Map> { self.services.service_map() }
+
+	fn build(&self) -> Result<(Router, Box<dyn Any + Send + Sync>)> {
+		let (router, guard) = R::build(self.services.clone())?;
+		Ok((router, Box::new(guard)))
+	}
+
+	async fn poll(&self) -> Result<()> { self.services.poll().await }
+
+	async fn stop(&self) { self.services.stop().await }
+
+	fn check_refs(self: Box<Self>) { self.services.check_refs() }
+}
+
+impl<S: Clone> Clone for Plugins<S> {
+	fn clone(&self) -> Self {
+		Self {
+			host: self.host.clone(),
+			loaded: self.loaded.clone(),
+		}
+	}
+}
+
+#[async_trait]
+impl<T> ServicesTrait for Plugins<T>
+where
+	T: ServicesTrait,
+	T::BuildResult: ServicesTrait,
+{
+	type BuildResult = Plugins<T::BuildResult>;
+
+	fn server(&self) -> Arc<Server> { self.host.server() }
+
+	fn service_map(&self) -> Arc<Map> {
+		let maps = iter::once(self.host.service_map()).chain(
+			self.loaded
+				.plugins
+				.iter()
+				.map(|plugin| plugin.service_map()),
+		);
+
+		let mut map = BTreeMap::new();
+		for in_map in maps {
+			let in_map = in_map.read().expect("locked for reading");
+			map.extend(in_map.iter().map(|(key, val)| (key.clone(), val.clone())));
+		}
+
+		Arc::new(RwLock::new(map))
+	}
+
+	fn db(&self) -> Arc<Database> { self.host.db() }
+
+	async fn start(server: Arc<Server>) -> Result<Self::BuildResult>
+	where
+		Self: Sized,
+	{
+		let host = T::start(server.clone()).await?;
+		let loaded = match load(&server).await {
+			| Ok(loaded) => loaded,
+			| Err(error) => {
+				host.stop().await;
+				return Err(error);
+			},
+		};
+
+		let maps: Vec<_> = iter::once(host.service_map())
+			.chain(loaded.plugins.iter().map(|plugin| plugin.service_map()))
+			.collect();
+
+		let duplicate = {
+			let maps: Vec<_> = maps
+				.iter()
+				.map(|map| map.read().expect("locked for reading"))
+				.collect();
+
+			duplicate(maps.iter().flat_map(|map| map.keys())).cloned()
+		};
+
+		if let Some(name) = duplicate {
+			loaded.stop().await;
+			host.stop().await;
+			return Err!("More than one service is named {name:?}.");
+		}
+
+		Ok(Plugins { host, loaded: Arc::new(loaded) })
+	}
+
+	async fn stop(&self) {
+		self.loaded.stop().await;
+		self.host.stop().await;
+	}
+
+	async fn poll(&self) -> Result<()> {
+		let plugins = self.loaded.plugins.iter().map(|plugin| plugin.poll());
+		try_join!(self.host.poll(), try_join_all(plugins)).map(|_| ())
+	}
+
+	fn name(&self) -> String {
+		let plugins = self.loaded.plugins.iter().map(|plugin| plugin.name());
+		iter::once(self.host.name())
+			.chain(plugins)
+			.collect::<Vec<_>>()
+			.join(" + ")
+	}
+
+	fn check_refs(self) {
+		self.host.check_refs();
+		match Arc::try_unwrap(self.loaded) {
+			| Ok(loaded) => loaded.check_refs(),
+			| Err(loaded) => debug_error!(
+				"{} dangling references to plugins after shutdown",
+				Arc::strong_count(&loaded)
+			),
+		}
+	}
+}
+
+impl<R> RouterServices for Plugins<R>
+where
+	R: RouterServices,
+	<R::Services as ServicesTrait>::BuildResult: ServicesTrait,
+{
+	type Guard = (R::Guard, Vec<Box<dyn Any + Send + Sync>>);
+	type Services = Plugins<R::Services>;
+
+	fn build(services: Self::Services) -> Result<(Router, Self::Guard)> {
+		let (mut router, guard) = R::build(services.host)?;
+		let mut guards = Vec::with_capacity(services.loaded.plugins.len());
+		for plugin in &services.loaded.plugins {
+			let (plugin_router, plugin_guard) = plugin.build()?;
+			router = merge(router, plugin_router)
+				.map_err(|e| err!("Routes of plugin {:?}: {e}", plugin.name()))?;
+			guards.push(plugin_guard);
+		}
+
+		Ok((router, (guard, guards)))
+	}
+}
+
+/// The first name which occurs more than once.
+fn duplicate<'a, I>(names: I) -> Option<&'a String>
+where
+	I: IntoIterator<Item = &'a String>,
+{
+	let mut seen = BTreeSet::new();
+	names.into_iter().find(|name| !seen.insert(*name))
+}
+
+impl Loaded {
+	/// Stop the plugins in the reverse order they were started.
+	async fn stop(&self) {
+		for plugin in self.plugins.iter().rev() {
+			plugin.stop().await;
+		}
+	}
+
+	fn check_refs(self) {
+		for plugin in self.plugins {
+			plugin.check_refs();
+		}
+	}
+}
+
+/// Load and start the plugins in the configured directory.
+#[cfg(feature = "plugins")]
+async fn load(server: &Arc<Server>) -> Result<Loaded> {
+	use conduwuit::{info, mods::Module};
+
+	let mut loaded = Loaded::default();
+	let Some(dir) = server.config.plugin_dir.as_deref() else {
+		return Ok(loaded);
+	};
+
+	for path in loader::discover(dir)? {
+		let module = match Module::from_path(path.into_os_string()) {
+			| Ok(module) => module,
+			| Err(error) => {
+				loaded.stop().await;
+				return Err(error);
+			},
+		};
+
+		let declaration = match loader::declaration(&module) {
+			| Ok(declaration) => declaration,
+			| Err(error) => {
+				loaded.stop().await;
+				return Err(error);
+			},
+		};
+
+		let name = declaration.name;
+		info!(plugin = name, version = declaration.version, "Starting plugin...");
+
+		let started = (declaration.start)(server.clone()).await;
+		loaded.modules.push(module);
+		match started {
+			| Ok(plugin) => loaded.plugins.push(plugin),
+			| Err(error) => {
+				loaded.stop().await;
+				return Err!("Starting plugin {name:?} failed: {error}");
+			},
+		}
+	}
+
+	Ok(loaded)
+}
+
+#[cfg(not(feature = "plugins"))]
+async fn load(server: &Arc<Server>) -> Result<Loaded> {
+	if server.config.plugin_dir.is_some() {
+		return conduwuit::Err!(Config(
+			"plugin_dir",
+			"conduwuit was not built with plugin support (\"plugins\")"
+		));
+	}
+
+	Ok(Loaded::default())
+}
+
+#[cfg(feature = "plugins")]
+mod loader {
+	use std::{
+		env::consts::DLL_EXTENSION,
+		fs,
+		path::{Path, PathBuf},
+	};
+
+	use conduwuit::{
+		Err, Result,
+		info::{rustc, version::SEMANTIC},
+		mods::Module,
+	};
+
+	use super::{Declaration, SYMBOL};
+
+	/// Crate whose flags a plugin's must agree with; it is the one the plugin
+	/// is loaded by.
+	const HOST_CRATE: &str = "router";
+
+	/// Paths of the dynamic libraries in the directory, in the order they are
+	/// loaded.
+	pub(super) fn discover(dir: &Path) -> Result<Vec<PathBuf>> {
+		let mut paths = Vec::new();
+		for entry in fs::read_dir(dir)? {
+			let path = entry?.path();
+			if path
+				.extension()
+				.is_some_and(|extension| extension == DLL_EXTENSION)
+			{
+				paths.push(path);
+			}
+		}
+
+		paths.sort();
+		Ok(paths)
+	}
+
+	/// The Declaration exported by the module, once checked against the
+	/// server.
+	pub(super) fn declaration(module: &Module) -> Result<&Declaration> {
+		let symbol = module.get::<*const Declaration>(SYMBOL)?;
+
+		// SAFETY: The symbol is the address of the Declaration static of the
+		// plugin, which lives as long as the module is loaded.
+		let declaration = unsafe { &**symbol };
+		let name = declaration.name;
+		if declaration.version != SEMANTIC {
+			return Err!(
+				"Plugin {name:?} was built against conduwuit {} but this is {SEMANTIC}.",
+				declaration.version
+			);
+		}
+
+		let host = rustc::FLAGS
+			.lock()
+			.expect("locked")
+			.get(HOST_CRATE)
+			.map(|flags| rustc::abi_flags(flags))
+			.unwrap_or_default();
+
+		let plugin = rustc::abi_flags(declaration.rustc_flags);
+		if plugin != host {
+			return Err!(
+				"Plugin {name:?} was built with flags {plugin:?} which differ from the server's \
+				 {host:?}."
+			);
+		}
+
+		Ok(declaration)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::duplicate;
+
+	#[test]
+	fn duplicate_names() {
+		let names = ["admin", "rooms::timeline", "example", "globals"].map(String::from);
+		assert_eq!(duplicate(&names), None);
+
+		let names = ["admin", "example", "globals", "example", "admin"].map(String::from);
+		assert_eq!(duplicate(&names).map(String::as_str), Some("example"));
+	}
+}
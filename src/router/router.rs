@@ -1,11 +1,11 @@
 use axum::{Router, response::IntoResponse};
-use conduwuit::Error;
+use conduwuit::{Error, Result};
 // use conduwuit_social_api::router::{state, state::Guard};
 use crate::RouterServices;
 use http::{StatusCode, Uri};
 use ruma::api::client::error::ErrorKind;
 
-pub(crate) fn build<R: RouterServices>(services: R::Services) -> (Router, R::Guard) {
+pub(crate) fn build<R: RouterServices>(services: R::Services) -> Result<(Router, R::Guard)> {
 	// let router = Router::<state::State>::new();
 	// let (state, guard) = state::create(services.clone());
 	// let router = conduwuit_social_api::router::build(router, &services.server)
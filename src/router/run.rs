@@ -1,4 +1,3 @@
-// extern crate conduwuit_social_admin as admin;
 extern crate conduwuit_core as conduwuit;
 
 use std::{
@@ -23,9 +22,6 @@ pub(crate) async fn run<R: RouterServices>(services: R::Services) -> Result<()>
 	let server = &services.server();
 	debug!("Start");
 
-	// Install the admin room callback here for now
-	// admin::init(&services.admin).await;
-
 	// Setup shutdown/signal handling
 	let handle = ServerHandle::new();
 	let (tx, _) = broadcast::channel::<()>(1);
@@ -49,9 +45,6 @@ pub(crate) async fn run<R: RouterServices>(services: R::Services) -> Result<()>
 	sigs.abort();
 	_ = sigs.await;
 
-	// Remove the admin room callback
-	// admin::fini(&services.admin).await;
-
 	debug_info!("Finish");
 	res
 }
@@ -1,4 +1,7 @@
+use std::panic::{AssertUnwindSafe, catch_unwind};
+
 use axum::Router;
+use conduwuit::{Error, Result, err};
 use service::ServicesTrait;
 
 /// Router-specific service collections
@@ -6,7 +9,7 @@ pub trait RouterServices: 'static {
 	type Services: ServicesTrait + Clone;
 	type Guard: Send + Sync;
 
-	fn build(services: Self::Services) -> (Router, Self::Guard);
+	fn build(services: Self::Services) -> Result<(Router, Self::Guard)>;
 }
 
 // TODO: use macro to impl this for arbitrary sized tuples
@@ -14,9 +17,50 @@ impl<T1: RouterServices, T2: RouterServices> RouterServices for (T1, T2) {
 	type Services = (T1::Services, T2::Services);
 	type Guard = (T1::Guard, T2::Guard);
 
-	fn build(services: Self::Services) -> (Router, Self::Guard) {
-		let (router1, guard1) = T1::build(services.0);
-		let (router2, guard2) = T2::build(services.1);
-		(router1.merge(router2), (guard1, guard2))
+	fn build(services: Self::Services) -> Result<(Router, Self::Guard)> {
+		let (router1, guard1) = T1::build(services.0)?;
+		let (router2, guard2) = T2::build(services.1)?;
+		Ok((merge(router1, router2)?, (guard1, guard2)))
+	}
+}
+
+/// Merge the routes of other into the router. Routes which overlap, or a
+/// fallback on both, are an error rather than the panic of Router::merge.
+pub fn merge(router: Router, other: Router) -> Result<Router> {
+	catch_unwind(AssertUnwindSafe(|| router.merge(other)))
+		.map_err(Error::from_panic)
+		.map_err(|e| err!("Routes conflict: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+	use axum::{Router, routing::get};
+
+	use super::merge;
+
+	async fn handler() -> &'static str { "" }
+
+	#[test]
+	fn merge_disjoint() {
+		let router = Router::new().route("/a", get(handler));
+		let other = Router::new().route("/b", get(handler)).fallback(handler);
+
+		assert!(merge(router, other).is_ok());
+	}
+
+	#[test]
+	fn merge_overlapping() {
+		let router = Router::new().route("/a", get(handler));
+		let other = Router::new().route("/a", get(handler));
+
+		assert!(merge(router, other).is_err());
+	}
+
+	#[test]
+	fn merge_two_fallbacks() {
+		let router = Router::new().fallback(handler);
+		let other = Router::new().fallback(handler);
+
+		assert!(merge(router, other).is_err());
 	}
 }
@@ -0,0 +1,23 @@
+use axum_extra::{
+	TypedHeader,
+	headers::{Authorization, authorization::Bearer},
+};
+use conduwuit::{Err, Result};
+
+/// Token presented for an endpoint guarded by a configured token.
+pub(crate) type Token = Option<TypedHeader<Authorization<Bearer>>>;
+
+/// Endpoints guarded by a token are hidden unless one is configured, and
+/// require it as a bearer token.
+pub(crate) fn authorize(expected: Option<&str>, token: Token) -> Result {
+	let Some(expected) = expected else {
+		return Err!(Request(NotFound("Not Found")));
+	};
+
+	match token {
+		| None => Err!(Request(MissingToken("Missing bearer token."))),
+		| Some(TypedHeader(Authorization(bearer))) if bearer.token() != expected =>
+			Err!(Request(Forbidden("Invalid bearer token."))),
+		| Some(_) => Ok(()),
+	}
+}
@@ -0,0 +1,118 @@
+use std::path::PathBuf;
+
+use axum::{
+	Json,
+	extract::{Path, Query, State},
+	response::{IntoResponse, Response},
+};
+use conduwuit::{Result, alloc, alloc::profile};
+use conduwuit_service::Services;
+use http::header;
+use serde::Deserialize;
+use serde_json::json;
+
+use crate::auth::{Token, authorize};
+
+/// Body of a request to start or stop sampling allocations.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetActive {
+	active: bool,
+}
+
+/// Parameters of a summary of a profile.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Params {
+	/// Summarize what changed since this earlier profile.
+	base: Option<String>,
+
+	/// Number of allocation sites.
+	#[serde(default = "default_limit")]
+	limit: usize,
+}
+
+/// Whether profiling is available and active, and the profiles written so far.
+pub(crate) async fn status(
+	State(services): State<conduwuit_router::State<Services>>,
+	token: Token,
+) -> Result<Response> {
+	authorize(services.server.config.heap_api_token.as_deref(), token)?;
+	let dir = dir(&services);
+	let profiles = services
+		.server
+		.runtime()
+		.spawn_blocking(move || profile::list(&dir))
+		.await??;
+
+	Ok(Json(json!({
+		"available": alloc::is_prof_available(),
+		"active": alloc::is_prof_enabled().unwrap_or(false),
+		"profiles": profiles,
+	}))
+	.into_response())
+}
+
+/// Start or stop sampling allocations.
+pub(crate) async fn set_active(
+	State(services): State<conduwuit_router::State<Services>>,
+	token: Token,
+	Json(body): Json<SetActive>,
+) -> Result<Response> {
+	authorize(services.server.config.heap_api_token.as_deref(), token)?;
+	alloc::prof_enable(body.active)?;
+
+	Ok(Json(json!({ "active": alloc::is_prof_enabled()? })).into_response())
+}
+
+/// Write a profile of the sampled allocations.
+pub(crate) async fn dump(
+	State(services): State<conduwuit_router::State<Services>>,
+	token: Token,
+) -> Result<Response> {
+	authorize(services.server.config.heap_api_token.as_deref(), token)?;
+	let dir = dir(&services);
+	let name = services
+		.server
+		.runtime()
+		.spawn_blocking(move || profile::dump(&dir))
+		.await??;
+
+	Ok(Json(json!({ "name": name })).into_response())
+}
+
+/// Download a profile, e.g. for jeprof.
+pub(crate) async fn download(
+	State(services): State<conduwuit_router::State<Services>>,
+	token: Token,
+	Path(name): Path<String>,
+) -> Result<Response> {
+	authorize(services.server.config.heap_api_token.as_deref(), token)?;
+	let path = profile::path(&dir(&services), &name)?;
+	let body = tokio::fs::read(path).await?;
+
+	Ok(([(header::CONTENT_TYPE, "application/octet-stream")], body).into_response())
+}
+
+/// The allocation sites of a profile holding the most bytes, or those which
+/// grew the most since a base profile.
+pub(crate) async fn top(
+	State(services): State<conduwuit_router::State<Services>>,
+	token: Token,
+	Path(name): Path<String>,
+	Query(params): Query<Params>,
+) -> Result<Response> {
+	authorize(services.server.config.heap_api_token.as_deref(), token)?;
+	let dir = dir(&services);
+	let (total, sites) = services
+		.server
+		.runtime()
+		.spawn_blocking(move || {
+			profile::summarize(&dir, &name, params.base.as_deref(), params.limit)
+		})
+		.await??;
+
+	Ok(Json(json!({ "total": total, "sites": sites })).into_response())
+}
+
+fn dir(services: &Services) -> PathBuf { services.server.config.get_heap_profile_dir() }
+
+fn default_limit() -> usize { 20 }
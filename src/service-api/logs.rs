@@ -0,0 +1,163 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use axum::{
+	Json,
+	extract::{Path, Query, State},
+	response::{
+		IntoResponse, Response,
+		sse::{Event, KeepAlive, Sse},
+	},
+};
+use conduwuit::{
+	Err, Result, err,
+	log::{Directive, Level, ring},
+	utils::time,
+};
+use conduwuit_service::Services;
+use futures::{StreamExt, stream};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::auth::{Token, authorize};
+
+/// Filter over the in-memory log buffer, as query parameters.
+#[derive(Debug, Deserialize)]
+pub(crate) struct Params {
+	level: Option<String>,
+	since: Option<String>,
+	target: Option<String>,
+	span: Option<String>,
+	contains: Option<String>,
+
+	/// Number of buffered events to send before tailing.
+	#[serde(default)]
+	backlog: usize,
+}
+
+/// Tail the in-memory log buffer as server-sent events. Each `log` event
+/// carries one record as JSON; a `lagged` event reports records dropped
+/// because the client fell behind.
+pub(crate) async fn logs(
+	State(services): State<conduwuit_router::State<Services>>,
+	bearer: Token,
+	Query(params): Query<Params>,
+) -> Result<Response> {
+	authorize(services.server.config.log_api_token.as_deref(), bearer)?;
+	let server = &services.server;
+	let ring = &server.log.ring;
+	if !ring.is_enabled() {
+		return Err!(Request(NotFound("The in-memory log buffer is disabled.")));
+	}
+
+	let query = ring::Query {
+		level: params
+			.level
+			.as_deref()
+			.map(str::parse::<Level>)
+			.transpose()
+			.map_err(|e| err!(Request(InvalidParam("Invalid level: {e}"))))?,
+		since: params
+			.since
+			.as_deref()
+			.map(time::parse_timepoint_ago)
+			.transpose()?,
+		target: params.target,
+		span: params.span,
+		contains: params.contains,
+	};
+
+	// Subscribe before reading the backlog so nothing is missed in between.
+	let live = ring.subscribe(query.clone());
+	let backlog = ring.query(&query, params.backlog);
+	let shutdown = Arc::clone(server);
+	let events = stream::iter(backlog.into_iter().map(Ok))
+		.chain(live)
+		.take_until(async move { shutdown.until_shutdown().await })
+		.map(|record| match record {
+			| Ok(record) => Event::default().event("log").json_data(&*record),
+			| Err(e) => Ok(Event::default().event("lagged").data(e.to_string())),
+		});
+
+	Ok(Sse::new(events)
+		.keep_alive(KeepAlive::default())
+		.into_response())
+}
+
+/// Body of a request to override the level of a target.
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetDirective {
+	level: String,
+
+	/// Remove the override after this long, e.g. `15m`.
+	#[serde(rename = "for")]
+	duration: Option<String>,
+}
+
+/// Response to setting an override.
+#[derive(Debug, Serialize)]
+struct SetResult {
+	#[serde(flatten)]
+	directive: Directive,
+
+	/// False when the database is read-only and the override ends at restart.
+	persisted: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct Directives {
+	directives: Vec<Directive>,
+	filters: BTreeMap<String, String>,
+}
+
+/// List the per-target level overrides and the effective filter of each log
+/// output.
+pub(crate) async fn directives(
+	State(services): State<conduwuit_router::State<Services>>,
+	bearer: Token,
+) -> Result<Response> {
+	authorize(services.server.config.log_api_token.as_deref(), bearer)?;
+
+	Ok(Json(Directives {
+		directives: services.log_levels.list(),
+		filters: services.server.log.filters(),
+	})
+	.into_response())
+}
+
+/// Override the level of a target, persisted until reset or expiry unless the
+/// database is read-only.
+pub(crate) async fn set_directive(
+	State(services): State<conduwuit_router::State<Services>>,
+	bearer: Token,
+	Path(target): Path<String>,
+	Json(body): Json<SetDirective>,
+) -> Result<Response> {
+	authorize(services.server.config.log_api_token.as_deref(), bearer)?;
+	let duration = body
+		.duration
+		.as_deref()
+		.map(time::parse_duration)
+		.transpose()?;
+
+	let directive = services.log_levels.set(&target, &body.level, duration)?;
+
+	Ok(Json(SetResult {
+		directive,
+		persisted: services.log_levels.is_persistent(),
+	})
+	.into_response())
+}
+
+/// Remove the override for a target.
+pub(crate) async fn reset_directive(
+	State(services): State<conduwuit_router::State<Services>>,
+	bearer: Token,
+	Path(target): Path<String>,
+) -> Result<Response> {
+	authorize(services.server.config.log_api_token.as_deref(), bearer)?;
+	if !services.log_levels.reset(&target) {
+		return Err!(Request(NotFound("There is no override for {target:?}.")));
+	}
+
+	Ok(Json(json!({})).into_response())
+}
@@ -1,3 +1,6 @@
+mod auth;
+mod heap;
+mod logs;
 pub mod router;
 
 extern crate conduwuit_core as conduwuit;
@@ -1,7 +1,7 @@
 use axum::Router;
 use axum::response::IntoResponse;
-use axum::routing::get;
-use conduwuit::Error;
+use axum::routing::{get, post, put};
+use conduwuit::{Error, Result};
 use conduwuit_router::{Guard, RouterServices, State, state};
 use conduwuit_service::Services;
 use http::{StatusCode, Uri};
@@ -14,14 +14,33 @@ impl RouterServices for ServiceApiRouter {
 	type Services = Arc<Services>;
 	type Guard = Guard<Services>;
 
-	fn build(services: Self::Services) -> (Router, Self::Guard) {
+	fn build(services: Self::Services) -> Result<(Router, Self::Guard)> {
+		let server = services.server.clone();
 		let router = Router::<State<Services>>::new();
 		let (state, guard) = state::create(services);
 		let router = router
 			.route("/", get(it_works))
-			.fallback(not_found)
-			.with_state(state);
-		(router, guard)
+			.route("/_conduwuit/logs", get(crate::logs::logs))
+			.route("/_conduwuit/log/directives", get(crate::logs::directives))
+			.route(
+				"/_conduwuit/log/directives/:target",
+				put(crate::logs::set_directive).delete(crate::logs::reset_directive),
+			)
+			.route("/_conduwuit/heap", get(crate::heap::status))
+			.route("/_conduwuit/heap/active", put(crate::heap::set_active))
+			.route("/_conduwuit/heap/dump", post(crate::heap::dump))
+			.route("/_conduwuit/heap/profiles/:name", get(crate::heap::download))
+			.route("/_conduwuit/heap/profiles/:name/top", get(crate::heap::top));
+
+		// A read-only client router answers unknown routes as read-only instead.
+		let config = &server.config;
+		let router = if config.rocksdb_read_only || config.rocksdb_secondary {
+			router
+		} else {
+			router.fallback(not_found)
+		};
+
+		Ok((router.with_state(state), guard))
 	}
 }
 
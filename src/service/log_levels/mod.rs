@@ -0,0 +1,195 @@
+//! Per-target log level overrides which survive a restart. Overrides are kept
+//! in the `global` map and restored at startup; those with an expiry are
+//! removed once it passes.
+
+use std::{
+	sync::{Arc, Mutex, Weak},
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Result, Server, err, implement, info,
+	log::Directive,
+	utils::{stream::TryIgnore, time::now_millis},
+	warn,
+};
+use database::{Database, Ignore, Interfix, Json, Map};
+use futures::StreamExt;
+use tokio::{sync::Notify, time::sleep};
+
+pub struct Service {
+	changed: Notify,
+	interrupt: Notify,
+	server: Arc<Server>,
+	db: Arc<Database>,
+}
+
+/// Key prefix of the stored overrides in the `global` map.
+const PREFIX: &str = "log_directive";
+
+/// Longest time the worker sleeps between checks for expired overrides.
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// The instance built with the core services. The other sets of services in
+/// the process use it rather than keeping overrides of their own.
+static SHARED: Mutex<Weak<Service>> = Mutex::new(Weak::new());
+
+#[async_trait]
+impl service::Service for Service {
+	fn build(args: crate::service::Args<'_>) -> Result<Arc<Self>> {
+		let service = Arc::new(Self {
+			changed: Notify::new(),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			db: args.db.clone(),
+		});
+
+		*SHARED.lock().expect("locked") = Arc::downgrade(&service);
+
+		Ok(service)
+	}
+
+	#[tracing::instrument(skip_all, name = "log_levels", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		self.restore().await;
+		while self.server.running() {
+			let wait = self
+				.server
+				.log
+				.reload
+				.next_expiry()
+				.map(|expires| Duration::from_millis(expires.saturating_sub(now_millis())))
+				.map_or(MAX_WAIT, |wait| wait.min(MAX_WAIT));
+
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				() = self.changed.notified() => continue,
+				() = sleep(wait) => (),
+			}
+
+			self.expire();
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// The instance built with the core services, which must have started
+	/// first. Its worker runs with the core services only.
+	pub fn shared() -> Result<Arc<Self>> {
+		SHARED
+			.lock()
+			.expect("locked")
+			.upgrade()
+			.ok_or_else(|| err!("Log level service is not running."))
+	}
+}
+
+/// Overrides currently in effect.
+#[implement(Service)]
+#[must_use]
+pub fn list(&self) -> Vec<Directive> { self.server.log.directives() }
+
+/// Override the level of the target, for the duration if one is given.
+#[implement(Service)]
+pub fn set(&self, target: &str, level: &str, duration: Option<Duration>) -> Result<Directive> {
+	let expires = duration.map(|duration| {
+		let millis = u64::try_from(duration.as_millis()).unwrap_or(u64::MAX);
+		now_millis().saturating_add(millis)
+	});
+
+	let directive = Directive {
+		target: target.to_owned(),
+		level: level.to_lowercase(),
+		expires,
+	};
+
+	self.server.log.set_directive(directive.clone())?;
+	if self.is_persistent() {
+		self.global().put((PREFIX, target), Json(&directive));
+	}
+
+	self.changed.notify_waiters();
+
+	Ok(directive)
+}
+
+/// Whether overrides survive a restart. They do not while the database is
+/// read-only.
+#[implement(Service)]
+#[must_use]
+pub fn is_persistent(&self) -> bool { !self.db.is_read_only() }
+
+/// Remove the override for the target. Returns whether there was one.
+#[implement(Service)]
+pub fn reset(&self, target: &str) -> bool {
+	self.forget(target);
+	self.server.log.reset_directive(target)
+}
+
+/// Remove every override. Returns the number removed.
+#[implement(Service)]
+pub fn reset_all(&self) -> usize {
+	let removed = self.server.log.reload.reset_directives();
+	for directive in &removed {
+		self.forget(&directive.target);
+	}
+
+	removed.len()
+}
+
+/// Apply the stored overrides, discarding those which expired or which are no
+/// longer valid.
+#[implement(Service)]
+async fn restore(&self) {
+	let now = now_millis();
+	let stored: Vec<Directive> = self
+		.global()
+		.stream_prefix(&(PREFIX, Interfix))
+		.ignore_err()
+		.map(|(_, directive): (Ignore, Directive)| directive)
+		.collect()
+		.await;
+
+	for directive in stored {
+		let target = directive.target.clone();
+		if directive.is_expired(now) {
+			self.forget(&target);
+			continue;
+		}
+
+		if let Err(e) = self.server.log.set_directive(directive) {
+			warn!(%target, "Discarding stored log level override: {e}");
+			self.forget(&target);
+		}
+	}
+
+	let count = self.server.log.directives().len();
+	if count > 0 {
+		info!(count, "Restored log level overrides");
+	}
+}
+
+#[implement(Service)]
+fn expire(&self) {
+	for directive in self.server.log.reload.expire_directives() {
+		info!(target = %directive.target, level = %directive.level, "Log level override expired");
+		self.forget(&directive.target);
+	}
+}
+
+#[implement(Service)]
+fn forget(&self, target: &str) {
+	if self.is_persistent() {
+		self.global().del((PREFIX, target));
+	}
+}
+
+#[implement(Service)]
+fn global(&self) -> &Arc<Map> { &self.db["global"] }
@@ -3,6 +3,7 @@
 
 pub mod services;
 pub mod config;
+pub mod log_levels;
 
 
 
@@ -1,5 +1,5 @@
 use crate::{
-	config,
+	config, log_levels,
 	service::{Args, Map, Service},
 };
 use async_trait::async_trait;
@@ -18,6 +18,7 @@
 
 pub struct Services {
 	pub config: Arc<config::Service>,
+	pub log_levels: Arc<log_levels::Service>,
 	manager: Mutex<Option<Arc<Manager<Self>>>>,
 	pub(crate) service_map: Arc<Map>,
 	pub server: Arc<Server>,
@@ -48,6 +49,7 @@ macro_rules! build {
 
 		let built = Arc::new(Self {
 			config: build!(config::Service),
+			log_levels: build!(log_levels::Service),
 			manager: Mutex::new(None),
 			service_map,
 			server,
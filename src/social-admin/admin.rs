@@ -4,8 +4,8 @@
 use crate::{
 	appservice, appservice::AppserviceCommand, check, check::CheckCommand, command::Command,
 	debug, debug::DebugCommand, federation, federation::FederationCommand, media,
-	media::MediaCommand, query, query::QueryCommand, room, room::RoomCommand, server,
-	server::ServerCommand, user, user::UserCommand,
+	media::MediaCommand, query, query::QueryCommand, reports, reports::ReportsCommand, room,
+	room::RoomCommand, server, server::ServerCommand, user, user::UserCommand,
 };
 
 #[derive(Debug, Parser)]
@@ -23,6 +23,10 @@ pub(super) enum AdminCommand {
 	/// - Commands for managing rooms
 	Rooms(RoomCommand),
 
+	#[command(subcommand)]
+	/// - Commands for handling abuse reports
+	Reports(ReportsCommand),
+
 	#[command(subcommand)]
 	/// - Commands for managing federation
 	Federation(FederationCommand),
@@ -57,6 +61,7 @@ pub(super) async fn process(command: AdminCommand, context: &Command<'_>) -> Res
 		| Media(command) => media::process(command, context).await?,
 		| Users(command) => user::process(command, context).await?,
 		| Rooms(command) => room::process(command, context).await?,
+		| Reports(command) => reports::process(command, context).await?,
 		| Federation(command) => federation::process(command, context).await?,
 		| Server(command) => server::process(command, context).await?,
 		| Debug(command) => debug::process(command, context).await?,
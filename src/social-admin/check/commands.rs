@@ -1,4 +1,5 @@
 use conduwuit::Result;
+use conduwuit_database::Priority;
 use conduwuit_social_macros::implement;
 use futures::StreamExt;
 use ruma::events::room::message::RoomMessageEventContent;
@@ -11,7 +12,9 @@
 #[implement(Command, params = "<'_>")]
 pub(super) async fn check_all_users(&self) -> Result<RoomMessageEventContent> {
 	let timer = tokio::time::Instant::now();
-	let users = self.services.users.iter().collect::<Vec<_>>().await;
+	let users = Priority::Background
+		.scope(self.services.users.iter().collect::<Vec<_>>())
+		.await;
 	let query_time = timer.elapsed();
 
 	let total = users.len();
@@ -8,7 +8,7 @@
 	io::{AsyncWriteExt, BufWriter},
 	lock::Mutex,
 };
-use ruma::EventId;
+use ruma::{EventId, OwnedUserId};
 
 pub(crate) struct Command<'a> {
 	pub(crate) services: &'a Services,
@@ -29,6 +29,17 @@ pub(crate) fn write_fmt(
 		})
 	}
 
+	/// The user who sent the command, or the server user for commands which
+	/// did not come from the admin room.
+	pub(crate) async fn sender_user(&self) -> OwnedUserId {
+		let pdu = match self.reply_id {
+			| Some(event_id) => self.services.rooms.timeline.get_pdu(event_id).await.ok(),
+			| None => None,
+		};
+
+		pdu.map_or_else(|| self.services.globals.server_user.clone(), |pdu| pdu.sender)
+	}
+
 	pub(crate) fn write_str<'a>(
 		&'a self,
 		s: &'a str,
@@ -2,11 +2,11 @@
 	collections::HashMap,
 	fmt::Write,
 	iter::once,
-	time::{Instant, SystemTime},
+	time::{Duration, Instant, SystemTime},
 };
 
 use conduwuit::{
-	Error, Result, debug_error, err, info,
+	Err, Error, Result, debug_error, err, info,
 	matrix::pdu::{PduEvent, PduId, RawPduId},
 	trace, utils,
 	utils::{
@@ -423,11 +423,17 @@ pub(super) async fn change_log_level(
 	&self,
 	filter: Option<String>,
 	reset: bool,
+	ring: bool,
 ) -> Result<RoomMessageEventContent> {
-	let handles = &["console"];
+	let config = &self.services.server.config;
+	let (handles, config_filter) = if ring {
+		(&["ring"], &config.log_ring)
+	} else {
+		(&["console"], &config.log)
+	};
 
 	if reset {
-		let old_filter_layer = match EnvFilter::try_new(&self.services.server.config.log) {
+		let old_filter_layer = match EnvFilter::try_new(config_filter) {
 			| Ok(s) => s,
 			| Err(e) => {
 				return Ok(RoomMessageEventContent::text_plain(format!(
@@ -445,8 +451,7 @@ pub(super) async fn change_log_level(
 		{
 			| Ok(()) => {
 				return Ok(RoomMessageEventContent::text_plain(format!(
-					"Successfully changed log level back to config value {}",
-					self.services.server.config.log
+					"Successfully changed log level back to config value {config_filter}"
 				)));
 			},
 			| Err(e) => {
@@ -488,6 +493,85 @@ pub(super) async fn change_log_level(
 	Ok(RoomMessageEventContent::text_plain("No log level was specified."))
 }
 
+#[admin_command]
+pub(super) async fn log_directives(&self) -> Result<RoomMessageEventContent> {
+	let log = &self.services.server.log;
+	let now = utils::time::now_millis();
+	let mut out = String::from("Overrides:\n");
+	let directives = self.services.log_levels.list();
+	if directives.is_empty() {
+		out.push_str("- none\n");
+	}
+
+	for directive in &directives {
+		write!(out, "- `{}={}`", directive.target, directive.level)?;
+		if let Some(expires) = directive.expires {
+			let remaining = Duration::from_millis(expires.saturating_sub(now));
+			write!(out, " for {}", utils::time::pretty(remaining))?;
+		}
+
+		out.push('\n');
+	}
+
+	out.push_str("\nOutputs:\n");
+	for (name, filter) in log.filters() {
+		writeln!(out, "- {name}: `{filter}`")?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[admin_command]
+pub(super) async fn set_log_directive(
+	&self,
+	target: String,
+	level: String,
+	duration: Option<String>,
+) -> Result<RoomMessageEventContent> {
+	let duration = duration
+		.as_deref()
+		.map(utils::time::parse_duration)
+		.transpose()?;
+
+	let directive = self.services.log_levels.set(&target, &level, duration)?;
+
+	let until = duration
+		.map(|duration| format!(" for {}", utils::time::pretty(duration)))
+		.unwrap_or_default();
+
+	let unsaved = if self.services.log_levels.is_persistent() {
+		""
+	} else {
+		" The database is read-only, so the override was not saved and ends at restart."
+	};
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Logging `{}` at `{}`{until}.{unsaved}",
+		directive.target, directive.level
+	)))
+}
+
+#[admin_command]
+pub(super) async fn reset_log_directive(
+	&self,
+	target: Option<String>,
+	all: bool,
+) -> Result<RoomMessageEventContent> {
+	let log_levels = &self.services.log_levels;
+	match (target, all) {
+		| (_, true) => {
+			let count = log_levels.reset_all();
+			Ok(RoomMessageEventContent::notice_plain(format!("Removed {count} overrides.")))
+		},
+		| (Some(target), false) if log_levels.reset(&target) =>
+			Ok(RoomMessageEventContent::notice_markdown(format!(
+				"Removed the override for `{target}`."
+			))),
+		| (Some(target), false) => Err!("There is no override for {target:?}."),
+		| (None, false) => Err!("Specify a target or --all."),
+	}
+}
+
 #[admin_command]
 pub(super) async fn sign_json(&self) -> Result<RoomMessageEventContent> {
 	if self.body.len() < 2
@@ -0,0 +1,157 @@
+use std::{fmt::Write, path::PathBuf};
+
+use conduwuit::{Err, Result, alloc, alloc::profile, err};
+use conduwuit_social_macros::implement;
+use ruma::events::room::message::RoomMessageEventContent;
+
+use crate::{Command, admin_command, admin_command_dispatch};
+
+#[admin_command_dispatch]
+#[derive(Debug, clap::Subcommand)]
+pub(crate) enum HeapCommand {
+	/// - Show whether heap profiling is active and list the profiles written
+	Status,
+
+	/// - Start sampling allocations
+	Enable,
+
+	/// - Stop sampling allocations
+	Disable,
+
+	/// - Write a heap profile of the sampled allocations
+	Dump,
+
+	/// - Show the allocation sites of a profile holding the most bytes
+	Top {
+		/// Name of the profile; the latest when omitted
+		name: Option<String>,
+
+		/// Number of allocation sites
+		#[arg(short = 'n', long, default_value("20"))]
+		limit: usize,
+	},
+
+	/// - Show the allocation sites which grew the most between two profiles
+	Diff {
+		/// Name of the earlier profile
+		base: String,
+
+		/// Name of the later profile; the latest when omitted
+		name: Option<String>,
+
+		/// Number of allocation sites
+		#[arg(short = 'n', long, default_value("20"))]
+		limit: usize,
+	},
+}
+
+#[admin_command]
+async fn status(&self) -> Result<RoomMessageEventContent> {
+	let dir = self.services.server.config.get_heap_profile_dir();
+	if !alloc::is_prof_available() {
+		return Err!(
+			"Heap profiling is not available; it requires jemalloc built with the jemalloc_prof \
+			 feature."
+		);
+	}
+
+	let active = alloc::is_prof_enabled()?;
+	let mut out = format!("Profiling is {}.\n", if active { "active" } else { "inactive" });
+	writeln!(out, "Profiles in `{}`:", dir.display())?;
+	for name in self.list(dir).await? {
+		writeln!(out, "- `{name}`")?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[admin_command]
+async fn enable(&self) -> Result<RoomMessageEventContent> {
+	alloc::prof_enable(true)?;
+
+	Ok(RoomMessageEventContent::notice_plain("Heap profiling is active."))
+}
+
+#[admin_command]
+async fn disable(&self) -> Result<RoomMessageEventContent> {
+	alloc::prof_enable(false)?;
+
+	Ok(RoomMessageEventContent::notice_plain("Heap profiling is inactive."))
+}
+
+#[admin_command]
+async fn dump(&self) -> Result<RoomMessageEventContent> {
+	let dir = self.services.server.config.get_heap_profile_dir();
+	let name = self
+		.services
+		.server
+		.runtime()
+		.spawn_blocking(move || profile::dump(&dir))
+		.await??;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Wrote heap profile `{name}`."
+	)))
+}
+
+#[admin_command]
+async fn top(&self, name: Option<String>, limit: usize) -> Result<RoomMessageEventContent> {
+	self.summarize(name, None, limit).await
+}
+
+#[admin_command]
+async fn diff(
+	&self,
+	base: String,
+	name: Option<String>,
+	limit: usize,
+) -> Result<RoomMessageEventContent> {
+	self.summarize(name, Some(base), limit).await
+}
+
+#[implement(Command, params = "<'_>")]
+async fn summarize(
+	&self,
+	name: Option<String>,
+	base: Option<String>,
+	limit: usize,
+) -> Result<RoomMessageEventContent> {
+	let dir = self.services.server.config.get_heap_profile_dir();
+	let name = match name {
+		| Some(name) => name,
+		| None => self
+			.list(dir.clone())
+			.await?
+			.pop()
+			.ok_or_else(|| err!("No heap profiles have been written."))?,
+	};
+
+	let title = match &base {
+		| Some(base) => format!("Growth from `{base}` to `{name}`"),
+		| None => format!("Heap profile `{name}`"),
+	};
+
+	let (total, sites) = self
+		.services
+		.server
+		.runtime()
+		.spawn_blocking(move || profile::summarize(&dir, &name, base.as_deref(), limit))
+		.await??;
+
+	let mut out = format!("{title}: {total}\n```\n");
+	for site in &sites {
+		writeln!(out, "{site}")?;
+	}
+
+	out.push_str("```");
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[implement(Command, params = "<'_>")]
+async fn list(&self, dir: PathBuf) -> Result<Vec<String>> {
+	self.services
+		.server
+		.runtime()
+		.spawn_blocking(move || profile::list(&dir))
+		.await?
+}
@@ -1,4 +1,5 @@
 mod commands;
+pub(crate) mod heap;
 pub(crate) mod tester;
 
 use clap::Subcommand;
@@ -6,7 +7,7 @@
 use ruma::{EventId, OwnedRoomOrAliasId, RoomId, ServerName};
 use service::rooms::short::{ShortEventId, ShortRoomId};
 
-use self::tester::TesterCommand;
+use self::{heap::HeapCommand, tester::TesterCommand};
 use crate::admin_command_dispatch;
 
 #[admin_command_dispatch]
@@ -123,6 +124,42 @@ pub(super) enum DebugCommand {
 		/// Resets the log level/filter to the one in your config
 		#[arg(short, long)]
 		reset: bool,
+
+		/// Change the filter of the in-memory log buffer instead of the
+		/// console
+		#[arg(long)]
+		ring: bool,
+	},
+
+	/// - List per-target log level overrides and the effective filter of each
+	///   log output
+	LogDirectives,
+
+	/// - Override the log level of a target on every log output
+	///
+	/// The override is kept across restarts until it is reset or expires.
+	/// Example: `set-log-directive conduwuit_social_service::sending trace
+	/// --for 15m`
+	SetLogDirective {
+		/// Target prefix, usually a module path
+		target: String,
+
+		/// Level: trace, debug, info, warn, error or off
+		level: String,
+
+		/// Remove the override after this long, e.g. 15m
+		#[arg(long = "for")]
+		duration: Option<String>,
+	},
+
+	/// - Remove a per-target log level override
+	ResetLogDirective {
+		/// Target of the override
+		target: Option<String>,
+
+		/// Remove every override
+		#[arg(short, long)]
+		all: bool,
 	},
 
 	/// - Verify json signatures
@@ -226,6 +263,10 @@ pub(super) enum DebugCommand {
 	/// - Trim memory usage
 	TrimMemory,
 
+	/// - Heap profiling of the allocator
+	#[command(subcommand)]
+	Heap(HeapCommand),
+
 	/// - List database files
 	DatabaseFiles {
 		map: Option<String>,
@@ -3,6 +3,7 @@
 use conduwuit::{
 	Result, debug, debug_info, debug_warn, error, info, trace, utils::time::parse_timepoint_ago,
 };
+use conduwuit_database::Priority;
 use conduwuit_social_service::media::Dim;
 use ruma::{
 	EventId, Mxc, MxcUri, OwnedMxcUri, OwnedServerName, ServerName,
@@ -249,15 +250,13 @@ pub(super) async fn delete_past_remote_media(
 	assert!(!(before && after), "--before and --after should not be specified together");
 
 	let duration = parse_timepoint_ago(&duration)?;
-	let deleted_count = self
-		.services
-		.media
-		.delete_all_remote_media_at_after_time(
+	let deleted_count = Priority::Background
+		.scope(self.services.media.delete_all_remote_media_at_after_time(
 			duration,
 			before,
 			after,
 			yes_i_want_to_delete_local_media,
-		)
+		))
 		.await?;
 
 	Ok(RoomMessageEventContent::text_plain(format!(
@@ -272,7 +271,9 @@ pub(super) async fn delete_all_from_user(
 ) -> Result<RoomMessageEventContent> {
 	let user_id = parse_local_user_id(self.services, &username)?;
 
-	let deleted_count = self.services.media.delete_from_user(&user_id).await?;
+	let deleted_count = Priority::Background
+		.scope(self.services.media.delete_from_user(&user_id))
+		.await?;
 
 	Ok(RoomMessageEventContent::text_plain(format!(
 		"Deleted {deleted_count} total files.",
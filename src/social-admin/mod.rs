@@ -15,6 +15,7 @@
 pub(crate) mod federation;
 pub(crate) mod media;
 pub(crate) mod query;
+pub(crate) mod reports;
 pub(crate) mod room;
 pub(crate) mod server;
 pub(crate) mod user;
@@ -37,26 +38,6 @@
 conduwuit::mod_dtor! {}
 conduwuit::rustc_flags_capture! {}
 
-/// Install the admin command processor
-pub async fn init(admin_service: &service::admin::Service) {
-	_ = admin_service
-		.complete
-		.write()
-		.expect("locked for writing")
-		.insert(processor::complete);
-	_ = admin_service
-		.handle
-		.write()
-		.await
-		.insert(processor::dispatch);
-}
-
-/// Uninstall the admin command handler
-pub async fn fini(admin_service: &service::admin::Service) {
-	_ = admin_service.handle.write().await.take();
-	_ = admin_service
-		.complete
-		.write()
-		.expect("locked for writing")
-		.take();
-}
+/// Register the admin command processor with the admin service, which
+/// installs it whenever the services start.
+pub fn register() { service::admin::register(processor::dispatch, processor::complete); }
@@ -1,16 +1,15 @@
-use std::{borrow::Cow, collections::BTreeMap, ops::Deref, sync::Arc};
+use std::{ops::Deref, sync::atomic::Ordering};
 
 use clap::Subcommand;
 use conduwuit::{
-	Err, Result, apply, at, is_zero,
+	Err, Result, at, is_zero,
 	utils::{
-		stream::{IterStream, ReadyExt, TryIgnore, TryParallelExt},
+		stream::{IterStream, TryParallelExt},
 		string::EMPTY,
 	},
 };
-use conduwuit_database::Map;
-use conduwuit_social_service::Services;
-use futures::{FutureExt, Stream, StreamExt, TryStreamExt};
+use conduwuit_database::inspect::{self, Range};
+use futures::{FutureExt, StreamExt, TryStreamExt};
 use ruma::events::room::message::RoomMessageEventContent;
 use tokio::time::Instant;
 
@@ -24,6 +23,15 @@ pub(crate) enum RawCommand {
 	/// - List database maps
 	RawMaps,
 
+	/// - Per-map operation counters since startup
+	MapStats {
+		/// Map name
+		map: Option<String>,
+	},
+
+	/// - Database pool queue statistics for each priority class
+	PoolStats,
+
 	/// - Raw database query
 	RawGet {
 		/// Map name
@@ -235,10 +243,7 @@ pub(super) async fn raw_count(
 	let prefix = prefix.as_deref().unwrap_or(EMPTY);
 
 	let timer = Instant::now();
-	let count = with_maps_or(map.as_deref(), self.services)
-		.then(|map| map.raw_count_prefix(&prefix))
-		.ready_fold(0_usize, usize::saturating_add)
-		.await;
+	let count = inspect::count(&self.services.db, map.as_deref(), prefix).await;
 
 	let query_time = timer.elapsed();
 	self.write_str(&format!("Query completed in {query_time:?}:\n\n```rs\n{count:#?}\n```"))
@@ -257,11 +262,8 @@ pub(super) async fn raw_keys(
 
 	let map = self.services.db.get(map.as_str())?;
 	let timer = Instant::now();
-	prefix
-		.as_deref()
-		.map_or_else(|| map.raw_keys().boxed(), |prefix| map.raw_keys_prefix(prefix).boxed())
-		.map_ok(String::from_utf8_lossy)
-		.try_for_each(|str| writeln!(self, "{str:?}"))
+	inspect::keys(map, range(prefix.as_deref()))
+		.try_for_each(|key| writeln!(self, "{key}"))
 		.boxed()
 		.await?;
 
@@ -281,17 +283,7 @@ pub(super) async fn raw_keys_sizes(
 	let prefix = prefix.as_deref().unwrap_or(EMPTY);
 
 	let timer = Instant::now();
-	let result = with_maps_or(map.as_deref(), self.services)
-		.map(|map| map.raw_keys_prefix(&prefix))
-		.flatten()
-		.ignore_err()
-		.map(<[u8]>::len)
-		.ready_fold_default(|mut map: BTreeMap<_, usize>, len| {
-			let entry = map.entry(len).or_default();
-			*entry = entry.saturating_add(1);
-			map
-		})
-		.await;
+	let result = inspect::keys_sizes(&self.services.db, map.as_deref(), prefix).await;
 
 	let query_time = timer.elapsed();
 	let result = format!("```\n{result:#?}\n```\n\nQuery completed in {query_time:?}");
@@ -309,13 +301,7 @@ pub(super) async fn raw_keys_total(
 	let prefix = prefix.as_deref().unwrap_or(EMPTY);
 
 	let timer = Instant::now();
-	let result = with_maps_or(map.as_deref(), self.services)
-		.map(|map| map.raw_keys_prefix(&prefix))
-		.flatten()
-		.ignore_err()
-		.map(<[u8]>::len)
-		.ready_fold_default(|acc: usize, len| acc.saturating_add(len))
-		.await;
+	let result = inspect::keys_total(&self.services.db, map.as_deref(), prefix).await;
 
 	let query_time = timer.elapsed();
 
@@ -334,18 +320,7 @@ pub(super) async fn raw_vals_sizes(
 	let prefix = prefix.as_deref().unwrap_or(EMPTY);
 
 	let timer = Instant::now();
-	let result = with_maps_or(map.as_deref(), self.services)
-		.map(|map| map.raw_stream_prefix(&prefix))
-		.flatten()
-		.ignore_err()
-		.map(at!(1))
-		.map(<[u8]>::len)
-		.ready_fold_default(|mut map: BTreeMap<_, usize>, len| {
-			let entry = map.entry(len).or_default();
-			*entry = entry.saturating_add(1);
-			map
-		})
-		.await;
+	let result = inspect::vals_sizes(&self.services.db, map.as_deref(), prefix).await;
 
 	let query_time = timer.elapsed();
 	let result = format!("```\n{result:#?}\n```\n\nQuery completed in {query_time:?}");
@@ -363,14 +338,7 @@ pub(super) async fn raw_vals_total(
 	let prefix = prefix.as_deref().unwrap_or(EMPTY);
 
 	let timer = Instant::now();
-	let result = with_maps_or(map.as_deref(), self.services)
-		.map(|map| map.raw_stream_prefix(&prefix))
-		.flatten()
-		.ignore_err()
-		.map(at!(1))
-		.map(<[u8]>::len)
-		.ready_fold_default(|acc: usize, len| acc.saturating_add(len))
-		.await;
+	let result = inspect::vals_total(&self.services.db, map.as_deref(), prefix).await;
 
 	let query_time = timer.elapsed();
 
@@ -390,12 +358,8 @@ pub(super) async fn raw_iter(
 
 	let map = self.services.db.get(&map)?;
 	let timer = Instant::now();
-	prefix
-		.as_deref()
-		.map_or_else(|| map.raw_stream().boxed(), |prefix| map.raw_stream_prefix(prefix).boxed())
-		.map_ok(apply!(2, String::from_utf8_lossy))
-		.map_ok(apply!(2, Cow::into_owned))
-		.try_for_each(|keyval| writeln!(self, "{keyval:?}"))
+	inspect::iter(map, range(prefix.as_deref()))
+		.try_for_each(|record| writeln!(self, "{record}"))
 		.boxed()
 		.await?;
 
@@ -417,10 +381,9 @@ pub(super) async fn raw_keys_from(
 
 	let map = self.services.db.get(&map)?;
 	let timer = Instant::now();
-	map.raw_keys_from(&start)
-		.map_ok(String::from_utf8_lossy)
+	inspect::keys(map, Range::From(start.as_bytes()))
 		.take(limit.unwrap_or(usize::MAX))
-		.try_for_each(|str| writeln!(self, "{str:?}"))
+		.try_for_each(|key| writeln!(self, "{key}"))
 		.boxed()
 		.await?;
 
@@ -440,17 +403,15 @@ pub(super) async fn raw_iter_from(
 ) -> Result<RoomMessageEventContent> {
 	let map = self.services.db.get(&map)?;
 	let timer = Instant::now();
-	let result = map
-		.raw_stream_from(&start)
-		.map_ok(apply!(2, String::from_utf8_lossy))
-		.map_ok(apply!(2, Cow::into_owned))
+	let result = inspect::iter(map, Range::From(start.as_bytes()))
 		.take(limit.unwrap_or(usize::MAX))
-		.try_collect::<Vec<(String, String)>>()
+		.try_collect::<Vec<_>>()
 		.await?;
 
 	let query_time = timer.elapsed();
+	let result = serde_json::to_string_pretty(&result)?;
 	Ok(RoomMessageEventContent::notice_markdown(format!(
-		"Query completed in {query_time:?}:\n\n```rs\n{result:#?}\n```"
+		"Query completed in {query_time:?}:\n\n```json\n{result}\n```"
 	)))
 }
 
@@ -470,12 +431,12 @@ pub(super) async fn raw_del(&self, map: String, key: String) -> Result<RoomMessa
 pub(super) async fn raw_get(&self, map: String, key: String) -> Result<RoomMessageEventContent> {
 	let map = self.services.db.get(&map)?;
 	let timer = Instant::now();
-	let handle = map.get(&key).await?;
+	let result = inspect::get(map, key.as_bytes()).await?;
 	let query_time = timer.elapsed();
-	let result = String::from_utf8_lossy(&handle);
+	let result = serde_json::to_string_pretty(&result)?;
 
 	Ok(RoomMessageEventContent::notice_markdown(format!(
-		"Query completed in {query_time:?}:\n\n```rs\n{result:?}\n```"
+		"Query completed in {query_time:?}:\n\n```json\n{result}\n```"
 	)))
 }
 
@@ -486,19 +447,67 @@ pub(super) async fn raw_maps(&self) -> Result<RoomMessageEventContent> {
 	Ok(RoomMessageEventContent::notice_markdown(format!("{list:#?}")))
 }
 
-fn with_maps_or<'a>(
-	map: Option<&'a str>,
-	services: &'a Services,
-) -> impl Stream<Item = &'a Arc<Map>> + Send + 'a {
-	let default_all_maps = map
-		.is_none()
-		.then(|| services.db.keys().map(Deref::deref))
+#[admin_command]
+pub(super) async fn map_stats(&self, map: Option<String>) -> Result<RoomMessageEventContent> {
+	if let Some(map) = map.as_deref() {
+		self.services.db.get(map)?;
+	}
+
+	let rows: String = self
+		.services
+		.server
+		.metrics
+		.maps()
 		.into_iter()
-		.flatten();
+		.filter(|(name, _)| map.as_deref().is_none_or(|map| map == *name))
+		.map(|(name, metrics)| {
+			format!(
+				"| {name} | {} | {} ({:.1}%) | {} | {} | {} | {} | {} | {} | {} | {:?} | {:?} \
+				 |\n",
+				metrics.gets.load(Ordering::Relaxed),
+				metrics.cache_hits.load(Ordering::Relaxed),
+				metrics.cache_hit_ratio() * 100.0,
+				metrics.not_found.load(Ordering::Relaxed),
+				metrics.errors.load(Ordering::Relaxed),
+				metrics.puts.load(Ordering::Relaxed),
+				metrics.deletes.load(Ordering::Relaxed),
+				metrics.expired.load(Ordering::Relaxed),
+				metrics.iterations.load(Ordering::Relaxed),
+				metrics.pool_requests.load(Ordering::Relaxed),
+				metrics.pool_latency_avg(),
+				metrics.pool_latency_max(),
+			)
+		})
+		.collect();
 
-	map.into_iter()
-		.chain(default_all_maps)
-		.map(|map| services.db.get(map))
-		.filter_map(Result::ok)
-		.stream()
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"| Map | Gets | Cache hits | Not found | Errors | Puts | Deletes | Expired | Iterations \
+		 | Pool requests | Pool avg | Pool max |\n| --- | --- | --- | --- | --- | --- | --- | \
+		 --- | --- | --- | --- | --- |\n{rows}"
+	)))
+}
+
+#[admin_command]
+pub(super) async fn pool_stats(&self) -> Result<RoomMessageEventContent> {
+	let rows: String = self
+		.services
+		.db
+		.pool_stats()
+		.into_iter()
+		.map(|stats| {
+			format!(
+				"| {} | {} | {} | {:?} | {:?} |\n",
+				stats.priority, stats.queued, stats.requests, stats.wait_avg, stats.wait_max,
+			)
+		})
+		.collect();
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"| Priority | Queued | Requests | Wait avg | Wait max |\n| --- | --- | --- | --- | --- \
+		 |\n{rows}"
+	)))
+}
+
+fn range(prefix: Option<&str>) -> Range<'_> {
+	prefix.map_or(Range::All, |prefix| Range::Prefix(prefix.as_bytes()))
 }
@@ -0,0 +1,179 @@
+use std::{fmt::Write, time::Duration};
+
+use conduwuit::{Result, utils::time::rfc2822_from_seconds};
+use futures::StreamExt;
+use ruma::{OwnedRoomId, OwnedUserId, events::room::message::RoomMessageEventContent};
+use service::reports::{Filter, Kind, Report, Status};
+
+use crate::admin_command;
+
+#[admin_command]
+pub(super) async fn list(
+	&self,
+	status: Option<Status>,
+	kind: Option<Kind>,
+	reporter: Option<OwnedUserId>,
+	room: Option<OwnedRoomId>,
+	user: Option<OwnedUserId>,
+	handler: Option<OwnedUserId>,
+	from: Option<u64>,
+	limit: usize,
+) -> Result<RoomMessageEventContent> {
+	let filter = Filter {
+		status,
+		kind,
+		reporter,
+		room_id: room,
+		user_id: user,
+		handler,
+	};
+
+	let reports: Vec<Report> = self
+		.services
+		.reports
+		.reports(&filter, from)
+		.take(limit.saturating_add(1))
+		.collect()
+		.await;
+
+	if reports.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("No reports match."));
+	}
+
+	let mut out = String::from(
+		"| ID | Status | Kind | About | Reporter | Handler | Reason |\n| --- | --- | --- | --- \
+		 | --- | --- | --- |\n",
+	);
+	for report in reports.iter().take(limit) {
+		let about = report
+			.user_id()
+			.map(ToString::to_string)
+			.or_else(|| report.room_id().map(ToString::to_string))
+			.unwrap_or_default();
+
+		writeln!(
+			out,
+			"| {} | {} | {} | {about} | {} | {} | {} |",
+			report.id,
+			report.status,
+			report.target.kind(),
+			report.reporter,
+			report
+				.handler
+				.as_ref()
+				.map(ToString::to_string)
+				.unwrap_or_default(),
+			report
+				.reason
+				.as_deref()
+				.unwrap_or("")
+				.replace(['|', '\n'], " "),
+		)?;
+	}
+
+	if let Some(next) = reports.get(limit) {
+		writeln!(out, "\nMore reports follow; list them with `--from {}`.", next.id)?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[admin_command]
+pub(super) async fn show(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let report = self.services.reports.get(id).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(describe(&report)?))
+}
+
+#[admin_command]
+pub(super) async fn claim(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let sender_user = self.sender_user().await;
+	let report = self.services.reports.claim(id, &sender_user).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Report {id} claimed by {sender_user}. {}",
+		report.link()
+	)))
+}
+
+#[admin_command]
+pub(super) async fn resolve(
+	&self,
+	id: u64,
+	note: Vec<String>,
+) -> Result<RoomMessageEventContent> {
+	let sender_user = self.sender_user().await;
+	let note = Some(note.join(" ")).filter(|note| !note.is_empty());
+	self.services
+		.reports
+		.resolve(id, &sender_user, note)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!("Report {id} resolved.")))
+}
+
+#[admin_command]
+pub(super) async fn reopen(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let report = self.services.reports.reopen(id).await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!(
+		"Report {id} is {} again.",
+		report.status
+	)))
+}
+
+#[admin_command]
+pub(super) async fn note(&self, id: u64, note: Vec<String>) -> Result<RoomMessageEventContent> {
+	let note = note.join(" ");
+	if note.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("The note is empty."));
+	}
+
+	let sender_user = self.sender_user().await;
+	self.services
+		.reports
+		.add_note(id, &sender_user, note)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!("Note added to report {id}.")))
+}
+
+#[admin_command]
+pub(super) async fn jump(&self, id: u64) -> Result<RoomMessageEventContent> {
+	let report = self.services.reports.get(id).await?;
+
+	Ok(RoomMessageEventContent::notice_plain(report.link()))
+}
+
+#[admin_command]
+pub(super) async fn delete(&self, id: u64) -> Result<RoomMessageEventContent> {
+	self.services.reports.delete(id).await?;
+
+	Ok(RoomMessageEventContent::notice_plain(format!("Report {id} deleted.")))
+}
+
+fn describe(report: &Report) -> Result<String> {
+	let mut out = format!("{report}\n\n{}\n\n", report.link());
+	writeln!(out, "Received: {}", rfc2822_from_millis(report.received))?;
+	writeln!(out, "Updated: {}", rfc2822_from_millis(report.updated))?;
+	if let Some(handler) = &report.handler {
+		writeln!(out, "Handler: {handler}")?;
+	}
+
+	for note in &report.notes {
+		writeln!(
+			out,
+			"\n> {}\n\n— {}, {}",
+			note.body.replace('\n', "\n> "),
+			note.author,
+			rfc2822_from_millis(note.ts)
+		)?;
+	}
+
+	Ok(out)
+}
+
+fn rfc2822_from_millis(ms: u64) -> String {
+	let secs = Duration::from_millis(ms).as_secs();
+	rfc2822_from_seconds(i64::try_from(secs).unwrap_or(i64::MAX))
+}
@@ -0,0 +1,83 @@
+mod commands;
+
+use clap::Subcommand;
+use conduwuit::Result;
+use ruma::{OwnedRoomId, OwnedUserId};
+use service::reports::{Kind, Status};
+
+use crate::admin_command_dispatch;
+
+#[admin_command_dispatch]
+#[derive(Debug, Subcommand)]
+pub(super) enum ReportsCommand {
+	/// - List reports, newest first
+	List {
+		/// Only reports with this status: open, acknowledged or resolved
+		#[arg(long)]
+		status: Option<Status>,
+
+		/// Only reports about an event, room or user
+		#[arg(long)]
+		kind: Option<Kind>,
+
+		/// Only reports sent by this user
+		#[arg(long)]
+		reporter: Option<OwnedUserId>,
+
+		/// Only reports about this room or events in it
+		#[arg(long)]
+		room: Option<OwnedRoomId>,
+
+		/// Only reports about this user or events they sent
+		#[arg(long)]
+		user: Option<OwnedUserId>,
+
+		/// Only reports claimed by this admin
+		#[arg(long)]
+		handler: Option<OwnedUserId>,
+
+		/// ID of the newest report to list
+		#[arg(long)]
+		from: Option<u64>,
+
+		#[arg(short = 'n', long, default_value("20"))]
+		limit: usize,
+	},
+
+	/// - Show a report with its notes
+	Show {
+		id: u64,
+	},
+
+	/// - Claim a report to handle it yourself, acknowledging it
+	Claim {
+		id: u64,
+	},
+
+	/// - Mark a report as resolved, with an optional closing note
+	Resolve {
+		id: u64,
+		note: Vec<String>,
+	},
+
+	/// - Open a resolved report again
+	Reopen {
+		id: u64,
+	},
+
+	/// - Add a note to a report
+	Note {
+		id: u64,
+		note: Vec<String>,
+	},
+
+	/// - Link to the event, room or user a report is about
+	Jump {
+		id: u64,
+	},
+
+	/// - Delete a report
+	Delete {
+		id: u64,
+	},
+}
@@ -1,6 +1,15 @@
-use conduwuit::Result;
+use std::{
+	fmt::Write,
+	time::{Duration, UNIX_EPOCH},
+};
+
+use conduwuit::{
+	Result, err,
+	utils::time::{now_millis, parse_timepoint_ago, pretty},
+};
 use futures::StreamExt;
-use ruma::{OwnedRoomId, events::room::message::RoomMessageEventContent};
+use ruma::{EventId, OwnedRoomId, events::room::message::RoomMessageEventContent};
+use service::rooms::purge::Before;
 
 use crate::{PAGE_SIZE, admin_command, get_room_info};
 
@@ -67,3 +76,97 @@ pub(super) async fn exists(&self, room_id: OwnedRoomId) -> Result<RoomMessageEve
 
 	Ok(RoomMessageEventContent::notice_markdown(format!("{result}")))
 }
+
+#[admin_command]
+pub(super) async fn purge(
+	&self,
+	room_id: OwnedRoomId,
+	media: bool,
+) -> Result<RoomMessageEventContent> {
+	let job = self.services.rooms.purge.queue(&room_id, media).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Queued room {room_id} to be purged (deleting media: {}). Progress will be posted here.",
+		job.delete_media
+	)))
+}
+
+#[admin_command]
+pub(super) async fn purge_status(&self) -> Result<RoomMessageEventContent> {
+	let jobs = self.services.rooms.purge.jobs().await;
+	let history_jobs = self.services.rooms.purge.history_jobs().await;
+	if jobs.is_empty() && history_jobs.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("No room purges are in progress."));
+	}
+
+	let mut out = String::new();
+	if !jobs.is_empty() {
+		out.push_str(
+			"| Room | Phase | PDUs | Media | Running for |\n| --- | --- | --- | --- | --- |\n",
+		);
+	}
+
+	for job in jobs {
+		let elapsed = Duration::from_millis(now_millis().saturating_sub(job.started));
+		let media = if job.delete_media {
+			job.media.to_string()
+		} else {
+			"kept".to_owned()
+		};
+		writeln!(
+			out,
+			"| {} | {:?} | {} | {media} | {} |",
+			job.room_id,
+			job.phase,
+			job.pdus,
+			pretty(elapsed)
+		)?;
+	}
+
+	if !history_jobs.is_empty() {
+		out.push_str(
+			"\n| Room | History before | Events | Running for |\n| --- | --- | --- | --- |\n",
+		);
+	}
+
+	for job in history_jobs {
+		let elapsed = Duration::from_millis(now_millis().saturating_sub(job.started));
+		let before = match &job.before {
+			| Before::Timestamp(ts) => ts.to_string(),
+			| Before::Event(event_id) => event_id.to_string(),
+		};
+		writeln!(out, "| {} | {before} | {} | {} |", job.room_id, job.removed, pretty(elapsed))?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[admin_command]
+pub(super) async fn purge_history(
+	&self,
+	room_id: OwnedRoomId,
+	before: String,
+) -> Result<RoomMessageEventContent> {
+	let before = if before.starts_with('$') {
+		Before::Event(EventId::parse(&before)?)
+	} else if let Ok(ts) = before.parse::<u64>() {
+		Before::Timestamp(ts)
+	} else {
+		let ts = parse_timepoint_ago(&before)?
+			.duration_since(UNIX_EPOCH)
+			.map_err(|e| err!("Invalid point in time {before:?}: {e}"))?
+			.as_millis();
+
+		Before::Timestamp(u64::try_from(ts)?)
+	};
+
+	self.services
+		.rooms
+		.purge
+		.queue_history(&room_id, before)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Queued the history of {room_id} to be purged. Its progress is shown by `purge-status`."
+	)))
+}
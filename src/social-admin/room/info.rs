@@ -1,5 +1,8 @@
 use clap::Subcommand;
-use conduwuit::{Result, utils::ReadyExt};
+use conduwuit::{
+	Result,
+	utils::{ReadyExt, time::pretty},
+};
 use futures::StreamExt;
 use ruma::{RoomId, events::room::message::RoomMessageEventContent};
 
@@ -24,6 +27,12 @@ pub(crate) enum RoomInfoCommand {
 	ViewRoomTopic {
 		room_id: Box<RoomId>,
 	},
+
+	/// - Displays the message retention policy of a room and the lifetime of
+	///   its events once the server's bounds are applied
+	ViewRetention {
+		room_id: Box<RoomId>,
+	},
 }
 
 #[admin_command]
@@ -94,3 +103,22 @@ async fn view_room_topic(&self, room_id: Box<RoomId>) -> Result<RoomMessageEvent
 		"Room topic:\n```\n{room_topic}\n```"
 	)))
 }
+
+#[admin_command]
+async fn view_retention(&self, room_id: Box<RoomId>) -> Result<RoomMessageEventContent> {
+	let policy = self.services.rooms.retention.policy(&room_id).await;
+	let room = policy.room.map_or_else(
+		|| "none".to_owned(),
+		|room| serde_json::to_string(&room).unwrap_or_default(),
+	);
+
+	let lifetime = policy
+		.max_lifetime
+		.map_or_else(|| "forever".to_owned(), pretty);
+
+	let enforced = self.services.server.config.enforce_retention;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Room policy: `{room}`\nEvents kept for: {lifetime}\nEnforced: {enforced}"
+	)))
+}
@@ -56,4 +56,36 @@ pub(super) enum RoomCommand {
 	Exists {
 		room_id: OwnedRoomId,
 	},
+
+	/// - Erase a banned room from the database
+	///
+	/// The room's events, state, search index, receipts, memberships,
+	/// notification counts, account data and aliases are removed in the
+	/// background. Progress is posted to the admin room, and an interrupted
+	/// purge resumes when the server starts again.
+	Purge {
+		room_id: OwnedRoomId,
+
+		/// Also delete the media which no other room, account data or profile
+		/// refers to. This reads every event, so it is slow on large servers.
+		#[arg(long)]
+		media: bool,
+	},
+
+	/// - List the room and history purges which have not completed
+	PurgeStatus,
+
+	/// - Delete the history of a room before a point
+	///
+	/// Messages and other non-state events before the point are deleted from
+	/// the timeline and the search index in the background. State events are
+	/// kept, and the deleted events are not backfilled again.
+	PurgeHistory {
+		room_id: OwnedRoomId,
+
+		/// An event ID, a timestamp in milliseconds since the epoch, or a
+		/// duration before now such as `90d`
+		#[arg(long)]
+		before: String,
+	},
 }
@@ -46,6 +46,9 @@ pub(crate) enum RoomModerationCommand {
 		/// information
 		no_details: bool,
 	},
+
+	/// - List the ban rules enforced from the policy lists in the config
+	ListPolicyRules,
 }
 
 #[admin_command]
@@ -491,3 +494,23 @@ async fn list_banned_rooms(&self, no_details: bool) -> Result<RoomMessageEventCo
 
 	Ok(RoomMessageEventContent::notice_markdown(output_plain))
 }
+
+#[admin_command]
+async fn list_policy_rules(&self) -> Result<RoomMessageEventContent> {
+	let rules = self.services.policy_lists.rules();
+	if rules.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("No policy list rules are enforced."));
+	}
+
+	let output = format!(
+		"Policy Rules ({}):\n```\n{}\n```",
+		rules.len(),
+		rules
+			.iter()
+			.map(|rule| format!("{}\t{}\t{}\t{}", rule.kind, rule.entity, rule.list, rule.reason))
+			.collect::<Vec<_>>()
+			.join("\n")
+	);
+
+	Ok(RoomMessageEventContent::notice_markdown(output))
+}
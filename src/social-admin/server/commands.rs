@@ -1,6 +1,11 @@
 use std::{fmt::Write, path::PathBuf, sync::Arc};
 
-use conduwuit::{Err, Result, info, utils::time, warn};
+use conduwuit::{
+	Err, Result, info,
+	log::{Level, ring::Query},
+	utils::time,
+	warn,
+};
 use ruma::events::room::message::RoomMessageEventContent;
 use conduwuit_service_core::services::ServicesTrait;
 
@@ -122,6 +127,90 @@ pub(super) async fn backup_database(&self) -> Result<RoomMessageEventContent> {
 	Ok(RoomMessageEventContent::notice_markdown(result))
 }
 
+#[admin_command]
+pub(super) async fn rotate_database_key(&self) -> Result<RoomMessageEventContent> {
+	let maps: Vec<_> = self.services.db.encrypted().cloned().collect();
+	if maps.is_empty() {
+		return Err!("Database encryption is not enabled.");
+	}
+
+	if self.services.db.is_read_only() {
+		return Err!("Database is read-only.");
+	}
+
+	let names = maps
+		.iter()
+		.map(|map| map.name())
+		.collect::<Vec<_>>()
+		.join(", ");
+	let admin = Arc::clone(&self.services.admin);
+	let runtime = self.services.server.runtime().clone();
+	self.services.server.runtime().spawn(async move {
+		let result = runtime
+			.spawn_blocking(move || -> Result<usize> {
+				maps.iter().try_fold(0_usize, |count, map| {
+					Ok(count.saturating_add(map.reseal_blocking()?))
+				})
+			})
+			.await
+			.map_err(Into::into)
+			.and_then(|result| result);
+
+		let message = match result {
+			| Ok(count) =>
+				format!("Database key rotation complete; re-encrypted {count} values."),
+			| Err(e) => format!("Database key rotation failed: {e}"),
+		};
+
+		info!("{message}");
+		admin.send_text(&message).await;
+	});
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Re-encrypting {names} in the background."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn logs(
+	&self,
+	level: Option<Level>,
+	since: Option<String>,
+	target: Option<String>,
+	span: Option<String>,
+	contains: Option<String>,
+	limit: usize,
+) -> Result<RoomMessageEventContent> {
+	let ring = &self.services.server.log.ring;
+	if !ring.is_enabled() {
+		return Err!("The in-memory log buffer is disabled by log_ring_capacity.");
+	}
+
+	let query = Query {
+		level,
+		since: since
+			.as_deref()
+			.map(time::parse_timepoint_ago)
+			.transpose()?,
+		target,
+		span,
+		contains,
+	};
+
+	let records = ring.query(&query, limit);
+	if records.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("No matching log events."));
+	}
+
+	let mut out = String::from("```\n");
+	for record in &records {
+		writeln!(out, "{record}")?;
+	}
+
+	out.push_str("```");
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
 #[admin_command]
 pub(super) async fn admin_notice(&self, message: Vec<String>) -> Result<RoomMessageEventContent> {
 	let message = message.join(" ");
@@ -3,7 +3,7 @@
 use std::path::PathBuf;
 
 use clap::Subcommand;
-use conduwuit::Result;
+use conduwuit::{Result, log::Level};
 
 use crate::admin_command_dispatch;
 
@@ -46,12 +46,48 @@ pub(super) enum ServerCommand {
 	/// - List database backups
 	ListBackups,
 
+	/// - Re-encrypt encrypted database maps under the active key
+	///
+	/// Runs in the background and reports to the admin room when finished.
+	/// Older keys can be removed from the configuration afterward.
+	RotateDatabaseKey,
+
+	/// - Query recent log events kept in memory
+	///
+	/// Events are kept according to the `log_ring` filter, which can be
+	/// changed at runtime with `debug change-log-level --ring`.
+	Logs {
+		/// Least severe level to show: error, warn, info, debug or trace
+		#[arg(short, long)]
+		level: Option<Level>,
+
+		/// Only events newer than this, e.g. 10m or 2h
+		#[arg(short, long)]
+		since: Option<String>,
+
+		/// Prefix of the event's target, usually a module path
+		#[arg(short, long)]
+		target: Option<String>,
+
+		/// Only events inside a span of this name
+		#[arg(long)]
+		span: Option<String>,
+
+		/// Only events whose message or fields contain this text
+		#[arg(short, long)]
+		contains: Option<String>,
+
+		/// Maximum number of events, most recent last
+		#[arg(short = 'n', long, default_value("100"))]
+		limit: usize,
+	},
+
 	/// - Send a message to the admin room.
 	AdminNotice {
 		message: Vec<String>,
 	},
 
-	/// - Hot-reload the server
+	/// - Hot-reload the server or its plugins
 	#[clap(alias = "reload")]
 	ReloadMods,
 
@@ -1,13 +1,17 @@
-use std::{collections::BTreeMap, fmt::Write as _};
+use std::{collections::BTreeMap, fmt::Write as _, time::Duration};
 
 use api::client::{full_user_deactivate, join_room_by_id_helper, leave_room};
 use conduwuit::{
 	Result, debug, debug_warn, error, info, is_equal_to,
 	matrix::pdu::PduBuilder,
-	utils::{self, ReadyExt},
+	utils::{
+		self, ReadyExt,
+		time::{now_millis, pretty, rfc2822_from_seconds},
+	},
 	warn,
 };
 use conduwuit_social_api::client::{leave_all_rooms, update_avatar_url, update_displayname};
+use conduwuit_social_service::Services;
 use futures::StreamExt;
 use ruma::{
 	EventId, OwnedRoomId, OwnedRoomOrAliasId, OwnedUserId, RoomId, UserId,
@@ -24,7 +28,7 @@
 
 use crate::{
 	admin_command, get_room_info,
-	utils::{parse_active_local_user_id, parse_local_user_id},
+	utils::{parse_active_local_user_id, parse_local_user_id, parse_user_id},
 };
 
 const AUTO_GEN_PASSWORD_LENGTH: usize = 25;
@@ -798,6 +802,227 @@ pub(super) async fn make_user_admin(&self, user_id: String) -> Result<RoomMessag
 	)))
 }
 
+#[admin_command]
+pub(super) async fn shadow_ban(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+
+	if user_id == self.services.globals.server_user {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to shadow-ban the server service account.",
+		));
+	}
+
+	if self.services.users.is_admin(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(
+			"Not allowed to shadow-ban an admin; demote them first.",
+		));
+	}
+
+	if self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"{user_id} is already shadow-banned."
+		)));
+	}
+
+	self.services.users.set_shadow_banned(&user_id, true);
+	info!("{user_id} has been shadow-banned");
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"{user_id} has been shadow-banned."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn lift_shadow_ban(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+
+	if !self.services.users.is_shadow_banned(&user_id).await {
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"{user_id} is not shadow-banned."
+		)));
+	}
+
+	self.services.users.set_shadow_banned(&user_id, false);
+	info!("Shadow ban of {user_id} has been lifted");
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Shadow ban of {user_id} has been lifted."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn list_shadow_banned(&self) -> Result<RoomMessageEventContent> {
+	let users: Vec<_> = self
+		.services
+		.users
+		.list_shadow_banned()
+		.map(ToString::to_string)
+		.collect()
+		.await;
+
+	if users.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("No users are shadow-banned."));
+	}
+
+	let mut plain_msg = format!("Found {} shadow-banned user(s):\n```\n", users.len());
+	plain_msg += users.join("\n").as_str();
+	plain_msg += "\n```";
+
+	Ok(RoomMessageEventContent::notice_markdown(plain_msg))
+}
+
+#[admin_command]
+pub(super) async fn suspend(
+	&self,
+	user_id: String,
+	reason: Vec<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	if let Some(refusal) = refuse_restriction(self.services, &user_id).await {
+		return Ok(refusal);
+	}
+
+	let reason = Some(reason.join(" ")).filter(|reason| !reason.is_empty());
+	let sender_user = self.sender_user().await;
+	if !self
+		.services
+		.users
+		.set_suspended(&user_id, true, &sender_user, reason)
+		.await?
+	{
+		return Ok(RoomMessageEventContent::text_plain(format!(
+			"{user_id} is already suspended."
+		)));
+	}
+
+	info!("{user_id} has been suspended by {sender_user}");
+
+	Ok(RoomMessageEventContent::text_plain(format!("{user_id} has been suspended.")))
+}
+
+#[admin_command]
+pub(super) async fn unsuspend(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let sender_user = self.sender_user().await;
+	if !self
+		.services
+		.users
+		.set_suspended(&user_id, false, &sender_user, None)
+		.await?
+	{
+		return Ok(RoomMessageEventContent::text_plain(format!("{user_id} is not suspended.")));
+	}
+
+	info!("Suspension of {user_id} has been lifted by {sender_user}");
+
+	Ok(RoomMessageEventContent::text_plain(format!(
+		"Suspension of {user_id} has been lifted."
+	)))
+}
+
+#[admin_command]
+pub(super) async fn lock(
+	&self,
+	user_id: String,
+	reason: Vec<String>,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_active_local_user_id(self.services, &user_id).await?;
+	if let Some(refusal) = refuse_restriction(self.services, &user_id).await {
+		return Ok(refusal);
+	}
+
+	let reason = Some(reason.join(" ")).filter(|reason| !reason.is_empty());
+	let sender_user = self.sender_user().await;
+	if !self
+		.services
+		.users
+		.set_locked(&user_id, true, &sender_user, reason)
+		.await?
+	{
+		return Ok(RoomMessageEventContent::text_plain(format!("{user_id} is already locked.")));
+	}
+
+	info!("{user_id} has been locked by {sender_user}");
+
+	Ok(RoomMessageEventContent::text_plain(format!("{user_id} has been locked.")))
+}
+
+#[admin_command]
+pub(super) async fn unlock(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_local_user_id(self.services, &user_id)?;
+	let sender_user = self.sender_user().await;
+	if !self
+		.services
+		.users
+		.set_locked(&user_id, false, &sender_user, None)
+		.await?
+	{
+		return Ok(RoomMessageEventContent::text_plain(format!("{user_id} is not locked.")));
+	}
+
+	info!("{user_id} has been unlocked by {sender_user}");
+
+	Ok(RoomMessageEventContent::text_plain(format!("{user_id} has been unlocked.")))
+}
+
+#[admin_command]
+pub(super) async fn list_restricted(&self) -> Result<RoomMessageEventContent> {
+	let restricted: Vec<_> = self.services.users.list_restricted().collect().await;
+
+	if restricted.is_empty() {
+		return Ok(RoomMessageEventContent::text_plain("No users are suspended or locked."));
+	}
+
+	let mut out = String::from(
+		"| User | Restriction | By | Since | Reason |\n| --- | --- | --- | --- | --- |\n",
+	);
+	for (user_id, restrictions) in restricted {
+		for (kind, restriction) in
+			[("suspended", restrictions.suspended), ("locked", restrictions.locked)]
+		{
+			let Some(restriction) = restriction else {
+				continue;
+			};
+
+			let since = Duration::from_millis(restriction.ts).as_secs();
+			writeln!(
+				out,
+				"| {user_id} | {kind} | {} | {} | {} |",
+				restriction.by,
+				rfc2822_from_seconds(i64::try_from(since).unwrap_or(i64::MAX)),
+				restriction
+					.reason
+					.as_deref()
+					.unwrap_or("")
+					.replace(['|', '\n'], " "),
+			)?;
+		}
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+/// Admins and the server user cannot be suspended or locked, since that could
+/// lock everyone out of the admin room.
+async fn refuse_restriction(
+	services: &Services,
+	user_id: &UserId,
+) -> Option<RoomMessageEventContent> {
+	if user_id == services.globals.server_user {
+		return Some(RoomMessageEventContent::text_plain(
+			"Not allowed to restrict the server service account.",
+		));
+	}
+
+	if services.users.is_admin(user_id).await {
+		return Some(RoomMessageEventContent::text_plain(
+			"Not allowed to restrict an admin; demote them first.",
+		));
+	}
+
+	None
+}
+
 #[admin_command]
 pub(super) async fn put_room_tag(
 	&self,
@@ -955,3 +1180,73 @@ pub(super) async fn redact_event(
 
 	Ok(RoomMessageEventContent::text_plain(""))
 }
+
+#[admin_command]
+pub(super) async fn redact_all(
+	&self,
+	user_id: String,
+	room: Option<OwnedRoomOrAliasId>,
+	since: Option<u64>,
+	delay: u64,
+) -> Result<RoomMessageEventContent> {
+	let user_id = parse_user_id(self.services, &user_id)?;
+	let room_id = match room {
+		| Some(room) => Some(self.services.rooms.alias.resolve(&room).await?),
+		| None => None,
+	};
+
+	let job = self
+		.services
+		.rooms
+		.redaction
+		.queue(&user_id, room_id, since, delay)
+		.await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Queued the redaction of events sent by {user_id} in {} rooms. Progress will be posted \
+		 here.",
+		job.rooms.len()
+	)))
+}
+
+#[admin_command]
+pub(super) async fn redact_status(&self) -> Result<RoomMessageEventContent> {
+	let jobs = self.services.rooms.redaction.jobs().await;
+	if jobs.is_empty() {
+		return Ok(RoomMessageEventContent::notice_plain("No redactions are in progress."));
+	}
+
+	let mut out = String::from(
+		"| User | Rooms left | Redacted | Failed | Skipped rooms | Running for |\n| --- | --- | \
+		 --- | --- | --- | --- |\n",
+	);
+
+	for job in jobs {
+		let elapsed = Duration::from_millis(now_millis().saturating_sub(job.started));
+		writeln!(
+			out,
+			"| {} | {} | {} | {} | {} | {} |",
+			job.user_id,
+			job.rooms.len(),
+			job.redacted,
+			job.failed,
+			job.skipped.len(),
+			pretty(elapsed)
+		)?;
+	}
+
+	Ok(RoomMessageEventContent::notice_markdown(out))
+}
+
+#[admin_command]
+pub(super) async fn redact_cancel(&self, user_id: String) -> Result<RoomMessageEventContent> {
+	let user_id = parse_user_id(self.services, &user_id)?;
+	let job = self.services.rooms.redaction.cancel(&user_id).await?;
+
+	Ok(RoomMessageEventContent::notice_markdown(format!(
+		"Cancelled the redaction of events sent by {user_id}; {} events were redacted and {} \
+		 rooms were not done.",
+		job.redacted,
+		job.rooms.len()
+	)))
+}
@@ -93,6 +93,62 @@ pub(super) enum UserCommand {
 		user_id: String,
 	},
 
+	/// - Shadow-ban a local user
+	///
+	/// The messages, state and redactions they send appear to be sent but are
+	/// dropped, as are their invites. Their other events, such as joins, are
+	/// not federated, shown to other local users or pushed.
+	ShadowBan {
+		user_id: String,
+	},
+
+	/// - Lift the shadow ban of a local user
+	///
+	/// Joins and other events they sent while shadow-banned become visible to
+	/// other local users, but are not federated or pushed after the fact.
+	LiftShadowBan {
+		user_id: String,
+	},
+
+	/// - List shadow-banned users
+	ListShadowBanned,
+
+	/// - Suspend a local user
+	///
+	/// They can still read and leave rooms, but cannot send events, join
+	/// rooms, change their profile or upload media. Unlike deactivation this
+	/// can be lifted with unsuspend.
+	Suspend {
+		user_id: String,
+
+		/// Reason for the suspension, recorded with it
+		reason: Vec<String>,
+	},
+
+	/// - Lift the suspension of a local user
+	Unsuspend {
+		user_id: String,
+	},
+
+	/// - Lock a local user
+	///
+	/// All their requests are refused, but their sessions are kept so they
+	/// can carry on once unlocked.
+	Lock {
+		user_id: String,
+
+		/// Reason for the lock, recorded with it
+		reason: Vec<String>,
+	},
+
+	/// - Unlock a local user
+	Unlock {
+		user_id: String,
+	},
+
+	/// - List suspended and locked users
+	ListRestricted,
+
 	/// - Puts a room tag for the specified user and room ID.
 	///
 	/// This is primarily useful if you'd like to set your admin room
@@ -127,6 +183,39 @@ pub(super) enum UserCommand {
 		event_id: Box<EventId>,
 	},
 
+	/// - Redacts all the events a user sent, in every room they are or were in
+	///
+	/// Works for local and remote users. Redactions are sent as the server
+	/// user, or else as another local user with permission to redact in the
+	/// room; rooms where neither can are skipped and listed at the end. State
+	/// events are left alone. The redaction runs in the background, resumes
+	/// after a restart and posts its progress to the admin room as each room
+	/// is done.
+	RedactAll {
+		user_id: String,
+
+		/// Only redact events in this room
+		#[arg(long)]
+		room: Option<OwnedRoomOrAliasId>,
+
+		/// Only redact events sent at or after this timestamp, in milliseconds
+		/// since the unix epoch
+		#[arg(long)]
+		since: Option<u64>,
+
+		/// Milliseconds to wait between redactions
+		#[arg(long, default_value("100"))]
+		delay: u64,
+	},
+
+	/// - List the redactions of users' events which have not completed
+	RedactStatus,
+
+	/// - Stop redacting a user's events; those already redacted stay so
+	RedactCancel {
+		user_id: String,
+	},
+
 	/// - Force joins a specified list of local users to join the specified
 	///   room.
 	///
@@ -9,7 +9,7 @@
 	utils::{ReadyExt, stream::BroadbandExt},
 	warn,
 };
-use conduwuit_social_service::Services;
+use conduwuit_social_service::{Services, spam_checker::Verdict};
 use futures::{FutureExt, StreamExt};
 use register::RegistrationKind;
 use ruma::{
@@ -338,10 +338,39 @@ pub(crate) async fn register_route(
 		}
 	}
 
+	let shadow_ban = if body.appservice_info.is_none() {
+		match services.spam_checker.check_registration(&user_id).await {
+			| Verdict::Allow => false,
+			| Verdict::Deny(e) => {
+				info!(%user_id, "Registration refused by spam checker: {e}");
+				return Err(e);
+			},
+			// The account is created but shadow-banned, and the admins are told.
+			| Verdict::SoftFail => {
+				warn!(%user_id, "Registration flagged by spam checker, shadow-banning");
+				services
+					.admin
+					.send_message(RoomMessageEventContent::notice_plain(format!(
+						"The spam checker flagged the registration of \"{user_id}\" from IP \
+						 {client}; the account has been shadow-banned."
+					)))
+					.await
+					.ok();
+
+				true
+			},
+		}
+	} else {
+		false
+	};
+
 	let password = if is_guest { None } else { body.password.as_deref() };
 
 	// Create user
 	services.users.create(&user_id, password)?;
+	if shadow_ban {
+		services.users.set_shadow_banned(&user_id, true);
+	}
 
 	// Default to pretty displayname
 	let mut displayname = user_id.localpart().to_owned();
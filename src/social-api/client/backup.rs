@@ -58,19 +58,15 @@ pub(crate) async fn get_latest_backup_info_route(
 		.await
 		.map_err(|_| err!(Request(NotFound("Key backup does not exist."))))?;
 
+	let (count, etag) = services
+		.key_backups
+		.count_keys_and_etag(body.sender_user(), &version)
+		.await;
+
 	Ok(get_latest_backup_info::v3::Response {
 		algorithm,
-		count: (UInt::try_from(
-			services
-				.key_backups
-				.count_keys(body.sender_user(), &version)
-				.await,
-		)
-		.expect("user backup keys count should not be that high")),
-		etag: services
-			.key_backups
-			.get_etag(body.sender_user(), &version)
-			.await,
+		count: (UInt::try_from(count).expect("user backup keys count should not be that high")),
+		etag,
 		version,
 	})
 }
@@ -90,17 +86,15 @@ pub(crate) async fn get_backup_info_route(
 			err!(Request(NotFound("Key backup does not exist at version {:?}", body.version)))
 		})?;
 
+	let (count, etag) = services
+		.key_backups
+		.count_keys_and_etag(body.sender_user(), &body.version)
+		.await;
+
 	Ok(get_backup_info::v3::Response {
 		algorithm,
-		count: services
-			.key_backups
-			.count_keys(body.sender_user(), &body.version)
-			.await
-			.try_into()?,
-		etag: services
-			.key_backups
-			.get_etag(body.sender_user(), &body.version)
-			.await,
+		count: count.try_into()?,
+		etag,
 		version: body.version.clone(),
 	})
 }
@@ -27,6 +27,7 @@
 		state::RoomMutexGuard,
 		state_compressor::{CompressedState, HashSetCompressStateEvent},
 	},
+	spam_checker::Verdict,
 };
 use futures::{FutureExt, StreamExt, TryFutureExt, future::join4, join};
 use ruma::{
@@ -916,6 +917,8 @@ pub async fn join_room_by_id_helper(
 		return Err!(Request(Forbidden("Guests are not allowed to join this room")));
 	}
 
+	services.policy_lists.check_user(sender_user)?;
+
 	if services
 		.rooms
 		.state_cache
@@ -1637,6 +1640,27 @@ pub(crate) async fn invite_helper(
 		return Err!(Request(Forbidden("Invites are not allowed on this server.")));
 	}
 
+	services.policy_lists.check_user(sender_user)?;
+	services.policy_lists.check_user(user_id)?;
+
+	if services.users.is_shadow_banned(sender_user).await {
+		debug_warn!(%room_id, "Dropping invite of {user_id} by shadow-banned {sender_user}");
+		return Ok(());
+	}
+
+	match services
+		.spam_checker
+		.check_invite(sender_user, user_id, room_id)
+		.await
+	{
+		| Verdict::Allow => {},
+		| Verdict::Deny(e) => return Err(e),
+		| Verdict::SoftFail => {
+			debug_warn!(%room_id, "Invite of {user_id} by {sender_user} soft failed by spam checker");
+			return Ok(());
+		},
+	}
+
 	if !services.globals.user_is_local(user_id) {
 		let (pdu, pdu_json, invite_room_state) = {
 			let state_lock = services.rooms.state.mutex.lock(room_id).await;
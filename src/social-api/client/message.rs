@@ -258,6 +258,11 @@ pub(crate) async fn is_ignored_pdu(
 		return true;
 	}
 
+	// Events of shadow-banned users are only shown to themselves.
+	if pdu.sender() != user_id && services.users.is_shadow_banned(&pdu.sender).await {
+		return true;
+	}
+
 	let ignored_type = IGNORED_MESSAGE_TYPES.binary_search(&pdu.kind).is_ok();
 
 	let ignored_server = services
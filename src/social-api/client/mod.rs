@@ -21,6 +21,7 @@
 pub(super) mod redact;
 pub(super) mod relations;
 pub(super) mod report;
+pub(super) mod restrictions;
 pub(super) mod room;
 pub(super) mod search;
 pub(super) mod send;
@@ -65,6 +66,7 @@
 pub(super) use redact::*;
 pub(super) use relations::*;
 pub(super) use report::*;
+pub(super) use restrictions::*;
 pub(super) use room::*;
 pub(super) use search::*;
 pub(super) use send::*;
@@ -73,7 +73,7 @@ pub(crate) async fn get_displayname_route(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	body: Ruma<get_display_name::v3::Request>,
 ) -> Result<get_display_name::v3::Response> {
-	if !services.globals.user_is_local(&body.user_id) {
+	if !services.globals.user_is_local(&body.user_id) && !services.globals.is_read_only() {
 		// Create and update our local copy of the user
 		if let Ok(response) = services
 			.sending
@@ -169,7 +169,7 @@ pub(crate) async fn get_avatar_url_route(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	body: Ruma<get_avatar_url::v3::Request>,
 ) -> Result<get_avatar_url::v3::Response> {
-	if !services.globals.user_is_local(&body.user_id) {
+	if !services.globals.user_is_local(&body.user_id) && !services.globals.is_read_only() {
 		// Create and update our local copy of the user
 		if let Ok(response) = services
 			.sending
@@ -227,7 +227,7 @@ pub(crate) async fn get_profile_route(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	body: Ruma<get_profile::v3::Request>,
 ) -> Result<get_profile::v3::Response> {
-	if !services.globals.user_is_local(&body.user_id) {
+	if !services.globals.user_is_local(&body.user_id) && !services.globals.is_read_only() {
 		// Create and update our local copy of the user
 		if let Ok(response) = services
 			.sending
@@ -18,23 +18,29 @@ pub(crate) async fn redact_event_route(
 	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
 	let body = body.body;
 
+	let pdu_builder = PduBuilder {
+		redacts: Some(body.event_id.clone()),
+		..PduBuilder::timeline(&RoomRedactionEventContent {
+			redacts: Some(body.event_id.clone()),
+			reason: body.reason.clone(),
+		})
+	};
+
+	if let Some(event_id) = services
+		.rooms
+		.timeline
+		.check_local_event(&pdu_builder, sender_user, &body.room_id)
+		.await?
+	{
+		return Ok(redact_event::v3::Response { event_id });
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	let event_id = services
 		.rooms
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				redacts: Some(body.event_id.clone()),
-				..PduBuilder::timeline(&RoomRedactionEventContent {
-					redacts: Some(body.event_id.clone()),
-					reason: body.reason.clone(),
-				})
-			},
-			sender_user,
-			&body.room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, sender_user, &body.room_id, &state_lock)
 		.await?;
 
 	drop(state_lock);
@@ -1,22 +1,31 @@
 use std::time::Duration;
 
-use axum::extract::State;
+use axum::{
+	Json,
+	extract::{Query, State},
+	response::IntoResponse,
+};
 use axum_client_ip::InsecureClientIp;
 use conduwuit::{Err, Error, Result, debug_info, info, matrix::pdu::PduEvent, utils::ReadyExt};
-use conduwuit_social_service::Services;
+use conduwuit_social_service::{
+	Services,
+	reports::{Filter, Kind, Report, Status, Target},
+};
+use futures::StreamExt;
 use rand::Rng;
 use ruma::{
-	EventId, RoomId, UserId,
+	EventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
 	api::client::{
 		error::ErrorKind,
+		report_user,
 		room::{report_content, report_room},
 	},
-	events::room::message,
 	int,
 };
+use serde::{Deserialize, Serialize};
 use tokio::time::sleep;
 
-use crate::Ruma;
+use crate::{Admin, Ruma};
 
 /// # `POST /_matrix/client/v3/rooms/{roomId}/report`
 ///
@@ -56,18 +65,15 @@ pub(crate) async fn report_room_route(
 		)));
 	}
 
-	// send admin room message that we received the report with an @room ping for
-	// urgency
 	services
-		.admin
-		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Room report received from {} -\n\nRoom ID: {}\n\nReport Reason: {}",
-			sender_user.to_owned(),
-			body.room_id,
-			body.reason.as_deref().unwrap_or("")
-		)))
-		.await
-		.ok();
+		.reports
+		.submit(
+			sender_user,
+			Target::Room { room_id: body.room_id.clone() },
+			body.reason.clone(),
+			None,
+		)
+		.await?;
 
 	Ok(report_room::v3::Response {})
 }
@@ -110,26 +116,129 @@ pub(crate) async fn report_event_route(
 	)
 	.await?;
 
-	// send admin room message that we received the report with an @room ping for
-	// urgency
 	services
-		.admin
-		.send_message(message::RoomMessageEventContent::text_markdown(format!(
-			"@room Event report received from {} -\n\nEvent ID: {}\nRoom ID: {}\nSent By: \
-			 {}\n\nReport Score: {}\nReport Reason: {}",
-			sender_user.to_owned(),
-			pdu.event_id,
-			pdu.room_id,
-			pdu.sender,
-			body.score.unwrap_or_else(|| ruma::Int::from(0)),
-			body.reason.as_deref().unwrap_or("")
-		)))
-		.await
-		.ok();
+		.reports
+		.submit(
+			sender_user,
+			Target::Event {
+				room_id: pdu.room_id.clone(),
+				event_id: pdu.event_id.clone(),
+				sender: pdu.sender.clone(),
+			},
+			body.reason.clone(),
+			body.score.map(i64::from),
+		)
+		.await?;
 
 	Ok(report_content::v3::Response {})
 }
 
+/// # `POST /_matrix/client/v3/users/{userId}/report`
+///
+/// Reports an abusive user to homeserver admins (MSC4260)
+#[tracing::instrument(skip_all, fields(%client), name = "report_user")]
+pub(crate) async fn report_user_route(
+	State(services): State<conduwuit_router::State<service::Services>>,
+	InsecureClientIp(client): InsecureClientIp,
+	body: Ruma<report_user::v3::Request>,
+) -> Result<report_user::v3::Response> {
+	// user authentication
+	let sender_user = body.sender_user.as_ref().expect("user is authenticated");
+
+	info!(
+		"Received user report by user {sender_user} for user {} with reason: \"{}\"",
+		body.user_id, body.reason
+	);
+
+	if body.reason.len() > 750 {
+		return Err(Error::BadRequest(
+			ErrorKind::InvalidParam,
+			"Reason too long, should be 750 characters or fewer",
+		));
+	}
+
+	if body.user_id == *sender_user {
+		return Err!(Request(InvalidParam("You cannot report yourself.")));
+	}
+
+	delay_response().await;
+
+	if services.globals.user_is_local(&body.user_id)
+		&& !services.users.exists(&body.user_id).await
+	{
+		return Err!(Request(NotFound("User does not exist.")));
+	}
+
+	services
+		.reports
+		.submit(
+			sender_user,
+			Target::User { user_id: body.user_id.clone() },
+			Some(body.reason.clone()).filter(|reason| !reason.is_empty()),
+			None,
+		)
+		.await?;
+
+	Ok(report_user::v3::Response {})
+}
+
+/// Page of reports, as query parameters.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReportsParams {
+	status: Option<Status>,
+	kind: Option<Kind>,
+	reporter: Option<OwnedUserId>,
+	room_id: Option<OwnedRoomId>,
+	user_id: Option<OwnedUserId>,
+	handler: Option<OwnedUserId>,
+
+	/// ID of the newest report to return, from a previous `next_batch`.
+	from: Option<u64>,
+	limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+struct ReportsPage {
+	reports: Vec<Report>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	next_batch: Option<u64>,
+}
+
+/// # `GET /_conduwuit/admin/reports`
+///
+/// Pages through the stored abuse reports, newest first. Requires the access
+/// token of a server admin.
+pub(crate) async fn get_reports_route(
+	State(services): State<conduwuit_router::State<service::Services>>,
+	Admin(_): Admin,
+	Query(params): Query<ReportsParams>,
+) -> Result<impl IntoResponse> {
+	let filter = Filter {
+		status: params.status,
+		kind: params.kind,
+		reporter: params.reporter,
+		room_id: params.room_id,
+		user_id: params.user_id,
+		handler: params.handler,
+	};
+
+	let limit = params.limit.unwrap_or(50).clamp(1, 500);
+	let mut reports: Vec<Report> = services
+		.reports
+		.reports(&filter, params.from)
+		.take(limit.saturating_add(1))
+		.collect()
+		.await;
+
+	let next_batch = (reports.len() > limit)
+		.then(|| reports.pop())
+		.flatten()
+		.map(|report| report.id);
+
+	Ok(Json(ReportsPage { reports, next_batch }))
+}
+
 /// in the following order:
 ///
 /// check if the room ID from the URI matches the PDU's room ID
@@ -0,0 +1,106 @@
+use axum::{
+	Json,
+	extract::{Path, State},
+	response::IntoResponse,
+};
+use conduwuit::{Err, Result, info};
+use conduwuit_social_service::Services;
+use ruma::{OwnedUserId, UserId};
+use serde::{Deserialize, Serialize};
+
+use crate::Admin;
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetSuspended {
+	suspended: bool,
+	reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SetLocked {
+	locked: bool,
+	reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Changed {
+	changed: bool,
+}
+
+/// # `GET /_conduwuit/admin/users/{userId}/restrictions`
+///
+/// Whether a local user is suspended or locked, with who did it, when and why.
+/// Requires the access token of a server admin.
+pub(crate) async fn get_user_restrictions_route(
+	State(services): State<conduwuit_router::State<service::Services>>,
+	Admin(_): Admin,
+	Path(user_id): Path<OwnedUserId>,
+) -> Result<impl IntoResponse> {
+	if !services.users.exists(&user_id).await {
+		return Err!(Request(NotFound("{user_id} does not exist.")));
+	}
+
+	Ok(Json(services.users.restrictions(&user_id).await))
+}
+
+/// # `PUT /_conduwuit/admin/users/{userId}/suspend`
+///
+/// Suspends a local user, or lifts their suspension, with an optional reason.
+/// Requires the access token of a server admin.
+pub(crate) async fn set_user_suspended_route(
+	State(services): State<conduwuit_router::State<service::Services>>,
+	Admin(sender_user): Admin,
+	Path(user_id): Path<OwnedUserId>,
+	Json(body): Json<SetSuspended>,
+) -> Result<impl IntoResponse> {
+	check_target(&services, &user_id).await?;
+
+	let changed = services
+		.users
+		.set_suspended(&user_id, body.suspended, &sender_user, body.reason)
+		.await?;
+
+	if changed {
+		info!(%sender_user, suspended = body.suspended, "Changed suspension of {user_id}");
+	}
+
+	Ok(Json(Changed { changed }))
+}
+
+/// # `PUT /_conduwuit/admin/users/{userId}/lock`
+///
+/// Locks a local user, or unlocks them, with an optional reason. Requires the
+/// access token of a server admin.
+pub(crate) async fn set_user_locked_route(
+	State(services): State<conduwuit_router::State<service::Services>>,
+	Admin(sender_user): Admin,
+	Path(user_id): Path<OwnedUserId>,
+	Json(body): Json<SetLocked>,
+) -> Result<impl IntoResponse> {
+	check_target(&services, &user_id).await?;
+
+	let changed = services
+		.users
+		.set_locked(&user_id, body.locked, &sender_user, body.reason)
+		.await?;
+
+	if changed {
+		info!(%sender_user, locked = body.locked, "Changed lock of {user_id}");
+	}
+
+	Ok(Json(Changed { changed }))
+}
+
+/// Admins and the server user cannot be restricted, since that could lock
+/// everyone out of the admin room.
+async fn check_target(services: &Services, user_id: &UserId) -> Result {
+	if user_id == services.globals.server_user {
+		return Err!(Request(Forbidden("The server user cannot be restricted.")));
+	}
+
+	if services.users.is_admin(user_id).await {
+		return Err!(Request(Forbidden("Admins cannot be restricted; demote them first.")));
+	}
+
+	Ok(())
+}
@@ -1,5 +1,6 @@
 use axum::extract::State;
 use axum_client_ip::InsecureClientIp;
+use bytes::BufMut;
 use conduwuit::{
 	Err, Result, debug_warn, trace,
 	utils::{IterStream, future::TryExtExt},
@@ -12,16 +13,30 @@
 use ruma::{
 	OwnedServerName, RoomId, UserId,
 	api::{
+		OutgoingResponse,
 		client::room::get_summary,
+		error::IntoHttpError,
 		federation::space::{SpaceHierarchyParentSummary, get_hierarchy},
 	},
 	events::room::member::MembershipState,
 	space::SpaceRoomJoinRule::{self, *},
 };
-use service::Services;
+use serde_json::Value as JsonValue;
+use service::{Services, rooms::retention::RoomRetentionEventContent};
 
 use crate::{Ruma, RumaResponse};
 
+/// A room summary along with the retention of the room's events (MSC1763),
+/// which the summary response has no field for. Only rooms this server is in
+/// have their retention included.
+pub(crate) struct RoomSummary {
+	summary: get_summary::msc3266::Response,
+	retention: Option<RoomRetentionEventContent>,
+}
+
+/// Key of the retention in the summary response.
+const RETENTION_KEY: &str = "m.room.retention";
+
 /// # `GET /_matrix/client/unstable/im.nheko.summary/rooms/{roomIdOrAlias}/summary`
 ///
 /// Returns a short description of the state of a room.
@@ -35,11 +50,10 @@ pub(crate) async fn get_room_summary_legacy(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_summary::msc3266::Request>,
-) -> Result<RumaResponse<get_summary::msc3266::Response>> {
+) -> Result<RumaResponse<RoomSummary>> {
 	get_room_summary(State(services), InsecureClientIp(client), body)
 		.boxed()
 		.await
-		.map(RumaResponse)
 }
 
 /// # `GET /_matrix/client/unstable/im.nheko.summary/summary/{roomIdOrAlias}`
@@ -52,7 +66,7 @@ pub(crate) async fn get_room_summary(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	InsecureClientIp(client): InsecureClientIp,
 	body: Ruma<get_summary::msc3266::Request>,
-) -> Result<get_summary::msc3266::Response> {
+) -> Result<RumaResponse<RoomSummary>> {
 	let (room_id, servers) = services
 		.rooms
 		.alias
@@ -66,6 +80,7 @@ pub(crate) async fn get_room_summary(
 	room_summary_response(&services, &room_id, &servers, body.sender_user.as_deref())
 		.boxed()
 		.await
+		.map(RumaResponse)
 }
 
 async fn room_summary_response(
@@ -73,22 +88,32 @@ async fn room_summary_response(
 	room_id: &RoomId,
 	servers: &[OwnedServerName],
 	sender_user: Option<&UserId>,
-) -> Result<get_summary::msc3266::Response> {
+) -> Result<RoomSummary> {
 	if services
 		.rooms
 		.state_cache
 		.server_in_room(services.globals.server_name(), room_id)
 		.await
 	{
-		return local_room_summary_response(services, room_id, sender_user)
+		let summary = local_room_summary_response(services, room_id, sender_user)
 			.boxed()
-			.await;
+			.await?;
+
+		let policy = services.rooms.retention.policy(room_id).await;
+		let retention = RoomRetentionEventContent {
+			max_lifetime: policy
+				.max_lifetime
+				.map(|lifetime| u64::try_from(lifetime.as_millis()).unwrap_or(u64::MAX)),
+			min_lifetime: policy.room.and_then(|room| room.min_lifetime),
+		};
+
+		return Ok(RoomSummary { summary, retention: Some(retention) });
 	}
 
 	let room =
 		remote_room_summary_hierarchy_response(services, room_id, servers, sender_user).await?;
 
-	Ok(get_summary::msc3266::Response {
+	let summary = get_summary::msc3266::Response {
 		room_id: room_id.to_owned(),
 		canonical_alias: room.canonical_alias,
 		avatar_url: room.avatar_url,
@@ -103,7 +128,9 @@ async fn room_summary_response(
 		encryption: room.encryption,
 		allowed_room_ids: room.allowed_room_ids,
 		membership: sender_user.is_some().then_some(MembershipState::Leave),
-	})
+	};
+
+	Ok(RoomSummary { summary, retention: None })
 }
 
 async fn local_room_summary_response(
@@ -328,3 +355,24 @@ async fn user_can_see_summary<'a, I>(
 		},
 	}
 }
+
+impl OutgoingResponse for RoomSummary {
+	fn try_into_http_response<T: Default + BufMut>(
+		self,
+	) -> Result<http::Response<T>, IntoHttpError> {
+		let (parts, body) = self
+			.summary
+			.try_into_http_response::<Vec<u8>>()?
+			.into_parts();
+
+		let mut json: serde_json::Map<String, JsonValue> = serde_json::from_slice(&body)?;
+		if let Some(retention) = self.retention {
+			json.insert(RETENTION_KEY.to_owned(), serde_json::to_value(retention)?);
+		}
+
+		let mut body = T::default();
+		body.put_slice(&serde_json::to_vec(&json)?);
+
+		Ok(http::Response::from_parts(parts, body))
+	}
+}
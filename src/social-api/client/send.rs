@@ -30,6 +30,26 @@ pub(crate) async fn send_message_event_route(
 		return Err!(Request(Forbidden("Encryption has been disabled")));
 	}
 
+	let mut unsigned = BTreeMap::new();
+	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
+
+	let content = from_str(body.body.body.json().get())
+		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
+
+	let pdu_builder = PduBuilder {
+		event_type: body.event_type.clone().into(),
+		content,
+		unsigned: Some(unsigned),
+		timestamp: appservice_info.and(body.timestamp),
+		..Default::default()
+	};
+
+	let dropped = services
+		.rooms
+		.timeline
+		.check_local_event(&pdu_builder, sender_user, &body.room_id)
+		.await?;
+
 	let state_lock = services.rooms.state.mutex.lock(&body.room_id).await;
 
 	if body.event_type == MessageLikeEventType::CallInvite
@@ -59,28 +79,16 @@ pub(crate) async fn send_message_event_route(
 		});
 	}
 
-	let mut unsigned = BTreeMap::new();
-	unsigned.insert("transaction_id".to_owned(), body.txn_id.to_string().into());
-
-	let content = from_str(body.body.body.json().get())
-		.map_err(|e| err!(Request(BadJson("Invalid JSON body: {e}"))))?;
-
-	let event_id = services
-		.rooms
-		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				event_type: body.event_type.clone().into(),
-				content,
-				unsigned: Some(unsigned),
-				timestamp: appservice_info.and(body.timestamp),
-				..Default::default()
-			},
-			sender_user,
-			&body.room_id,
-			&state_lock,
-		)
-		.await?;
+	// A dropped event keeps its ID for the transaction so retries get it again
+	let event_id = match dropped {
+		| Some(event_id) => event_id,
+		| None =>
+			services
+				.rooms
+				.timeline
+				.build_and_append_pdu(pdu_builder, sender_user, &body.room_id, &state_lock)
+				.await?,
+	};
 
 	services.transaction_ids.add_txnid(
 		sender_user,
@@ -171,22 +171,28 @@ async fn send_state_event_for_key_helper(
 	timestamp: Option<ruma::MilliSecondsSinceUnixEpoch>,
 ) -> Result<OwnedEventId> {
 	allowed_to_send_state_event(services, room_id, event_type, state_key, json).await?;
+	let pdu_builder = PduBuilder {
+		event_type: event_type.to_string().into(),
+		content: serde_json::from_str(json.json().get())?,
+		state_key: Some(state_key.into()),
+		timestamp,
+		..Default::default()
+	};
+
+	if let Some(event_id) = services
+		.rooms
+		.timeline
+		.check_local_event(&pdu_builder, sender, room_id)
+		.await?
+	{
+		return Ok(event_id);
+	}
+
 	let state_lock = services.rooms.state.mutex.lock(room_id).await;
 	let event_id = services
 		.rooms
 		.timeline
-		.build_and_append_pdu(
-			PduBuilder {
-				event_type: event_type.to_string().into(),
-				content: serde_json::from_str(json.json().get())?,
-				state_key: Some(state_key.into()),
-				timestamp,
-				..Default::default()
-			},
-			sender,
-			room_id,
-			&state_lock,
-		)
+		.build_and_append_pdu(pdu_builder, sender, room_id, &state_lock)
 		.await?;
 
 	Ok(event_id)
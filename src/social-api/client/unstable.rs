@@ -278,7 +278,7 @@ pub(crate) async fn get_timezone_key_route(
 	State(services): State<conduwuit_router::State<service::Services>>,
 	body: Ruma<get_timezone_key::unstable::Request>,
 ) -> Result<get_timezone_key::unstable::Response> {
-	if !services.globals.user_is_local(&body.user_id) {
+	if !services.globals.user_is_local(&body.user_id) && !services.globals.is_read_only() {
 		// Create and update our local copy of the user
 		if let Ok(response) = services
 			.sending
@@ -338,7 +338,7 @@ pub(crate) async fn get_profile_key_route(
 ) -> Result<get_profile_key::unstable::Response> {
 	let mut profile_key_value: BTreeMap<String, serde_json::Value> = BTreeMap::new();
 
-	if !services.globals.user_is_local(&body.user_id) {
+	if !services.globals.user_is_local(&body.user_id) && !services.globals.is_read_only() {
 		// Create and update our local copy of the user
 		if let Ok(response) = services
 			.sending
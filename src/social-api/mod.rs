@@ -8,7 +8,7 @@
 extern crate conduwuit_core as conduwuit;
 extern crate conduwuit_social_service as service;
 
-pub(crate) use self::router::{Ruma, RumaResponse};
+pub(crate) use self::router::{Admin, Ruma, RumaResponse};
 
 pub use router::SocialApiRouter;
 
@@ -5,14 +5,14 @@
 mod response;
 
 use self::handler::RouterExt;
-pub(super) use self::{args::Args as Ruma, response::RumaResponse};
+pub(super) use self::{args::Args as Ruma, auth::Admin, response::RumaResponse};
 use crate::{client, server};
 use axum::{
 	Router,
 	response::{IntoResponse, Redirect},
-	routing::{any, get, post},
+	routing::{any, get, post, put},
 };
-use conduwuit::{Server, err};
+use conduwuit::{Result, Server, err};
 use conduwuit_router::{Guard, RouterServices, State};
 use http::{Uri, uri};
 use service::Services;
@@ -25,17 +25,21 @@ impl RouterServices for SocialApiRouter {
 	type Services = Arc<Services>;
 	type Guard = Guard<Services>;
 
-	fn build(services: Self::Services) -> (Router, Self::Guard) {
+	fn build(services: Self::Services) -> Result<(Router, Self::Guard)> {
 		let server = services.server.clone();
 		let router = Router::<State<Services>>::new();
 		let (state, guard) = conduwuit_router::state::create(services);
 		let router = build(router, &server).with_state(state);
-		(router, guard)
+		Ok((router, guard))
 	}
 }
 
 pub fn build(router: Router<State<Services>>, server: &Server) -> Router<State<Services>> {
 	let config = &server.config;
+	if config.rocksdb_read_only || config.rocksdb_secondary {
+		return build_replica(router, server);
+	}
+
 	let mut router = router
         .ruma_route(&client::get_timezone_key_route)
         .ruma_route(&client::get_profile_key_route)
@@ -109,6 +113,7 @@ pub fn build(router: Router<State<Services>>, server: &Server) -> Router<State<S
 		.ruma_route(&client::redact_event_route)
 		.ruma_route(&client::report_event_route)
 		.ruma_route(&client::report_room_route)
+		.ruma_route(&client::report_user_route)
 		.ruma_route(&client::create_alias_route)
 		.ruma_route(&client::delete_alias_route)
 		.ruma_route(&client::get_alias_route)
@@ -193,7 +198,10 @@ pub fn build(router: Router<State<Services>>, server: &Server) -> Router<State<S
 		.ruma_route(&client::get_relating_events_route)
 		.ruma_route(&client::get_hierarchy_route)
 		.ruma_route(&client::get_mutual_rooms_route)
-		.ruma_route(&client::get_room_summary)
+		.route(
+			"/_matrix/client/unstable/im.nheko.summary/summary/:room_id_or_alias",
+			get(client::get_room_summary)
+		)
 		.route(
 			"/_matrix/client/unstable/im.nheko.summary/rooms/:room_id_or_alias/summary",
 			get(client::get_room_summary_legacy)
@@ -201,6 +209,13 @@ pub fn build(router: Router<State<Services>>, server: &Server) -> Router<State<S
 		.ruma_route(&client::well_known_support)
 		.ruma_route(&client::well_known_client)
 		.route("/_conduwuit/server_version", get(client::conduwuit_server_version))
+		.route("/_conduwuit/admin/reports", get(client::get_reports_route))
+		.route(
+			"/_conduwuit/admin/users/:user_id/restrictions",
+			get(client::get_user_restrictions_route),
+		)
+		.route("/_conduwuit/admin/users/:user_id/suspend", put(client::set_user_suspended_route))
+		.route("/_conduwuit/admin/users/:user_id/lock", put(client::set_user_locked_route))
 		.ruma_route(&client::room_initial_sync_route)
 		.route("/client/server.json", get(client::syncv3_client_server_json));
 
@@ -290,6 +305,75 @@ pub fn build(router: Router<State<Services>>, server: &Server) -> Router<State<S
 	router
 }
 
+/// Routes served on a read-only database, such as a read replica. Only reads
+/// are mounted, and all else is refused so a reverse proxy can send reads here
+/// and everything else to the primary. Media, profiles and room messages are
+/// served from what is stored: remote media and profiles are not fetched,
+/// thumbnails are not saved, and `/messages` does not backfill.
+fn build_replica(router: Router<State<Services>>, server: &Server) -> Router<State<Services>> {
+	let config = &server.config;
+	let mut router = router
+		.ruma_route(&client::get_supported_versions_route)
+		.ruma_route(&client::get_capabilities_route)
+		.ruma_route(&client::whoami_route)
+		.ruma_route(&client::get_public_rooms_route)
+		.ruma_route(&client::get_public_rooms_filtered_route)
+		.ruma_route(&client::get_room_visibility_route)
+		.ruma_route(&client::get_alias_route)
+		.ruma_route(&client::get_room_aliases_route)
+		.ruma_route(&client::get_room_event_route)
+		.ruma_route(&client::joined_members_route)
+		.ruma_route(&client::get_member_events_route)
+		.ruma_route(&client::get_state_events_route)
+		.ruma_route(&client::get_state_events_for_key_route)
+		.ruma_route(&client::get_threads_route)
+		.ruma_route(&client::get_relating_events_with_rel_type_and_event_type_route)
+		.ruma_route(&client::get_relating_events_with_rel_type_route)
+		.ruma_route(&client::get_relating_events_route)
+		.ruma_route(&client::get_hierarchy_route)
+		.ruma_route(&client::get_message_events_route)
+		.route(
+			"/_matrix/client/unstable/im.nheko.summary/summary/:room_id_or_alias",
+			get(client::get_room_summary),
+		)
+		.ruma_route(&client::get_displayname_route)
+		.ruma_route(&client::get_avatar_url_route)
+		.ruma_route(&client::get_profile_route)
+		.ruma_route(&client::get_profile_key_route)
+		.ruma_route(&client::get_timezone_key_route)
+		.ruma_route(&client::get_media_config_route)
+		.ruma_route(&client::get_content_thumbnail_route)
+		.ruma_route(&client::get_content_route)
+		.ruma_route(&client::get_content_as_filename_route)
+		.ruma_route(&client::well_known_support)
+		.ruma_route(&client::well_known_client)
+		.route("/_conduwuit/server_version", get(client::conduwuit_server_version))
+		.fallback(replica_read_only);
+
+	if config.allow_legacy_media {
+		router = router
+			.ruma_route(&client::get_media_config_legacy_route)
+			.ruma_route(&client::get_content_legacy_route)
+			.ruma_route(&client::get_content_as_filename_legacy_route)
+			.ruma_route(&client::get_content_thumbnail_legacy_route)
+			.route("/_matrix/media/v1/config", get(client::get_media_config_legacy_legacy_route))
+			.route(
+				"/_matrix/media/v1/download/:server_name/:media_id",
+				get(client::get_content_legacy_legacy_route),
+			)
+			.route(
+				"/_matrix/media/v1/download/:server_name/:media_id/:file_name",
+				get(client::get_content_as_filename_legacy_legacy_route),
+			)
+			.route(
+				"/_matrix/media/v1/thumbnail/:server_name/:media_id",
+				get(client::get_content_thumbnail_legacy_legacy_route),
+			);
+	}
+
+	router
+}
+
 async fn redirect_legacy_preview(uri: Uri) -> impl IntoResponse {
 	let path = "/_matrix/client/v1/media/preview_url";
 	let query = uri.query().unwrap_or_default();
@@ -314,3 +398,7 @@ async fn legacy_media_disabled() -> impl IntoResponse {
 async fn federation_disabled() -> impl IntoResponse {
 	err!(Request(Forbidden("Federation is disabled.")))
 }
+
+async fn replica_read_only() -> impl IntoResponse {
+	err!(Request(Forbidden("This server is a read-only replica.")))
+}
@@ -1,20 +1,42 @@
-use axum::RequestPartsExt;
+#[cfg(test)]
+mod tests;
+
+use async_trait::async_trait;
+use axum::{RequestPartsExt, extract::FromRequestParts};
 use axum_extra::{
 	TypedHeader,
 	headers::{Authorization, authorization::Bearer},
 	typed_header::TypedHeaderRejectionReason,
 };
 use conduwuit::{Err, Error, Result, debug_error, err, warn};
+use http::request::Parts;
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, OwnedDeviceId, OwnedServerName, OwnedUserId, UserId,
 	api::{
 		AuthScheme, IncomingRequest, Metadata,
 		client::{
-			directory::get_public_rooms,
+			alias::{create_alias, delete_alias},
+			directory::{get_public_rooms, set_room_visibility},
 			error::ErrorKind,
+			knock::knock_room,
+			media::create_content,
+			membership::{
+				ban_user, invite_user, join_room_by_id, join_room_by_id_or_alias, kick_user,
+				unban_user,
+			},
+			message::send_message_event,
+			presence::set_presence,
 			profile::{
-				get_avatar_url, get_display_name, get_profile, get_profile_key, get_timezone_key,
+				delete_profile_key, delete_timezone_key, get_avatar_url, get_display_name,
+				get_profile, get_profile_key, get_timezone_key, set_avatar_url, set_display_name,
+				set_profile_key, set_timezone_key,
 			},
+			read_marker::set_read_marker,
+			receipt::create_receipt,
+			room::{create_room, upgrade_room},
+			session::{logout, logout_all},
+			state::send_state_event,
+			typing::create_typing_event,
 			voip::get_turn_server_info,
 		},
 		federation::{authentication::XMatrix, openid::get_openid_userinfo},
@@ -23,6 +45,7 @@
 use service::{
 	Services,
 	server_keys::{PubKeyMap, PubKeys},
+	users::{Restrictions, locked_error, suspended_error},
 };
 
 use super::request::Request;
@@ -42,6 +65,10 @@ pub(super) struct Auth {
 	pub(super) appservice_info: Option<RegistrationInfo>,
 }
 
+/// Extractor for the endpoints under `/_conduwuit/admin`, rejecting requests
+/// without the access token of a server admin.
+pub(crate) struct Admin(pub(crate) OwnedUserId);
+
 pub(super) async fn auth(
 	services: &Services,
 	request: &mut Request,
@@ -112,7 +139,7 @@ pub(super) async fn auth(
 		}
 	}
 
-	match (metadata.authentication, token) {
+	let auth = match (metadata.authentication, token) {
 		| (AuthScheme::AccessToken, Token::Appservice(info)) =>
 			Ok(auth_appservice(services, request, info).await?),
 		| (
@@ -192,6 +219,101 @@ pub(super) async fn auth(
 			ErrorKind::UnknownToken { soft_logout: false },
 			"Unknown access token.",
 		)),
+	}?;
+
+	// Covers users asserted by an appservice as well as those using their own
+	// access token.
+	if let Some(user_id) = &auth.sender_user {
+		check_restrictions(services, user_id, metadata).await?;
+	}
+
+	Ok(auth)
+}
+
+/// Locked users may only log out. Suspended users may read and manage their
+/// own account, but not make changes other users would see.
+async fn check_restrictions(
+	services: &Services,
+	user_id: &UserId,
+	metadata: &Metadata,
+) -> Result {
+	let restrictions = services.users.restrictions(user_id).await;
+
+	enforce_restrictions(&restrictions, metadata)
+}
+
+fn enforce_restrictions(restrictions: &Restrictions, metadata: &Metadata) -> Result {
+	if restrictions.locked.is_some()
+		&& !matches!(
+			metadata,
+			&logout::v3::Request::METADATA | &logout_all::v3::Request::METADATA
+		) {
+		return Err(locked_error());
+	}
+
+	if restrictions.suspended.is_some() && is_suspended_write(metadata) {
+		return Err(suspended_error());
+	}
+
+	Ok(())
+}
+
+fn is_suspended_write(metadata: &Metadata) -> bool {
+	matches!(
+		metadata,
+		&send_message_event::v3::Request::METADATA
+			| &send_state_event::v3::Request::METADATA
+			| &create_typing_event::v3::Request::METADATA
+			| &create_receipt::v3::Request::METADATA
+			| &set_read_marker::v3::Request::METADATA
+			| &set_presence::v3::Request::METADATA
+			| &create_room::v3::Request::METADATA
+			| &upgrade_room::v3::Request::METADATA
+			| &join_room_by_id::v3::Request::METADATA
+			| &join_room_by_id_or_alias::v3::Request::METADATA
+			| &knock_room::v3::Request::METADATA
+			// also invites by third-party ID, which share the endpoint
+			| &invite_user::v3::Request::METADATA
+			| &kick_user::v3::Request::METADATA
+			| &ban_user::v3::Request::METADATA
+			| &unban_user::v3::Request::METADATA
+			| &create_alias::v3::Request::METADATA
+			| &delete_alias::v3::Request::METADATA
+			| &set_room_visibility::v3::Request::METADATA
+			| &create_content::v3::Request::METADATA
+			| &set_display_name::v3::Request::METADATA
+			| &set_avatar_url::v3::Request::METADATA
+			| &set_profile_key::unstable::Request::METADATA
+			| &delete_profile_key::unstable::Request::METADATA
+			| &set_timezone_key::unstable::Request::METADATA
+			| &delete_timezone_key::unstable::Request::METADATA
+	)
+}
+
+#[async_trait]
+impl FromRequestParts<conduwuit_router::State<Services>> for Admin {
+	type Rejection = Error;
+
+	async fn from_request_parts(
+		parts: &mut Parts,
+		services: &conduwuit_router::State<Services>,
+	) -> Result<Self, Self::Rejection> {
+		let bearer: Option<TypedHeader<Authorization<Bearer>>> = parts.extract().await?;
+		let Some(TypedHeader(Authorization(bearer))) = bearer else {
+			return Err!(Request(MissingToken("Missing access token.")));
+		};
+
+		let (sender_user, _) = services
+			.users
+			.find_from_token(bearer.token())
+			.await
+			.map_err(|_| err!(Request(UnknownToken("Unknown access token."))))?;
+
+		if !services.users.is_admin(&sender_user).await {
+			return Err!(Request(Forbidden("Only server admins can use this endpoint.")));
+		}
+
+		Ok(Self(sender_user))
 	}
 }
 
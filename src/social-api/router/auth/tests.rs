@@ -0,0 +1,66 @@
+use conduwuit::http::StatusCode;
+use ruma::{
+	api::{
+		IncomingRequest,
+		client::{
+			membership::invite_user, message::send_message_event, presence::set_presence,
+			read_marker::set_read_marker, receipt::create_receipt, session::logout,
+			sync::sync_events,
+		},
+	},
+	owned_user_id,
+};
+use service::users::{Restriction, Restrictions};
+
+use super::enforce_restrictions;
+
+fn restriction() -> Option<Restriction> {
+	Some(Restriction {
+		reason: None,
+		by: owned_user_id!("@admin:example.com"),
+		ts: 0,
+	})
+}
+
+fn suspended() -> Restrictions { Restrictions { suspended: restriction(), locked: None } }
+
+fn locked() -> Restrictions { Restrictions { suspended: None, locked: restriction() } }
+
+#[test]
+fn unrestricted_allowed() {
+	let restrictions = Restrictions::default();
+	assert!(
+		enforce_restrictions(&restrictions, &send_message_event::v3::Request::METADATA).is_ok()
+	);
+	assert!(enforce_restrictions(&restrictions, &sync_events::v3::Request::METADATA).is_ok());
+}
+
+#[test]
+fn suspended_reads_allowed() {
+	assert!(enforce_restrictions(&suspended(), &sync_events::v3::Request::METADATA).is_ok());
+	assert!(enforce_restrictions(&suspended(), &logout::v3::Request::METADATA).is_ok());
+}
+
+#[test]
+fn suspended_writes_refused() {
+	for metadata in [
+		&send_message_event::v3::Request::METADATA,
+		&invite_user::v3::Request::METADATA,
+		&set_presence::v3::Request::METADATA,
+		&create_receipt::v3::Request::METADATA,
+		&set_read_marker::v3::Request::METADATA,
+	] {
+		let error = enforce_restrictions(&suspended(), metadata).unwrap_err();
+		assert_eq!(error.kind().to_string(), "M_USER_SUSPENDED");
+		assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+	}
+}
+
+#[test]
+fn locked_only_logs_out() {
+	assert!(enforce_restrictions(&locked(), &logout::v3::Request::METADATA).is_ok());
+
+	let error = enforce_restrictions(&locked(), &sync_events::v3::Request::METADATA).unwrap_err();
+	assert_eq!(error.kind().to_string(), "M_USER_LOCKED");
+	assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+}
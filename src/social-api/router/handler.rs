@@ -5,6 +5,7 @@
 	routing::{MethodFilter, on},
 };
 use conduwuit::Result;
+use conduwuit_database::Priority;
 use futures::{Future, TryFutureExt};
 use http::Method;
 use ruma::api::IncomingRequest;
@@ -51,7 +52,8 @@ fn add_routes(&'static self, router: Router<State<Services>>) -> Router<State<Se
 			}
 
 			fn add_route(&'static self, router: Router<State<Services>>, path: &str) -> Router<State<Services>> {
-				let action = |$($tx,)* req| self($($tx,)* req).map_ok(RumaResponse);
+				let priority = path_priority(path);
+				let action = move |$($tx,)* req| priority.scope(self($($tx,)* req)).map_ok(RumaResponse);
 				let method = method_to_filter(&Req::METADATA.method);
 				router.route(path, on(method, action))
 			}
@@ -64,6 +66,16 @@ fn add_route(&'static self, router: Router<State<Services>>, path: &str) -> Rout
 ruma_handler!(T1, T2, T3);
 ruma_handler!(T1, T2, T3, T4);
 
+/// Database requests made while serving federation are scheduled beneath those
+/// made while serving clients.
+fn path_priority(path: &str) -> Priority {
+	if path.starts_with("/_matrix/federation/") || path.starts_with("/_matrix/key/") {
+		Priority::Federation
+	} else {
+		Priority::Interactive
+	}
+}
+
 const fn method_to_filter(method: &Method) -> MethodFilter {
 	match *method {
 		| Method::DELETE => MethodFilter::DELETE,
@@ -96,6 +96,8 @@ pub(crate) async fn create_invite_route(
 		.try_into()
 		.map_err(|e| err!(Request(InvalidParam("Invalid sender property: {e}"))))?;
 
+	services.policy_lists.check_user(sender)?;
+
 	if services.rooms.metadata.is_banned(&body.room_id).await
 		&& !services.users.is_admin(&invited_user).await
 	{
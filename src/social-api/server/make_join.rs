@@ -34,6 +34,8 @@ pub(crate) async fn create_join_event_template_route(
 		return Err!(Request(BadJson("Not allowed to join on behalf of another server/user.")));
 	}
 
+	services.policy_lists.check_user(&body.user_id)?;
+
 	// ACL check origin server
 	services
 		.rooms
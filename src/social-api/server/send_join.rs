@@ -124,6 +124,8 @@ async fn create_join_event(
 		return Err!(Request(Forbidden("Not allowed to join on behalf of another server.")));
 	}
 
+	services.policy_lists.check_user(&sender)?;
+
 	let state_key: OwnedUserId = serde_json::from_value(
 		value
 			.get("state_key")
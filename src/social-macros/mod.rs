@@ -5,6 +5,7 @@
 mod implement;
 mod refutable;
 mod rustc;
+mod table;
 mod utils;
 
 use proc_macro::TokenStream;
@@ -54,6 +55,12 @@ pub fn config_example_generator(args: TokenStream, input: TokenStream) -> TokenS
 	attribute_macro::<ItemStruct, _>(args, input, config::example_generator)
 }
 
+#[proc_macro_derive(Table, attributes(table))]
+pub fn table(input: TokenStream) -> TokenStream {
+	let item = parse_macro_input!(input as ItemStruct);
+	table::table(item).unwrap_or_else(|e| e.to_compile_error().into())
+}
+
 fn attribute_macro<I, F>(args: TokenStream, input: TokenStream, func: F) -> TokenStream
 where
 	F: Fn(I, &[Meta]) -> Result<TokenStream>,
@@ -0,0 +1,147 @@
+use proc_macro::TokenStream;
+use proc_macro2::Span;
+use quote::{ToTokens, format_ident, quote};
+use syn::{
+	Error, Fields, Ident, ItemStruct, Meta, Type, parse::Parser, punctuated::Punctuated,
+	spanned::Spanned,
+};
+
+use crate::{Result, utils::get_simple_settings};
+
+/// Declare a typed table whose key is the derived struct. Generates a
+/// marker type describing the table, the key's serialization as a tuple of its
+/// fields in order, and a prefix constructor for each proper leading subset of
+/// the fields.
+pub(super) fn table(input: ItemStruct) -> Result<TokenStream> {
+	let args = table_args(&input)?;
+	let settings = get_simple_settings(&args);
+	let map = settings
+		.get("map")
+		.ok_or_else(|| Error::new(input.span(), "missing required 'map' table argument"))?;
+
+	let name = settings
+		.get("name")
+		.ok_or_else(|| Error::new(input.span(), "missing required 'name' table argument"))?;
+
+	let value: Type = syn::parse_str(settings.get("value").map_or("()", String::as_str))?;
+
+	let Fields::Named(fields) = &input.fields else {
+		return Err(Error::new(input.span(), "table keys must be structs with named fields"));
+	};
+
+	let fields: Vec<(&Ident, &Type)> = fields
+		.named
+		.iter()
+		.filter_map(|field| Some((field.ident.as_ref()?, &field.ty)))
+		.collect();
+
+	if fields.is_empty() {
+		return Err(Error::new(input.span(), "table keys must have at least one field"));
+	}
+
+	let columns: Vec<(String, String)> = fields
+		.iter()
+		.map(|(ident, ty)| (ident.to_string(), type_string(ty)))
+		.collect();
+
+	let layout = layout_id(&columns, &type_string(&value));
+	let schema = columns
+		.iter()
+		.map(|(name, ty)| format!("{name}: {ty}"))
+		.collect::<Vec<_>>()
+		.join(" | ");
+
+	let doc = format!(
+		"Table over the `{map}` map, keyed by [`{}`].\n\nLayout: `{schema} => {}`",
+		input.ident,
+		type_string(&value)
+	);
+
+	let vis = &input.vis;
+	let ident = &input.ident;
+	let marker = Ident::new(name, Span::call_site());
+	let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+	let column_names = columns.iter().map(|(name, _)| name);
+	let column_types = columns.iter().map(|(_, ty)| ty);
+	let field_names = fields.iter().map(|(ident, _)| ident);
+	let prefixes = (1..fields.len()).map(|len| {
+		let (last, _) = fields[len.saturating_sub(1)];
+		let func = format_ident!("prefix_{last}");
+		let names: Vec<_> = fields[..len].iter().map(|(ident, _)| ident).collect();
+		let types = fields[..len].iter().map(|(_, ty)| ty);
+		let doc = format!("Prefix of keys in `{map}` up to and including `{last}`.");
+		quote! {
+			#[doc = #doc]
+			#vis fn #func(#( #names: #types ),*) -> ::conduwuit_core::Result<::conduwuit_database::Prefix<#marker>> {
+				::conduwuit_database::Prefix::new((#( #names, )* ::conduwuit_database::Interfix))
+			}
+		}
+	});
+
+	let output = quote! {
+		#[doc = #doc]
+		#[derive(Debug)]
+		#vis struct #marker;
+
+		impl ::conduwuit_database::Table for #marker {
+			const MAP: &'static str = #map;
+			const COLUMNS: &'static [::conduwuit_database::table::Column] = &[
+				#( ::conduwuit_database::table::Column { name: #column_names, ty: #column_types }, )*
+			];
+			const LAYOUT: u64 = #layout;
+
+			type Value = #value;
+		}
+
+		impl #impl_generics ::conduwuit_database::TableKey for #ident #ty_generics #where_clause {
+			type Table = #marker;
+
+			fn serialize(&self) -> ::conduwuit_core::Result<::conduwuit_database::keyval::KeyBuf> {
+				::conduwuit_database::serialize_key((#( &self.#field_names, )*))
+			}
+		}
+
+		impl #impl_generics #ident #ty_generics #where_clause {
+			#( #prefixes )*
+		}
+	};
+
+	Ok(output.into())
+}
+
+/// Arguments of the `#[table(...)]` attribute, which is consumed here.
+fn table_args(input: &ItemStruct) -> Result<Vec<Meta>> {
+	let attr = input
+		.attrs
+		.iter()
+		.find(|attr| attr.path().is_ident("table"))
+		.ok_or_else(|| Error::new(input.span(), "missing #[table(...)] attribute"))?;
+
+	let list = attr.meta.require_list()?;
+	Punctuated::<Meta, syn::Token![,]>::parse_terminated
+		.parse2(list.tokens.clone())
+		.map(|args| args.into_iter().collect())
+}
+
+/// Normalized text of a type, independent of the token spacing.
+fn type_string(ty: &Type) -> String {
+	ty.to_token_stream()
+		.to_string()
+		.split_whitespace()
+		.collect::<Vec<_>>()
+		.join(" ")
+}
+
+/// FNV-1a over the component names and types and the value type. Stable across
+/// builds and compiler versions so it can be persisted.
+fn layout_id(columns: &[(String, String)], value: &str) -> u64 {
+	const OFFSET: u64 = 0xCBF2_9CE4_8422_2325;
+	const PRIME: u64 = 0x0100_0000_01B3;
+
+	columns
+		.iter()
+		.flat_map(|(name, ty)| [name.as_str(), ":", ty.as_str(), ";"])
+		.chain(["=>", value])
+		.flat_map(str::bytes)
+		.fold(OFFSET, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(PRIME))
+}
@@ -4,7 +4,7 @@
 	Err, Result, err, implement,
 	utils::{ReadyExt, result::LogErr, stream::TryIgnore},
 };
-use database::{Deserialized, Handle, Ignore, Json, Map};
+use database::{Deserialized, Handle, Ignore, Interfix, Json, Map};
 use futures::{Stream, StreamExt, TryFutureExt};
 use ruma::{
 	RoomId, UserId,
@@ -157,3 +157,16 @@ pub fn changes_since<'a>(
 		})
 		.ignore_err()
 }
+
+/// Remove the account data every user has in a room.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn purge_room(&self, room_id: &RoomId) {
+	let prefix = (room_id, Interfix);
+	for map in [&self.db.roomuserdataid_accountdata, &self.db.roomusertype_roomuserdataid] {
+		map.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| map.remove(key))
+			.await;
+	}
+}
@@ -11,7 +11,7 @@
 
 use async_trait::async_trait;
 use conduwuit::{
-	Error, PduEvent, Result, Server, debug, err, error, error::default_log, pdu::PduBuilder,
+	Error, PduEvent, Result, Server, debug, err, error, error::default_log, pdu::PduBuilder, warn,
 };
 pub use create::create_admin_room;
 use futures::{FutureExt, TryFutureExt};
@@ -77,6 +77,19 @@ pub struct CommandInput {
 /// Maximum number of commands which can be queued for dispatch.
 const COMMAND_QUEUE_LIMIT: usize = 512;
 
+/// Command processor and tab-completer registered by the admin crate, which
+/// depends on this one. They are installed into the service while it runs.
+static HOOKS: StdRwLock<Option<(Processor, Completer)>> = StdRwLock::new(None);
+
+/// Register the command processor and tab-completer installed by every admin
+/// service started from now on.
+pub fn register(processor: Processor, completer: Completer) {
+	HOOKS
+		.write()
+		.expect("locked for writing")
+		.replace((processor, completer));
+}
+
 #[async_trait]
 impl ServiceTrait for Service {
 	fn build(args: Args<'_>) -> Result<Arc<Self>> {
@@ -179,6 +192,29 @@ pub async fn command_in_place(
 			.await
 	}
 
+	/// Install the registered command processor; called by Services before the
+	/// workers start, so commands executed at startup are processed.
+	pub async fn init(&self) {
+		let Some((processor, completer)) = *HOOKS.read().expect("locked for reading") else {
+			warn!("No admin command processor is registered; admin commands are unavailable.");
+			return;
+		};
+
+		_ = self
+			.complete
+			.write()
+			.expect("locked for writing")
+			.insert(completer);
+		_ = self.handle.write().await.insert(processor);
+	}
+
+	/// Uninstall the command processor; called by Services after the workers
+	/// stop.
+	pub async fn fini(&self) {
+		_ = self.handle.write().await.take();
+		_ = self.complete.write().expect("locked for writing").take();
+	}
+
 	/// Invokes the tab-completer to complete the command. When unavailable,
 	/// None is returned.
 	pub fn complete_command(&self, command: &str) -> Option<String> {
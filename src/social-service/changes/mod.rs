@@ -0,0 +1,212 @@
+use std::{
+	fs::Permissions,
+	os::unix::fs::{MetadataExt, PermissionsExt},
+	path::{Path, PathBuf},
+	pin::pin,
+	sync::Arc,
+	time::Duration,
+};
+
+use async_trait::async_trait;
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use conduwuit::{Err, Result, Server, debug, info, warn};
+use database::{Change, ChangeOp, Database};
+use futures::{Stream, StreamExt};
+use serde_json::json;
+use service_core::{Args, Service as ServiceTrait};
+use tokio::{
+	fs::{self, OpenOptions},
+	io::AsyncWriteExt,
+	net::{UnixListener, UnixStream},
+	sync::Notify,
+	task::JoinSet,
+	time::timeout,
+};
+
+/// Time allowed for a socket client to accept a record before it is
+/// disconnected.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Mode of the file and socket sinks; only the server's own user may read
+/// records, which include values of maps encrypted at rest.
+const SINK_MODE: u32 = 0o600;
+
+/// Delivers change-data capture records from the database to the configured
+/// file and socket sinks as JSON lines. The file and each socket client
+/// consume records independently, so one falling behind does not hold back
+/// the others.
+pub struct Service {
+	file: Option<PathBuf>,
+	socket: Option<PathBuf>,
+	interrupt: Notify,
+	server: Arc<Server>,
+	db: Arc<Database>,
+}
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+
+		Ok(Arc::new(Self {
+			file: config.database_cdc_file.clone(),
+			socket: config.database_cdc_socket.clone(),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			db: args.db.clone(),
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "changes", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		if self.file.is_none() && self.socket.is_none() {
+			return Ok(());
+		}
+
+		if let Err(e) = self.db.changes() {
+			warn!("Change-data capture sinks are configured but capture is disabled: {e}");
+			return Ok(());
+		}
+
+		let (file, socket) = futures::join!(self.file_sink(), self.socket_sink());
+
+		file.and(socket)
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Append records to the configured file until shutdown.
+	async fn file_sink(&self) -> Result {
+		let Some(path) = self.file.as_deref() else {
+			return Ok(());
+		};
+
+		let mut file = OpenOptions::new()
+			.create(true)
+			.append(true)
+			.mode(SINK_MODE)
+			.open(path)
+			.await?;
+
+		// The mode above only applies when the file is created.
+		fs::set_permissions(path, Permissions::from_mode(SINK_MODE)).await?;
+
+		let mut changes = pin!(self.db.changes()?);
+		while self.server.running() {
+			let line = tokio::select! {
+				() = self.interrupt.notified() => break,
+				record = changes.next() => match record {
+					| Some(record) => encode(record) + "\n",
+					| None => break,
+				},
+			};
+
+			file.write_all(line.as_bytes()).await?;
+		}
+
+		file.sync_data().await?;
+
+		Ok(())
+	}
+
+	/// Serve records to clients of the configured socket until shutdown. Only
+	/// clients running as the server's own user are accepted.
+	async fn socket_sink(&self) -> Result {
+		let Some(path) = self.socket.as_deref() else {
+			return Ok(());
+		};
+
+		let listener = bind(path).await?;
+		let owner = fs::metadata(path).await?.uid();
+		let mut clients = JoinSet::new();
+		while self.server.running() {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				Some(_) = clients.join_next() => continue,
+				accepted = listener.accept() => match accepted {
+					| Ok((client, _)) if is_owner(&client, owner) => {
+						debug!(clients = clients.len(), "Change-data capture client connected");
+						clients.spawn(serve_client(client, self.db.changes()?));
+					},
+					| Ok(_) => warn!("Refused change-data capture client of another user."),
+					| Err(e) => debug!("Failed to accept change-data capture client: {e}"),
+				},
+			}
+		}
+
+		clients.shutdown().await;
+		drop(listener);
+		fs::remove_file(path).await.ok();
+
+		Ok(())
+	}
+}
+
+async fn bind(path: &Path) -> Result<UnixListener> {
+	if path.exists() {
+		warn!("Removing existing change-data capture socket {path:?} (unclean shutdown?)...");
+		fs::remove_file(path).await?;
+	}
+
+	let listener = match UnixListener::bind(path) {
+		| Ok(listener) => listener,
+		| Err(e) => return Err!("Failed to bind change-data capture socket {path:?}: {e}"),
+	};
+
+	fs::set_permissions(path, Permissions::from_mode(SINK_MODE)).await?;
+	info!("Serving change-data capture records at {path:?}");
+
+	Ok(listener)
+}
+
+/// Whether the peer of a socket connection runs as the given user.
+fn is_owner(client: &UnixStream, owner: u32) -> bool {
+	client.peer_cred().is_ok_and(|cred| cred.uid() == owner)
+}
+
+/// Write records to a client until it disconnects or stops reading.
+async fn serve_client<S>(mut client: UnixStream, changes: S)
+where
+	S: Stream<Item = Result<Arc<Change>>> + Send,
+{
+	let mut changes = pin!(changes);
+	while let Some(record) = changes.next().await {
+		let line = encode(record) + "\n";
+		match timeout(CLIENT_TIMEOUT, client.write_all(line.as_bytes())).await {
+			| Ok(Ok(())) => continue,
+			| Ok(Err(e)) => debug!("Change-data capture client disconnected: {e}"),
+			| Err(_) => warn!("Disconnecting change-data capture client which stopped reading."),
+		}
+
+		break;
+	}
+}
+
+/// Encode a record, or the error of a consumer which fell behind.
+fn encode(record: Result<Arc<Change>>) -> String {
+	let change = match record {
+		| Ok(change) => change,
+		| Err(e) => {
+			warn!("{e}");
+			return json!({ "error": e.to_string() }).to_string();
+		},
+	};
+
+	let op = match change.op {
+		| ChangeOp::Put => "put",
+		| ChangeOp::Delete => "delete",
+	};
+
+	json!({
+		"sequence": change.sequence,
+		"map": change.map,
+		"op": op,
+		"key": STANDARD.encode(&change.key),
+		"val": change.val.as_deref().map(|val| STANDARD.encode(val)),
+	})
+	.to_string()
+}
@@ -0,0 +1,77 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use conduwuit::{Result, Server, debug, warn};
+use database::{Database, Priority};
+use service_core::{Args, Service as ServiceTrait};
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval},
+};
+
+/// Periodically removes expired records from database maps which declare an
+/// expiry.
+pub struct Service {
+	interval: Duration,
+	interrupt: Notify,
+	server: Arc<Server>,
+	db: Arc<Database>,
+}
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+
+		Ok(Arc::new(Self {
+			interval: Duration::from_secs(config.database_expiry_interval),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			db: args.db.clone(),
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "expiry", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		if self.interval.is_zero() || self.db.is_read_only() {
+			return Ok(());
+		}
+
+		let mut i = interval(self.interval);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		while self.server.running() {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			if let Err(e) = Priority::Background.scope(self.sweep()).await {
+				warn!(%e, "Failed to remove expired records");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Remove expired records from every expiring map now. Returns the number
+	/// of records removed.
+	#[tracing::instrument(skip_all, level = "debug")]
+	pub async fn sweep(&self) -> Result<usize> {
+		let db = self.db.clone();
+		let count = self
+			.server
+			.runtime()
+			.spawn_blocking(move || db.expire_blocking())
+			.await??;
+
+		debug!(count, "Swept expired records");
+
+		Ok(count)
+	}
+}
@@ -3,7 +3,7 @@
 use bytes::Bytes;
 use conduwuit::{
 	Err, Error, Result, debug, debug::INFO_SPAN_LEVEL, debug_error, debug_warn, err,
-	error::inspect_debug_log, implement, trace, utils::string::EMPTY,
+	error::inspect_debug_log, implement, log::propagate, trace, utils::string::EMPTY,
 };
 use http::{HeaderValue, header::AUTHORIZATION};
 use ipaddress::IPAddress;
@@ -105,6 +105,7 @@ async fn perform<T>(
 #[implement(super::Service)]
 fn prepare(&self, dest: &ServerName, mut request: http::Request<Vec<u8>>) -> Result<Request> {
 	self.sign_request(&mut request, dest);
+	propagate::inject(request.headers_mut());
 
 	let request = Request::try_from(request)?;
 	self.validate_url(request.url())?;
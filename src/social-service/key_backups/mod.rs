@@ -4,7 +4,7 @@
 	Err, Result, err, implement,
 	utils::stream::{ReadyExt, TryIgnore},
 };
-use database::{Deserialized, Ignore, Interfix, Json, Map};
+use database::{Database, Deserialized, Ignore, Interfix, Json, Map};
 use futures::StreamExt;
 use ruma::{
 	OwnedRoomId, RoomId, UserId,
@@ -21,6 +21,7 @@ pub struct Service {
 }
 
 struct Data {
+	db: Arc<Database>,
 	backupid_algorithm: Arc<Map>,
 	backupid_etag: Arc<Map>,
 	backupkeyid_backup: Arc<Map>,
@@ -34,6 +35,7 @@ impl ServiceTrait for Service {
 	fn build(args: Args<'_>) -> Result<Arc<Self>> {
 		Ok(Arc::new(Self {
 			db: Data {
+				db: args.db.clone(),
 				backupid_algorithm: args.db["backupid_algorithm"].clone(),
 				backupid_etag: args.db["backupid_etag"].clone(),
 				backupkeyid_backup: args.db["backupkeyid_backup"].clone(),
@@ -192,6 +194,32 @@ pub async fn get_etag(&self, user_id: &UserId, version: &str) -> String {
 		.expect("Backup has no etag.")
 }
 
+/// The number of keys in a backup and its etag, read together so the etag
+/// describes exactly the keys counted.
+#[implement(Service)]
+pub async fn count_keys_and_etag(&self, user_id: &UserId, version: &str) -> (usize, String) {
+	let snapshot = self.db.db.snapshot();
+	let key = (user_id, version);
+	let count = self
+		.db
+		.backupkeyid_backup
+		.keys_prefix_raw_at(&snapshot, &key)
+		.count()
+		.await;
+
+	let etag = self
+		.db
+		.backupid_etag
+		.qry_at(&snapshot, &key)
+		.await
+		.deserialized::<u64>()
+		.as_ref()
+		.map(ToString::to_string)
+		.expect("Backup has no etag.");
+
+	(count, etag)
+}
+
 #[implement(Service)]
 pub async fn get_all(
 	&self,
@@ -4,18 +4,29 @@
 	Err, Result, debug, debug_info, err,
 	utils::{ReadyExt, str_from_bytes, stream::TryIgnore, string_from_bytes},
 };
-use database::{Database, Interfix, Map};
+use database::{Database, Interfix, Map, Table, Typed};
 use futures::StreamExt;
 use ruma::{Mxc, OwnedMxcUri, UserId, http_headers::ContentDisposition};
 
 use super::{preview::UrlPreviewData, thumbnail::Dim};
 
 pub(crate) struct Data {
-	mediaid_file: Arc<Map>,
+	mediaid_file: Typed<MediaFile>,
 	mediaid_user: Arc<Map>,
 	url_previews: Arc<Map>,
 }
 
+/// Stored file, keyed by its MXC, thumbnail dimensions (zero for the
+/// original) and the headers it is served with. Values are empty.
+#[derive(Table)]
+#[table(map = "mediaid_file", name = "MediaFile")]
+pub(crate) struct MediaFileKey<'a> {
+	pub(super) mxc: &'a Mxc<'a>,
+	pub(super) dim: &'a [u32],
+	pub(super) content_disposition: Option<&'a ContentDisposition>,
+	pub(super) content_type: Option<&'a str>,
+}
+
 #[derive(Debug)]
 pub(super) struct Metadata {
 	pub(super) content_disposition: Option<ContentDisposition>,
@@ -26,7 +37,7 @@ pub(super) struct Metadata {
 impl Data {
 	pub(super) fn new(db: &Arc<Database>) -> Self {
 		Self {
-			mediaid_file: db["mediaid_file"].clone(),
+			mediaid_file: Typed::open(db).expect("mediaid_file map exists"),
 			mediaid_user: db["mediaid_user"].clone(),
 			url_previews: db["url_previews"].clone(),
 		}
@@ -41,9 +52,13 @@ pub(super) fn create_file_metadata(
 		content_type: Option<&str>,
 	) -> Result<Vec<u8>> {
 		let dim: &[u32] = &[dim.width, dim.height];
-		let key = (mxc, dim, content_disposition, content_type);
-		let key = database::serialize_key(key)?;
-		self.mediaid_file.insert(&key, []);
+		let key = self.mediaid_file.insert(&MediaFileKey {
+			mxc,
+			dim,
+			content_disposition,
+			content_type,
+		})?;
+
 		if let Some(user) = user {
 			let key = (mxc, user);
 			self.mediaid_user.put_raw(key, user);
@@ -55,13 +70,15 @@ pub(super) fn create_file_metadata(
 	pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 		debug!("MXC URI: {mxc}");
 
-		let prefix = (mxc, Interfix);
+		let file_prefix = MediaFileKey::prefix_mxc(mxc).expect("failed to serialize query key");
+
 		self.mediaid_file
-			.keys_prefix_raw(&prefix)
+			.keys_prefix(&file_prefix)
 			.ignore_err()
-			.ready_for_each(|key| self.mediaid_file.remove(key))
+			.ready_for_each(|key| self.mediaid_file.map().remove(key))
 			.await;
 
+		let prefix = (mxc, Interfix);
 		self.mediaid_user
 			.stream_prefix_raw(&prefix)
 			.ignore_err()
@@ -83,10 +100,10 @@ pub(super) async fn delete_file_mxc(&self, mxc: &Mxc<'_>) {
 	pub(super) async fn search_mxc_metadata_prefix(&self, mxc: &Mxc<'_>) -> Result<Vec<Vec<u8>>> {
 		debug!("MXC URI: {mxc}");
 
-		let prefix = (mxc, Interfix);
+		let prefix = MediaFileKey::prefix_mxc(mxc)?;
 		let keys: Vec<Vec<u8>> = self
 			.mediaid_file
-			.keys_prefix_raw(&prefix)
+			.keys_prefix(&prefix)
 			.ignore_err()
 			.map(<[u8]>::to_vec)
 			.collect()
@@ -107,11 +124,11 @@ pub(super) async fn search_file_metadata(
 		dim: &Dim,
 	) -> Result<Metadata> {
 		let dim: &[u32] = &[dim.width, dim.height];
-		let prefix = (mxc, dim, Interfix);
+		let prefix = MediaFileKey::prefix_dim(mxc, dim)?;
 
 		let key = self
 			.mediaid_file
-			.keys_prefix_raw(&prefix)
+			.keys_prefix(&prefix)
 			.ignore_err()
 			.map(ToOwned::to_owned)
 			.next()
@@ -157,6 +174,7 @@ pub(super) async fn get_all_user_mxcs(&self, user_id: &UserId) -> Vec<OwnedMxcUr
 	/// associated with it such as width, height, content-type, etc)
 	pub(crate) async fn get_all_media_keys(&self) -> Vec<Vec<u8>> {
 		self.mediaid_file
+			.map()
 			.raw_keys()
 			.ignore_err()
 			.map(<[u8]>::to_vec)
@@ -20,9 +20,13 @@
 	io::{AsyncReadExt, AsyncWriteExt, BufReader},
 };
 
+pub(crate) use self::data::MediaFile;
 use self::data::{Data, Metadata};
 pub use self::thumbnail::Dim;
-use crate::{client, globals, sending};
+use crate::{
+	client, globals, sending,
+	spam_checker::{self, Verdict},
+};
 use service_core::{Dep, Args, Service as ServiceTrait};
 
 #[derive(Debug)]
@@ -43,6 +47,7 @@ struct Services {
 	client: Dep<client::Service>,
 	globals: Dep<globals::Service>,
 	sending: Dep<sending::Service>,
+	spam_checker: Dep<spam_checker::Service>,
 }
 
 /// generated MXC ID (`media-id`) length
@@ -65,6 +70,7 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 				client: args.depend::<client::Service>("client"),
 				globals: args.depend::<globals::Service>("globals"),
 				sending: args.depend::<sending::Service>("sending"),
+				spam_checker: args.depend::<spam_checker::Service>("spam_checker"),
 			},
 		}))
 	}
@@ -88,6 +94,28 @@ pub async fn create(
 		content_type: Option<&str>,
 		file: &[u8],
 	) -> Result<()> {
+		// Only uploads are checked; remote media is cached under its own server.
+		if let Some(user) = user.filter(|_| self.services.globals.server_is_ours(mxc.server_name))
+		{
+			match self
+				.services
+				.spam_checker
+				.check_media_upload(user, content_type, file)
+				.await
+			{
+				| Verdict::Allow => {},
+				| Verdict::Deny(e) => return Err(e),
+				// A dropped upload would leave the uploader with a URI that never
+				// resolves, so a soft fail is refused like a denial.
+				| Verdict::SoftFail => {
+					debug_warn!(?mxc, %user, "Media upload soft failed by spam checker");
+					return Err!(Request(Forbidden(
+						"This action was refused by the spam checker."
+					)));
+				},
+			}
+		}
+
 		// Width, Height = 0 if it's not a thumbnail
 		let key = self.db.create_file_metadata(
 			mxc,
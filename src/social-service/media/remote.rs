@@ -421,6 +421,12 @@ pub async fn fetch_remote_content_legacy(
 
 #[implement(super::Service)]
 fn check_fetch_authorized(&self, mxc: &Mxc<'_>) -> Result<()> {
+	if self.services.globals.is_read_only() {
+		// fetched media is stored, which a read-only database cannot do; only what
+		// is already stored is served.
+		return Err!(Request(NotFound("Media not found.")));
+	}
+
 	if self
 		.services
 		.server
@@ -103,3 +103,54 @@ fn get_url_preview(&self, _url: &str) -> Option<UrlPreviewData> { todo!() }
 		r.to_str().unwrap().len()
 	);
 }
+
+/// The typed `mediaid_file` table must keep the byte layout of the tuple keys
+/// it replaced, so existing databases are read without a migration.
+#[test]
+fn media_file_key_layout() {
+	use database::{Interfix, TableKey, serialize_key};
+	use ruma::{
+		Mxc,
+		http_headers::{ContentDisposition, ContentDispositionType},
+	};
+
+	use super::data::MediaFileKey;
+
+	let mxc: Mxc<'_> = "mxc://example.com/abc".try_into().unwrap();
+	let dim: &[u32] = &[800, 600];
+	let disposition = ContentDisposition::new(ContentDispositionType::Inline)
+		.with_filename(Some("cat.png".into()));
+
+	for content_disposition in [None, Some(&disposition)] {
+		for content_type in [None, Some("image/png")] {
+			let typed = MediaFileKey {
+				mxc: &mxc,
+				dim,
+				content_disposition,
+				content_type,
+			}
+			.serialize()
+			.unwrap();
+
+			let tuple = serialize_key((&mxc, dim, content_disposition, content_type)).unwrap();
+			assert_eq!(typed, tuple, "{content_disposition:?} {content_type:?}");
+		}
+	}
+
+	let prefix = MediaFileKey::prefix_mxc(&mxc).unwrap();
+	assert_eq!(prefix.as_ref(), &*serialize_key((&mxc, Interfix)).unwrap());
+
+	let prefix = MediaFileKey::prefix_dim(&mxc, dim).unwrap();
+	assert_eq!(prefix.as_ref(), &*serialize_key((&mxc, dim, Interfix)).unwrap());
+
+	let key = MediaFileKey {
+		mxc: &mxc,
+		dim,
+		content_disposition: None,
+		content_type: None,
+	}
+	.serialize()
+	.unwrap();
+
+	assert!(key.starts_with(prefix.as_ref()), "keys are found by their prefixes");
+}
@@ -122,6 +122,10 @@ async fn get_thumbnail_generate(
 		.write_to(&mut cursor, image::ImageFormat::Png)
 		.map_err(|error| err!(error!(?error, "Error writing PNG thumbnail.")))?;
 
+	if self.services.globals.is_read_only() {
+		return Ok(Some(into_filemeta(data, thumbnail_bytes)));
+	}
+
 	// Save thumbnail in database so we don't have to generate it again next time
 	let thumbnail_key = self.db.create_file_metadata(
 		mxc,
@@ -5,10 +5,12 @@
 	result::NotFound,
 	utils::{
 		IterStream, ReadyExt,
+		hash::sha256,
 		stream::{TryExpect, TryIgnore},
 	},
 	warn,
 };
+use database::table;
 use futures::{FutureExt, StreamExt};
 use itertools::Itertools;
 use ruma::{
@@ -45,11 +47,38 @@ pub(crate) async fn migrations(services: &Services) -> Result<()> {
 		}
 	}
 
-	if users_count > 0 {
-		migrate(services).await
+	// Migrations are the primary's responsibility; a replica can only verify
+	// the primary has already brought the schema up to date.
+	if services.db.is_read_only() {
+		check_read_only(services).await?;
+	} else if users_count > 0 {
+		migrate(services).await?;
 	} else {
-		fresh(services).await
+		fresh(services).await?;
 	}
+
+	check_table_layouts(services).await
+}
+
+async fn check_read_only(services: &Services) -> Result<()> {
+	let version = services.globals.db.database_version().await;
+	if version != DATABASE_VERSION {
+		return Err!(Database(
+			"Read-only database is at schema version {version} but this server requires \
+			 {DATABASE_VERSION}. Start the primary to migrate it first."
+		));
+	}
+
+	Ok(())
+}
+
+/// Typed tables must be in the layout this server was built with; a layout
+/// change requires a migration which rewrites the table and records the new
+/// layout.
+async fn check_table_layouts(services: &Services) -> Result<()> {
+	table::check_layout::<media::MediaFile>(&services.db).await?;
+
+	Ok(())
 }
 
 async fn fresh(services: &Services) -> Result<()> {
@@ -62,6 +91,7 @@ async fn fresh(services: &Services) -> Result<()> {
 	db["global"].insert(b"retroactively_fix_bad_data_from_roomuserid_joined", []);
 	db["global"].insert(b"fix_referencedevents_missing_sep", []);
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
+	db["global"].insert(b"feat_hashed_access_tokens", []);
 
 	// Create the admin room and server user on first run
 	crate::admin::create_admin_room(services).boxed().await?;
@@ -133,6 +163,14 @@ async fn migrate(services: &Services) -> Result<()> {
 		fix_readreceiptid_readreceipt_duplicates(services).await?;
 	}
 
+	if db["global"]
+		.get(b"feat_hashed_access_tokens")
+		.await
+		.is_not_found()
+	{
+		hash_access_tokens(services).await?;
+	}
+
 	if services.globals.db.database_version().await < 17 {
 		services.globals.db.bump_database_version(17);
 		info!("Migration: Bumped database version to 17");
@@ -557,3 +595,33 @@ async fn fix_readreceiptid_readreceipt_duplicates(services: &Services) -> Result
 	db["global"].insert(b"fix_readreceiptid_readreceipt_duplicates", []);
 	db.db.sort()
 }
+
+/// Access tokens were stored as the keys of `token_userdeviceid`; rebuild it
+/// from the plaintext `userdeviceid_token` keyed by each token's digest. The
+/// rebuild starts from a cleared map so an interrupted run can simply be
+/// repeated without hashing any digest a second time.
+async fn hash_access_tokens(services: &Services) -> Result {
+	warn!("Rebuilding token_userdeviceid keyed by access token digests...");
+
+	let db = &services.db;
+	let cork = db.cork_and_sync();
+	let token_userdeviceid = db["token_userdeviceid"].clone();
+	let userdeviceid_token = db["userdeviceid_token"].clone();
+
+	token_userdeviceid.clear().await;
+
+	let total = userdeviceid_token
+		.raw_stream()
+		.expect_ok()
+		.ready_fold(0_usize, |total, (userdeviceid, token)| {
+			token_userdeviceid.insert(&sha256::hash(token), userdeviceid);
+			total.saturating_add(1)
+		})
+		.await;
+
+	drop(cork);
+	info!(?total, "Rebuilt token_userdeviceid from access token digests.");
+
+	db["global"].insert(b"feat_hashed_access_tokens", []);
+	db.db.sort()
+}
@@ -9,19 +9,25 @@
 pub mod account_data;
 pub mod admin;
 pub mod appservice;
+pub mod changes;
 pub mod client;
 // pub mod config;
 pub mod emergency;
+pub mod expiry;
 pub mod federation;
 pub mod globals;
 pub mod key_backups;
 pub mod media;
+pub mod policy_lists;
 pub mod presence;
 pub mod pusher;
+pub mod replica;
+pub mod reports;
 pub mod resolver;
 pub mod rooms;
 pub mod sending;
 pub mod server_keys;
+pub mod spam_checker;
 pub mod sync;
 pub mod transaction_ids;
 pub mod uiaa;
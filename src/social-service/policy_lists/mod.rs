@@ -0,0 +1,346 @@
+//! Moderation policy lists. The rules of the policy rooms named by the
+//! `policy_lists` option are kept in memory and reloaded whenever the state of
+//! one of the rooms changes. Rules recommending a ban are enforced: banned
+//! users and servers are refused, and banned rooms are banned through the
+//! admin command.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+	fmt,
+	sync::{Arc, RwLock},
+};
+
+use async_trait::async_trait;
+use conduwuit::{Err, Result, Server, debug, implement, info, utils::stream::TryIgnore, warn};
+use futures::StreamExt;
+use ruma::{
+	OwnedRoomId, RoomId, ServerName, UserId,
+	events::{StateEventType, policy::rule::PolicyRuleEventContent},
+};
+use service_core::{Args, Dep, Service as ServiceTrait};
+use tokio::sync::Notify;
+
+use crate::{admin, globals, rooms};
+
+pub struct Service {
+	rules: RwLock<Rules>,
+	changed: Notify,
+	interrupt: Notify,
+	server: Arc<Server>,
+	services: Services,
+}
+
+struct Services {
+	admin: Dep<admin::Service>,
+	globals: Dep<globals::Service>,
+	metadata: Dep<rooms::metadata::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+}
+
+#[derive(Debug, Default)]
+struct Rules {
+	users: Vec<Rule>,
+	rooms: Vec<Rule>,
+	servers: Vec<Rule>,
+}
+
+/// A ban recommended by a policy list.
+#[derive(Clone, Debug)]
+pub struct Rule {
+	pub kind: Kind,
+
+	/// The banned entity, possibly with `*` and `?` globs.
+	pub entity: String,
+	pub reason: String,
+
+	/// The policy room the rule is in.
+	pub list: OwnedRoomId,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Kind {
+	User,
+	Room,
+	Server,
+}
+
+/// Recommendations which are enforced; the second is its name before it was
+/// specified.
+const BAN: [&str; 2] = ["m.ban", "org.matrix.mjolnir.ban"];
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			rules: RwLock::default(),
+			changed: Notify::new(),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			services: Services {
+				admin: args.depend::<admin::Service>("admin"),
+				globals: args.depend::<globals::Service>("globals"),
+				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "policy_lists", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.server.config.policy_lists.is_empty() {
+			return Ok(());
+		}
+
+		while self.server.running() {
+			self.reload().await;
+			if !self.services.globals.is_read_only() {
+				self.ban_rooms().await;
+			}
+
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				() = self.changed.notified() => (),
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+/// Called when a state event of a policy rule type is added to a room; the
+/// rules are reloaded if the room is a policy list.
+#[implement(Service)]
+pub fn rules_changed(&self, room_id: &RoomId) {
+	if self.is_policy_list(room_id) {
+		debug!(%room_id, "Policy list changed");
+		self.changed.notify_one();
+	}
+}
+
+#[implement(Service)]
+#[must_use]
+pub fn is_policy_list(&self, room_id: &RoomId) -> bool {
+	self.server
+		.config
+		.policy_lists
+		.iter()
+		.any(|list| list == room_id)
+}
+
+/// Fails if the user is banned by a policy list.
+#[implement(Service)]
+pub fn check_user(&self, user_id: &UserId) -> Result {
+	match self.user_rule(user_id) {
+		| Some(rule) => Err!(Request(Forbidden(debug_warn!(
+			"User {user_id} is banned on this homeserver: {}",
+			rule.reason
+		)))),
+		| None => Ok(()),
+	}
+}
+
+/// Fails if the server is banned by a policy list.
+#[implement(Service)]
+pub fn check_server(&self, server_name: &ServerName) -> Result {
+	match self.server_rule(server_name) {
+		| Some(rule) => Err!(Request(Forbidden(debug_warn!(
+			"Server {server_name} is banned on this homeserver: {}",
+			rule.reason
+		)))),
+		| None => Ok(()),
+	}
+}
+
+#[implement(Service)]
+#[must_use]
+pub fn user_rule(&self, user_id: &UserId) -> Option<Rule> {
+	find(&self.rules.read().expect("locked for reading").users, user_id.as_str())
+}
+
+#[implement(Service)]
+#[must_use]
+pub fn server_rule(&self, server_name: &ServerName) -> Option<Rule> {
+	find(&self.rules.read().expect("locked for reading").servers, server_name.as_str())
+}
+
+/// The rules being enforced.
+#[implement(Service)]
+#[must_use]
+pub fn rules(&self) -> Vec<Rule> {
+	let rules = self.rules.read().expect("locked for reading");
+	rules
+		.users
+		.iter()
+		.chain(&rules.rooms)
+		.chain(&rules.servers)
+		.cloned()
+		.collect()
+}
+
+/// Read the rules from the current state of each policy list.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn reload(&self) {
+	let mut rules = Rules::default();
+	for list in &self.server.config.policy_lists {
+		let mut state = self
+			.services
+			.state_accessor
+			.room_state_full(list)
+			.ignore_err()
+			.boxed();
+
+		let mut found = 0_usize;
+		while let Some(((event_type, _), pdu)) = state.next().await {
+			let kind = match event_type {
+				| StateEventType::PolicyRuleUser => Kind::User,
+				| StateEventType::PolicyRuleRoom => Kind::Room,
+				| StateEventType::PolicyRuleServer => Kind::Server,
+				| _ => continue,
+			};
+
+			// Removed rules have empty content.
+			let Ok(content) = pdu.get_content::<PolicyRuleEventContent>() else {
+				continue;
+			};
+
+			if !BAN.contains(&content.recommendation.as_str()) {
+				continue;
+			}
+
+			let rule = Rule {
+				kind,
+				entity: content.entity,
+				reason: content.reason,
+				list: list.clone(),
+			};
+
+			found = found.saturating_add(1);
+			match kind {
+				| Kind::User => rules.users.push(rule),
+				| Kind::Room => rules.rooms.push(rule),
+				| Kind::Server => rules.servers.push(rule),
+			}
+		}
+
+		if found == 0 {
+			warn!(%list, "No ban rules in policy list; is a local user joined to it?");
+		}
+	}
+
+	info!(
+		users = rules.users.len(),
+		rooms = rules.rooms.len(),
+		servers = rules.servers.len(),
+		"Loaded policy list rules"
+	);
+
+	*self.rules.write().expect("locked for writing") = rules;
+}
+
+/// Ban the rooms matched by room rules which are not banned yet, through the
+/// admin command so local users are made to leave them.
+#[implement(Service)]
+async fn ban_rooms(&self) {
+	let rules = self.rules.read().expect("locked for reading").rooms.clone();
+	let mut room_ids: Vec<OwnedRoomId> = rules
+		.iter()
+		.filter(|rule| !is_glob(&rule.entity))
+		.filter_map(|rule| RoomId::parse(&rule.entity).ok())
+		.collect();
+
+	if rules.iter().any(|rule| is_glob(&rule.entity)) {
+		let known: Vec<OwnedRoomId> = self
+			.services
+			.metadata
+			.iter_ids()
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		room_ids.extend(
+			known
+				.into_iter()
+				.filter(|room_id| find(&rules, room_id.as_str()).is_some()),
+		);
+	}
+
+	room_ids.sort_unstable();
+	room_ids.dedup();
+	for room_id in room_ids {
+		if self.services.metadata.is_banned(&room_id).await
+			|| self.services.admin.is_admin_room(&room_id).await
+			|| self.is_policy_list(&room_id)
+		{
+			continue;
+		}
+
+		info!(%room_id, "Banning room listed in a policy list");
+		let command = format!("!admin rooms moderation ban-room {room_id}");
+		if let Err(e) = self.services.admin.command(command, None) {
+			warn!(%room_id, "Failed to ban room listed in a policy list: {e}");
+		}
+	}
+}
+
+fn find(rules: &[Rule], entity: &str) -> Option<Rule> {
+	rules
+		.iter()
+		.find(|rule| glob_match(&rule.entity, entity))
+		.cloned()
+}
+
+fn is_glob(entity: &str) -> bool { entity.contains(['*', '?']) }
+
+/// Whether the text matches the pattern, where `*` matches any run of
+/// characters and `?` any one character.
+fn glob_match(pattern: &str, text: &str) -> bool {
+	let pattern: Vec<char> = pattern.chars().collect();
+	let text: Vec<char> = text.chars().collect();
+
+	// Position after the last `*` and the text position it was tried at.
+	let mut star = None;
+	let (mut p, mut t) = (0_usize, 0_usize);
+	while let Some(&c) = text.get(t) {
+		match pattern.get(p) {
+			| Some('*') => {
+				p = p.saturating_add(1);
+				star = Some((p, t));
+			},
+			| Some(&pc) if pc == '?' || pc == c => {
+				p = p.saturating_add(1);
+				t = t.saturating_add(1);
+			},
+			| _ => {
+				let Some((after_star, tried)) = star else {
+					return false;
+				};
+
+				p = after_star;
+				t = tried.saturating_add(1);
+				star = Some((after_star, t));
+			},
+		}
+	}
+
+	pattern
+		.get(p..)
+		.is_none_or(|rest| rest.iter().all(|&c| c == '*'))
+}
+
+impl fmt::Display for Kind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			| Self::User => "user",
+			| Self::Room => "room",
+			| Self::Server => "server",
+		})
+	}
+}
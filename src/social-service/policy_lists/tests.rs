@@ -0,0 +1,32 @@
+use super::{glob_match, is_glob};
+
+#[test]
+fn glob_literal() {
+	assert!(glob_match("@spam:example.com", "@spam:example.com"));
+	assert!(!glob_match("@spam:example.com", "@spam:example.org"));
+	assert!(!glob_match("@spam:example.com", "@spam:example.com.evil"));
+}
+
+#[test]
+fn glob_star() {
+	assert!(glob_match("*", "anything"));
+	assert!(glob_match("*.example.com", "matrix.example.com"));
+	assert!(!glob_match("*.example.com", "example.com"));
+	assert!(glob_match("@*:example.com", "@spam:example.com"));
+	assert!(glob_match("@spam*bot*:*", "@spam-bot-1:example.com"));
+	assert!(!glob_match("@spam*bot*:*", "@ham-bot-1:example.com"));
+}
+
+#[test]
+fn glob_question() {
+	assert!(glob_match("@spam?:example.com", "@spam1:example.com"));
+	assert!(!glob_match("@spam?:example.com", "@spam:example.com"));
+	assert!(!glob_match("@spam?:example.com", "@spam12:example.com"));
+}
+
+#[test]
+fn glob_detect() {
+	assert!(is_glob("*.example.com"));
+	assert!(is_glob("@spam?:example.com"));
+	assert!(!is_glob("!room:example.com"));
+}
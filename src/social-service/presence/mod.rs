@@ -7,7 +7,7 @@
 use conduwuit::{
 	Error, Result, Server, checked, debug, debug_warn, error, result::LogErr, trace,
 };
-use database::Database;
+use database::{Database, Priority};
 use futures::{Stream, StreamExt, TryFutureExt, stream::FuturesUnordered};
 use loole::{Receiver, Sender};
 use ruma::{OwnedUserId, UInt, UserId, events::presence::PresenceEvent, presence::PresenceState};
@@ -57,13 +57,21 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 	}
 
 	async fn worker(self: Arc<Self>) -> Result<()> {
+		if self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
 		let receiver = self.timer_channel.1.clone();
 
 		let mut presence_timers = FuturesUnordered::new();
 		while !receiver.is_closed() {
 			tokio::select! {
 				Some(user_id) = presence_timers.next() => {
-					self.process_presence_timer(&user_id).await.log_err().ok();
+					Priority::Background
+						.scope(self.process_presence_timer(&user_id))
+						.await
+						.log_err()
+						.ok();
 				},
 				event = receiver.recv_async() => match event {
 					Err(_) => break,
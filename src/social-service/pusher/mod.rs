@@ -2,7 +2,9 @@
 
 use bytes::BytesMut;
 use conduwuit::{
-	Err, PduEvent, Result, debug_warn, err, trace,
+	Err, PduEvent, Result, debug_warn, err,
+	log::propagate,
+	trace,
 	utils::{stream::TryIgnore, string_from_bytes},
 	warn,
 };
@@ -207,7 +209,8 @@ pub async fn send_request<T>(&self, dest: &str, request: T) -> Result<T::Incomin
 			})?
 			.map(BytesMut::freeze);
 
-		let reqwest_request = reqwest::Request::try_from(http_request)?;
+		let mut reqwest_request = reqwest::Request::try_from(http_request)?;
+		propagate::inject(reqwest_request.headers_mut());
 
 		if let Some(url_host) = reqwest_request.url().host_str() {
 			trace!("Checking request URL for IP");
@@ -282,6 +285,10 @@ pub async fn send_push_notice(
 		ruleset: Ruleset,
 		pdu: &PduEvent,
 	) -> Result<()> {
+		if self.services.users.is_shadow_banned(&pdu.sender).await {
+			return Ok(());
+		}
+
 		let mut notify = None;
 		let mut tweaks = Vec::new();
 
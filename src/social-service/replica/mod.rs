@@ -0,0 +1,92 @@
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use conduwuit::{Result, Server, debug, info, warn};
+use database::Database;
+use tokio::{
+	sync::Notify,
+	time::{MissedTickBehavior, interval},
+};
+
+use service_core::{Args, Service as ServiceTrait};
+
+/// Drives a secondary (read replica) instance. The database is periodically
+/// caught up with the primary so reads observe its recent writes.
+pub struct Service {
+	interval: Duration,
+	interrupt: Notify,
+	server: Arc<Server>,
+	db: Arc<Database>,
+}
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		let config = &args.server.config;
+
+		Ok(Arc::new(Self {
+			interval: Duration::from_millis(config.rocksdb_secondary_catchup_interval.max(1)),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			db: args.db.clone(),
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "replica", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result<()> {
+		if !self.is_replica() {
+			return Ok(());
+		}
+
+		info!(
+			interval = ?self.interval,
+			sequence = %self.db.db.current_sequence(),
+			"Running as a read replica."
+		);
+
+		let mut i = interval(self.interval);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		i.reset_after(self.interval);
+		while self.server.running() {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			if let Err(e) = self.catch_up().await {
+				warn!(%e, "Failed to catch up with primary");
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+impl Service {
+	/// Replay the primary's recent writes into this instance.
+	#[tracing::instrument(skip_all, level = "trace")]
+	pub async fn catch_up(&self) -> Result {
+		let db = self.db.db.clone();
+		let prev = db.current_sequence();
+		self.server
+			.runtime()
+			.spawn_blocking(move || db.update())
+			.await??;
+
+		let sequence = self.db.db.current_sequence();
+		if sequence != prev {
+			debug!(%prev, %sequence, "Caught up with primary");
+		}
+
+		Ok(())
+	}
+
+	/// Whether this instance is a read replica of another.
+	#[inline]
+	#[must_use]
+	pub fn is_replica(&self) -> bool { self.db.is_secondary() }
+}
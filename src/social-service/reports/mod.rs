@@ -0,0 +1,442 @@
+//! Abuse reports sent by users about events, rooms and other users. Reports
+//! are kept until deleted so the admins handling them can track each one from
+//! open to resolved, with notes along the way.
+
+#[cfg(test)]
+mod tests;
+
+use std::{fmt, str::FromStr, sync::Arc};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Error, Result, err, implement, info,
+	utils::{MutexMap, ReadyExt, stream::TryIgnore, time::now_millis},
+};
+use database::{Deserialized, Json, Map};
+use futures::{Stream, StreamExt};
+use ruma::{
+	OwnedEventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	events::room::message::RoomMessageEventContent,
+};
+use serde::{Deserialize, Serialize};
+use service_core::{Args, Dep, Service as ServiceTrait};
+
+use crate::{admin, globals};
+
+pub struct Service {
+	services: Services,
+	db: Data,
+
+	/// Serializes the changes to each report, keyed by its ID.
+	mutex_report: MutexMap<String, ()>,
+}
+
+struct Services {
+	admin: Dep<admin::Service>,
+	globals: Dep<globals::Service>,
+}
+
+struct Data {
+	reportid_report: Arc<Map>,
+}
+
+/// A report and the state of its handling.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Report {
+	pub id: u64,
+	pub reporter: OwnedUserId,
+	pub target: Target,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub reason: Option<String>,
+
+	/// Offensiveness from 0 (inoffensive) to -100 (most offensive), for
+	/// event reports.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub score: Option<i64>,
+
+	pub status: Status,
+
+	/// The admin who claimed the report.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub handler: Option<OwnedUserId>,
+
+	#[serde(default)]
+	pub notes: Vec<Note>,
+
+	/// When the report was received, in milliseconds since the epoch.
+	pub received: u64,
+
+	/// When the report was last changed, in milliseconds since the epoch.
+	pub updated: u64,
+}
+
+/// What a report is about.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Target {
+	Event {
+		room_id: OwnedRoomId,
+		event_id: OwnedEventId,
+		sender: OwnedUserId,
+	},
+	Room {
+		room_id: OwnedRoomId,
+	},
+	User {
+		user_id: OwnedUserId,
+	},
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+	Event,
+	Room,
+	User,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+	/// Not yet looked at.
+	#[default]
+	Open,
+
+	/// Claimed by an admin who is looking into it.
+	Acknowledged,
+
+	/// Dealt with.
+	Resolved,
+}
+
+/// A remark by an admin handling a report.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Note {
+	pub author: OwnedUserId,
+	pub body: String,
+
+	/// When the note was added, in milliseconds since the epoch.
+	pub ts: u64,
+}
+
+/// Criteria reports are listed by; unset fields match every report.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Filter {
+	pub status: Option<Status>,
+	pub kind: Option<Kind>,
+	pub reporter: Option<OwnedUserId>,
+	pub room_id: Option<OwnedRoomId>,
+
+	/// The reported user, or the sender of the reported event.
+	pub user_id: Option<OwnedUserId>,
+	pub handler: Option<OwnedUserId>,
+}
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			services: Services {
+				admin: args.depend::<admin::Service>("admin"),
+				globals: args.depend::<globals::Service>("globals"),
+			},
+			db: Data {
+				reportid_report: args.db["reportid_report"].clone(),
+			},
+			mutex_report: MutexMap::new(),
+		}))
+	}
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+/// Store a report and notify the admin room of it. Returns the ID of the
+/// report.
+#[implement(Service)]
+pub async fn submit(
+	&self,
+	reporter: &UserId,
+	target: Target,
+	reason: Option<String>,
+	score: Option<i64>,
+) -> Result<u64> {
+	let id = self.services.globals.next_count()?;
+	let now = now_millis();
+	let report = Report {
+		id,
+		reporter: reporter.to_owned(),
+		target,
+		reason,
+		score,
+		status: Status::Open,
+		handler: None,
+		notes: Vec::new(),
+		received: now,
+		updated: now,
+	};
+
+	self.save(&report);
+	info!(id, %reporter, kind = %report.target.kind(), "Received report");
+
+	// @room ping for urgency
+	self.services
+		.admin
+		.send_message(RoomMessageEventContent::text_markdown(format!(
+			"@room {report}\n\nUse `!admin reports claim {id}` to handle it."
+		)))
+		.await
+		.ok();
+
+	Ok(id)
+}
+
+#[implement(Service)]
+pub async fn get(&self, id: u64) -> Result<Report> {
+	self.db
+		.reportid_report
+		.qry(&id)
+		.await
+		.deserialized()
+		.map_err(|_| err!(Request(NotFound("Report {id} does not exist."))))
+}
+
+/// Reports matching the filter, newest first, starting from the report with
+/// the ID `from` when given.
+#[implement(Service)]
+pub fn reports<'a>(
+	&'a self,
+	filter: &'a Filter,
+	from: Option<u64>,
+) -> impl Stream<Item = Report> + Send + 'a {
+	let reports = match from {
+		| Some(from) => self.db.reportid_report.rev_stream_from(&from).boxed(),
+		| None => self.db.reportid_report.rev_stream().boxed(),
+	};
+
+	reports
+		.ignore_err()
+		.map(|(_, report): (u64, Report)| report)
+		.ready_filter(move |report| filter.matches(report))
+}
+
+/// Claim a report for an admin, acknowledging it.
+#[implement(Service)]
+pub async fn claim(&self, id: u64, handler: &UserId) -> Result<Report> {
+	self.update(id, |report| {
+		if report.status == Status::Resolved {
+			return Err!(Request(InvalidParam("Report {id} is already resolved.")));
+		}
+
+		report.status = Status::Acknowledged;
+		report.handler = Some(handler.to_owned());
+		Ok(())
+	})
+	.await
+}
+
+/// Mark a report as dealt with, with an optional closing note.
+#[implement(Service)]
+pub async fn resolve(&self, id: u64, handler: &UserId, note: Option<String>) -> Result<Report> {
+	self.update(id, |report| {
+		report.status = Status::Resolved;
+		report.handler.get_or_insert_with(|| handler.to_owned());
+		if let Some(body) = note {
+			report.notes.push(Note {
+				author: handler.to_owned(),
+				body,
+				ts: now_millis(),
+			});
+		}
+
+		Ok(())
+	})
+	.await
+}
+
+/// Open a resolved report again.
+#[implement(Service)]
+pub async fn reopen(&self, id: u64) -> Result<Report> {
+	self.update(id, |report| {
+		report.status = if report.handler.is_some() {
+			Status::Acknowledged
+		} else {
+			Status::Open
+		};
+
+		Ok(())
+	})
+	.await
+}
+
+#[implement(Service)]
+pub async fn add_note(&self, id: u64, author: &UserId, body: String) -> Result<Report> {
+	self.update(id, |report| {
+		report.notes.push(Note {
+			author: author.to_owned(),
+			body,
+			ts: now_millis(),
+		});
+
+		Ok(())
+	})
+	.await
+}
+
+#[implement(Service)]
+pub async fn delete(&self, id: u64) -> Result {
+	let _lock = self.mutex_report.lock(&id.to_string()).await;
+	self.get(id).await?;
+	self.db.reportid_report.del(id);
+
+	Ok(())
+}
+
+#[implement(Service)]
+async fn update<F>(&self, id: u64, f: F) -> Result<Report>
+where
+	F: FnOnce(&mut Report) -> Result + Send,
+{
+	let _lock = self.mutex_report.lock(&id.to_string()).await;
+	let mut report = self.get(id).await?;
+	f(&mut report)?;
+	report.updated = now_millis();
+	self.save(&report);
+
+	Ok(report)
+}
+
+#[implement(Service)]
+fn save(&self, report: &Report) { self.db.reportid_report.put(report.id, Json(report)); }
+
+impl Report {
+	/// The room the report concerns, if any.
+	#[must_use]
+	pub fn room_id(&self) -> Option<&RoomId> {
+		match &self.target {
+			| Target::Event { room_id, .. } | Target::Room { room_id } => Some(room_id),
+			| Target::User { .. } => None,
+		}
+	}
+
+	/// The user the report concerns: the reported user or the sender of the
+	/// reported event.
+	#[must_use]
+	pub fn user_id(&self) -> Option<&UserId> {
+		match &self.target {
+			| Target::Event { sender, .. } => Some(sender),
+			| Target::User { user_id } => Some(user_id),
+			| Target::Room { .. } => None,
+		}
+	}
+
+	/// A matrix.to link to what the report is about.
+	#[must_use]
+	pub fn link(&self) -> String {
+		match &self.target {
+			| Target::Event { room_id, event_id, .. } =>
+				format!("https://matrix.to/#/{room_id}/{event_id}"),
+			| Target::Room { room_id } => format!("https://matrix.to/#/{room_id}"),
+			| Target::User { user_id } => format!("https://matrix.to/#/{user_id}"),
+		}
+	}
+}
+
+impl fmt::Display for Report {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Report {} ({}) from {}", self.id, self.status, self.reporter)?;
+		match &self.target {
+			| Target::Event { room_id, event_id, sender } =>
+				write!(f, " about event {event_id} sent by {sender} in room {room_id}")?,
+			| Target::Room { room_id } => write!(f, " about room {room_id}")?,
+			| Target::User { user_id } => write!(f, " about user {user_id}")?,
+		}
+
+		if let Some(score) = self.score {
+			write!(f, "\n\nScore: {score}")?;
+		}
+
+		write!(f, "\n\nReason: {}", self.reason.as_deref().unwrap_or(""))
+	}
+}
+
+impl Target {
+	#[must_use]
+	pub fn kind(&self) -> Kind {
+		match self {
+			| Self::Event { .. } => Kind::Event,
+			| Self::Room { .. } => Kind::Room,
+			| Self::User { .. } => Kind::User,
+		}
+	}
+}
+
+impl Filter {
+	#[must_use]
+	pub fn matches(&self, report: &Report) -> bool {
+		self.status.is_none_or(|status| report.status == status)
+			&& self.kind.is_none_or(|kind| report.target.kind() == kind)
+			&& self
+				.reporter
+				.as_ref()
+				.is_none_or(|reporter| report.reporter == *reporter)
+			&& self
+				.room_id
+				.as_deref()
+				.is_none_or(|room_id| report.room_id() == Some(room_id))
+			&& self
+				.user_id
+				.as_deref()
+				.is_none_or(|user_id| report.user_id() == Some(user_id))
+			&& self
+				.handler
+				.as_deref()
+				.is_none_or(|handler| report.handler.as_deref() == Some(handler))
+	}
+}
+
+impl fmt::Display for Status {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			| Self::Open => "open",
+			| Self::Acknowledged => "acknowledged",
+			| Self::Resolved => "resolved",
+		})
+	}
+}
+
+impl FromStr for Status {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			| "open" => Ok(Self::Open),
+			| "acknowledged" => Ok(Self::Acknowledged),
+			| "resolved" => Ok(Self::Resolved),
+			| _ => Err!("Unknown report status {s:?}; expected open, acknowledged or resolved."),
+		}
+	}
+}
+
+impl fmt::Display for Kind {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			| Self::Event => "event",
+			| Self::Room => "room",
+			| Self::User => "user",
+		})
+	}
+}
+
+impl FromStr for Kind {
+	type Err = Error;
+
+	fn from_str(s: &str) -> Result<Self> {
+		match s {
+			| "event" => Ok(Self::Event),
+			| "room" => Ok(Self::Room),
+			| "user" => Ok(Self::User),
+			| _ => Err!("Unknown report kind {s:?}; expected event, room or user."),
+		}
+	}
+}
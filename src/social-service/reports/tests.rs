@@ -0,0 +1,72 @@
+use ruma::{owned_event_id, owned_room_id, owned_user_id};
+
+use super::{Filter, Kind, Report, Status, Target};
+
+fn event_report() -> Report {
+	Report {
+		id: 1,
+		reporter: owned_user_id!("@alice:example.com"),
+		target: Target::Event {
+			room_id: owned_room_id!("!room:example.com"),
+			event_id: owned_event_id!("$event:example.com"),
+			sender: owned_user_id!("@mallory:example.org"),
+		},
+		reason: Some("spam".to_owned()),
+		score: Some(-100),
+		status: Status::Open,
+		handler: None,
+		notes: Vec::new(),
+		received: 0,
+		updated: 0,
+	}
+}
+
+#[test]
+fn filter_default_matches() {
+	assert!(Filter::default().matches(&event_report()));
+}
+
+#[test]
+fn filter_event_sender() {
+	let report = event_report();
+	let filter = Filter {
+		kind: Some(Kind::Event),
+		room_id: Some(owned_room_id!("!room:example.com")),
+		user_id: Some(owned_user_id!("@mallory:example.org")),
+		..Filter::default()
+	};
+
+	assert!(filter.matches(&report));
+
+	let filter = Filter {
+		user_id: Some(owned_user_id!("@alice:example.com")),
+		..Filter::default()
+	};
+
+	assert!(!filter.matches(&report));
+}
+
+#[test]
+fn filter_status_and_handler() {
+	let mut report = event_report();
+	let filter = Filter {
+		status: Some(Status::Acknowledged),
+		handler: Some(owned_user_id!("@admin:example.com")),
+		..Filter::default()
+	};
+
+	assert!(!filter.matches(&report));
+
+	report.status = Status::Acknowledged;
+	report.handler = Some(owned_user_id!("@admin:example.com"));
+	assert!(filter.matches(&report));
+}
+
+#[test]
+fn status_round_trip() {
+	for status in [Status::Open, Status::Acknowledged, Status::Resolved] {
+		assert_eq!(status.to_string().parse::<Status>().unwrap(), status);
+	}
+
+	assert!("closed".parse::<Status>().is_err());
+}
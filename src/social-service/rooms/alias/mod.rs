@@ -118,6 +118,29 @@ pub async fn remove_alias(&self, alias: &RoomAliasId, user_id: &UserId) -> Resul
 		Ok(())
 	}
 
+	/// Remove every local alias of a room without any permission checks.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		self.db
+			.aliasid_alias
+			.stream_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|(aliasid, alias)| {
+				let alias = str::from_utf8(alias)
+					.ok()
+					.and_then(|alias| <&RoomAliasId>::try_from(alias).ok());
+
+				if let Some(alias) = alias {
+					self.db.alias_roomid.remove(alias.alias().as_bytes());
+					self.db.alias_userid.remove(alias.alias().as_bytes());
+				}
+
+				self.db.aliasid_alias.remove(aliasid);
+			})
+			.await;
+	}
+
 	#[inline]
 	pub async fn resolve(&self, room: &RoomOrAliasId) -> Result<OwnedRoomId> {
 		self.resolve_with_servers(room, None)
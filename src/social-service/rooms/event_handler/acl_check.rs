@@ -4,10 +4,12 @@
 	events::{StateEventType, room::server_acl::RoomServerAclEventContent},
 };
 
-/// Returns Ok if the acl allows the server
+/// Returns Ok if the acl allows the server and no policy list bans it
 #[implement(super::Service)]
 #[tracing::instrument(skip_all, level = "debug")]
 pub async fn acl_check(&self, server_name: &ServerName, room_id: &RoomId) -> Result {
+	self.services.policy_lists.check_server(server_name)?;
+
 	let Ok(acl_event_content) = self
 		.services
 		.state_accessor
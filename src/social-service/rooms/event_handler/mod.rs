@@ -24,7 +24,7 @@
 	events::room::create::RoomCreateEventContent,
 };
 
-use crate::{globals, rooms, sending, server_keys};
+use crate::{globals, policy_lists, rooms, sending, server_keys, spam_checker};
 use service_core::{Dep, Args, Service as ServiceTrait};
 
 pub struct Service {
@@ -40,8 +40,10 @@ struct Services {
 	metadata: Dep<rooms::metadata::Service>,
 	outlier: Dep<rooms::outlier::Service>,
 	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
+	policy_lists: Dep<policy_lists::Service>,
 	server_keys: Dep<server_keys::Service>,
 	short: Dep<rooms::short::Service>,
+	spam_checker: Dep<spam_checker::Service>,
 	state: Dep<rooms::state::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	state_compressor: Dep<rooms::state_compressor::Service>,
@@ -66,7 +68,9 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 				outlier: args.depend::<rooms::outlier::Service>("rooms::outlier"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
 				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
+				policy_lists: args.depend::<policy_lists::Service>("policy_lists"),
 				short: args.depend::<rooms::short::Service>("rooms::short"),
+				spam_checker: args.depend::<spam_checker::Service>("spam_checker"),
 				state: args.depend::<rooms::state::Service>("rooms::state"),
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
@@ -11,9 +11,12 @@
 use ruma::{CanonicalJsonValue, RoomId, ServerName, events::StateEventType};
 
 use super::{get_room_version_id, to_room_version};
-use crate::rooms::{
-	state_compressor::{CompressedState, HashSetCompressStateEvent},
-	timeline::RawPduId,
+use crate::{
+	rooms::{
+		state_compressor::{CompressedState, HashSetCompressStateEvent},
+		timeline::RawPduId,
+	},
+	spam_checker::Verdict,
 };
 
 #[implement(super::Service)]
@@ -133,6 +136,12 @@ pub(super) async fn upgrade_outlier_to_timeline_pdu(
 				.await?,
 	};
 
+	// Events from other servers the spam checker refuses are soft failed rather
+	// than rejected, as the other servers in the room may have accepted them.
+	// The check is made before the room is locked.
+	let soft_fail = soft_fail
+		|| !matches!(self.services.spam_checker.check_event(&incoming_pdu).await, Verdict::Allow);
+
 	// 13. Use state resolution to find new room state
 
 	// We start looking at current room state now, so lets lock the room
@@ -116,6 +116,9 @@ fn witness<'a, I>(
 	let make_key =
 		|sender: &'a UserId| -> Key<'a> { (ctx.user_id, ctx.device_id, ctx.room_id, sender) };
 
+	// A read-only database cannot record what is sent now.
+	let writable = !self.db.db.is_read_only();
+
 	senders
 		.clone()
 		.stream()
@@ -124,6 +127,10 @@ fn witness<'a, I>(
 		.map(into_status)
 		.zip(senders.stream())
 		.map(move |(status, sender)| {
+			if !writable {
+				return status;
+			}
+
 			if matches!(status, Status::Unseen) {
 				self.db
 					.lazyloadedids
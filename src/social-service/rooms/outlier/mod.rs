@@ -1,9 +1,15 @@
 use std::sync::Arc;
 
-use conduwuit::{Result, implement, matrix::pdu::PduEvent};
+use conduwuit::{
+	Result, implement,
+	matrix::pdu::PduEvent,
+	utils,
+	utils::{ReadyExt, stream::TryIgnore},
+};
 use conduwuit_database::{Deserialized, Json, Map};
-use ruma::{CanonicalJsonObject, EventId};
-
+use futures::StreamExt;
+use ruma::{CanonicalJsonObject, EventId, OwnedEventId, RoomId};
+use serde::Deserialize;
 use service_core::{Args, Service as ServiceTrait};
 
 pub struct Service {
@@ -52,3 +58,36 @@ pub async fn get_pdu_outlier(&self, event_id: &EventId) -> Result<PduEvent> {
 pub fn add_pdu_outlier(&self, event_id: &EventId, pdu: &CanonicalJsonObject) {
 	self.db.eventid_outlierpdu.raw_put(event_id, Json(pdu));
 }
+
+/// Remove the outliers of a room. They are only keyed by event ID, so this
+/// scans all of them. Returns the IDs of the removed events.
+#[implement(Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn purge_room(&self, room_id: &RoomId) -> Vec<OwnedEventId> {
+	#[derive(Deserialize)]
+	struct Outlier<'a> {
+		#[serde(borrow)]
+		room_id: &'a RoomId,
+	}
+
+	let removed: Vec<OwnedEventId> = self
+		.db
+		.eventid_outlierpdu
+		.raw_stream()
+		.ignore_err()
+		.ready_filter_map(|(key, val)| {
+			let outlier: Outlier<'_> = serde_json::from_slice(val).ok()?;
+			let event_id = utils::str_from_bytes(key).ok()?;
+			(outlier.room_id == room_id)
+				.then(|| OwnedEventId::try_from(event_id).ok())
+				.flatten()
+		})
+		.collect()
+		.await;
+
+	for event_id in &removed {
+		self.db.eventid_outlierpdu.remove(event_id);
+	}
+
+	removed
+}
@@ -10,7 +10,7 @@
 		u64_from_u8,
 	},
 };
-use database::Map;
+use database::{Interfix, Map};
 use futures::{Stream, StreamExt};
 use ruma::{EventId, RoomId, UserId, api::Direction};
 
@@ -113,4 +113,31 @@ pub(super) fn mark_event_soft_failed(&self, event_id: &EventId) {
 	pub(super) async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 		self.softfailedeventids.get(event_id).await.is_ok()
 	}
+
+	pub(super) fn delete_relation(&self, from: u64, to: u64) {
+		let key: &[u64] = &[to, from];
+		self.tofrom_relation.del(key);
+	}
+
+	pub(super) async fn delete_relations(&self, to: u64) {
+		let prefix = to.to_be_bytes();
+		self.tofrom_relation
+			.raw_keys_prefix(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.tofrom_relation.remove(key))
+			.await;
+	}
+
+	pub(super) async fn delete_referenced(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		self.referencedevents
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.referencedevents.remove(key))
+			.await;
+	}
+
+	pub(super) fn unmark_event_soft_failed(&self, event_id: &EventId) {
+		self.softfailedeventids.remove(event_id);
+	}
 }
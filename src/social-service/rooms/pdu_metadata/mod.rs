@@ -135,4 +135,30 @@ pub fn mark_event_soft_failed(&self, event_id: &EventId) {
 	pub async fn is_event_soft_failed(&self, event_id: &EventId) -> bool {
 		self.db.is_event_soft_failed(event_id).await
 	}
+
+	#[inline]
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub fn unmark_event_soft_failed(&self, event_id: &EventId) {
+		self.db.unmark_event_soft_failed(event_id);
+	}
+
+	/// Remove the relation of an event to another.
+	#[tracing::instrument(skip(self, from, to), level = "debug")]
+	pub fn delete_relation(&self, from: PduCount, to: PduCount) {
+		if let (PduCount::Normal(f), PduCount::Normal(t)) = (from, to) {
+			self.db.delete_relation(f, t);
+		}
+	}
+
+	/// Remove the relations to an event.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn delete_relations(&self, to: PduCount) {
+		if let PduCount::Normal(to) = to {
+			self.db.delete_relations(to).await;
+		}
+	}
+
+	/// Remove the referenced events of a room.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, room_id: &RoomId) { self.db.delete_referenced(room_id).await; }
 }
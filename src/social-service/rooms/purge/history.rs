@@ -0,0 +1,317 @@
+//! Removal of the old history of a room. The non-state events before a point
+//! are deleted; state events are kept because the current state and the auth
+//! of later events rest on them. Like a room purge this is a background job
+//! kept in the `global` map, resuming after the last event it considered. The
+//! time the history was purged before is recorded so backfill does not fetch
+//! the deleted events again, and a cursor before the first event kept for
+//! being recent lets the next purge of the room start there.
+
+use std::collections::HashSet;
+
+use conduwuit::{
+	Err, Result, err, implement, info,
+	matrix::pdu::{PduCount, PduEvent},
+	utils::{stream::TryIgnore, time::now_millis},
+	warn,
+};
+use database::{Deserialized, Ignore, Interfix, Json};
+use futures::StreamExt;
+use ruma::{EventId, OwnedEventId, OwnedRoomId, RoomId};
+use serde::{Deserialize, Serialize};
+
+use super::BATCH_SIZE;
+
+/// Key prefix of the stored history purges in the `global` map.
+const PREFIX: &str = "room_history_purge";
+
+/// Key prefix of the times rooms' history was purged before in the `global`
+/// map.
+const PURGED_PREFIX: &str = "room_history_purged_before";
+
+/// Key prefix of the cursors of rooms' history in the `global` map.
+const CURSOR_PREFIX: &str = "room_history_cursor";
+
+/// Where the purge of a room's history stops.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum Before {
+	/// Events sent before this time, in milliseconds since the epoch.
+	Timestamp(u64),
+
+	/// Events preceding this one in the timeline.
+	Event(OwnedEventId),
+}
+
+/// The history of a room being purged.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct HistoryJob {
+	pub room_id: OwnedRoomId,
+
+	pub before: Before,
+
+	/// The last event considered, as a signed count; the purge resumes after
+	/// it.
+	last: Option<i64>,
+
+	/// The last event considered before the first one kept for being recent,
+	/// which the next purge of the room starts after.
+	cursor: Option<i64>,
+
+	/// Whether an event was kept for being recent.
+	deferred: bool,
+
+	/// Number of events removed so far.
+	pub removed: u64,
+
+	/// When the job was queued, in milliseconds since the epoch.
+	pub started: u64,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Until {
+	Count(PduCount),
+	Timestamp(u64),
+}
+
+/// What becomes of an event of the timeline.
+#[derive(Debug, Eq, PartialEq)]
+pub(super) enum Verdict {
+	Purge,
+
+	/// Kept for being part of the state or an extremity.
+	Keep,
+
+	/// Kept for being sent after the point; a later purge may delete it.
+	Later,
+
+	Stop,
+}
+
+/// Queue the deletion of the non-state events of the room before the point,
+/// except for its forward extremities. Queueing a room already queued moves
+/// its point.
+#[implement(super::Service)]
+#[tracing::instrument(skip(self), level = "debug")]
+pub async fn queue_history(&self, room_id: &RoomId, before: Before) -> Result<HistoryJob> {
+	if self.db.db.is_read_only() {
+		return Err!("The database is read-only.");
+	}
+
+	if self.services.short.get_shortroomid(room_id).await.is_err() {
+		return Err!(Request(NotFound("Room {room_id} is not known.")));
+	}
+
+	if let Before::Event(event_id) = &before {
+		self.event_until(room_id, event_id).await?;
+	}
+
+	let job = match self.history_job(room_id).await {
+		| Some(job) => HistoryJob { before, ..job },
+		| None => {
+			let cursor = self.history_cursor(room_id).await;
+			HistoryJob {
+				room_id: room_id.to_owned(),
+				before,
+				last: cursor,
+				cursor,
+				deferred: false,
+				removed: 0,
+				started: now_millis(),
+			}
+		},
+	};
+
+	self.save_history(&job);
+	self.queued.notify_one();
+
+	Ok(job)
+}
+
+/// The history purge of a room, if one was queued and has not completed.
+#[implement(super::Service)]
+pub async fn history_job(&self, room_id: &RoomId) -> Option<HistoryJob> {
+	self.db
+		.global
+		.qry(&(PREFIX, room_id))
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// History purges which were queued and have not completed, in the order they
+/// are run.
+#[implement(super::Service)]
+pub async fn history_jobs(&self) -> Vec<HistoryJob> {
+	self.db
+		.global
+		.stream_prefix(&(PREFIX, Interfix))
+		.ignore_err()
+		.map(|(_, job): (Ignore, HistoryJob)| job)
+		.collect()
+		.await
+}
+
+/// The last event of the room before which every event was considered by a
+/// purge, as a signed count.
+#[implement(super::Service)]
+async fn history_cursor(&self, room_id: &RoomId) -> Option<i64> {
+	self.db
+		.global
+		.qry(&(CURSOR_PREFIX, room_id))
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Time before which the history of the room was purged, in milliseconds
+/// since the epoch. Backfilled events older than this are not stored again.
+#[implement(super::Service)]
+pub async fn history_purged_before(&self, room_id: &RoomId) -> Option<u64> {
+	self.db
+		.global
+		.qry(&(PURGED_PREFIX, room_id))
+		.await
+		.deserialized()
+		.ok()
+}
+
+#[implement(super::Service)]
+#[tracing::instrument(skip(self, job), fields(room_id = %job.room_id), level = "debug")]
+pub(super) async fn run_history(&self, mut job: HistoryJob) {
+	let room_id = job.room_id.clone();
+	let until = match &job.before {
+		| Before::Timestamp(ts) => Ok((Until::Timestamp(*ts), *ts)),
+		| Before::Event(event_id) => self
+			.event_until(&room_id, event_id)
+			.await
+			.map(|(count, ts)| (Until::Count(count), ts)),
+	};
+
+	let shortroomid = self.services.short.get_shortroomid(&room_id).await;
+	let (Ok((until, until_ts)), Ok(shortroomid)) = (until, shortroomid) else {
+		warn!(%room_id, "History purge abandoned; its room or event is gone");
+		self.forget_history_job(&room_id);
+		return;
+	};
+
+	let extremities: HashSet<OwnedEventId> = self
+		.services
+		.state
+		.get_forward_extremities(&room_id)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	'batches: loop {
+		let last = job.last.map(PduCount::from_signed);
+		let batch: Vec<(PduCount, PduEvent)> = self
+			.services
+			.timeline
+			.pdus(None, &room_id, last)
+			.ignore_err()
+			.take(BATCH_SIZE)
+			.collect()
+			.await;
+
+		if batch.is_empty() {
+			break;
+		}
+
+		for (count, pdu) in batch {
+			let keep = pdu.state_key.is_some() || extremities.contains(&pdu.event_id);
+			match until.verdict(count, u64::from(pdu.origin_server_ts), keep) {
+				| Verdict::Stop => break 'batches,
+				| Verdict::Keep => (),
+				| Verdict::Later => job.deferred = true,
+				| Verdict::Purge => {
+					self.services
+						.timeline
+						.purge_pdu(shortroomid, count, &pdu)
+						.await;
+
+					if let Ok(shorteventid) =
+						self.services.short.get_shorteventid(&pdu.event_id).await
+					{
+						self.services
+							.state
+							.delete_event_shortstatehash(shorteventid);
+					}
+
+					job.removed = job.removed.saturating_add(1);
+				},
+			}
+
+			job.last = Some(count.into_signed());
+			if !job.deferred {
+				job.cursor = job.last;
+			}
+		}
+
+		self.save_history(&job);
+		if !self.server.running() {
+			info!(%room_id, removed = job.removed, "Purge of room history paused until restart");
+			return;
+		}
+	}
+
+	if self
+		.history_purged_before(&room_id)
+		.await
+		.is_none_or(|purged| purged < until_ts)
+	{
+		self.db.global.put((PURGED_PREFIX, &room_id), until_ts);
+	}
+
+	if let Some(cursor) = job.cursor {
+		self.db.global.put((CURSOR_PREFIX, &room_id), cursor);
+	}
+
+	self.forget_history_job(&room_id);
+	info!(%room_id, removed = job.removed, "Purged room history");
+}
+
+#[implement(super::Service)]
+fn save_history(&self, job: &HistoryJob) {
+	self.db.global.put((PREFIX, &job.room_id), Json(job));
+}
+
+#[implement(super::Service)]
+fn forget_history_job(&self, room_id: &RoomId) { self.db.global.del((PREFIX, room_id)); }
+
+#[implement(super::Service)]
+pub(super) fn forget_history(&self, room_id: &RoomId) {
+	self.forget_history_job(room_id);
+	self.db.global.del((PURGED_PREFIX, room_id));
+	self.db.global.del((CURSOR_PREFIX, room_id));
+}
+
+/// The count and timestamp of the event a purge stops at.
+#[implement(super::Service)]
+async fn event_until(&self, room_id: &RoomId, event_id: &EventId) -> Result<(PduCount, u64)> {
+	let pdu = self
+		.services
+		.timeline
+		.get_pdu(event_id)
+		.await
+		.map_err(|_| err!(Request(NotFound("Event {event_id} is not in the timeline."))))?;
+
+	if pdu.room_id != room_id {
+		return Err!(Request(InvalidParam("Event {event_id} is not in room {room_id}.")));
+	}
+
+	let count = self.services.timeline.get_pdu_count(event_id).await?;
+
+	Ok((count, u64::from(pdu.origin_server_ts)))
+}
+
+impl Until {
+	/// The timeline is in count order, so reaching the count ends the purge.
+	/// Timestamps need not be in order, so later events are only skipped.
+	pub(super) fn verdict(&self, count: PduCount, origin_server_ts: u64, keep: bool) -> Verdict {
+		match self {
+			| Self::Count(until) if count >= *until => Verdict::Stop,
+			| Self::Timestamp(until) if origin_server_ts >= *until => Verdict::Later,
+			| _ if keep => Verdict::Keep,
+			| _ => Verdict::Purge,
+		}
+	}
+}
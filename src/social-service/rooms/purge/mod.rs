@@ -0,0 +1,599 @@
+//! Erasure of rooms from the database. A purge is a background job removing a
+//! room from each service in turn. Jobs are kept in the `global` map so one
+//! interrupted by a shutdown resumes at the phase it was in; each phase can be
+//! repeated.
+
+mod history;
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashSet, iter, pin::pin, str, sync::Arc};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Result, Server, implement, info,
+	matrix::pdu::{PduCount, PduEvent, PduId},
+	utils::{ReadyExt, stream::TryIgnore, time::now_millis},
+	warn,
+};
+use database::{Database, Ignore, Interfix, Json, Map};
+use futures::{Stream, StreamExt};
+use ruma::{Mxc, OwnedRoomId, RoomId};
+use serde::{Deserialize, Serialize};
+use service_core::{Args, Dep, Service as ServiceTrait};
+use tokio::sync::Notify;
+
+pub use self::history::{Before, HistoryJob};
+use crate::{account_data, admin, media, rooms, rooms::short::ShortRoomId};
+
+pub struct Service {
+	queued: Notify,
+	interrupt: Notify,
+	server: Arc<Server>,
+	services: Services,
+	db: Data,
+}
+
+struct Services {
+	account_data: Dep<account_data::Service>,
+	admin: Dep<admin::Service>,
+	alias: Dep<rooms::alias::Service>,
+	directory: Dep<rooms::directory::Service>,
+	media: Dep<media::Service>,
+	metadata: Dep<rooms::metadata::Service>,
+	outlier: Dep<rooms::outlier::Service>,
+	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
+	read_receipt: Dep<rooms::read_receipt::Service>,
+	search: Dep<rooms::search::Service>,
+	short: Dep<rooms::short::Service>,
+	state: Dep<rooms::state::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	state_compressor: Dep<rooms::state_compressor::Service>,
+	threads: Dep<rooms::threads::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+	user: Dep<rooms::user::Service>,
+}
+
+struct Data {
+	global: Arc<Map>,
+	pduid_pdu: Arc<Map>,
+	eventid_outlierpdu: Arc<Map>,
+	roomuserdataid_accountdata: Arc<Map>,
+	userid_avatarurl: Arc<Map>,
+	useridprofilekey_value: Arc<Map>,
+	db: Arc<Database>,
+}
+
+/// A room being purged.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Job {
+	pub room_id: OwnedRoomId,
+
+	/// Also delete the media which no other room refers to.
+	pub delete_media: bool,
+
+	/// The phase being run; those before it are complete.
+	pub phase: Phase,
+
+	/// Number of PDUs removed so far.
+	pub pdus: u64,
+
+	/// Number of media files removed so far.
+	pub media: u64,
+
+	/// When the job was queued, in milliseconds since the epoch.
+	pub started: u64,
+}
+
+/// Steps of a purge, in the order they are run. The state of the room must be
+/// collected before the timeline is removed, and the short room ID, which
+/// locates most of the room's records, is removed last.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub enum Phase {
+	Media,
+	Search,
+	Threads,
+	Receipts,
+	State,
+	Timeline,
+	Outliers,
+	Membership,
+	Room,
+}
+
+/// Key prefix of the stored jobs in the `global` map.
+const PREFIX: &str = "room_purge";
+
+/// Number of PDUs removed between checks for shutdown.
+const BATCH_SIZE: usize = 1024;
+
+const PHASES: [Phase; 9] = [
+	Phase::Media,
+	Phase::Search,
+	Phase::Threads,
+	Phase::Receipts,
+	Phase::State,
+	Phase::Timeline,
+	Phase::Outliers,
+	Phase::Membership,
+	Phase::Room,
+];
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			queued: Notify::new(),
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			services: Services {
+				account_data: args.depend::<account_data::Service>("account_data"),
+				admin: args.depend::<admin::Service>("admin"),
+				alias: args.depend::<rooms::alias::Service>("rooms::alias"),
+				directory: args.depend::<rooms::directory::Service>("rooms::directory"),
+				media: args.depend::<media::Service>("media"),
+				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
+				outlier: args.depend::<rooms::outlier::Service>("rooms::outlier"),
+				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
+				read_receipt: args.depend::<rooms::read_receipt::Service>("rooms::read_receipt"),
+				search: args.depend::<rooms::search::Service>("rooms::search"),
+				short: args.depend::<rooms::short::Service>("rooms::short"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				state_compressor: args
+					.depend::<rooms::state_compressor::Service>("rooms::state_compressor"),
+				threads: args.depend::<rooms::threads::Service>("rooms::threads"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+				user: args.depend::<rooms::user::Service>("rooms::user"),
+			},
+			db: Data {
+				global: args.db["global"].clone(),
+				pduid_pdu: args.db["pduid_pdu"].clone(),
+				eventid_outlierpdu: args.db["eventid_outlierpdu"].clone(),
+				roomuserdataid_accountdata: args.db["roomuserdataid_accountdata"].clone(),
+				userid_avatarurl: args.db["userid_avatarurl"].clone(),
+				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
+				db: args.db.clone(),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "purge", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.db.db.is_read_only() {
+			return Ok(());
+		}
+
+		while self.server.running() {
+			while let Some(job) = self.jobs().await.into_iter().next() {
+				self.run(job).await;
+				if !self.server.running() {
+					return Ok(());
+				}
+			}
+
+			while let Some(job) = self.history_jobs().await.into_iter().next() {
+				self.run_history(job).await;
+				if !self.server.running() {
+					return Ok(());
+				}
+			}
+
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				() = self.queued.notified() => (),
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+impl Job {
+	/// Add the deletion of media to a queued job. Once the media phase has run
+	/// this is refused, as the media would not be deleted.
+	pub fn with_delete_media(self, delete_media: bool) -> Result<Self> {
+		if delete_media && !self.delete_media && self.phase != Phase::Media {
+			return Err!(Request(InvalidParam(
+				"The purge of room {} is past its media phase; its media cannot be deleted \
+				 anymore.",
+				self.room_id
+			)));
+		}
+
+		Ok(Self {
+			delete_media: self.delete_media || delete_media,
+			..self
+		})
+	}
+}
+
+impl Phase {
+	#[must_use]
+	pub fn next(self) -> Option<Self> {
+		let pos = PHASES.iter().position(|phase| *phase == self)?;
+		PHASES.get(pos.saturating_add(1)).copied()
+	}
+}
+
+/// Queue a room to be purged. The room must have been banned first so nothing
+/// is added to it while it is purged. Queueing a room already queued only
+/// adds the deletion of media to it, which fails once its media phase is over.
+#[implement(Service)]
+pub async fn queue(&self, room_id: &RoomId, delete_media: bool) -> Result<Job> {
+	if self.db.db.is_read_only() {
+		return Err!("The database is read-only.");
+	}
+
+	if self.services.admin.is_admin_room(room_id).await {
+		return Err!(Request(Forbidden("The admin room cannot be purged.")));
+	}
+
+	if !self.services.metadata.is_banned(room_id).await {
+		return Err!(Request(Forbidden("Room {room_id} must be banned before it is purged.")));
+	}
+
+	let job = match self.get(room_id).await {
+		| Some(job) => job.with_delete_media(delete_media)?,
+		| None => Job {
+			room_id: room_id.to_owned(),
+			delete_media,
+			phase: Phase::Media,
+			pdus: 0,
+			media: 0,
+			started: now_millis(),
+		},
+	};
+
+	self.save(&job);
+	self.queued.notify_one();
+
+	Ok(job)
+}
+
+/// The purge of a room, if one was queued and has not completed.
+#[implement(Service)]
+pub async fn get(&self, room_id: &RoomId) -> Option<Job> {
+	self.jobs()
+		.await
+		.into_iter()
+		.find(|job| job.room_id == room_id)
+}
+
+/// Purges which were queued and have not completed, in the order they are run.
+#[implement(Service)]
+pub async fn jobs(&self) -> Vec<Job> {
+	self.db
+		.global
+		.stream_prefix(&(PREFIX, Interfix))
+		.ignore_err()
+		.map(|(_, job): (Ignore, Job)| job)
+		.collect()
+		.await
+}
+
+#[implement(Service)]
+#[tracing::instrument(skip(self, job), fields(room_id = %job.room_id), level = "debug")]
+async fn run(&self, mut job: Job) {
+	let room_id = job.room_id.clone();
+	info!(%room_id, phase = ?job.phase, "Purging room...");
+	self.services
+		.admin
+		.send_text(&format!("Purging room {room_id} from the {:?} phase.", job.phase))
+		.await;
+
+	let shortroomid = self.services.short.get_shortroomid(&room_id).await.ok();
+	loop {
+		if !self.server.running() {
+			info!(%room_id, phase = ?job.phase, "Purge of room paused until restart");
+			return;
+		}
+
+		let phase = job.phase;
+		self.run_phase(&mut job, shortroomid).await;
+		info!(%room_id, ?phase, pdus = job.pdus, media = job.media, "Purge phase complete");
+
+		// The deletion of media may have been added while the media phase ran.
+		let delete_media = self
+			.get(&room_id)
+			.await
+			.is_some_and(|stored| stored.delete_media);
+
+		if delete_media && !job.delete_media {
+			job.delete_media = true;
+			if phase == Phase::Media {
+				continue;
+			}
+		}
+
+		let Some(next) = phase.next() else {
+			break;
+		};
+
+		job.phase = next;
+		self.save(&job);
+	}
+
+	self.forget(&room_id);
+	info!(%room_id, pdus = job.pdus, media = job.media, "Purged room");
+	self.services
+		.admin
+		.send_text(&format!(
+			"Purged room {room_id}: removed {} PDUs and {} media files.",
+			job.pdus, job.media
+		))
+		.await;
+}
+
+/// Run the current phase of the job. Phases which locate records by the short
+/// room ID are skipped when it is gone.
+#[implement(Service)]
+async fn run_phase(&self, job: &mut Job, shortroomid: Option<ShortRoomId>) {
+	let room_id = job.room_id.clone();
+	match (job.phase, shortroomid) {
+		| (Phase::Media, Some(shortroomid)) if job.delete_media => {
+			self.purge_media(job, shortroomid).await;
+		},
+		| (Phase::Search, Some(shortroomid)) => {
+			self.services.search.deindex_room(shortroomid).await;
+		},
+		| (Phase::Threads, Some(shortroomid)) => {
+			self.services.threads.delete_room_threads(shortroomid).await;
+		},
+		| (Phase::Receipts, _) => {
+			self.services.read_receipt.purge_room(&room_id).await;
+		},
+		| (Phase::State, _) => {
+			self.purge_state(&room_id).await;
+		},
+		| (Phase::Timeline, Some(shortroomid)) => {
+			self.purge_timeline(job, shortroomid).await;
+		},
+		| (Phase::Outliers, _) => {
+			self.purge_outliers(&room_id).await;
+		},
+		| (Phase::Membership, _) => {
+			let users = self.services.state_cache.room_users(&room_id).await;
+			self.services.user.purge_room(&room_id, &users).await;
+			self.services.account_data.purge_room(&room_id).await;
+			self.services.state_cache.purge_room(&room_id, &users).await;
+		},
+		| (Phase::Room, _) => {
+			self.services.alias.purge_room(&room_id).await;
+			self.services.directory.set_not_public(&room_id);
+			self.services.pdu_metadata.purge_room(&room_id).await;
+			self.services.state.purge_room(&room_id).await;
+			self.services.short.delete_shortroomid(&room_id);
+			self.forget_history(&room_id);
+		},
+		| _ => (),
+	}
+}
+
+/// Delete the media referred to by the room's events and by nothing else: the
+/// events and outliers of other rooms, account data outside the room, and
+/// profiles are all checked. Without an index of media references this reads
+/// each of those maps in full, stopping once every candidate is referred to.
+#[implement(Service)]
+async fn purge_media(&self, job: &mut Job, shortroomid: ShortRoomId) {
+	let room_id = job.room_id.clone();
+	let prefix = shortroomid.to_be_bytes();
+	let mut candidates = HashSet::<String>::new();
+	self.db
+		.pduid_pdu
+		.raw_stream_prefix(&prefix)
+		.ignore_err()
+		.ready_for_each(|(_, pdu)| candidates.extend(mxc_uris(pdu).map(ToOwned::to_owned)))
+		.await;
+
+	let room_prefix = [room_id.as_bytes(), &[0xFF]].concat();
+	let sources = [
+		(&self.db.pduid_pdu, prefix.as_slice()),
+		(&self.db.roomuserdataid_accountdata, room_prefix.as_slice()),
+		(&self.db.userid_avatarurl, [].as_slice()),
+		(&self.db.useridprofilekey_value, [].as_slice()),
+	];
+
+	for (map, skip) in sources {
+		let records = map
+			.raw_stream()
+			.ignore_err()
+			.ready_filter(|(key, _)| skip.is_empty() || !key.starts_with(skip));
+
+		release_referenced(&mut candidates, records).await;
+	}
+
+	let outliers = self
+		.db
+		.eventid_outlierpdu
+		.raw_stream()
+		.ignore_err()
+		.ready_filter(|(_, pdu)| !in_room(pdu, &room_id));
+
+	release_referenced(&mut candidates, outliers).await;
+
+	for uri in &candidates {
+		let Ok(mxc) = Mxc::try_from(uri.as_str()) else {
+			continue;
+		};
+
+		match self.services.media.delete(&mxc).await {
+			| Ok(()) => job.media = job.media.saturating_add(1),
+			| Err(e) => warn!(%uri, "Failed to delete media of purged room: {e}"),
+		}
+	}
+}
+
+/// Delete every state the room has been in. The states are found through the
+/// PDUs, so this runs before the timeline is removed.
+#[implement(Service)]
+async fn purge_state(&self, room_id: &RoomId) {
+	let mut shortstatehashes: HashSet<_> = self
+		.services
+		.timeline
+		.pdus(None, room_id, None)
+		.ignore_err()
+		.filter_map(|(_, pdu): (PduCount, PduEvent)| async move {
+			self.services
+				.state_accessor
+				.pdu_shortstatehash(&pdu.event_id)
+				.await
+				.ok()
+		})
+		.collect()
+		.await;
+
+	if let Ok(shortstatehash) = self.services.state.get_room_shortstatehash(room_id).await {
+		shortstatehashes.insert(shortstatehash);
+	}
+
+	let removed = self
+		.services
+		.state_compressor
+		.delete_states(shortstatehashes)
+		.await;
+
+	self.services.short.delete_shortstatehashes(&removed).await;
+}
+
+/// Delete the PDUs of the room in batches, along with their relations and
+/// short IDs. Progress is saved after each batch.
+#[implement(Service)]
+async fn purge_timeline(&self, job: &mut Job, shortroomid: ShortRoomId) {
+	let room_id = job.room_id.clone();
+	loop {
+		let batch: Vec<(PduCount, PduEvent)> = self
+			.services
+			.timeline
+			.pdus(None, &room_id, None)
+			.ignore_err()
+			.take(BATCH_SIZE)
+			.collect()
+			.await;
+
+		if batch.is_empty() {
+			break;
+		}
+
+		for (count, pdu) in &batch {
+			let event_id = &pdu.event_id;
+			self.services.pdu_metadata.delete_relations(*count).await;
+			self.services
+				.pdu_metadata
+				.unmark_event_soft_failed(event_id);
+			if let Ok(shorteventid) = self.services.short.get_shorteventid(event_id).await {
+				self.services
+					.state
+					.delete_event_shortstatehash(shorteventid);
+				self.services
+					.short
+					.delete_shorteventid(event_id, shorteventid);
+			}
+
+			let pdu_id = PduId { shortroomid, shorteventid: *count };
+			self.services.timeline.delete_pdu(&pdu_id.into(), event_id);
+		}
+
+		let removed = u64::try_from(batch.len()).unwrap_or(u64::MAX);
+		job.pdus = job.pdus.saturating_add(removed);
+		self.save(job);
+
+		if !self.server.running() {
+			return;
+		}
+	}
+
+	let remaining = self.services.timeline.purge_room(shortroomid).await;
+	let remaining = u64::try_from(remaining).unwrap_or(u64::MAX);
+	job.pdus = job.pdus.saturating_add(remaining);
+}
+
+#[implement(Service)]
+async fn purge_outliers(&self, room_id: &RoomId) {
+	for event_id in self.services.outlier.purge_room(room_id).await {
+		self.services
+			.pdu_metadata
+			.unmark_event_soft_failed(&event_id);
+
+		if let Ok(shorteventid) = self.services.short.get_shorteventid(&event_id).await {
+			self.services
+				.state
+				.delete_event_shortstatehash(shorteventid);
+			self.services
+				.short
+				.delete_shorteventid(&event_id, shorteventid);
+		}
+	}
+}
+
+#[implement(Service)]
+fn save(&self, job: &Job) { self.db.global.put((PREFIX, &job.room_id), Json(job)); }
+
+#[implement(Service)]
+fn forget(&self, room_id: &RoomId) { self.db.global.del((PREFIX, room_id)); }
+
+/// Remove from the candidates the URIs referred to by the values of the
+/// records, stopping once none remain.
+async fn release_referenced<'a, S>(candidates: &mut HashSet<String>, records: S)
+where
+	S: Stream<Item = (&'a [u8], &'a [u8])> + Send,
+{
+	let mut records = pin!(records);
+	while !candidates.is_empty() {
+		let Some((_, value)) = records.next().await else {
+			break;
+		};
+
+		release(candidates, value);
+	}
+}
+
+fn release(candidates: &mut HashSet<String>, value: &[u8]) {
+	for uri in mxc_uris(value) {
+		candidates.remove(uri);
+	}
+}
+
+/// Whether the JSON of an event belongs to the room.
+fn in_room(pdu: &[u8], room_id: &RoomId) -> bool {
+	#[derive(Deserialize)]
+	struct Event<'a> {
+		#[serde(borrow)]
+		room_id: &'a RoomId,
+	}
+
+	serde_json::from_slice::<Event<'_>>(pdu).is_ok_and(|event| event.room_id == room_id)
+}
+
+/// MXC URIs appearing anywhere in the JSON of an event.
+fn mxc_uris(json: &[u8]) -> impl Iterator<Item = &str> + '_ {
+	const SCHEME: &[u8] = b"mxc://";
+
+	let mut rest = json;
+	iter::from_fn(move || {
+		loop {
+			let start = rest
+				.windows(SCHEME.len())
+				.position(|window| window == SCHEME)?;
+
+			let uri = rest.get(start..)?;
+			let end = uri
+				.iter()
+				.position(|&byte| byte == b'"' || byte == b'\\')
+				.unwrap_or(uri.len());
+
+			let (uri, tail) = uri.split_at(end);
+			rest = tail;
+			if let Ok(uri) = str::from_utf8(uri) {
+				return Some(uri);
+			}
+		}
+	})
+}
@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use conduwuit::matrix::pdu::PduCount;
+use ruma::room_id;
+
+use super::{
+	Job, PHASES, Phase,
+	history::{Until, Verdict},
+	in_room, mxc_uris, release,
+};
+
+fn job(phase: Phase, delete_media: bool) -> Job {
+	Job {
+		room_id: room_id!("!purged:example.com").to_owned(),
+		delete_media,
+		phase,
+		pdus: 0,
+		media: 0,
+		started: 0,
+	}
+}
+
+#[test]
+fn mxc_uris_found() {
+	let json = br#"{"content":{"body":"cat.png","url":"mxc://example.com/abc","info":{"thumbnail_url":"mxc://example.com/def"}}}"#;
+	let uris: Vec<_> = mxc_uris(json).collect();
+	assert_eq!(uris, ["mxc://example.com/abc", "mxc://example.com/def"]);
+}
+
+#[test]
+fn mxc_uris_escaped() {
+	let json = br#"{"content":{"formatted_body":"<img src=\"mxc://example.com/abc\">"}}"#;
+	let uris: Vec<_> = mxc_uris(json).collect();
+	assert_eq!(uris, ["mxc://example.com/abc"]);
+}
+
+#[test]
+fn mxc_uris_none() {
+	let json = br#"{"content":{"body":"hello","url":"https://example.com/mxc"}}"#;
+	assert_eq!(mxc_uris(json).count(), 0);
+}
+
+#[test]
+fn release_referenced_uris() {
+	let mut candidates: HashSet<String> = ["mxc://example.com/abc", "mxc://example.com/def"]
+		.into_iter()
+		.map(ToOwned::to_owned)
+		.collect();
+
+	release(&mut candidates, b"mxc://example.com/abc");
+	release(&mut candidates, br#"{"avatar_url":"mxc://example.com/ghi"}"#);
+	assert_eq!(candidates.len(), 1);
+	assert!(candidates.contains("mxc://example.com/def"));
+}
+
+#[test]
+fn in_room_matches() {
+	let room_id = room_id!("!purged:example.com");
+	assert!(in_room(
+		br#"{"room_id":"!purged:example.com","type":"m.room.message"}"#,
+		room_id
+	));
+	assert!(!in_room(br#"{"room_id":"!other:example.com"}"#, room_id));
+	assert!(!in_room(b"{}", room_id));
+}
+
+#[test]
+fn phases_in_order() {
+	let mut phases = vec![Phase::Media];
+	while let Some(next) = phases.last().and_then(|phase| phase.next()) {
+		phases.push(next);
+	}
+
+	assert_eq!(phases, PHASES);
+}
+
+#[test]
+fn delete_media_added_before_media_phase() {
+	let job = job(Phase::Media, false)
+		.with_delete_media(true)
+		.expect("media phase not run yet");
+
+	assert!(job.delete_media);
+}
+
+#[test]
+fn delete_media_refused_after_media_phase() {
+	assert!(job(Phase::Search, false).with_delete_media(true).is_err());
+	assert!(job(Phase::Search, true).with_delete_media(true).is_ok());
+	assert!(
+		!job(Phase::Room, false)
+			.with_delete_media(false)
+			.expect("nothing added")
+			.delete_media
+	);
+}
+
+#[test]
+fn history_until_count() {
+	let until = Until::Count(PduCount::Normal(10));
+	assert_eq!(until.verdict(PduCount::Normal(9), u64::MAX, false), Verdict::Purge);
+	assert_eq!(until.verdict(PduCount::Normal(9), 0, true), Verdict::Keep);
+	assert_eq!(until.verdict(PduCount::Normal(10), 0, false), Verdict::Stop);
+	assert_eq!(until.verdict(PduCount::Backfilled(-5), 0, false), Verdict::Purge);
+}
+
+#[test]
+fn history_until_timestamp_out_of_order() {
+	let until = Until::Timestamp(1000);
+	let events = [(1, 500), (2, 1500), (3, 700), (4, 2000), (5, 900)];
+	let verdicts: Vec<_> = events
+		.into_iter()
+		.map(|(count, ts)| until.verdict(PduCount::Normal(count), ts, false))
+		.collect();
+
+	assert_eq!(verdicts, [
+		Verdict::Purge,
+		Verdict::Later,
+		Verdict::Purge,
+		Verdict::Later,
+		Verdict::Purge
+	]);
+	assert_eq!(until.verdict(PduCount::Normal(6), 10, true), Verdict::Keep);
+}
@@ -4,7 +4,7 @@
 	Result,
 	utils::{ReadyExt, stream::TryIgnore},
 };
-use database::{Deserialized, Json, Map};
+use database::{Deserialized, Interfix, Json, Map};
 use futures::{Stream, StreamExt};
 use ruma::{
 	CanonicalJsonObject, RoomId, UserId,
@@ -116,4 +116,18 @@ pub(super) async fn last_privateread_update(
 			.deserialized()
 			.unwrap_or(0)
 	}
+
+	pub(super) async fn delete_room(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		for map in [
+			&self.readreceiptid_readreceipt,
+			&self.roomuserid_privateread,
+			&self.roomuserid_lastprivatereadupdate,
+		] {
+			map.keys_prefix_raw(&prefix)
+				.ignore_err()
+				.ready_for_each(|key| map.remove(key))
+				.await;
+		}
+	}
 }
@@ -138,6 +138,10 @@ pub async fn private_read_get_count(
 	pub async fn last_privateread_update(&self, user_id: &UserId, room_id: &RoomId) -> u64 {
 		self.db.last_privateread_update(user_id, room_id).await
 	}
+
+	/// Removes all public and private receipts in this room.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, room_id: &RoomId) { self.db.delete_room(room_id).await; }
 }
 
 #[must_use]
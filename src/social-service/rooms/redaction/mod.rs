@@ -0,0 +1,493 @@
+//! Bulk redaction of the events a user sent. Like a purge this is a background
+//! job kept in the `global` map, resuming in the room and at the event it had
+//! reached after a shutdown. Removing a job from the map cancels it before its
+//! next page of events.
+
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use conduwuit::{
+	Err, Result, Server, implement, info,
+	matrix::pdu::{PduBuilder, PduCount, PduEvent},
+	utils::{MutexMap, ReadyExt, stream::TryIgnore, time::now_millis},
+	warn,
+};
+use database::{Database, Deserialized, Ignore, Interfix, Json, Map};
+use futures::StreamExt;
+use ruma::{
+	EventId, OwnedRoomId, OwnedUserId, RoomId, UserId,
+	events::{TimelineEventType, room::redaction::RoomRedactionEventContent},
+};
+use serde::{Deserialize, Serialize};
+use service_core::{Args, Dep, Service as ServiceTrait};
+use tokio::{sync::Notify, time::sleep};
+
+use crate::{admin, globals, rooms};
+
+pub struct Service {
+	queued: Notify,
+	interrupt: Notify,
+	mutex_job: MutexMap<String, ()>,
+	server: Arc<Server>,
+	services: Services,
+	db: Data,
+}
+
+struct Services {
+	admin: Dep<admin::Service>,
+	globals: Dep<globals::Service>,
+	state: Dep<rooms::state::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+	state_cache: Dep<rooms::state_cache::Service>,
+	timeline: Dep<rooms::timeline::Service>,
+}
+
+struct Data {
+	global: Arc<Map>,
+	db: Arc<Database>,
+}
+
+/// The events of a user being redacted.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Job {
+	pub user_id: OwnedUserId,
+
+	/// Rooms not done yet, the one being gone through first.
+	pub rooms: Vec<OwnedRoomId>,
+
+	/// Only events sent at or after this time, in milliseconds since the
+	/// epoch, are redacted.
+	pub since: Option<u64>,
+
+	/// Milliseconds to wait between redactions.
+	pub delay: u64,
+
+	/// The last event considered in the current room, as a signed count; the
+	/// job resumes before it.
+	last: Option<i64>,
+
+	/// Number of events sent before `since` met in a row in the current room.
+	stale: usize,
+
+	/// Number of events redacted so far.
+	pub redacted: u64,
+
+	/// Number of redactions which failed so far.
+	pub failed: u64,
+
+	/// Rooms where no local user may redact the user's events.
+	pub skipped: Vec<OwnedRoomId>,
+
+	/// When the job was queued, in milliseconds since the epoch.
+	pub started: u64,
+}
+
+/// How the job left a room.
+enum Outcome {
+	Done,
+	Skipped,
+	Paused,
+	Cancelled,
+}
+
+/// Key prefix of the stored jobs in the `global` map.
+const PREFIX: &str = "user_redaction";
+
+/// Number of events read between saves of the progress.
+const PAGE_SIZE: usize = 100;
+
+/// Number of events sent before `since` met in a row after which the rest of a
+/// room is taken to be older too.
+const SINCE_SLACK: usize = 1000;
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			queued: Notify::new(),
+			interrupt: Notify::new(),
+			mutex_job: MutexMap::new(),
+			server: args.server.clone(),
+			services: Services {
+				admin: args.depend::<admin::Service>("admin"),
+				globals: args.depend::<globals::Service>("globals"),
+				state: args.depend::<rooms::state::Service>("rooms::state"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+				state_cache: args.depend::<rooms::state_cache::Service>("rooms::state_cache"),
+				timeline: args.depend::<rooms::timeline::Service>("rooms::timeline"),
+			},
+			db: Data {
+				global: args.db["global"].clone(),
+				db: args.db.clone(),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "redaction", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		if self.db.db.is_read_only() {
+			return Ok(());
+		}
+
+		while self.server.running() {
+			while let Some(job) = self.jobs().await.into_iter().next() {
+				self.run(job).await;
+				if !self.server.running() {
+					return Ok(());
+				}
+			}
+
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				() = self.queued.notified() => (),
+			}
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+/// Queue the redaction of the non-state events the user sent in the room, or
+/// in every room they are or were in. A user's events are redacted by one job
+/// at a time.
+#[implement(Service)]
+pub async fn queue(
+	&self,
+	user_id: &UserId,
+	room_id: Option<OwnedRoomId>,
+	since: Option<u64>,
+	delay: u64,
+) -> Result<Job> {
+	if self.db.db.is_read_only() {
+		return Err!("The database is read-only.");
+	}
+
+	let _lock = self.mutex_job.lock(&user_id.to_string()).await;
+	if self.get(user_id).await.is_some() {
+		return Err!(Request(Forbidden(
+			"The events of {user_id} are already being redacted; cancel that first."
+		)));
+	}
+
+	let rooms: Vec<OwnedRoomId> = match room_id {
+		| Some(room_id) => vec![room_id],
+		| None =>
+			self.services
+				.state_cache
+				.rooms_joined(user_id)
+				.map(ToOwned::to_owned)
+				.chain(
+					self.services
+						.state_cache
+						.rooms_left(user_id)
+						.map(|(room_id, _)| room_id),
+				)
+				.collect()
+				.await,
+	};
+
+	let job = Job {
+		user_id: user_id.to_owned(),
+		rooms,
+		since,
+		delay,
+		last: None,
+		stale: 0,
+		redacted: 0,
+		failed: 0,
+		skipped: Vec::new(),
+		started: now_millis(),
+	};
+
+	self.db.global.put((PREFIX, user_id), Json(&job));
+	self.queued.notify_one();
+
+	Ok(job)
+}
+
+/// Cancel the redaction of a user's events; those already redacted stay so.
+#[implement(Service)]
+pub async fn cancel(&self, user_id: &UserId) -> Result<Job> {
+	let _lock = self.mutex_job.lock(&user_id.to_string()).await;
+	let Some(job) = self.get(user_id).await else {
+		return Err!(Request(NotFound("The events of {user_id} are not being redacted.")));
+	};
+
+	self.db.global.del((PREFIX, user_id));
+
+	Ok(job)
+}
+
+/// The redaction of a user's events, if one was queued and has not completed.
+#[implement(Service)]
+pub async fn get(&self, user_id: &UserId) -> Option<Job> {
+	self.db
+		.global
+		.qry(&(PREFIX, user_id))
+		.await
+		.deserialized()
+		.ok()
+}
+
+/// Redactions which were queued and have not completed, in the order they are
+/// run.
+#[implement(Service)]
+pub async fn jobs(&self) -> Vec<Job> {
+	self.db
+		.global
+		.stream_prefix(&(PREFIX, Interfix))
+		.ignore_err()
+		.map(|(_, job): (Ignore, Job)| job)
+		.collect()
+		.await
+}
+
+#[implement(Service)]
+#[tracing::instrument(skip(self, job), fields(user_id = %job.user_id), level = "debug")]
+async fn run(&self, mut job: Job) {
+	let user_id = job.user_id.clone();
+	let reason = format!(
+		"The administrator(s) of {} has redacted this user's message.",
+		self.services.globals.server_name()
+	);
+
+	self.services
+		.admin
+		.send_text(&format!("Redacting events sent by {user_id} in {} rooms...", job.rooms.len()))
+		.await;
+
+	while let Some(room_id) = job.rooms.first().cloned() {
+		let (redacted, failed) = (job.redacted, job.failed);
+		match self.redact_room(&mut job, &room_id, &reason).await {
+			| Outcome::Paused => {
+				info!(%user_id, redacted = job.redacted, "Redaction paused until restart");
+				return;
+			},
+			| Outcome::Cancelled => {
+				self.cancelled(&job).await;
+				return;
+			},
+			| Outcome::Skipped => {
+				self.services
+					.admin
+					.send_text(&format!(
+						"No local user can redact {user_id}'s events in {room_id}, skipping."
+					))
+					.await;
+				job.skipped.push(room_id);
+			},
+			| Outcome::Done =>
+				if job.redacted > redacted || job.failed > failed {
+					self.services
+						.admin
+						.send_text(&format!(
+							"Redacted {} events in {room_id} ({} failed).",
+							job.redacted.saturating_sub(redacted),
+							job.failed.saturating_sub(failed)
+						))
+						.await;
+				},
+		}
+
+		job.rooms.remove(0);
+		job.last = None;
+		job.stale = 0;
+		if !self.save(&job).await {
+			self.cancelled(&job).await;
+			return;
+		}
+	}
+
+	self.db.global.del((PREFIX, &user_id));
+	info!(%user_id, redacted = job.redacted, failed = job.failed, "Redacted user's events");
+
+	let mut out = format!(
+		"Finished redacting events sent by {user_id}: {} redacted, {} failed.",
+		job.redacted, job.failed
+	);
+
+	if !job.skipped.is_empty() {
+		out.push_str("\n\nRooms skipped for lack of permission to redact:");
+		for room_id in &job.skipped {
+			out.push_str("\n- ");
+			out.push_str(room_id.as_str());
+		}
+	}
+
+	self.services.admin.send_text(&out).await;
+}
+
+#[implement(Service)]
+async fn cancelled(&self, job: &Job) {
+	let user_id = &job.user_id;
+	info!(%user_id, redacted = job.redacted, "Redaction cancelled");
+	self.services
+		.admin
+		.send_text(&format!(
+			"Cancelled redacting events sent by {user_id} after {} redactions.",
+			job.redacted
+		))
+		.await;
+}
+
+#[implement(Service)]
+async fn redact_room(&self, job: &mut Job, room_id: &RoomId, reason: &str) -> Outcome {
+	let mut redactor = None;
+	loop {
+		let last = job.last.map(PduCount::from_signed);
+		let page: Vec<(PduCount, PduEvent)> = self
+			.services
+			.timeline
+			.pdus_rev(None, room_id, last)
+			.ignore_err()
+			.take(PAGE_SIZE)
+			.collect()
+			.await;
+
+		let full = page.len() == PAGE_SIZE;
+		for (count, pdu) in page {
+			job.last = Some(count.into_signed());
+			let recent = is_recent(job.since, u64::from(pdu.origin_server_ts), &mut job.stale);
+			if job.stale >= SINCE_SLACK {
+				return Outcome::Done;
+			}
+
+			if !recent || !is_redactable(&pdu, &job.user_id) {
+				continue;
+			}
+
+			if redactor.is_none() {
+				redactor = Some(
+					self.find_redactor(&pdu.event_id, &job.user_id, room_id)
+						.await,
+				);
+			}
+
+			let Some(Some(sender)) = &redactor else {
+				return Outcome::Skipped;
+			};
+
+			let state_lock = self.services.state.mutex.lock(room_id).await;
+			let result = self
+				.services
+				.timeline
+				.build_and_append_pdu(
+					PduBuilder {
+						redacts: Some(pdu.event_id.clone()),
+						..PduBuilder::timeline(&RoomRedactionEventContent {
+							redacts: Some(pdu.event_id.clone()),
+							reason: Some(reason.to_owned()),
+						})
+					},
+					sender,
+					room_id,
+					&state_lock,
+				)
+				.await;
+
+			drop(state_lock);
+			match result {
+				| Ok(_) => job.redacted = job.redacted.saturating_add(1),
+				| Err(e) => {
+					warn!(%room_id, event_id = %pdu.event_id, %sender, "Failed to redact event: {e}");
+					job.failed = job.failed.saturating_add(1);
+				},
+			}
+
+			sleep(Duration::from_millis(job.delay)).await;
+		}
+
+		if !self.save(job).await {
+			return Outcome::Cancelled;
+		}
+
+		if !self.server.running() {
+			return Outcome::Paused;
+		}
+
+		if !full {
+			return Outcome::Done;
+		}
+	}
+}
+
+/// Store the progress of a job; false when it was cancelled meanwhile.
+#[implement(Service)]
+async fn save(&self, job: &Job) -> bool {
+	let _lock = self.mutex_job.lock(&job.user_id.to_string()).await;
+	if self.get(&job.user_id).await.is_none() {
+		return false;
+	}
+
+	self.db.global.put((PREFIX, &job.user_id), Json(job));
+
+	true
+}
+
+/// The local user to redact someone's events in a room as: the server user,
+/// else another local user allowed to. The sender themselves is never used.
+#[implement(Service)]
+async fn find_redactor(
+	&self,
+	event_id: &EventId,
+	user_id: &UserId,
+	room_id: &RoomId,
+) -> Option<OwnedUserId> {
+	let server_user = &self.services.globals.server_user;
+	if self.can_redact(event_id, server_user, room_id).await {
+		return Some(server_user.clone());
+	}
+
+	let local_users: Vec<OwnedUserId> = self
+		.services
+		.state_cache
+		.local_users_in_room(room_id)
+		.ready_filter(|&local_user| local_user != user_id && local_user != server_user)
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	for local_user in local_users {
+		if self.can_redact(event_id, &local_user, room_id).await {
+			return Some(local_user);
+		}
+	}
+
+	None
+}
+
+#[implement(Service)]
+async fn can_redact(&self, event_id: &EventId, redactor: &UserId, room_id: &RoomId) -> bool {
+	self.services.state_cache.is_joined(redactor, room_id).await
+		&& self
+			.services
+			.state_accessor
+			.user_can_redact(event_id, redactor, room_id, false)
+			.await
+			.unwrap_or(false)
+}
+
+fn is_redactable(pdu: &PduEvent, user_id: &UserId) -> bool {
+	pdu.sender == user_id
+		&& pdu.state_key.is_none()
+		&& pdu.kind != TimelineEventType::RoomRedaction
+		&& !pdu.is_redacted()
+}
+
+/// Whether an event was sent at or after `since`. Timestamps need not be in
+/// timeline order, so older events are only skipped; `stale` counts those met
+/// in a row and is reset by a recent one.
+fn is_recent(since: Option<u64>, origin_server_ts: u64, stale: &mut usize) -> bool {
+	let recent = since.is_none_or(|since| origin_server_ts >= since);
+	*stale = if recent { 0 } else { stale.saturating_add(1) };
+
+	recent
+}
@@ -0,0 +1,30 @@
+use super::{SINCE_SLACK, is_recent};
+
+#[test]
+fn recent_without_since() {
+	let mut stale = 0;
+	assert!(is_recent(None, 0, &mut stale));
+	assert_eq!(stale, 0);
+}
+
+#[test]
+fn older_events_skipped_not_stopping() {
+	let mut stale = 0;
+	assert!(!is_recent(Some(1000), 999, &mut stale));
+	assert!(!is_recent(Some(1000), 500, &mut stale));
+	assert_eq!(stale, 2);
+
+	// an event sent later may come before older ones in the timeline
+	assert!(is_recent(Some(1000), 1000, &mut stale));
+	assert_eq!(stale, 0);
+}
+
+#[test]
+fn stale_run_reaches_slack() {
+	let mut stale = 0;
+	for _ in 0..SINCE_SLACK {
+		assert!(!is_recent(Some(1000), 0, &mut stale));
+	}
+
+	assert_eq!(stale, SINCE_SLACK);
+}
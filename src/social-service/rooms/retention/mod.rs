@@ -0,0 +1,190 @@
+//! Message retention (MSC1763). A room sets the lifetime of its events with an
+//! `m.room.retention` state event; the server applies a default to rooms which
+//! set none and bounds what rooms may set. Events past their lifetime are
+//! deleted from the room's history periodically, except in the admin room.
+
+#[cfg(test)]
+mod tests;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use conduwuit::{Result, Server, debug, debug_warn, implement, info, utils::time::now_millis};
+use futures::StreamExt;
+use ruma::{OwnedRoomId, RoomId, events::StateEventType};
+use serde::{Deserialize, Serialize};
+use service_core::{Args, Dep, Service as ServiceTrait};
+use tokio::{
+	sync::Notify,
+	time::{Instant, MissedTickBehavior, interval_at},
+};
+
+use crate::{admin, rooms, rooms::purge::Before};
+
+pub struct Service {
+	interrupt: Notify,
+	server: Arc<Server>,
+	services: Services,
+}
+
+struct Services {
+	admin: Dep<admin::Service>,
+	metadata: Dep<rooms::metadata::Service>,
+	purge: Dep<rooms::purge::Service>,
+	state_accessor: Dep<rooms::state_accessor::Service>,
+}
+
+/// Content of an `m.room.retention` state event. Lifetimes are in
+/// milliseconds.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize)]
+pub struct RoomRetentionEventContent {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub max_lifetime: Option<u64>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub min_lifetime: Option<u64>,
+}
+
+/// The retention of a room.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Policy {
+	/// As set by the room, if it sets one.
+	pub room: Option<RoomRetentionEventContent>,
+
+	/// Lifetime of the room's events once the server's default and bounds are
+	/// applied; none when they are kept forever.
+	pub max_lifetime: Option<Duration>,
+}
+
+pub const EVENT_TYPE: &str = "m.room.retention";
+
+#[async_trait]
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		Ok(Arc::new(Self {
+			interrupt: Notify::new(),
+			server: args.server.clone(),
+			services: Services {
+				admin: args.depend::<admin::Service>("admin"),
+				metadata: args.depend::<rooms::metadata::Service>("rooms::metadata"),
+				purge: args.depend::<rooms::purge::Service>("rooms::purge"),
+				state_accessor: args
+					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
+			},
+		}))
+	}
+
+	#[tracing::instrument(skip_all, name = "retention", level = "debug")]
+	async fn worker(self: Arc<Self>) -> Result {
+		let config = &self.server.config;
+		if !config.enforce_retention || config.retention_interval == 0 {
+			return Ok(());
+		}
+
+		// The first enforcement waits an interval rather than running at startup
+		let period = Duration::from_secs(config.retention_interval);
+		let now = Instant::now();
+		let mut i = interval_at(now.checked_add(period).unwrap_or(now), period);
+		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
+		while self.server.running() {
+			tokio::select! {
+				() = self.interrupt.notified() => break,
+				_ = i.tick() => (),
+			}
+
+			self.enforce().await;
+		}
+
+		Ok(())
+	}
+
+	fn interrupt(&self) { self.interrupt.notify_waiters(); }
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+/// The retention of the room, as set by it and as applied by the server.
+#[implement(Service)]
+pub async fn policy(&self, room_id: &RoomId) -> Policy {
+	let room: Option<RoomRetentionEventContent> = self
+		.services
+		.state_accessor
+		.room_state_get_content(room_id, &StateEventType::from(EVENT_TYPE), "")
+		.await
+		.ok();
+
+	let config = &self.server.config;
+	let max_lifetime = max_lifetime(
+		room.and_then(|room| room.max_lifetime),
+		config.retention_default_max_lifetime,
+		config.retention_min_lifetime,
+		config.retention_max_lifetime,
+	);
+
+	Policy { room, max_lifetime }
+}
+
+/// Queue the deletion of the events of every room which are past their
+/// lifetime.
+#[implement(Service)]
+#[tracing::instrument(skip_all, level = "debug")]
+pub async fn enforce(&self) {
+	let room_ids: Vec<OwnedRoomId> = self
+		.services
+		.metadata
+		.iter_ids()
+		.map(ToOwned::to_owned)
+		.collect()
+		.await;
+
+	let mut queued = 0_usize;
+	for room_id in &room_ids {
+		if !self.server.running() {
+			return;
+		}
+
+		if self.services.admin.is_admin_room(room_id).await {
+			continue;
+		}
+
+		let Some(max_lifetime) = self.policy(room_id).await.max_lifetime else {
+			continue;
+		};
+
+		let max_lifetime = u64::try_from(max_lifetime.as_millis()).unwrap_or(u64::MAX);
+		let before = Before::Timestamp(now_millis().saturating_sub(max_lifetime));
+		match self.services.purge.queue_history(room_id, before).await {
+			| Ok(_) => queued = queued.saturating_add(1),
+			| Err(e) => debug_warn!(%room_id, "Failed to enforce retention: {e}"),
+		}
+	}
+
+	if queued > 0 {
+		info!(queued, "Queued the deletion of events past their retention");
+	} else {
+		debug!("No events past their retention");
+	}
+}
+
+/// Lifetime of a room's events from the lifetime it sets in milliseconds and
+/// the server's default and bounds in seconds.
+fn max_lifetime(
+	room: Option<u64>,
+	default: Option<u64>,
+	min: Option<u64>,
+	max: Option<u64>,
+) -> Option<Duration> {
+	let lifetime = room
+		.map(Duration::from_millis)
+		.or_else(|| default.map(Duration::from_secs))?;
+
+	let lifetime = min
+		.map(Duration::from_secs)
+		.map_or(lifetime, |min| lifetime.max(min));
+
+	let lifetime = max
+		.map(Duration::from_secs)
+		.map_or(lifetime, |max| lifetime.min(max));
+
+	Some(lifetime)
+}
@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+use super::max_lifetime;
+
+#[test]
+fn max_lifetime_room() {
+	let lifetime = max_lifetime(Some(60_000), Some(3600), None, None);
+	assert_eq!(lifetime, Some(Duration::from_secs(60)));
+}
+
+#[test]
+fn max_lifetime_default() {
+	assert_eq!(max_lifetime(None, Some(3600), None, None), Some(Duration::from_secs(3600)));
+	assert_eq!(max_lifetime(None, None, Some(60), Some(3600)), None);
+}
+
+#[test]
+fn max_lifetime_bounded() {
+	let lifetime = max_lifetime(Some(1000), None, Some(60), Some(3600));
+	assert_eq!(lifetime, Some(Duration::from_secs(60)));
+
+	let lifetime = max_lifetime(Some(7_200_000), None, Some(60), Some(3600));
+	assert_eq!(lifetime, Some(Duration::from_secs(3600)));
+}
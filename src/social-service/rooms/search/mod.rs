@@ -100,6 +100,21 @@ pub fn deindex_pdu(&self, shortroomid: ShortRoomId, pdu_id: &RawPduId, message_b
 	}
 }
 
+/// Remove every search token of a room. Returns the number removed.
+#[implement(Service)]
+pub async fn deindex_room(&self, shortroomid: ShortRoomId) -> usize {
+	let prefix = shortroomid.to_be_bytes();
+	self.db
+		.tokenids
+		.raw_keys_prefix(&prefix)
+		.ignore_err()
+		.ready_fold(0_usize, |count, key| {
+			self.db.tokenids.remove(key);
+			count.saturating_add(1)
+		})
+		.await
+}
+
 #[implement(Service)]
 pub async fn search_pdus<'a>(
 	&'a self,
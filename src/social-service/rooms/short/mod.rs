@@ -1,7 +1,12 @@
-use std::{borrow::Borrow, fmt::Debug, mem::size_of_val, sync::Arc};
+use std::{borrow::Borrow, collections::HashSet, fmt::Debug, mem::size_of_val, sync::Arc};
 
 pub use conduwuit::matrix::pdu::{ShortEventId, ShortId, ShortRoomId, ShortStateKey};
-use conduwuit::{Result, err, implement, matrix::StateKey, utils, utils::IterStream};
+use conduwuit::{
+	Result, err, implement,
+	matrix::StateKey,
+	utils,
+	utils::{IterStream, ReadyExt, stream::TryIgnore},
+};
 use database::{Deserialized, Get, Map, Qry};
 use futures::{Stream, StreamExt};
 use ruma::{EventId, RoomId, events::StateEventType};
@@ -259,3 +264,36 @@ pub async fn get_or_create_shortroomid(&self, room_id: &RoomId) -> ShortRoomId {
 			short
 		})
 }
+
+/// Remove the short id of an event in both directions.
+#[implement(Service)]
+pub fn delete_shorteventid(&self, event_id: &EventId, shorteventid: ShortEventId) {
+	const BUFSIZE: usize = size_of::<ShortEventId>();
+
+	self.db.eventid_shorteventid.remove(event_id);
+	self.db
+		.shorteventid_eventid
+		.adel::<BUFSIZE, _>(shorteventid);
+}
+
+#[implement(Service)]
+pub fn delete_shortroomid(&self, room_id: &RoomId) { self.db.roomid_shortroomid.remove(room_id); }
+
+/// Remove the state hashes mapping to any of the given short state hashes.
+/// The map is keyed by the hash itself, so this scans all of it. Returns the
+/// number of entries removed.
+#[implement(Service)]
+pub async fn delete_shortstatehashes(&self, shortstatehashes: &HashSet<ShortStateHash>) -> usize {
+	self.db
+		.statehash_shortstatehash
+		.raw_stream()
+		.ignore_err()
+		.ready_filter(|(_, val)| {
+			utils::u64_from_bytes(val).is_ok_and(|short| shortstatehashes.contains(&short))
+		})
+		.ready_fold(0_usize, |count, (key, _)| {
+			self.db.statehash_shortstatehash.remove(key);
+			count.saturating_add(1)
+		})
+		.await
+}
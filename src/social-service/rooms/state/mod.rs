@@ -382,6 +382,29 @@ pub async fn get_room_shortstatehash(&self, room_id: &RoomId) -> Result<ShortSta
 			.deserialized()
 	}
 
+	/// Remove the state hash associated with an event.
+	pub fn delete_event_shortstatehash(&self, shorteventid: ShortEventId) {
+		const BUFSIZE: usize = size_of::<ShortEventId>();
+
+		self.db
+			.shorteventid_shortstatehash
+			.adel::<BUFSIZE, _>(shorteventid);
+	}
+
+	/// Remove the current state hash and the forward extremities of a room.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, room_id: &RoomId) {
+		let prefix = (room_id, Interfix);
+		self.db
+			.roomid_pduleaves
+			.keys_prefix_raw(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.roomid_pduleaves.remove(key))
+			.await;
+
+		self.db.roomid_shortstatehash.remove(room_id);
+	}
+
 	pub fn get_forward_extremities<'a>(
 		&'a self,
 		room_id: &'a RoomId,
@@ -13,7 +13,7 @@
 use futures::{Stream, StreamExt, future::join5, pin_mut, stream::iter};
 use itertools::Itertools;
 use ruma::{
-	OwnedRoomId, OwnedServerName, RoomId, ServerName, UserId,
+	OwnedRoomId, OwnedServerName, OwnedUserId, RoomId, ServerName, UserId,
 	events::{
 		AnyStrippedStateEvent, AnySyncStateEvent, GlobalAccountDataEventType,
 		RoomAccountDataEventType, StateEventType,
@@ -939,6 +939,82 @@ pub async fn update_joined_count(&self, room_id: &RoomId) {
 			.remove(room_id);
 	}
 
+	/// Every user with a membership in a room, or who was ever joined to it.
+	/// The once-joined entries are keyed by user first, so this reads all of
+	/// them.
+	#[tracing::instrument(level = "debug", skip(self))]
+	pub async fn room_users(&self, room_id: &RoomId) -> HashSet<OwnedUserId> {
+		let prefix = (room_id, Interfix);
+		let roomuser_maps = [
+			&self.db.roomuserid_joined,
+			&self.db.roomuserid_invitecount,
+			&self.db.roomuserid_leftcount,
+			&self.db.roomuserid_knockedcount,
+		];
+
+		let mut users = HashSet::<OwnedUserId>::new();
+		for map in roomuser_maps {
+			map.keys_prefix(&prefix)
+				.ignore_err()
+				.ready_for_each(|(_, user_id): (Ignore, &UserId)| {
+					users.insert(user_id.to_owned());
+				})
+				.await;
+		}
+
+		self.db
+			.roomuseroncejoinedids
+			.keys()
+			.ignore_err()
+			.ready_filter(|(_, room): &(&UserId, &RoomId)| *room == room_id)
+			.ready_for_each(|(user_id, _)| {
+				users.insert(user_id.to_owned());
+			})
+			.await;
+
+		users
+	}
+
+	/// Remove every membership of the users in a room along with its entries
+	/// in the rooms of each user and server.
+	#[tracing::instrument(level = "debug", skip(self, users))]
+	pub async fn purge_room(&self, room_id: &RoomId, users: &HashSet<OwnedUserId>) {
+		for user_id in users {
+			let userroom_id = (user_id, room_id);
+			self.db.userroomid_joined.del(userroom_id);
+			self.db.userroomid_invitestate.del(userroom_id);
+			self.db.userroomid_leftstate.del(userroom_id);
+			self.db.userroomid_knockedstate.del(userroom_id);
+			self.db.roomuseroncejoinedids.del(userroom_id);
+
+			let roomuser_id = (room_id, user_id);
+			self.db.roomuserid_joined.del(roomuser_id);
+			self.db.roomuserid_invitecount.del(roomuser_id);
+			self.db.roomuserid_leftcount.del(roomuser_id);
+			self.db.roomuserid_knockedcount.del(roomuser_id);
+		}
+
+		let servers: Vec<OwnedServerName> = self
+			.room_servers(room_id)
+			.map(ToOwned::to_owned)
+			.collect()
+			.await;
+
+		for server in &servers {
+			self.db.roomserverids.del((room_id, server));
+			self.db.serverroomids.del((server, room_id));
+		}
+
+		self.db.roomid_joinedcount.remove(room_id);
+		self.db.roomid_invitedcount.remove(room_id);
+		self.db.roomid_inviteviaservers.remove(room_id);
+
+		self.appservice_in_room_cache
+			.write()
+			.expect("locked")
+			.remove(room_id);
+	}
+
 	#[tracing::instrument(level = "debug", skip(self))]
 	fn mark_as_once_joined(&self, user_id: &UserId, room_id: &RoomId) {
 		let key = (user_id, room_id);
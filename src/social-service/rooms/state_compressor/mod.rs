@@ -1,5 +1,5 @@
 use std::{
-	collections::{BTreeSet, HashMap},
+	collections::{BTreeSet, HashMap, HashSet},
 	fmt::{Debug, Write},
 	mem::size_of,
 	sync::{Arc, Mutex},
@@ -432,6 +432,38 @@ pub async fn save_state(
 		})
 	}
 
+	/// Remove the given states along with every state they are based on.
+	/// Returns the short state hashes of all the states removed.
+	#[tracing::instrument(skip_all, level = "debug")]
+	pub async fn delete_states<I>(&self, shortstatehashes: I) -> HashSet<ShortStateHash>
+	where
+		I: IntoIterator<Item = ShortStateHash> + Send,
+	{
+		const BUFSIZE: usize = size_of::<ShortStateHash>();
+
+		let mut removed = HashSet::new();
+		let mut pending: Vec<_> = shortstatehashes.into_iter().collect();
+		while let Some(shortstatehash) = pending.pop() {
+			if !removed.insert(shortstatehash) {
+				continue;
+			}
+
+			if let Ok(StateDiff { parent: Some(parent), .. }) =
+				self.get_statediff(shortstatehash).await
+			{
+				pending.push(parent);
+			}
+
+			self.db
+				.shortstatehash_statediff
+				.adel::<BUFSIZE, _>(shortstatehash);
+		}
+
+		self.stateinfo_cache.lock().expect("locked").clear();
+
+		removed
+	}
+
 	#[tracing::instrument(skip(self), level = "debug", name = "get")]
 	async fn get_statediff(&self, shortstatehash: ShortStateHash) -> Result<StateDiff> {
 		const BUFSIZE: usize = size_of::<ShortStateHash>();
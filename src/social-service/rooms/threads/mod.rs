@@ -171,6 +171,17 @@ pub async fn threads_until<'a>(
 		Ok(stream)
 	}
 
+	/// Remove the participants of every thread in a room.
+	pub async fn delete_room_threads(&self, shortroomid: ShortRoomId) {
+		let prefix = shortroomid.to_be_bytes();
+		self.db
+			.threadid_userids
+			.raw_keys_prefix(&prefix)
+			.ignore_err()
+			.ready_for_each(|key| self.db.threadid_userids.remove(key))
+			.await;
+	}
+
 	pub(super) fn update_participants(
 		&self,
 		root_id: &RawPduId,
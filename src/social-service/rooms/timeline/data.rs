@@ -4,7 +4,10 @@
 	Err, PduCount, PduEvent, Result, at, err,
 	result::{LogErr, NotFound},
 	utils,
-	utils::stream::TryReadyExt,
+	utils::{
+		ReadyExt,
+		stream::{TryIgnore, TryReadyExt},
+	},
 };
 use database::{Database, Deserialized, Json, KeyVal, Map};
 use futures::{FutureExt, Stream, TryFutureExt, TryStreamExt, future::select_ok, pin_mut};
@@ -219,6 +222,24 @@ pub(super) async fn replace_pdu(
 		Ok(())
 	}
 
+	pub(super) fn delete_pdu(&self, pdu_id: &RawPduId, event_id: &EventId) {
+		self.pduid_pdu.remove(pdu_id);
+		self.eventid_pduid.remove(event_id);
+		self.eventid_outlierpdu.remove(event_id);
+	}
+
+	pub(super) async fn delete_room_pdus(&self, shortroomid: ShortRoomId) -> usize {
+		let prefix = shortroomid.to_be_bytes();
+		self.pduid_pdu
+			.raw_keys_prefix(&prefix)
+			.ignore_err()
+			.ready_fold(0_usize, |count, key| {
+				self.pduid_pdu.remove(key);
+				count.saturating_add(1)
+			})
+			.await
+	}
+
 	/// Returns an iterator over all events and their tokens in a room that
 	/// happened before the event with id `until` in reverse-chronological
 	/// order.
@@ -1,4 +1,6 @@
 mod data;
+#[cfg(test)]
+mod tests;
 
 use std::{
 	borrow::Borrow,
@@ -28,7 +30,7 @@
 };
 use ruma::{
 	CanonicalJsonObject, CanonicalJsonValue, EventId, OwnedEventId, OwnedRoomId, OwnedServerName,
-	RoomId, RoomVersionId, ServerName, UserId,
+	RoomId, RoomVersionId, ServerName, UInt, UserId,
 	api::federation,
 	canonical_json::to_canonical_value,
 	events::{
@@ -53,9 +55,11 @@
 use crate::{
 	account_data, admin, appservice,
 	appservice::NamespaceRegex,
-	globals, pusher, rooms,
+	globals, policy_lists, pusher, rooms,
 	rooms::{short::ShortRoomId, state_compressor::CompressedState},
-	sending, server_keys, users,
+	sending, server_keys,
+	spam_checker::{self, Verdict},
+	users,
 };
 use service_core::{Dep, Args, Service as ServiceTrait};
 
@@ -99,9 +103,12 @@ struct Services {
 	state_cache: Dep<rooms::state_cache::Service>,
 	state_accessor: Dep<rooms::state_accessor::Service>,
 	pdu_metadata: Dep<rooms::pdu_metadata::Service>,
+	policy_lists: Dep<policy_lists::Service>,
+	purge: Dep<rooms::purge::Service>,
 	read_receipt: Dep<rooms::read_receipt::Service>,
 	sending: Dep<sending::Service>,
 	server_keys: Dep<server_keys::Service>,
+	spam_checker: Dep<spam_checker::Service>,
 	user: Dep<rooms::user::Service>,
 	users: Dep<users::Service>,
 	pusher: Dep<pusher::Service>,
@@ -131,9 +138,12 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 				state_accessor: args
 					.depend::<rooms::state_accessor::Service>("rooms::state_accessor"),
 				pdu_metadata: args.depend::<rooms::pdu_metadata::Service>("rooms::pdu_metadata"),
+				policy_lists: args.depend::<policy_lists::Service>("policy_lists"),
+				purge: args.depend::<rooms::purge::Service>("rooms::purge"),
 				read_receipt: args.depend::<rooms::read_receipt::Service>("rooms::read_receipt"),
 				sending: args.depend::<sending::Service>("sending"),
 				server_keys: args.depend::<server_keys::Service>("server_keys"),
+				spam_checker: args.depend::<spam_checker::Service>("spam_checker"),
 				user: args.depend::<rooms::user::Service>("rooms::user"),
 				users: args.depend::<users::Service>("users"),
 				pusher: args.depend::<pusher::Service>("pusher"),
@@ -261,6 +271,46 @@ pub async fn replace_pdu(
 		self.db.replace_pdu(pdu_id, pdu_json, pdu).await
 	}
 
+	/// Removes a PDU from the timeline. Nothing referring to the PDU is
+	/// updated; this is only for purging its room.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub fn delete_pdu(&self, pdu_id: &RawPduId, event_id: &EventId) {
+		self.db.delete_pdu(pdu_id, event_id);
+	}
+
+	/// Removes a PDU from the timeline and the search index, along with the
+	/// relations to and from it. The PDU remains in the state it is part of.
+	#[tracing::instrument(skip(self, pdu), level = "debug")]
+	pub async fn purge_pdu(&self, shortroomid: ShortRoomId, count: PduCount, pdu: &PduEvent) {
+		let pdu_id: RawPduId = PduId { shortroomid, shorteventid: count }.into();
+		if let Ok(content) = pdu.get_content::<ExtractBody>() {
+			if let Some(body) = content.body {
+				self.services
+					.search
+					.deindex_pdu(shortroomid, &pdu_id, &body);
+			}
+		}
+
+		for related in relates_to(pdu) {
+			if let Ok(related_pducount) = self.get_pdu_count(&related).await {
+				self.services
+					.pdu_metadata
+					.delete_relation(count, related_pducount);
+			}
+		}
+
+		self.services.pdu_metadata.delete_relations(count).await;
+		self.db.delete_pdu(&pdu_id, &pdu.event_id);
+	}
+
+	/// Removes whatever remains in the timeline of a room whose PDUs were
+	/// deleted, such as PDUs which failed to parse. Returns the number of
+	/// PDUs removed.
+	#[tracing::instrument(skip(self), level = "debug")]
+	pub async fn purge_room(&self, shortroomid: ShortRoomId) -> usize {
+		self.db.delete_room_pdus(shortroomid).await
+	}
+
 	/// Creates a new persisted data unit and adds it to a room.
 	///
 	/// By this point the incoming event should be fully authenticated, no auth
@@ -402,6 +452,11 @@ pub async fn append_pdu<'a, Leafs>(
 			}
 		}
 
+		// Nobody is notified of events of shadow-banned users.
+		if self.services.users.is_shadow_banned(&pdu.sender).await {
+			push_target.clear();
+		}
+
 		for user in &push_target {
 			let rules_for_user = self
 				.services
@@ -501,6 +556,12 @@ pub async fn append_pdu<'a, Leafs>(
 						.await
 						.remove(&pdu.room_id);
 				},
+			| TimelineEventType::PolicyRuleUser
+			| TimelineEventType::PolicyRuleRoom
+			| TimelineEventType::PolicyRuleServer =>
+				if pdu.state_key.is_some() {
+					self.services.policy_lists.rules_changed(&pdu.room_id);
+				},
 			| TimelineEventType::RoomMember => {
 				if let Some(state_key) = &pdu.state_key {
 					// if the state_key fails
@@ -820,6 +881,63 @@ pub async fn create_hash_and_sign_event(
 		Ok((pdu, pdu_json))
 	}
 
+	/// Checks an event a local user is about to send against their shadow ban
+	/// and the spam checkers. This is done before the room's state is locked
+	/// since the checkers may be slow, so the event is checked as the builder
+	/// describes it, without its place in the room. Returns the event ID to
+	/// give the sender when the event is to be dropped without them knowing.
+	#[tracing::instrument(skip(self, pdu_builder), level = "debug")]
+	pub async fn check_local_event(
+		&self,
+		pdu_builder: &PduBuilder,
+		sender: &UserId,
+		room_id: &RoomId,
+	) -> Result<Option<OwnedEventId>> {
+		if sender == self.services.globals.server_user {
+			return Ok(None);
+		}
+
+		let pdu = PduEvent {
+			event_id: fabricated_event_id(),
+			room_id: room_id.to_owned(),
+			sender: sender.to_owned(),
+			origin: None,
+			origin_server_ts: pdu_builder.timestamp.map_or_else(
+				|| {
+					utils::millis_since_unix_epoch()
+						.try_into()
+						.expect("u64 fits into UInt")
+				},
+				|ts| ts.get(),
+			),
+			kind: pdu_builder.event_type.clone(),
+			content: pdu_builder.content.clone(),
+			state_key: pdu_builder.state_key.clone(),
+			prev_events: Vec::new(),
+			depth: UInt::MIN,
+			auth_events: Vec::new(),
+			redacts: pdu_builder.redacts.clone(),
+			unsigned: None,
+			hashes: EventHash { sha256: String::new() },
+			signatures: None,
+		};
+
+		let shadow_banned = self.services.users.is_shadow_banned(sender).await;
+		let verdict = if shadow_banned {
+			Verdict::Allow
+		} else {
+			self.services.spam_checker.check_event(&pdu).await
+		};
+
+		let dropped =
+			dropped_event(pdu.event_id, pdu.state_key.is_some(), shadow_banned, verdict)?;
+		if let Some(event_id) = &dropped {
+			debug_warn!(%event_id, shadow_banned, "Dropping event of {sender}");
+		}
+
+		Ok(dropped)
+	}
+
 	/// Creates a new persisted data unit and adds it to a room. This function
 	/// takes a roomid_mutex_state, meaning that only this function is able to
 	/// mutate the room state.
@@ -941,6 +1059,12 @@ pub async fn build_and_append_pdu(
 		// room_servers() and/or the if statement above
 		servers.remove(self.services.globals.server_name());
 
+		// Events of shadow-banned users, such as their joins, stay on this server.
+		if self.services.users.is_shadow_banned(sender).await {
+			debug!(event_id = %pdu.event_id, "Not federating event of shadow-banned user");
+			return Ok(pdu.event_id);
+		}
+
 		self.services
 			.sending
 			.send_pdu_servers(servers.iter().map(AsRef::as_ref).stream(), &pdu_id)
@@ -1066,6 +1190,11 @@ pub async fn redact_pdu(
 
 	#[tracing::instrument(name = "backfill", level = "debug", skip(self))]
 	pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Result<()> {
+		if self.services.globals.is_read_only() {
+			// Backfilled events would have to be stored; serve what is stored.
+			return Ok(());
+		}
+
 		if self
 			.services
 			.state_cache
@@ -1158,7 +1287,13 @@ pub async fn backfill_if_required(&self, room_id: &RoomId, from: PduCount) -> Re
 				.await;
 			match response {
 				| Ok(response) => {
+					// Events from before a purge of the history are not stored again
+					let purged_before = self.services.purge.history_purged_before(room_id).await;
 					for pdu in response.pdus {
+						if purged_before.is_some_and(|before| origin_server_ts(&pdu) < before) {
+							continue;
+						}
+
 						if let Err(e) = self.backfill_pdu(backfill_server, pdu).boxed().await {
 							debug_warn!("Failed to add backfilled pdu in room {room_id}: {e}");
 						}
@@ -1306,3 +1441,58 @@ async fn check_pdu_for_admin_room(&self, pdu: &PduEvent, sender: &UserId) -> Res
 
 	Ok(())
 }
+
+/// The events a PDU was recorded as related to when it was appended.
+fn relates_to(pdu: &PduEvent) -> impl Iterator<Item = OwnedEventId> + use<> {
+	let related = pdu
+		.get_content::<ExtractRelatesToEventId>()
+		.ok()
+		.map(|content| content.relates_to.event_id);
+
+	let reply = pdu
+		.get_content::<ExtractRelatesTo>()
+		.ok()
+		.and_then(|content| match content.relates_to {
+			| Relation::Reply { in_reply_to } => Some(in_reply_to.event_id),
+			| _ => None,
+		});
+
+	related.into_iter().chain(reply)
+}
+
+/// Timestamp of a PDU received over federation, zero when it has none.
+fn origin_server_ts(pdu: &RawJsonValue) -> u64 {
+	#[derive(Deserialize)]
+	struct ExtractTimestamp {
+		origin_server_ts: u64,
+	}
+
+	serde_json::from_str::<ExtractTimestamp>(pdu.get()).map_or(0, |pdu| pdu.origin_server_ts)
+}
+
+/// What becomes of an event a local user sends: `None` when it is to be sent,
+/// or the ID to give the sender when it is dropped without them knowing.
+/// Events of shadow-banned users are always dropped. Those the spam checker
+/// soft fails are dropped unless they are state, which cannot be faked since
+/// the client would see it missing.
+fn dropped_event(
+	event_id: OwnedEventId,
+	is_state: bool,
+	shadow_banned: bool,
+	verdict: Verdict,
+) -> Result<Option<OwnedEventId>> {
+	match verdict {
+		| _ if shadow_banned => Ok(Some(event_id)),
+		| Verdict::Allow => Ok(None),
+		| Verdict::Deny(e) => Err(e),
+		| Verdict::SoftFail if !is_state => Ok(Some(event_id)),
+		| Verdict::SoftFail =>
+			Err!(Request(Forbidden("This event was refused by the spam checker."))),
+	}
+}
+
+/// An ID for an event which is never created, in the form of the IDs of
+/// current room versions.
+fn fabricated_event_id() -> OwnedEventId {
+	EventId::parse(format!("${}", utils::random_string(43))).expect("valid event ID")
+}
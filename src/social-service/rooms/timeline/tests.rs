@@ -0,0 +1,46 @@
+use conduwuit::{Error, err};
+use ruma::{event_id, owned_event_id};
+
+use super::{dropped_event, fabricated_event_id};
+use crate::spam_checker::Verdict;
+
+#[test]
+fn shadow_banned_events_dropped() {
+	let event_id = owned_event_id!("$message");
+	let dropped = dropped_event(event_id.clone(), false, true, Verdict::Allow);
+	assert_eq!(dropped.expect("dropped").as_deref(), Some(event_id!("$message")));
+
+	let dropped = dropped_event(owned_event_id!("$state"), true, true, Verdict::Allow);
+	assert_eq!(dropped.expect("dropped").as_deref(), Some(event_id!("$state")));
+}
+
+#[test]
+fn allowed_events_sent() {
+	let dropped = dropped_event(owned_event_id!("$message"), false, false, Verdict::Allow);
+	assert!(dropped.expect("allowed").is_none());
+}
+
+#[test]
+fn soft_failed_events() {
+	let dropped = dropped_event(owned_event_id!("$message"), false, false, Verdict::SoftFail);
+	assert!(dropped.expect("dropped").is_some());
+
+	let refused = dropped_event(owned_event_id!("$state"), true, false, Verdict::SoftFail);
+	assert!(refused.is_err());
+}
+
+#[test]
+fn denied_events_refused() {
+	let denial: Error = err!(Request(Forbidden("No")));
+	let refused = dropped_event(owned_event_id!("$message"), false, false, Verdict::Deny(denial));
+	assert!(refused.is_err());
+}
+
+#[test]
+fn fabricated_event_ids_differ() {
+	let a = fabricated_event_id();
+	let b = fabricated_event_id();
+	assert_ne!(a, b);
+	assert!(a.as_str().starts_with('$'));
+	assert_eq!(a.as_str().len(), 44);
+}
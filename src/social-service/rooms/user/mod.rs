@@ -1,8 +1,11 @@
-use std::sync::Arc;
+use std::{collections::HashSet, sync::Arc};
 
-use conduwuit::{Result, implement};
+use conduwuit::{
+	Result, implement,
+	utils::{ReadyExt, stream::TryIgnore},
+};
 use database::{Database, Deserialized, Map};
-use ruma::{RoomId, UserId};
+use ruma::{OwnedUserId, RoomId, UserId};
 
 use crate::{globals, rooms, rooms::short::ShortStateHash};
 use service_core::{Dep, Args, Service as ServiceTrait};
@@ -128,3 +131,30 @@ pub async fn get_token_shortstatehash(
 		.await
 		.deserialized()
 }
+
+/// Remove the notification counts of the users in a room and the states its
+/// sync tokens were associated with.
+#[implement(Service)]
+#[tracing::instrument(skip(self, users), level = "debug")]
+pub async fn purge_room(&self, room_id: &RoomId, users: &HashSet<OwnedUserId>) {
+	for user_id in users {
+		let userroom_id = (user_id, room_id);
+		self.db.userroomid_notificationcount.del(userroom_id);
+		self.db.userroomid_highlightcount.del(userroom_id);
+		self.db
+			.roomuserid_lastnotificationread
+			.del((room_id, user_id));
+	}
+
+	let Ok(shortroomid) = self.services.short.get_shortroomid(room_id).await else {
+		return;
+	};
+
+	let prefix = shortroomid.to_be_bytes();
+	self.db
+		.roomsynctoken_shortstatehash
+		.raw_keys_prefix(&prefix)
+		.ignore_err()
+		.ready_for_each(|key| self.db.roomsynctoken_shortstatehash.remove(key))
+		.await;
+}
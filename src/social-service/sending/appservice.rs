@@ -1,7 +1,7 @@
 use std::{fmt::Debug, mem};
 
 use bytes::BytesMut;
-use conduwuit::{Err, Result, debug_error, err, trace, utils, warn};
+use conduwuit::{Err, Result, debug_error, err, log::propagate, trace, utils, warn};
 use reqwest::Client;
 use ruma::api::{
 	IncomingResponse, MatrixVersion, OutgoingRequest, SendAccessToken, appservice::Registration,
@@ -56,7 +56,8 @@ pub(crate) async fn send_request<T>(
 	);
 	*http_request.uri_mut() = parts.try_into().expect("our manipulation is always valid");
 
-	let reqwest_request = reqwest::Request::try_from(http_request)?;
+	let mut reqwest_request = reqwest::Request::try_from(http_request)?;
+	propagate::inject(reqwest_request.headers_mut());
 
 	let mut response = client.execute(reqwest_request).await.map_err(|e| {
 		warn!("Could not send request to appservice \"{}\" at {dest}: {e:?}", registration.id);
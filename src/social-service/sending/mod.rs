@@ -106,6 +106,11 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 	}
 
 	async fn worker(self: Arc<Self>) -> Result {
+		// Outgoing transactions are the primary's responsibility.
+		if self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
 		let mut senders =
 			self.channels
 				.iter()
@@ -9,31 +9,38 @@
 };
 use tokio::sync::Mutex;
 use crate::{
-	account_data, admin, appservice, client, emergency, federation, globals, key_backups, media,
-	presence, pusher, resolver, rooms, sending, server_keys, sync, transaction_ids, uiaa,
-	updates, users,
+	account_data, admin, appservice, changes, client, emergency, expiry, federation, globals,
+	key_backups, media, policy_lists, presence, pusher, replica, reports, resolver, rooms, sending,
+	server_keys, spam_checker, sync, transaction_ids, uiaa, updates, users,
 };
 
 use service_core::{Args, Manager, Map, Service, ServicesTrait};
-use conduwuit_service::config;
+use conduwuit_service::{config, log_levels};
 
 pub struct Services {
 	pub account_data: Arc<account_data::Service>,
 	pub admin: Arc<admin::Service>,
 	pub appservice: Arc<appservice::Service>,
+	pub changes: Arc<changes::Service>,
 	pub config: Arc<config::Service>,
 	pub client: Arc<client::Service>,
 	pub emergency: Arc<emergency::Service>,
+	pub expiry: Arc<expiry::Service>,
 	pub globals: Arc<globals::Service>,
 	pub key_backups: Arc<key_backups::Service>,
+	pub log_levels: Arc<log_levels::Service>,
 	pub media: Arc<media::Service>,
+	pub policy_lists: Arc<policy_lists::Service>,
 	pub presence: Arc<presence::Service>,
 	pub pusher: Arc<pusher::Service>,
+	pub replica: Arc<replica::Service>,
+	pub reports: Arc<reports::Service>,
 	pub resolver: Arc<resolver::Service>,
 	pub rooms: rooms::Service,
 	pub federation: Arc<federation::Service>,
 	pub sending: Arc<sending::Service>,
 	pub server_keys: Arc<server_keys::Service>,
+	pub spam_checker: Arc<spam_checker::Service>,
 	pub sync: Arc<sync::Service>,
 	pub transaction_ids: Arc<transaction_ids::Service>,
 	pub uiaa: Arc<uiaa::Service>,
@@ -72,15 +79,21 @@ macro_rules! build {
 			account_data: build!(account_data::Service),
 			admin: build!(admin::Service),
 			appservice: build!(appservice::Service),
+			changes: build!(changes::Service),
 			resolver: build!(resolver::Service),
 			client: build!(client::Service),
 			config: build!(config::Service),
 			emergency: build!(emergency::Service),
+			expiry: build!(expiry::Service),
 			globals: build!(globals::Service),
 			key_backups: build!(key_backups::Service),
+			log_levels: log_levels::Service::shared()?,
 			media: build!(media::Service),
+			policy_lists: build!(policy_lists::Service),
 			presence: build!(presence::Service),
 			pusher: build!(pusher::Service),
+			replica: build!(replica::Service),
+			reports: build!(reports::Service),
 			rooms: rooms::Service {
 				alias: build!(rooms::alias::Service),
 				auth_chain: build!(rooms::auth_chain::Service),
@@ -90,7 +103,10 @@ macro_rules! build {
 				metadata: build!(rooms::metadata::Service),
 				outlier: build!(rooms::outlier::Service),
 				pdu_metadata: build!(rooms::pdu_metadata::Service),
+				purge: build!(rooms::purge::Service),
 				read_receipt: build!(rooms::read_receipt::Service),
+				redaction: build!(rooms::redaction::Service),
+				retention: build!(rooms::retention::Service),
 				search: build!(rooms::search::Service),
 				short: build!(rooms::short::Service),
 				spaces: build!(rooms::spaces::Service),
@@ -106,6 +122,7 @@ macro_rules! build {
 			federation: build!(federation::Service),
 			sending: build!(sending::Service),
 			server_keys: build!(server_keys::Service),
+			spam_checker: build!(spam_checker::Service),
 			sync: build!(sync::Service),
 			transaction_ids: build!(transaction_ids::Service),
 			uiaa: build!(uiaa::Service),
@@ -121,6 +138,7 @@ macro_rules! build {
 		debug_info!("Starting services...");
 
 		built.admin.set_services(Some(Arc::clone(&built)).as_ref());
+		built.admin.init().await;
 		super::migrations::migrations(&built).await?;
 		built
 			.manager
@@ -161,6 +179,7 @@ async fn stop(&self) {
 			manager.stop().await;
 		}
 
+		self.admin.fini().await;
 		self.admin.set_services(None);
 
 		debug_info!("Services shutdown complete.");
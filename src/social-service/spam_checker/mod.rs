@@ -0,0 +1,163 @@
+//! Spam checkers. Checkers are asked about events, invites, registrations and
+//! media uploads before they take effect; each can allow the action, deny it
+//! with an error for the client, or soft-fail it so it is silently dropped.
+//! Events from other servers are never refused outright: any verdict other
+//! than allow soft-fails them, while a soft-failed upload is denied. A webhook
+//! checker is registered when `spam_checker_webhook_url` is set and others can
+//! be added with [`Service::register`].
+
+#[cfg(test)]
+mod tests;
+mod webhook;
+
+use std::sync::{Arc, RwLock};
+
+use async_trait::async_trait;
+use conduwuit::{Error, PduEvent, Result, debug_info, implement};
+use ruma::{RoomId, UserId};
+use service_core::{Args, Service as ServiceTrait};
+
+use self::webhook::Webhook;
+use crate::client;
+
+pub struct Service {
+	checkers: RwLock<Vec<Arc<dyn SpamChecker>>>,
+}
+
+/// A check which may stop an action. Every check allows by default.
+#[async_trait]
+pub trait SpamChecker: Send + Sync {
+	/// A message, state or redaction event a local user is sending, before it
+	/// has a place in the room, or an event received over federation.
+	async fn check_event(&self, _pdu: &PduEvent) -> Verdict { Verdict::Allow }
+
+	/// An invite sent by a local user.
+	async fn check_invite(
+		&self,
+		_inviter: &UserId,
+		_invitee: &UserId,
+		_room_id: &RoomId,
+	) -> Verdict {
+		Verdict::Allow
+	}
+
+	/// A new local account, before it is created.
+	async fn check_registration(&self, _user_id: &UserId) -> Verdict { Verdict::Allow }
+
+	/// Media uploaded by a local user, before it is stored. Soft-failing an
+	/// upload refuses it as a denial would.
+	async fn check_media_upload(
+		&self,
+		_user_id: &UserId,
+		_content_type: Option<&str>,
+		_file: &[u8],
+	) -> Verdict {
+		Verdict::Allow
+	}
+}
+
+#[derive(Debug)]
+pub enum Verdict {
+	Allow,
+
+	/// Refuse the action, returning the error to the client.
+	Deny(Error),
+
+	/// Pretend to the sender that the action succeeded but drop it.
+	SoftFail,
+}
+
+impl ServiceTrait for Service {
+	fn build(args: Args<'_>) -> Result<Arc<Self>> {
+		let mut checkers: Vec<Arc<dyn SpamChecker>> = Vec::new();
+		if let Some(url) = &args.server.config.spam_checker_webhook_url {
+			checkers.push(Arc::new(Webhook {
+				url: url.clone(),
+				token: args.server.config.spam_checker_webhook_token.clone(),
+				timeout: args.server.config.spam_checker_webhook_timeout,
+				client: args.depend::<client::Service>("client"),
+			}));
+		}
+
+		Ok(Arc::new(Self { checkers: RwLock::new(checkers) }))
+	}
+
+	fn name(&self) -> &str { service_core::service::make_name(std::module_path!()) }
+}
+
+/// Add a checker, consulted after those already registered.
+#[implement(Service)]
+pub fn register(&self, checker: Arc<dyn SpamChecker>) {
+	debug_info!("Registering spam checker");
+	self.checkers
+		.write()
+		.expect("locked for writing")
+		.push(checker);
+}
+
+#[implement(Service)]
+pub async fn check_event(&self, pdu: &PduEvent) -> Verdict {
+	for checker in self.checkers() {
+		match checker.check_event(pdu).await {
+			| Verdict::Allow => continue,
+			| verdict => return verdict,
+		}
+	}
+
+	Verdict::Allow
+}
+
+#[implement(Service)]
+pub async fn check_invite(
+	&self,
+	inviter: &UserId,
+	invitee: &UserId,
+	room_id: &RoomId,
+) -> Verdict {
+	for checker in self.checkers() {
+		match checker.check_invite(inviter, invitee, room_id).await {
+			| Verdict::Allow => continue,
+			| verdict => return verdict,
+		}
+	}
+
+	Verdict::Allow
+}
+
+#[implement(Service)]
+pub async fn check_registration(&self, user_id: &UserId) -> Verdict {
+	for checker in self.checkers() {
+		match checker.check_registration(user_id).await {
+			| Verdict::Allow => continue,
+			| verdict => return verdict,
+		}
+	}
+
+	Verdict::Allow
+}
+
+#[implement(Service)]
+pub async fn check_media_upload(
+	&self,
+	user_id: &UserId,
+	content_type: Option<&str>,
+	file: &[u8],
+) -> Verdict {
+	for checker in self.checkers() {
+		match checker
+			.check_media_upload(user_id, content_type, file)
+			.await
+		{
+			| Verdict::Allow => continue,
+			| verdict => return verdict,
+		}
+	}
+
+	Verdict::Allow
+}
+
+/// The checkers, copied so the lock is not held across the checks.
+#[implement(Service)]
+fn checkers(&self) -> Vec<Arc<dyn SpamChecker>> {
+	self.checkers.read().expect("locked for reading").clone()
+}
@@ -0,0 +1,64 @@
+use conduwuit::{Error, http::StatusCode};
+use ruma::api::client::error::ErrorKind;
+
+use super::{
+	Verdict,
+	webhook::{Action, Answer, error_kind, error_status},
+};
+
+fn answer(json: &str) -> Verdict {
+	serde_json::from_str::<Answer>(json)
+		.expect("valid answer")
+		.into()
+}
+
+#[test]
+fn answer_allow() {
+	assert!(matches!(answer(r#"{"action": "allow"}"#), Verdict::Allow));
+	assert!(matches!(answer(r#"{"action": "soft_fail"}"#), Verdict::SoftFail));
+}
+
+#[test]
+fn answer_deny() {
+	let verdict = answer(r#"{"action": "deny", "errcode": "M_TOO_LARGE", "error": "Too big"}"#);
+	let Verdict::Deny(Error::Request(kind, message, status)) = verdict else {
+		panic!("expected a denial, got {verdict:?}");
+	};
+
+	assert_eq!(kind, ErrorKind::TooLarge);
+	assert_eq!(message, "Too big");
+	assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+}
+
+#[test]
+fn answer_unknown_action() {
+	assert!(serde_json::from_str::<Answer>(r#"{"action": "maybe"}"#).is_err());
+	assert_eq!(
+		serde_json::from_str::<Answer>(r#"{"action": "deny"}"#)
+			.unwrap()
+			.action,
+		Action::Deny
+	);
+}
+
+#[test]
+fn errcode_mapping() {
+	assert_eq!(error_kind(Some("M_INVALID_PARAM")), ErrorKind::InvalidParam);
+	assert_eq!(error_kind(Some("M_LIMIT_EXCEEDED")), ErrorKind::LimitExceeded {
+		retry_after: None
+	});
+	assert_eq!(error_kind(Some("M_UNRECOGNIZED")), ErrorKind::forbidden());
+	assert_eq!(error_kind(None), ErrorKind::forbidden());
+}
+
+#[test]
+fn errcode_status() {
+	assert_eq!(error_status(&error_kind(Some("M_FORBIDDEN"))), StatusCode::FORBIDDEN);
+	assert_eq!(error_status(&error_kind(None)), StatusCode::FORBIDDEN);
+	assert_eq!(
+		error_status(&error_kind(Some("M_LIMIT_EXCEEDED"))),
+		StatusCode::TOO_MANY_REQUESTS
+	);
+	assert_eq!(error_status(&error_kind(Some("M_TOO_LARGE"))), StatusCode::PAYLOAD_TOO_LARGE);
+	assert_eq!(error_status(&error_kind(Some("M_BAD_JSON"))), StatusCode::BAD_REQUEST);
+}
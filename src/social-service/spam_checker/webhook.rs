@@ -0,0 +1,168 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use conduwuit::{Error, PduEvent, Result, debug, err, http::StatusCode, warn};
+use reqwest::{Url, header};
+use ruma::{RoomId, UserId, api::client::error::ErrorKind};
+use serde::Deserialize;
+use serde_json::{Value as JsonValue, json};
+use service_core::Dep;
+
+use super::{SpamChecker, Verdict};
+use crate::client;
+
+/// Checker which asks an HTTP endpoint. Checks are allowed when the endpoint
+/// fails or gives an answer which is not understood.
+pub(super) struct Webhook {
+	pub(super) url: Url,
+	pub(super) token: Option<String>,
+	pub(super) timeout: u64,
+	pub(super) client: Dep<client::Service>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(super) struct Answer {
+	pub(super) action: Action,
+	pub(super) errcode: Option<String>,
+	pub(super) error: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(super) enum Action {
+	Allow,
+	Deny,
+	SoftFail,
+}
+
+#[async_trait]
+impl SpamChecker for Webhook {
+	async fn check_event(&self, pdu: &PduEvent) -> Verdict {
+		self.check(json!({
+			"check": "event",
+			"event": pdu,
+		}))
+		.await
+	}
+
+	async fn check_invite(
+		&self,
+		inviter: &UserId,
+		invitee: &UserId,
+		room_id: &RoomId,
+	) -> Verdict {
+		self.check(json!({
+			"check": "invite",
+			"inviter": inviter,
+			"invitee": invitee,
+			"room_id": room_id,
+		}))
+		.await
+	}
+
+	async fn check_registration(&self, user_id: &UserId) -> Verdict {
+		self.check(json!({
+			"check": "registration",
+			"user_id": user_id,
+		}))
+		.await
+	}
+
+	async fn check_media_upload(
+		&self,
+		user_id: &UserId,
+		content_type: Option<&str>,
+		file: &[u8],
+	) -> Verdict {
+		self.check(json!({
+			"check": "media",
+			"user_id": user_id,
+			"content_type": content_type,
+			"size": file.len(),
+		}))
+		.await
+	}
+}
+
+impl Webhook {
+	async fn check(&self, body: JsonValue) -> Verdict {
+		match self.ask(&body).await {
+			| Ok(answer) => {
+				debug!(check = ?body["check"], ?answer, "Spam checker webhook answered");
+				answer.into()
+			},
+			| Err(e) => {
+				warn!(check = ?body["check"], "Spam checker webhook failed, allowing: {e}");
+				Verdict::Allow
+			},
+		}
+	}
+
+	async fn ask(&self, body: &JsonValue) -> Result<Answer> {
+		let mut request = self
+			.client
+			.default
+			.post(self.url.clone())
+			.timeout(Duration::from_secs(self.timeout))
+			.header(header::CONTENT_TYPE, "application/json")
+			.body(serde_json::to_vec(body)?);
+
+		if let Some(token) = &self.token {
+			request = request.bearer_auth(token);
+		}
+
+		let response = request.send().await?;
+		let status = response.status();
+		if !status.is_success() {
+			return Err(err!("Spam checker webhook returned {status}"));
+		}
+
+		Ok(serde_json::from_slice(&response.bytes().await?)?)
+	}
+}
+
+impl From<Answer> for Verdict {
+	fn from(answer: Answer) -> Self {
+		match answer.action {
+			| Action::Allow => Self::Allow,
+			| Action::SoftFail => Self::SoftFail,
+			| Action::Deny => {
+				let kind = error_kind(answer.errcode.as_deref());
+				let status = error_status(&kind);
+				Self::Deny(Error::Request(
+					kind,
+					answer
+						.error
+						.unwrap_or_else(|| {
+							"This action was refused by the spam checker.".to_owned()
+						})
+						.into(),
+					status,
+				))
+			},
+		}
+	}
+}
+
+/// The error for a Matrix error code given by the webhook; anything not
+/// recognised is `M_FORBIDDEN`.
+pub(super) fn error_kind(errcode: Option<&str>) -> ErrorKind {
+	match errcode {
+		| Some("M_TOO_LARGE") => ErrorKind::TooLarge,
+		| Some("M_INVALID_PARAM") => ErrorKind::InvalidParam,
+		| Some("M_LIMIT_EXCEEDED") => ErrorKind::LimitExceeded { retry_after: None },
+		| Some("M_BAD_JSON") => ErrorKind::BadJson,
+		| Some("M_USER_IN_USE") => ErrorKind::UserInUse,
+		| _ => ErrorKind::forbidden(),
+	}
+}
+
+/// The status of a denial with the error given by the webhook.
+pub(super) fn error_status(kind: &ErrorKind) -> StatusCode {
+	match kind {
+		| ErrorKind::Forbidden { .. } => StatusCode::FORBIDDEN,
+		| ErrorKind::LimitExceeded { .. } => StatusCode::TOO_MANY_REQUESTS,
+		| ErrorKind::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+		| _ => StatusCode::BAD_REQUEST,
+	}
+}
@@ -66,6 +66,10 @@ async fn worker(self: Arc<Self>) -> Result<()> {
 			return Ok(());
 		}
 
+		if self.services.globals.is_read_only() {
+			return Ok(());
+		}
+
 		let mut i = interval(self.interval);
 		i.set_missed_tick_behavior(MissedTickBehavior::Delay);
 		i.reset_after(self.interval);
@@ -1,8 +1,12 @@
+mod restrictions;
+#[cfg(test)]
+mod tests;
+
 use std::{collections::BTreeMap, mem, sync::Arc};
 
 use conduwuit::{
 	Err, Error, Result, Server, at, debug_warn, err, trace,
-	utils::{self, ReadyExt, stream::TryIgnore, string::Unquoted},
+	utils::{self, MutexMap, ReadyExt, hash::sha256, stream::TryIgnore, string::Unquoted},
 };
 use database::{Deserialized, Ignore, Interfix, Json, Map};
 use futures::{Stream, StreamExt, TryFutureExt};
@@ -18,12 +22,14 @@
 };
 use serde_json::json;
 
+pub use self::restrictions::{Restriction, Restrictions, locked_error, suspended_error};
 use crate::{account_data, admin, globals, rooms};
 use service_core::{Dep, Args, Service as ServiceTrait};
 
 pub struct Service {
 	services: Services,
 	db: Data,
+	mutex_restrictions: MutexMap<String, ()>,
 }
 
 struct Services {
@@ -53,7 +59,9 @@ struct Data {
 	userid_lastonetimekeyupdate: Arc<Map>,
 	userid_masterkeyid: Arc<Map>,
 	userid_password: Arc<Map>,
+	userid_restrictions: Arc<Map>,
 	userid_selfsigningkeyid: Arc<Map>,
+	userid_shadowbanned: Arc<Map>,
 	userid_usersigningkeyid: Arc<Map>,
 	useridprofilekey_value: Arc<Map>,
 }
@@ -88,10 +96,13 @@ fn build(args: Args<'_>) -> Result<Arc<Self>> {
 				userid_lastonetimekeyupdate: args.db["userid_lastonetimekeyupdate"].clone(),
 				userid_masterkeyid: args.db["userid_masterkeyid"].clone(),
 				userid_password: args.db["userid_password"].clone(),
+				userid_restrictions: args.db["userid_restrictions"].clone(),
 				userid_selfsigningkeyid: args.db["userid_selfsigningkeyid"].clone(),
+				userid_shadowbanned: args.db["userid_shadowbanned"].clone(),
 				userid_usersigningkeyid: args.db["userid_usersigningkeyid"].clone(),
 				useridprofilekey_value: args.db["useridprofilekey_value"].clone(),
 			},
+			mutex_restrictions: MutexMap::new(),
 		}))
 	}
 
@@ -176,7 +187,11 @@ pub async fn count(&self) -> usize { self.db.userid_password.count().await }
 
 	/// Find out which user an access token belongs to.
 	pub async fn find_from_token(&self, token: &str) -> Result<(OwnedUserId, OwnedDeviceId)> {
-		self.db.token_userdeviceid.get(token).await.deserialized()
+		self.db
+			.token_userdeviceid
+			.get(&sha256::hash(token))
+			.await
+			.deserialized()
 	}
 
 	/// Returns an iterator over all users on this homeserver (offered for
@@ -203,6 +218,28 @@ pub fn list_local_users(&self) -> impl Stream<Item = &UserId> + Send + '_ {
 			.ready_filter_map(|(u, p): (&UserId, &[u8])| (!p.is_empty()).then_some(u))
 	}
 
+	/// Shadow-ban a local user or lift their shadow ban. The messages, state
+	/// and redactions of a shadow-banned user are dropped while they are given
+	/// an event ID, and invites they send are dropped. Their other events are
+	/// stored and shown to them, but not federated, shown to other local users
+	/// or pushed.
+	pub fn set_shadow_banned(&self, user_id: &UserId, shadow_banned: bool) {
+		if shadow_banned {
+			self.db.userid_shadowbanned.insert(user_id, []);
+		} else {
+			self.db.userid_shadowbanned.remove(user_id);
+		}
+	}
+
+	pub async fn is_shadow_banned(&self, user_id: &UserId) -> bool {
+		self.services.globals.user_is_local(user_id)
+			&& self.db.userid_shadowbanned.get(user_id).await.is_ok()
+	}
+
+	pub fn list_shadow_banned(&self) -> impl Stream<Item = &UserId> + Send + '_ {
+		self.db.userid_shadowbanned.keys().ignore_err()
+	}
+
 	/// Returns the password hash for the given user.
 	pub async fn password_hash(&self, user_id: &UserId) -> Result<String> {
 		self.db.userid_password.get(user_id).await.deserialized()
@@ -305,7 +342,7 @@ pub async fn remove_device(&self, user_id: &UserId, device_id: &DeviceId) {
 		// Remove tokens
 		if let Ok(old_token) = self.db.userdeviceid_token.qry(&userdeviceid).await {
 			self.db.userdeviceid_token.del(userdeviceid);
-			self.db.token_userdeviceid.remove(&old_token);
+			self.db.token_userdeviceid.remove(&sha256::hash(old_token));
 		}
 
 		// Remove todevice events
@@ -361,13 +398,14 @@ pub async fn set_token(
 
 		// Remove old token
 		if let Ok(old_token) = self.db.userdeviceid_token.qry(&key).await {
-			self.db.token_userdeviceid.remove(&old_token);
+			self.db.token_userdeviceid.remove(&sha256::hash(old_token));
 			// It will be removed from userdeviceid_token by the insert later
 		}
 
-		// Assign token to user device combination
+		// Assign token to user device combination; only a digest of the token is
+		// kept in the key so it can be looked up without storing it there.
 		self.db.userdeviceid_token.put_raw(key, token);
-		self.db.token_userdeviceid.raw_put(token, key);
+		self.db.token_userdeviceid.raw_put(sha256::hash(token), key);
 
 		Ok(())
 	}
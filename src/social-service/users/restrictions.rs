@@ -0,0 +1,163 @@
+//! Account suspension and locking (MSC3823). Unlike deactivation both can be
+//! lifted again: a suspended user can still read but their writes are refused
+//! with `M_USER_SUSPENDED`, and a locked user is refused everything with
+//! `M_USER_LOCKED` while keeping their sessions.
+
+use conduwuit::{
+	Err, Error, Result,
+	http::StatusCode,
+	implement,
+	utils::{stream::TryIgnore, time::now_millis},
+};
+use database::{Deserialized, Json};
+use futures::{Stream, StreamExt};
+use ruma::{OwnedUserId, UserId, api::client::error::ErrorKind};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value as JsonValue, json};
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Restrictions {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub suspended: Option<Restriction>,
+
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub locked: Option<Restriction>,
+}
+
+/// Who suspended or locked an account, when and why.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Restriction {
+	pub reason: Option<String>,
+	pub by: OwnedUserId,
+	pub ts: u64,
+}
+
+impl Restrictions {
+	#[must_use]
+	pub fn is_empty(&self) -> bool { self.suspended.is_none() && self.locked.is_none() }
+}
+
+#[implement(super::Service)]
+pub async fn restrictions(&self, user_id: &UserId) -> Restrictions {
+	self.db
+		.userid_restrictions
+		.get(user_id)
+		.await
+		.deserialized()
+		.unwrap_or_default()
+}
+
+#[implement(super::Service)]
+pub async fn is_suspended(&self, user_id: &UserId) -> bool {
+	self.restrictions(user_id).await.suspended.is_some()
+}
+
+#[implement(super::Service)]
+pub async fn is_locked(&self, user_id: &UserId) -> bool {
+	self.restrictions(user_id).await.locked.is_some()
+}
+
+/// Suspend or lift the suspension of a local user; returns whether anything
+/// changed.
+#[implement(super::Service)]
+pub async fn set_suspended(
+	&self,
+	user_id: &UserId,
+	suspended: bool,
+	by: &UserId,
+	reason: Option<String>,
+) -> Result<bool> {
+	self.restrict(user_id, by, reason, suspended, |restrictions| &mut restrictions.suspended)
+		.await
+}
+
+/// Lock or unlock a local user; returns whether anything changed.
+#[implement(super::Service)]
+pub async fn set_locked(
+	&self,
+	user_id: &UserId,
+	locked: bool,
+	by: &UserId,
+	reason: Option<String>,
+) -> Result<bool> {
+	self.restrict(user_id, by, reason, locked, |restrictions| &mut restrictions.locked)
+		.await
+}
+
+/// Suspended or locked users with their restrictions.
+#[implement(super::Service)]
+pub fn list_restricted(&self) -> impl Stream<Item = (OwnedUserId, Restrictions)> + Send + '_ {
+	self.db.userid_restrictions.stream().ignore_err().map(
+		|(user_id, restrictions): (&UserId, Restrictions)| (user_id.to_owned(), restrictions),
+	)
+}
+
+#[implement(super::Service)]
+async fn restrict<F>(
+	&self,
+	user_id: &UserId,
+	by: &UserId,
+	reason: Option<String>,
+	on: bool,
+	field: F,
+) -> Result<bool>
+where
+	F: FnOnce(&mut Restrictions) -> &mut Option<Restriction> + Send,
+{
+	if !self.services.globals.user_is_local(user_id) {
+		return Err!(Request(InvalidParam("{user_id} is not a local user.")));
+	}
+
+	if !self.exists(user_id).await {
+		return Err!(Request(NotFound("{user_id} does not exist.")));
+	}
+
+	let _lock = self.mutex_restrictions.lock(&user_id.to_string()).await;
+	let mut restrictions = self.restrictions(user_id).await;
+	let restriction = field(&mut restrictions);
+	if restriction.is_some() == on {
+		return Ok(false);
+	}
+
+	*restriction = on.then(|| Restriction {
+		reason,
+		by: by.to_owned(),
+		ts: now_millis(),
+	});
+
+	if restrictions.is_empty() {
+		self.db.userid_restrictions.remove(user_id);
+	} else {
+		self.db
+			.userid_restrictions
+			.put(user_id, Json(&restrictions));
+	}
+
+	Ok(true)
+}
+
+/// The `M_USER_SUSPENDED` error for writes of a suspended user.
+#[must_use]
+pub fn suspended_error() -> Error {
+	Error::Request(
+		error_kind(json!({ "errcode": "M_USER_SUSPENDED" })),
+		"Your account has been suspended; you can read but not make changes.".into(),
+		StatusCode::FORBIDDEN,
+	)
+}
+
+/// The `M_USER_LOCKED` error for any request of a locked user.
+#[must_use]
+pub fn locked_error() -> Error {
+	Error::Request(
+		error_kind(json!({ "errcode": "M_USER_LOCKED", "soft_logout": true })),
+		"Your account has been locked.".into(),
+		StatusCode::UNAUTHORIZED,
+	)
+}
+
+/// The error kind for an error body, whether or not ruma has a variant for its
+/// code.
+fn error_kind(body: JsonValue) -> ErrorKind {
+	serde_json::from_value(body).expect("any errcode deserializes to an ErrorKind")
+}
@@ -0,0 +1,17 @@
+use conduwuit::http::StatusCode;
+
+use super::{locked_error, suspended_error};
+
+#[test]
+fn suspended_errcode() {
+	let error = suspended_error();
+	assert_eq!(error.kind().to_string(), "M_USER_SUSPENDED");
+	assert_eq!(error.status_code(), StatusCode::FORBIDDEN);
+}
+
+#[test]
+fn locked_errcode() {
+	let error = locked_error();
+	assert_eq!(error.kind().to_string(), "M_USER_LOCKED");
+	assert_eq!(error.status_code(), StatusCode::UNAUTHORIZED);
+}